@@ -0,0 +1,138 @@
+use frclib_core::value::FrcTimestamp;
+
+use crate::{DataLogError, DataLogReader};
+
+use super::{interpolate, numeric_series, require_sorted};
+
+/// Configuration for [`estimate_latency`]
+#[derive(Debug, Clone, Copy)]
+pub struct CrossCorrelationConfig {
+    /// The largest offset to search for in either direction, in microseconds
+    pub max_lag: FrcTimestamp,
+    /// The spacing of the resampled signals and the precision of the estimate, in microseconds
+    pub resolution: FrcTimestamp,
+    /// Only consider values between these timestamps
+    pub window: Option<(FrcTimestamp, FrcTimestamp)>,
+}
+impl Default for CrossCorrelationConfig {
+    fn default() -> Self {
+        Self {
+            max_lag: 500_000,
+            resolution: 5_000,
+            window: None
+        }
+    }
+}
+
+/// The result of a cross correlation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LatencyEstimate {
+    /// How far the second signal trails the first, in microseconds.
+    /// Negative if the second signal leads the first.
+    pub lag: i64,
+    /// The normalized correlation at `lag`, from -1 to 1
+    pub correlation: f64,
+}
+
+/// Estimates how far `delayed_key` trails `reference_key` by cross correlating the two entries,
+/// for example commanded vs measured velocity to find control latency
+///
+/// # Errors
+/// - [`DataLogError::Analysis`] if either entry has fewer than 2 numeric values,
+///   the entries don't overlap in time or neither signal varies
+pub fn estimate_latency(
+    reader: &DataLogReader,
+    reference_key: &str,
+    delayed_key: &str,
+    config: &CrossCorrelationConfig
+) -> Result<LatencyEstimate, DataLogError> {
+    cross_correlate(
+        &numeric_series(reader, reference_key),
+        &numeric_series(reader, delayed_key),
+        config
+    )
+}
+
+/// Cross correlates two series of `(timestamp, value)` pairs sorted by timestamp,
+/// see [`estimate_latency`]
+///
+/// # Errors
+/// - [`DataLogError::Analysis`] if either series has fewer than 2 values, is unsorted,
+///   the series don't overlap in time or neither signal varies
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+pub fn cross_correlate(
+    reference: &[(FrcTimestamp, f64)],
+    delayed: &[(FrcTimestamp, f64)],
+    config: &CrossCorrelationConfig
+) -> Result<LatencyEstimate, DataLogError> {
+    if reference.len() < 2 || delayed.len() < 2 {
+        return Err(DataLogError::Analysis("Not enough samples to correlate"));
+    }
+    if config.resolution == 0 {
+        return Err(DataLogError::Analysis("Resolution must be non zero"));
+    }
+    require_sorted(reference)?;
+    require_sorted(delayed)?;
+
+    let (mut start, mut end) = (
+        reference[0].0.max(delayed[0].0),
+        reference[reference.len() - 1].0.min(delayed[delayed.len() - 1].0)
+    );
+    if let Some((window_start, window_end)) = config.window {
+        start = start.max(window_start);
+        end = end.min(window_end);
+    }
+    if end <= start {
+        return Err(DataLogError::Analysis("Entries do not overlap"));
+    }
+
+    let resample = |series: &[(FrcTimestamp, f64)]| -> Vec<f64> {
+        (start..=end)
+            .step_by(config.resolution as usize)
+            .filter_map(|timestamp| interpolate(series, timestamp))
+            .collect()
+    };
+    let reference = resample(reference);
+    let delayed = resample(delayed);
+
+    let max_shift = (config.max_lag / config.resolution) as usize;
+    let mut best: Option<LatencyEstimate> = None;
+    for shift in 0..=max_shift {
+        // a positive lag pairs each reference sample with a later delayed sample
+        let candidates = [
+            (1i64, pearson(&reference, delayed.get(shift..).unwrap_or_default())),
+            (-1i64, pearson(reference.get(shift..).unwrap_or_default(), &delayed))
+        ];
+        for (sign, correlation) in candidates {
+            if let Some(correlation) = correlation {
+                let lag = sign * (shift as i64) * (config.resolution as i64);
+                if best.is_none_or(|best| correlation > best.correlation) {
+                    best = Some(LatencyEstimate { lag, correlation });
+                }
+            }
+        }
+    }
+
+    best.ok_or(DataLogError::Analysis("Signals do not vary"))
+}
+
+/// The pearson correlation of the overlapping prefix of two slices
+#[allow(clippy::cast_precision_loss)]
+fn pearson(a: &[f64], b: &[f64]) -> Option<f64> {
+    let len = a.len().min(b.len());
+    if len < 2 {
+        return None;
+    }
+    let (a, b) = (&a[..len], &b[..len]);
+    let mean_a = a.iter().sum::<f64>() / len as f64;
+    let mean_b = b.iter().sum::<f64>() / len as f64;
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        let (dx, dy) = (x - mean_a, y - mean_b);
+        covariance = dx.mul_add(dy, covariance);
+        variance_a = dx.mul_add(dx, variance_a);
+        variance_b = dy.mul_add(dy, variance_b);
+    }
+    let denominator = (variance_a * variance_b).sqrt();
+    (denominator > f64::EPSILON).then(|| covariance / denominator)
+}
@@ -0,0 +1,48 @@
+use frclib_core::value::{FrcTimestamp, FrcValue};
+
+use crate::{DataLogError, DataLogReader};
+
+/// Cross correlation of entries, for estimating latency between them
+pub mod correlation;
+
+/// Brownout detection, voltage sag and energy usage
+pub mod power;
+
+/// Reads the scalar numeric values of an entry as `(timestamp, value)` pairs,
+/// values that aren't numeric are skipped
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn numeric_series(reader: &DataLogReader, entry_key: &str) -> Vec<(FrcTimestamp, f64)> {
+    reader.read_entry(entry_key)
+        .into_iter()
+        .filter_map(|value| match value.value {
+            FrcValue::Double(v) => Some((value.timestamp, v)),
+            FrcValue::Float(v) => Some((value.timestamp, f64::from(v))),
+            FrcValue::Int(v) => Some((value.timestamp, v as f64)),
+            _ => None
+        })
+        .collect()
+}
+
+/// Linearly interpolates a series that is sorted by timestamp,
+/// timestamps outside of the series are clamped to the first/last value
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn interpolate(series: &[(FrcTimestamp, f64)], timestamp: FrcTimestamp) -> Option<f64> {
+    let index = series.partition_point(|(t, _)| *t <= timestamp);
+    match (index.checked_sub(1).and_then(|i| series.get(i)), series.get(index)) {
+        (Some(&(t0, v0)), Some(&(t1, v1))) => {
+            let fraction = (timestamp - t0) as f64 / (t1 - t0) as f64;
+            Some(fraction.mul_add(v1 - v0, v0))
+        }
+        (Some(&(_, v)), None) | (None, Some(&(_, v))) => Some(v),
+        (None, None) => None
+    }
+}
+
+/// Ensures the timestamps of a series are in order
+pub(crate) fn require_sorted(series: &[(FrcTimestamp, f64)]) -> Result<(), DataLogError> {
+    if series.is_sorted_by_key(|(timestamp, _)| *timestamp) {
+        Ok(())
+    } else {
+        Err(DataLogError::Analysis("Series is not sorted by timestamp"))
+    }
+}
@@ -0,0 +1,175 @@
+use frclib_core::value::FrcTimestamp;
+
+use crate::{DataLogError, DataLogReader};
+
+use super::{interpolate, numeric_series, require_sorted};
+
+/// The battery voltage entry logged by `AdvantageKit`
+pub const DEFAULT_VOLTAGE_KEY: &str = "/SystemStats/BatteryVoltage";
+/// The total current entry logged by `AdvantageKit`
+pub const DEFAULT_CURRENT_KEY: &str = "/PowerDistribution/TotalCurrent";
+
+/// Configuration for brownout detection and voltage sag statistics
+#[derive(Debug, Clone, Copy)]
+pub struct PowerAnalysisConfig {
+    /// Voltages below this are considered a brownout, the roboRIO 2 default is 6.75 volts
+    pub brownout_voltage: f64,
+    /// Brownouts shorter than this are ignored, in microseconds
+    pub min_brownout_duration: FrcTimestamp,
+}
+impl Default for PowerAnalysisConfig {
+    fn default() -> Self {
+        Self {
+            brownout_voltage: 6.75,
+            min_brownout_duration: 0
+        }
+    }
+}
+
+/// A period where the battery voltage was below [`PowerAnalysisConfig::brownout_voltage`]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BrownoutEvent {
+    /// The timestamp of the first sample below the threshold
+    pub start: FrcTimestamp,
+    /// The timestamp of the first sample back above the threshold,
+    /// or of the last sample if the log ended during the brownout
+    pub end: FrcTimestamp,
+    /// The lowest voltage during the event
+    pub min_voltage: f64,
+}
+
+/// Time weighted statistics of the battery voltage
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoltageSagStatistics {
+    /// The lowest voltage
+    pub min_voltage: f64,
+    /// The highest voltage
+    pub max_voltage: f64,
+    /// The time weighted mean voltage
+    pub mean_voltage: f64,
+    /// The total time spent below [`PowerAnalysisConfig::brownout_voltage`], in microseconds
+    pub time_below_threshold: FrcTimestamp,
+    /// The fraction of the log spent below [`PowerAnalysisConfig::brownout_voltage`]
+    pub fraction_below_threshold: f64,
+}
+
+/// Battery voltage and current samples ready for analysis
+#[derive(Debug, Clone)]
+pub struct PowerAnalysis {
+    voltage: Vec<(FrcTimestamp, f64)>,
+    current: Vec<(FrcTimestamp, f64)>
+}
+
+impl PowerAnalysis {
+    /// Creates an analysis from the numeric values of a voltage and a current entry,
+    /// see [`DEFAULT_VOLTAGE_KEY`] and [`DEFAULT_CURRENT_KEY`] for the conventional keys
+    ///
+    /// # Errors
+    /// - [`DataLogError::Analysis`] if the voltage entry has no numeric values
+    pub fn from_reader(reader: &DataLogReader, voltage_key: &str, current_key: &str) -> Result<Self, DataLogError> {
+        Self::from_series(
+            numeric_series(reader, voltage_key),
+            numeric_series(reader, current_key)
+        )
+    }
+
+    /// Creates an analysis from `(timestamp, value)` pairs sorted by timestamp
+    ///
+    /// # Errors
+    /// - [`DataLogError::Analysis`] if there are no voltage samples or either series is unsorted
+    pub fn from_series(voltage: Vec<(FrcTimestamp, f64)>, current: Vec<(FrcTimestamp, f64)>) -> Result<Self, DataLogError> {
+        if voltage.is_empty() {
+            return Err(DataLogError::Analysis("No voltage samples"));
+        }
+        require_sorted(&voltage)?;
+        require_sorted(&current)?;
+        Ok(Self {
+            voltage,
+            current
+        })
+    }
+
+    /// Finds every period the voltage dropped below the brownout threshold
+    #[must_use]
+    pub fn brownouts(&self, config: &PowerAnalysisConfig) -> Vec<BrownoutEvent> {
+        let mut events = Vec::new();
+        let mut current_event: Option<BrownoutEvent> = None;
+        for &(timestamp, voltage) in &self.voltage {
+            if voltage < config.brownout_voltage {
+                let event = current_event.get_or_insert(BrownoutEvent {
+                    start: timestamp,
+                    end: timestamp,
+                    min_voltage: voltage
+                });
+                event.end = timestamp;
+                event.min_voltage = event.min_voltage.min(voltage);
+            } else if let Some(mut event) = current_event.take() {
+                event.end = timestamp;
+                events.push(event);
+            }
+        }
+        events.extend(current_event);
+        events.retain(|event| event.end - event.start >= config.min_brownout_duration);
+        events
+    }
+
+    /// Computes time weighted voltage statistics, each sample is held until the next one
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn sag_statistics(&self, config: &PowerAnalysisConfig) -> VoltageSagStatistics {
+        let mut statistics = VoltageSagStatistics {
+            min_voltage: f64::INFINITY,
+            max_voltage: f64::NEG_INFINITY,
+            mean_voltage: 0.0,
+            time_below_threshold: 0,
+            fraction_below_threshold: 0.0
+        };
+        let mut weighted_sum = 0.0;
+        for (index, &(timestamp, voltage)) in self.voltage.iter().enumerate() {
+            statistics.min_voltage = statistics.min_voltage.min(voltage);
+            statistics.max_voltage = statistics.max_voltage.max(voltage);
+            let duration = self.voltage.get(index + 1)
+                .map_or(0, |(next, _)| next - timestamp);
+            weighted_sum = voltage.mul_add(duration as f64, weighted_sum);
+            if voltage < config.brownout_voltage {
+                statistics.time_below_threshold += duration;
+            }
+        }
+
+        let total_duration = self.voltage[self.voltage.len() - 1].0 - self.voltage[0].0;
+        if total_duration == 0 {
+            statistics.mean_voltage = self.voltage[0].1;
+        } else {
+            statistics.mean_voltage = weighted_sum / total_duration as f64;
+            statistics.fraction_below_threshold = statistics.time_below_threshold as f64 / total_duration as f64;
+        }
+        statistics
+    }
+
+    /// Estimates the energy drawn from the battery between two timestamps in joules,
+    /// by integrating voltage times current over the current samples
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn energy_between(&self, start: FrcTimestamp, end: FrcTimestamp) -> f64 {
+        let power = self.current.iter()
+            .filter(|(timestamp, _)| *timestamp >= start && *timestamp <= end)
+            .filter_map(|&(timestamp, current)| {
+                interpolate(&self.voltage, timestamp).map(|voltage| (timestamp, voltage * current))
+            })
+            .collect::<Vec<_>>();
+        power.windows(2)
+            .map(|pair| {
+                let ((t0, p0), (t1, p1)) = (pair[0], pair[pair.len() - 1]);
+                f64::midpoint(p0, p1) * ((t1 - t0) as f64 / 1e6)
+            })
+            .sum()
+    }
+
+    /// Estimates the energy drawn during each `(start, end)` segment, like the parts of a match
+    #[must_use]
+    pub fn energy_per_segment(&self, segments: &[(FrcTimestamp, FrcTimestamp)]) -> Vec<f64> {
+        segments.iter()
+            .map(|&(start, end)| self.energy_between(start, end))
+            .collect()
+    }
+}
@@ -0,0 +1,163 @@
+use std::{collections::VecDeque, io::Write};
+
+use crate::proto::records::{header_len, split_record, ControlRecord};
+
+/// A sink that keeps the latest records in memory until it is armed, like on enable
+///
+/// While disarmed data records go into a bounded buffer and the oldest are dropped once it is full,
+/// so the time spent in the pits doesn't grow the log. Control records and struct schemas are
+/// always kept so every entry can still be decoded. Arming writes the buffer to the inner sink
+/// and passes everything through from then on, see
+/// [`DataLogWriter::arm`](crate::DataLogWriter::arm) and [`DataLogWriter::disarm`](crate::DataLogWriter::disarm).
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::{arming::ArmableSink, DataLogWriter};
+///
+/// let file = File::create("/home/lvuser/logs/match.wpilog").expect("Failed to create file");
+/// let mut writer = DataLogWriter::new(ArmableSink::new(file, 1024 * 1024), "").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+///
+/// // once the robot is enabled
+/// writer.arm().expect("Failed to arm writer");
+/// ```
+#[derive(Debug)]
+pub struct ArmableSink<W: Write> {
+    inner: W,
+    armed: bool,
+    /// The start of a record cut off between writes
+    pending: Vec<u8>,
+    /// The header, until it is passed on
+    header: Option<Vec<u8>>,
+    header_read: bool,
+    /// Whole records in the order they were written, along with whether they can be dropped
+    records: VecDeque<(bool, Vec<u8>)>,
+    /// The bytes of droppable records in the buffer
+    buffered: usize,
+    capacity: usize,
+    schema_ids: Vec<u32>,
+    dropped_records: u64
+}
+
+impl <W: Write> ArmableSink<W> {
+    /// Creates a disarmed sink that keeps up to `capacity` bytes of data records
+    #[must_use]
+    pub const fn new(inner: W, capacity: usize) -> Self {
+        Self {
+            inner,
+            armed: false,
+            pending: Vec::new(),
+            header: None,
+            header_read: false,
+            records: VecDeque::new(),
+            buffered: 0,
+            capacity,
+            schema_ids: Vec::new(),
+            dropped_records: 0
+        }
+    }
+
+    /// Whether records are passed through to the inner sink
+    #[must_use]
+    pub const fn is_armed(&self) -> bool {
+        self.armed
+    }
+
+    /// The number of data records dropped while disarmed
+    #[must_use]
+    pub const fn dropped_records(&self) -> u64 {
+        self.dropped_records
+    }
+
+    /// The inner sink
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Writes everything buffered to the inner sink and passes records through from then on
+    pub(crate) fn arm(&mut self) -> std::io::Result<()> {
+        if self.armed {
+            return Ok(());
+        }
+        if let Some(header) = self.header.take() {
+            self.inner.write_all(&header)?;
+        }
+        for (_, record) in self.records.drain(..) {
+            self.inner.write_all(&record)?;
+        }
+        self.inner.write_all(&self.pending)?;
+        self.pending.clear();
+        self.buffered = 0;
+        self.armed = true;
+        Ok(())
+    }
+
+    /// Buffers records again, must only be called on a record boundary
+    pub(crate) const fn disarm(&mut self) {
+        self.armed = false;
+    }
+
+    /// Moves every whole record in `pending` into the buffer, dropping the oldest data past the capacity
+    fn buffer_records(&mut self) {
+        let mut consumed = if self.header_read {
+            0
+        } else {
+            let Some(len) = header_len(&self.pending) else {
+                return;
+            };
+            self.header = Some(self.pending[..len].to_vec());
+            self.header_read = true;
+            len
+        };
+
+        while let Some((id, _, payload, len)) = split_record(&self.pending[consumed..]) {
+            let droppable = if id == 0 {
+                if let Ok((ControlRecord::Start(_, entry_type, _), entry_id)) = ControlRecord::from_binary(payload) {
+                    if entry_type == "structschema" {
+                        self.schema_ids.push(entry_id);
+                    }
+                }
+                false
+            } else {
+                !self.schema_ids.contains(&id)
+            };
+            if droppable {
+                self.buffered += len;
+            }
+            self.records.push_back((droppable, self.pending[consumed..consumed + len].to_vec()));
+            consumed += len;
+        }
+        let _ = self.pending.drain(..consumed);
+
+        while self.buffered > self.capacity {
+            let Some(index) = self.records.iter().position(|(droppable, _)| *droppable) else {
+                break;
+            };
+            if let Some((_, record)) = self.records.remove(index) {
+                self.buffered -= record.len();
+                self.dropped_records += 1;
+            }
+        }
+    }
+}
+
+impl <W: Write> Write for ArmableSink<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.armed {
+            self.inner.write_all(buf)?;
+        } else {
+            self.pending.extend_from_slice(buf);
+            self.buffer_records();
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.armed {
+            self.inner.flush()?;
+        }
+        Ok(())
+    }
+}
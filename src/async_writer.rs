@@ -0,0 +1,154 @@
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, IntoFrcValue, StaticallyFrcTyped};
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::{writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+/// The default number of serialized bytes held before they are written to the sink
+pub const DEFAULT_ASYNC_BUFFER_SIZE: usize = 8 * 1024;
+
+/// A datalog writer for use inside of a tokio runtime
+/// 
+/// Records are serialized into an in memory buffer which is written to the
+/// [`AsyncWrite`] sink once it grows past the buffer size or on [`AsyncDataLogWriter::flush`],
+/// so logging never blocks an executor thread on IO.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::async_writer::AsyncDataLogWriter;
+/// 
+/// # async fn example() {
+/// let file = tokio::fs::File::create("path/to/file").await.unwrap();
+/// let mut writer = AsyncDataLogWriter::new(file, "").expect("Failed to create writer");
+/// 
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).await.expect("Failed to write entry");
+/// writer.flush().await.expect("Failed to flush");
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct AsyncDataLogWriter<W: AsyncWrite + Unpin> {
+    /// The serializer, writes into memory
+    inner: DataLogWriter<Vec<u8>>,
+    /// The sink
+    sink: W,
+    /// The number of bytes to buffer before writing to the sink
+    buffer_size: usize
+}
+
+impl <W: AsyncWrite + Unpin> AsyncDataLogWriter<W> {
+    /// Creates a new async datalog writer, the header is written to the sink on the first flush
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn new(sink: W, metadata: impl ToString) -> Result<Self, DataLogError> {
+        Self::with_buffer_size(sink, metadata, DEFAULT_ASYNC_BUFFER_SIZE)
+    }
+
+    /// Creates a new async datalog writer that buffers `buffer_size` bytes before writing to the sink
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn with_buffer_size(sink: W, metadata: impl ToString, buffer_size: usize) -> Result<Self, DataLogError> {
+        Ok(Self {
+            inner: DataLogWriter::new_in_memory(metadata)?,
+            sink,
+            buffer_size
+        })
+    }
+
+    async fn write_if_full(&mut self) -> Result<(), DataLogError> {
+        if self.inner.written_len() >= self.buffer_size {
+            let bytes = self.inner.take_written()?;
+            self.sink.write_all(&bytes).await?;
+        }
+        Ok(())
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry<T: StaticallyFrcTyped>(&mut self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.inner.get_entry(key, metadata)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry_dynamic`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry_dynamic(&mut self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.inner.get_entry_dynamic(key, entry_type, metadata)
+    }
+
+    /// Writes a value to the datalog, see [`DataLogWriter::write`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub async fn write<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T) -> Result<(), DataLogError> {
+        self.inner.write(id, value)?;
+        self.write_if_full().await
+    }
+
+    /// Writes a value to the datalog with a specific timestamp, see [`DataLogWriter::write_timestamped`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub async fn write_timestamped<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.inner.write_timestamped(id, value, timestamp)?;
+        self.write_if_full().await
+    }
+
+    /// Writes a value to the datalog, see [`DataLogWriter::write_dynamic`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::EntryTypeMismatch`] if the value type doesn't match the entry type
+    pub async fn write_dynamic(&mut self, id: EntryId, value: FrcTimestampedValue) -> Result<(), DataLogError> {
+        self.inner.write_dynamic(id, value)?;
+        self.write_if_full().await
+    }
+
+    /// Closes an entry, see [`DataLogWriter::close_entry`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub async fn close_entry(&mut self, id: EntryId) -> Result<(), DataLogError> {
+        self.inner.close_entry(id)?;
+        self.write_if_full().await
+    }
+
+    /// Writes everything buffered to the sink and flushes it
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub async fn flush(&mut self) -> Result<(), DataLogError> {
+        let bytes = self.inner.take_written()?;
+        self.sink.write_all(&bytes).await?;
+        self.sink.flush().await?;
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying sink
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub async fn into_inner(mut self) -> Result<W, DataLogError> {
+        self.flush().await?;
+        Ok(self.sink)
+    }
+}
@@ -0,0 +1,448 @@
+use std::{collections::{HashMap, VecDeque}, io::Write, sync::{mpsc::{self, SyncSender}, Arc, Condvar, Mutex, MutexGuard, PoisonError}, thread::JoinHandle};
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, IntoFrcValue, StaticallyFrcTyped};
+
+use crate::{now, writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+/// The default number of commands that can be queued before the [`QueuePolicy`] applies
+pub const DEFAULT_QUEUE_CAPACITY: usize = 4096;
+
+/// What a [`BackgroundWriterHandle`] does with a value queued while the queue is full,
+/// every value dropped is counted per entry in [`BackgroundWriterHandle::dropped`]
+/// 
+/// Only values are dropped, other commands fail with [`DataLogError::BackgroundWriterFull`]
+/// unless the policy blocks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QueuePolicy {
+    /// Drop the value being queued and return [`DataLogError::BackgroundWriterFull`]
+    #[default]
+    DropNewest,
+    /// Drop the oldest value still queued to make room for the new one
+    DropOldest,
+    /// Wait for the worker to make room, real time threads should never use this
+    Block
+}
+
+#[derive(Debug)]
+enum Command {
+    Entry {
+        key: String,
+        entry_type: FrcType,
+        metadata: Option<String>,
+        reply: SyncSender<Result<EntryId, DataLogError>>
+    },
+    Write {
+        id: EntryId,
+        value: FrcTimestampedValue,
+        check_type: bool
+    },
+    Close(EntryId),
+    Pause(bool),
+    Flush,
+    Stop
+}
+
+#[derive(Debug)]
+struct QueueState {
+    commands: VecDeque<Command>,
+    /// The number of handles alive, the worker stops once every one is dropped
+    handles: usize,
+    /// Set once the worker has stopped and nothing more will be taken
+    closed: bool,
+    /// The number of values dropped per entry id
+    dropped: HashMap<u32, u64>,
+    /// The number of commands the worker failed to carry out
+    failed: u64,
+    /// The error of the latest command the worker failed to carry out, until it is taken
+    last_error: Option<DataLogError>
+}
+
+impl QueueState {
+    fn count_dropped(&mut self, command: &Command) {
+        if let Command::Write { id, .. } = command {
+            *self.dropped.entry(id.entry_id()).or_default() += 1;
+        }
+    }
+}
+
+/// A bounded queue of commands that applies a [`QueuePolicy`] when full
+#[derive(Debug)]
+struct Queue {
+    state: Mutex<QueueState>,
+    capacity: usize,
+    policy: QueuePolicy,
+    /// Signaled when a command is queued or the last handle is dropped
+    queued: Condvar,
+    /// Signaled when the worker takes a command or stops
+    taken: Condvar
+}
+
+impl Queue {
+    fn new(capacity: usize, policy: QueuePolicy) -> Self {
+        Self {
+            state: Mutex::new(QueueState {
+                commands: VecDeque::with_capacity(capacity),
+                handles: 0,
+                closed: false,
+                dropped: HashMap::new(),
+                failed: 0,
+                last_error: None
+            }),
+            capacity: capacity.max(1),
+            policy,
+            queued: Condvar::new(),
+            taken: Condvar::new()
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, QueueState> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn push(&self, command: Command) -> Result<(), DataLogError> {
+        // entries and stopping wait for room no matter the policy, callers wait on them anyways
+        let wait = self.policy == QueuePolicy::Block || matches!(command, Command::Entry { .. } | Command::Stop);
+        let mut state = self.lock();
+        while !state.closed && state.commands.len() >= self.capacity {
+            if wait {
+                state = self.taken.wait(state).unwrap_or_else(PoisonError::into_inner);
+                continue;
+            }
+            let oldest = (self.policy == QueuePolicy::DropOldest && matches!(command, Command::Write { .. }))
+                .then(|| state.commands.iter().position(|queued| matches!(queued, Command::Write { .. })))
+                .flatten();
+            let Some(dropped) = oldest.and_then(|oldest| state.commands.remove(oldest)) else {
+                state.count_dropped(&command);
+                return Err(DataLogError::BackgroundWriterFull);
+            };
+            state.count_dropped(&dropped);
+        }
+        if state.closed {
+            return Err(DataLogError::BackgroundWriterClosed);
+        }
+        state.commands.push_back(command);
+        drop(state);
+        self.queued.notify_one();
+        Ok(())
+    }
+
+    /// Waits for the next command, [`None`] once every handle is dropped
+    fn pop(&self) -> Option<Command> {
+        let mut state = self.lock();
+        loop {
+            if let Some(command) = state.commands.pop_front() {
+                drop(state);
+                self.taken.notify_all();
+                return Some(command);
+            }
+            if state.handles == 0 {
+                return None;
+            }
+            state = self.queued.wait(state).unwrap_or_else(PoisonError::into_inner);
+        }
+    }
+
+    /// Keeps the error of a command that failed for the handles to take
+    fn report(&self, error: DataLogError) {
+        let mut state = self.lock();
+        state.failed += 1;
+        state.last_error = Some(error);
+    }
+
+    /// Stops taking commands, dropping the ones left so callers waiting on replies are released
+    fn close(&self) {
+        let mut state = self.lock();
+        state.closed = true;
+        state.commands.clear();
+        drop(state);
+        self.taken.notify_all();
+    }
+}
+
+/// A datalog writer that serializes and writes records on its own thread
+/// 
+/// Values are queued through cheap, non-blocking [`BackgroundWriterHandle`]s
+/// so robot loop code never waits on IO, what happens when the queue is full is set by a [`QueuePolicy`].
+/// A command the worker fails to carry out, like a value of the wrong type, doesn't stop it,
+/// the error is kept for [`BackgroundWriterHandle::take_error`] and the worker goes on with the next command.
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::background_writer::BackgroundDataLogWriter;
+/// 
+/// let writer = BackgroundDataLogWriter::new(File::create("path/to/file").unwrap(), "")
+///     .expect("Failed to create writer");
+/// let handle = writer.handle();
+/// 
+/// let entry = handle.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// handle.append(entry, 10.0).expect("Failed to queue value");
+/// 
+/// let file = writer.finish().expect("Failed to finish log");
+/// ```
+#[derive(Debug)]
+pub struct BackgroundDataLogWriter<W: Write + Send + 'static> {
+    handle: BackgroundWriterHandle,
+    worker: JoinHandle<Result<W, DataLogError>>
+}
+
+impl <W: Write + Send + 'static> BackgroundDataLogWriter<W> {
+    /// Creates a new background writer and starts its worker thread
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs or the thread can't be spawned
+    pub fn new(sink: W, metadata: impl ToString) -> Result<Self, DataLogError> {
+        Self::with_capacity(sink, metadata, DEFAULT_QUEUE_CAPACITY)
+    }
+
+    /// Creates a new background writer that can queue up to `capacity` commands
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs or the thread can't be spawned
+    pub fn with_capacity(sink: W, metadata: impl ToString, capacity: usize) -> Result<Self, DataLogError> {
+        Self::with_policy(sink, metadata, capacity, QueuePolicy::default())
+    }
+
+    /// Creates a new background writer that can queue up to `capacity` commands
+    /// and applies `policy` to values queued while it is full
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs or the thread can't be spawned
+    pub fn with_policy(sink: W, metadata: impl ToString, capacity: usize, policy: QueuePolicy) -> Result<Self, DataLogError> {
+        let writer = DataLogWriter::new(sink, metadata)?;
+        let handle = BackgroundWriterHandle::new(Arc::new(Queue::new(capacity, policy)));
+        let queue = Arc::clone(&handle.queue);
+        let worker = std::thread::Builder::new()
+            .name("datalog-writer".to_string())
+            .spawn(move || {
+                let result = run_worker(writer, &queue);
+                queue.close();
+                result
+            })?;
+        Ok(Self {
+            handle,
+            worker
+        })
+    }
+
+    /// Gets a handle that can queue values from any thread
+    #[must_use]
+    pub fn handle(&self) -> BackgroundWriterHandle {
+        self.handle.clone()
+    }
+
+    /// Waits for everything queued to be written, flushes and returns the underlying sink
+    /// 
+    /// Handles that are still alive will fail with [`DataLogError::BackgroundWriterClosed`] afterwards.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if the final flush fails
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker thread panicked
+    pub fn finish(self) -> Result<W, DataLogError> {
+        // the push only fails once the worker has stopped, then join returns why
+        let _ = self.handle.queue.push(Command::Stop);
+        self.worker.join().map_err(|_| DataLogError::BackgroundWriterClosed)?
+    }
+}
+
+fn run_worker<W: Write>(mut writer: DataLogWriter<W>, queue: &Queue) -> Result<W, DataLogError> {
+    while let Some(command) = queue.pop() {
+        let result = match command {
+            // entry errors go back to the caller who is waiting on them
+            Command::Entry { key, entry_type, metadata, reply } => {
+                let _ = reply.send(writer.get_entry_dynamic(&key, entry_type, metadata));
+                Ok(())
+            }
+            Command::Write { id, value, check_type } => writer.write_queued(id, value, check_type),
+            Command::Close(id) => writer.close_entry(id),
+            Command::Pause(true) => {
+                writer.pause();
+                Ok(())
+            }
+            Command::Pause(false) => {
+                writer.resume();
+                Ok(())
+            }
+            Command::Flush => writer.flush(),
+            Command::Stop => break
+        };
+        // one failed command doesn't lose the ones queued after it
+        if let Err(error) = result {
+            queue.report(error);
+        }
+    }
+    writer.into_inner()
+}
+
+/// A cheap, cloneable handle for queueing values to a [`BackgroundDataLogWriter`]
+/// 
+/// Appends only block with [`QueuePolicy::Block`], otherwise a full queue drops a value,
+/// with [`QueuePolicy::DropNewest`] the append fails with [`DataLogError::BackgroundWriterFull`].
+#[derive(Debug)]
+pub struct BackgroundWriterHandle {
+    queue: Arc<Queue>
+}
+
+impl Clone for BackgroundWriterHandle {
+    fn clone(&self) -> Self {
+        Self::new(Arc::clone(&self.queue))
+    }
+}
+
+impl Drop for BackgroundWriterHandle {
+    fn drop(&mut self) {
+        let mut state = self.queue.lock();
+        state.handles -= 1;
+        drop(state);
+        // wakes the worker in case this was the last handle
+        self.queue.queued.notify_all();
+    }
+}
+
+impl BackgroundWriterHandle {
+    fn new(queue: Arc<Queue>) -> Self {
+        queue.lock().handles += 1;
+        Self {
+            queue
+        }
+    }
+
+    fn send(&self, command: Command) -> Result<(), DataLogError> {
+        self.queue.push(command)
+    }
+
+    /// The number of values of an entry dropped because the queue was full
+    #[must_use]
+    pub fn dropped(&self, id: impl Into<EntryId>) -> u64 {
+        self.queue.lock().dropped.get(&id.into().entry_id()).copied().unwrap_or_default()
+    }
+
+    /// The number of values of every entry dropped because the queue was full
+    #[must_use]
+    pub fn total_dropped(&self) -> u64 {
+        self.queue.lock().dropped.values().sum()
+    }
+
+    /// The number of queued commands the worker failed to carry out
+    #[must_use]
+    pub fn failed(&self) -> u64 {
+        self.queue.lock().failed
+    }
+
+    /// Takes the error of the latest queued command the worker failed to carry out,
+    /// [`None`] if no command failed since the last time it was taken
+    #[must_use]
+    pub fn take_error(&self) -> Option<DataLogError> {
+        self.queue.lock().last_error.take()
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist
+    /// 
+    /// This waits for the worker to reply so it should be done during setup, not in a loop.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn get_entry_dynamic(&self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        let (reply, response) = mpsc::sync_channel(1);
+        self.send(Command::Entry {
+            key: key.to_string(),
+            entry_type,
+            metadata,
+            reply
+        })?;
+        response.recv().map_err(|_| DataLogError::BackgroundWriterClosed)?
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`BackgroundWriterHandle::get_entry_dynamic`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    pub fn get_entry<T: StaticallyFrcTyped>(&self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.get_entry_dynamic(key, T::TYPE, metadata).map(EntryId::typed::<T>)
+    }
+
+    /// Queues a value timestamped with the current time
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the queue is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    #[inline]
+    pub fn append<T: IntoFrcValue>(&self, id: TypedEntryId<T>, value: T) -> Result<(), DataLogError> {
+        self.append_timestamped(id, value, now())
+    }
+
+    /// Queues a value with a specific timestamp
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the queue is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    #[inline]
+    pub fn append_timestamped<T: IntoFrcValue>(&self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.send(Command::Write {
+            id: id.into(),
+            value: value.into_frc_value().to_timestamped(timestamp),
+            check_type: false
+        })
+    }
+
+    /// Queues a value, the type is checked against the entry on the worker
+    /// and a mismatch is reported through [`BackgroundWriterHandle::take_error`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the queue is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    pub fn append_dynamic(&self, id: EntryId, value: FrcTimestampedValue) -> Result<(), DataLogError> {
+        self.send(Command::Write {
+            id,
+            value,
+            check_type: true
+        })
+    }
+
+    /// Queues closing an entry
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the queue is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    pub fn close_entry(&self, id: EntryId) -> Result<(), DataLogError> {
+        self.send(Command::Close(id))
+    }
+
+    /// Queues pausing the writer, values queued after this are dropped, see [`DataLogWriter::pause`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the queue is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    pub fn pause(&self) -> Result<(), DataLogError> {
+        self.send(Command::Pause(true))
+    }
+
+    /// Queues resuming the writer after [`BackgroundWriterHandle::pause`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the queue is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    pub fn resume(&self) -> Result<(), DataLogError> {
+        self.send(Command::Pause(false))
+    }
+
+    /// Queues a flush of the underlying sink
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the queue is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the worker has stopped
+    pub fn flush(&self) -> Result<(), DataLogError> {
+        self.send(Command::Flush)
+    }
+}
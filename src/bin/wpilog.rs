@@ -0,0 +1,234 @@
+//! # wpilog
+//!
+//! A command line tool for inspecting and converting logs, built with the `cli` feature
+
+use std::{collections::HashMap, error::Error, fs::File, io::{BufWriter, Write}, process::ExitCode};
+
+use frclib_datalog::{
+    export::{csv::{write_csv, CsvExportConfig}, influx::{write_influx_lines, InfluxExportConfig}, json::{write_json, JsonExportConfig, JsonLayout}},
+    reader::DataLogReaderConfig, rewrite, time::{format_clock, format_seconds, from_seconds}, DataLogReader
+};
+
+const USAGE: &str = "\
+usage: wpilog <command> [args]
+
+commands:
+    info <log>                          summarize the header, entries and time span of a log
+    entries <log>                       list the entries of a log with their types and value counts
+    dump <log> <key>                    print every value of an entry
+    export <log> --format <format>      write entries as csv, json, jsonl, influx, arrow or parquet
+        [--keys <key,key>] [--output <file>]
+    validate <log>                      check that every record of a log can be read
+    trim <log> --start <seconds> --end <seconds> --output <file>
+                                        keep only the values between two times
+    merge <log> <log>... --output <file>
+                                        combine the entries of logs recorded at the same time
+    concat <log> <log> [--offset <seconds>] --output <file>
+                                        append a log after another, like after a reboot
+    rename <log> <from>=<to>... --output <file>
+                                        rename entries by key or by prefix, like /old/*=/new/*";
+
+type CliResult = Result<(), Box<dyn Error>>;
+
+/// The arguments after the command, split into positional arguments and `--name value` options
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, String>
+}
+
+impl Args {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        while let Some(arg) = args.next() {
+            let name = match arg.as_str() {
+                "-o" => "output",
+                arg => match arg.strip_prefix("--") {
+                    Some(name) => name,
+                    None => {
+                        positional.push(arg.to_string());
+                        continue;
+                    }
+                }
+            };
+            let value = args.next().ok_or_else(|| format!("--{name} needs a value"))?;
+            let _ = options.insert(name.to_string(), value);
+        }
+        Ok(Self { positional, options })
+    }
+
+    fn positional(&self, index: usize, name: &str) -> Result<&str, String> {
+        self.positional.get(index).map(String::as_str).ok_or_else(|| format!("missing <{name}>"))
+    }
+
+    fn option(&self, name: &str) -> Result<&str, String> {
+        self.options.get(name).map(String::as_str).ok_or_else(|| format!("missing --{name}"))
+    }
+
+    fn seconds(&self, name: &str) -> Result<u64, String> {
+        self.option(name)?.parse::<f64>()
+            .map(from_seconds)
+            .map_err(|_| format!("--{name} must be a number of seconds"))
+    }
+}
+
+fn read_log(path: &str) -> Result<DataLogReader, Box<dyn Error>> {
+    let bytes = std::fs::read(path)?;
+    Ok(DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())?)
+}
+
+fn sorted_keys(reader: &DataLogReader) -> Vec<&str> {
+    let mut keys = reader.get_all_entry_keys().into_iter().map(String::as_str).collect::<Vec<_>>();
+    keys.sort_unstable();
+    keys
+}
+
+fn create_output(path: &str) -> Result<BufWriter<File>, Box<dyn Error>> {
+    Ok(BufWriter::new(File::create(path)?))
+}
+
+fn info(args: &Args) -> CliResult {
+    let reader = read_log(args.positional(0, "log")?)?;
+    let keys = sorted_keys(&reader);
+    let values = keys.iter().map(|key| reader.read_entry(key)).collect::<Vec<_>>();
+    let timestamps = values.iter().flatten().map(|value| value.timestamp);
+    println!("metadata: {}", reader.get_header_metadata());
+    println!("entries:  {}", keys.len());
+    println!("values:   {}", values.iter().map(Vec::len).sum::<usize>());
+    if let (Some(first), Some(last)) = (timestamps.clone().min(), timestamps.max()) {
+        println!("span:     {} - {}", format_clock(first), format_clock(last));
+    }
+    Ok(())
+}
+
+fn entries(args: &Args) -> CliResult {
+    let reader = read_log(args.positional(0, "log")?)?;
+    let mut out = std::io::stdout().lock();
+    for key in sorted_keys(&reader) {
+        let type_str = reader.read_entry_type_str(key).last().map(|type_str| type_str.value.clone()).unwrap_or_default();
+        writeln!(out, "{key}\t{type_str}\t{}", reader.read_entry(key).len())?;
+    }
+    Ok(())
+}
+
+fn dump(args: &Args) -> CliResult {
+    let reader = read_log(args.positional(0, "log")?)?;
+    let key = args.positional(1, "key")?;
+    if !reader.get_all_entry_keys().iter().any(|existing| *existing == key) {
+        return Err(format!("no entry {key}").into());
+    }
+    let mut out = std::io::stdout().lock();
+    for value in reader.read_entry(key) {
+        writeln!(out, "{}\t{}", format_seconds(value.timestamp), value.value)?;
+    }
+    Ok(())
+}
+
+fn export(args: &Args) -> CliResult {
+    let reader = read_log(args.positional(0, "log")?)?;
+    let keys = match args.options.get("keys") {
+        Some(keys) => keys.split(',').collect(),
+        None => sorted_keys(&reader)
+    };
+    let out: Box<dyn Write + Send> = match args.options.get("output") {
+        Some(path) => Box::new(create_output(path)?),
+        None => Box::new(std::io::stdout())
+    };
+    match args.option("format")? {
+        "csv" => write_csv(&reader, &keys, CsvExportConfig::default(), out)?,
+        "json" => write_json(&reader, &keys, JsonExportConfig::default(), out)?,
+        "jsonl" => write_json(&reader, &keys, JsonExportConfig { layout: JsonLayout::Lines, ..Default::default() }, out)?,
+        "influx" => write_influx_lines(&reader, &keys, InfluxExportConfig::default(), out)?,
+        #[cfg(feature = "arrow")]
+        "arrow" => frclib_datalog::export::arrow::write_arrow_ipc(&reader, &keys, Default::default(), out)?,
+        #[cfg(feature = "parquet")]
+        "parquet" => frclib_datalog::export::parquet::write_parquet(&reader, &keys, Default::default(), out)?,
+        #[cfg(not(feature = "arrow"))]
+        "arrow" => return Err("arrow export needs the arrow feature".into()),
+        #[cfg(not(feature = "parquet"))]
+        "parquet" => return Err("parquet export needs the parquet feature".into()),
+        format => return Err(format!("unknown format {format}").into())
+    }
+    Ok(())
+}
+
+fn validate(args: &Args) -> CliResult {
+    let bytes = std::fs::read(args.positional(0, "log")?)?;
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())?;
+    #[cfg(feature = "integrity")]
+    {
+        let report = frclib_datalog::integrity::verify_checkpoints(&bytes)?;
+        if !report.is_intact() {
+            return Err(format!("checkpoints failed at byte offsets {:?}", report.failed).into());
+        }
+    }
+    println!("ok: {} entries", reader.get_all_entry_keys().len());
+    Ok(())
+}
+
+fn trim(args: &Args) -> CliResult {
+    let log = std::fs::read(args.positional(0, "log")?)?;
+    let out = create_output(args.option("output")?)?;
+    rewrite::trim(&log, args.seconds("start")?, args.seconds("end")?, out)?.flush()?;
+    Ok(())
+}
+
+fn merge(args: &Args) -> CliResult {
+    if args.positional.len() < 2 {
+        return Err("merge needs at least two logs".into());
+    }
+    let logs = args.positional.iter().map(std::fs::read).collect::<Result<Vec<_>, _>>()?;
+    let out = create_output(args.option("output")?)?;
+    rewrite::merge(&logs.iter().map(Vec::as_slice).collect::<Vec<_>>(), out)?.flush()?;
+    Ok(())
+}
+
+fn concat(args: &Args) -> CliResult {
+    let first = std::fs::read(args.positional(0, "log")?)?;
+    let second = std::fs::read(args.positional(1, "log")?)?;
+    let offset = args.options.contains_key("offset").then(|| args.seconds("offset")).transpose()?;
+    let out = create_output(args.option("output")?)?;
+    rewrite::concat(&first, &second, offset, out)?.flush()?;
+    Ok(())
+}
+
+fn rename(args: &Args) -> CliResult {
+    let log = std::fs::read(args.positional(0, "log")?)?;
+    let rules = args.positional.iter().skip(1)
+        .map(|rule| rule.split_once('=').ok_or_else(|| format!("{rule} isn't a <from>=<to> rule")))
+        .collect::<Result<Vec<_>, _>>()?;
+    let out = create_output(args.option("output")?)?;
+    rewrite::rename(&log, &rules, out)?.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(command) = args.next() else {
+        eprintln!("{USAGE}");
+        return ExitCode::FAILURE;
+    };
+    let result = Args::parse(args).map_err(Into::into).and_then(|args| match command.as_str() {
+        "info" => info(&args),
+        "entries" => entries(&args),
+        "dump" => dump(&args),
+        "export" => export(&args),
+        "validate" => validate(&args),
+        "trim" => trim(&args),
+        "merge" => merge(&args),
+        "concat" => concat(&args),
+        "rename" => rename(&args),
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        command => Err(format!("unknown command {command}\n\n{USAGE}").into())
+    });
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            eprintln!("wpilog: {error}");
+            ExitCode::FAILURE
+        }
+    }
+}
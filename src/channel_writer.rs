@@ -0,0 +1,102 @@
+use std::{collections::HashMap, io::Write, sync::{atomic::{AtomicU64, Ordering}, mpsc::Receiver, Arc}, thread::JoinHandle};
+
+use frclib_core::value::{FrcTimestampedValue, FrcValue};
+
+use crate::{writer::EntryId, DataLogError, DataLogWriter};
+
+/// A key and the value to write to it, sent to a [`ChannelDataLogWriter`]
+pub type KeyedValue = (String, FrcTimestampedValue);
+
+/// A writer driven by a channel on its own thread, so producers log by sending `(key, value)` pairs
+///
+/// Entries are created with the type of the first value sent to their key.
+/// Values whose type doesn't match their entry, or whose entry is closed, are dropped and counted in
+/// [`ChannelDataLogWriter::dropped`]; any other error stops the thread and is returned from
+/// [`ChannelDataLogWriter::join`]. The thread stops once every sender is dropped.
+/// # Example
+/// ```rust,no_run
+/// use std::sync::mpsc;
+/// use frclib_core::value::{FrcTimestampedValue, FrcValue};
+/// use frclib_datalog::{channel_writer::ChannelDataLogWriter, DataLogWriter};
+///
+/// let writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create writer");
+/// let (sender, receiver) = mpsc::channel();
+/// let consumer = ChannelDataLogWriter::new(writer, receiver).expect("Failed to start consumer");
+///
+/// sender.send(("/arm/angle".to_string(), FrcTimestampedValue::new(1_000, FrcValue::Double(0.5))))
+///     .expect("Consumer stopped");
+/// drop(sender);
+/// consumer.join().expect("Failed to log").close().expect("Failed to finish log");
+/// ```
+#[derive(Debug)]
+pub struct ChannelDataLogWriter<W: Write + Send + 'static> {
+    worker: JoinHandle<Result<DataLogWriter<W>, DataLogError>>,
+    dropped: Arc<AtomicU64>
+}
+
+impl <W: Write + Send + 'static> ChannelDataLogWriter<W> {
+    /// Starts a thread writing every value received on `receiver` to `writer`
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if the thread can't be spawned
+    pub fn new(writer: DataLogWriter<W>, receiver: Receiver<KeyedValue>) -> Result<Self, DataLogError> {
+        let dropped = Arc::new(AtomicU64::new(0));
+        let worker_dropped = Arc::clone(&dropped);
+        let worker = std::thread::Builder::new()
+            .name("datalog-channel".to_string())
+            .spawn(move || run_worker(writer, &receiver, &worker_dropped))?;
+        Ok(Self {
+            worker,
+            dropped
+        })
+    }
+
+    /// The number of values dropped as their type didn't match their entry or their entry was closed
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Whether the thread has stopped, after every sender was dropped or on an error
+    #[must_use]
+    pub fn is_finished(&self) -> bool {
+        self.worker.is_finished()
+    }
+
+    /// Waits for every sender to be dropped and the values sent to be written, returning the writer
+    ///
+    /// # Errors
+    /// - Any error the thread stopped on
+    /// - [`DataLogError::BackgroundWriterClosed`] if the thread panicked
+    pub fn join(self) -> Result<DataLogWriter<W>, DataLogError> {
+        self.worker.join().map_err(|_| DataLogError::BackgroundWriterClosed)?
+    }
+}
+
+fn run_worker<W: Write>(
+    mut writer: DataLogWriter<W>,
+    receiver: &Receiver<KeyedValue>,
+    dropped: &AtomicU64
+) -> Result<DataLogWriter<W>, DataLogError> {
+    let mut ids: HashMap<String, EntryId> = HashMap::new();
+    while let Ok((key, value)) = receiver.recv() {
+        if value.value == FrcValue::Void {
+            continue;
+        }
+        let written = match ids.get(&key) {
+            Some(id) => writer.write_dynamic(*id, value),
+            None => writer.get_entry_dynamic(&key, value.value.get_type(), None)
+                .and_then(|id| {
+                    let _ = ids.insert(key, id);
+                    writer.write_dynamic(id, value)
+                })
+        };
+        match written {
+            Err(DataLogError::EntryTypeMismatch | DataLogError::OutsideEntryLifetime) => {
+                let _ = dropped.fetch_add(1, Ordering::Relaxed);
+            }
+            result => result?
+        }
+    }
+    Ok(writer)
+}
@@ -0,0 +1,179 @@
+use std::{fmt::Debug, fs::File, io::Write};
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, IntoFrcValue, StaticallyFrcTyped};
+
+use crate::{writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+/// The most errors a [`DeferredDataLogWriter`] keeps, later errors are only counted
+pub const MAX_DEFERRED_ERRORS: usize = 32;
+
+type ErrorCallback = Box<dyn FnMut(&DataLogError) + Send>;
+
+/// A datalog writer whose appends never return an error
+///
+/// Errors from appending, closing entries and flushing are captured instead,
+/// either kept to be queried with [`DeferredDataLogWriter::errors`]
+/// or handed to a callback as they happen.
+/// Creating entries still returns errors as that is usually done once at startup.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{deferred_writer::DeferredDataLogWriter, DataLogWriter};
+///
+/// let writer = DataLogWriter::create("logs/match.wpilog", "").expect("Failed to create writer");
+/// let mut writer = DeferredDataLogWriter::with_callback(writer, |err| eprintln!("Logging failed: {err}"));
+///
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0);
+/// ```
+pub struct DeferredDataLogWriter<W: Write = File> {
+    writer: DataLogWriter<W>,
+    errors: Vec<DataLogError>,
+    error_count: u64,
+    callback: Option<ErrorCallback>
+}
+impl <W: Write + Debug> Debug for DeferredDataLogWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DeferredDataLogWriter")
+            .field("writer", &self.writer)
+            .field("errors", &self.errors)
+            .field("error_count", &self.error_count)
+            .finish_non_exhaustive()
+    }
+}
+
+impl <W: Write> From<DataLogWriter<W>> for DeferredDataLogWriter<W> {
+    fn from(writer: DataLogWriter<W>) -> Self {
+        Self::new(writer)
+    }
+}
+
+impl <W: Write> DeferredDataLogWriter<W> {
+    /// Wraps a writer, keeping up to [`MAX_DEFERRED_ERRORS`] errors
+    #[must_use]
+    pub const fn new(writer: DataLogWriter<W>) -> Self {
+        Self {
+            writer,
+            errors: Vec::new(),
+            error_count: 0,
+            callback: None
+        }
+    }
+
+    /// Wraps a writer, calling `callback` with every error instead of keeping them
+    #[must_use]
+    pub fn with_callback(writer: DataLogWriter<W>, callback: impl FnMut(&DataLogError) + Send + 'static) -> Self {
+        Self {
+            callback: Some(Box::new(callback)),
+            ..Self::new(writer)
+        }
+    }
+
+    fn capture(&mut self, result: Result<(), DataLogError>) {
+        if let Err(err) = result {
+            self.error_count += 1;
+            if let Some(callback) = self.callback.as_mut() {
+                callback(&err);
+            } else if self.errors.len() < MAX_DEFERRED_ERRORS {
+                self.errors.push(err);
+            }
+        }
+    }
+
+    /// The errors kept since they were last taken, oldest first
+    #[must_use]
+    pub fn errors(&self) -> &[DataLogError] {
+        &self.errors
+    }
+
+    /// Takes the kept errors, leaving none
+    pub fn take_errors(&mut self) -> Vec<DataLogError> {
+        std::mem::take(&mut self.errors)
+    }
+
+    /// The number of errors that have occurred, including ones that were
+    /// given to the callback or not kept
+    #[must_use]
+    pub const fn error_count(&self) -> u64 {
+        self.error_count
+    }
+
+    /// The wrapped writer
+    #[must_use]
+    pub const fn writer(&self) -> &DataLogWriter<W> {
+        &self.writer
+    }
+
+    /// The wrapped writer, for operations whose errors should be handled
+    pub const fn writer_mut(&mut self) -> &mut DataLogWriter<W> {
+        &mut self.writer
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry<T: StaticallyFrcTyped>(&mut self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.writer.get_entry(key, metadata)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry_dynamic`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry_dynamic(&mut self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.writer.get_entry_dynamic(key, entry_type, metadata)
+    }
+
+    /// Writes a value, capturing any error, see [`DataLogWriter::write`]
+    pub fn write<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T) {
+        let result = self.writer.write(id, value);
+        self.capture(result);
+    }
+
+    /// Writes a value with a specific timestamp, capturing any error,
+    /// see [`DataLogWriter::write_timestamped`]
+    pub fn write_timestamped<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) {
+        let result = self.writer.write_timestamped(id, value, timestamp);
+        self.capture(result);
+    }
+
+    /// Writes a value, capturing any error, see [`DataLogWriter::write_dynamic`]
+    pub fn write_dynamic(&mut self, id: EntryId, value: FrcTimestampedValue) {
+        let result = self.writer.write_dynamic(id, value);
+        self.capture(result);
+    }
+
+    /// Replaces the metadata of an entry, capturing any error,
+    /// see [`DataLogWriter::set_entry_metadata`]
+    pub fn set_entry_metadata(&mut self, id: EntryId, metadata: impl ToString) {
+        let result = self.writer.set_entry_metadata(id, metadata);
+        self.capture(result);
+    }
+
+    /// Closes an entry, capturing any error, see [`DataLogWriter::close_entry`]
+    pub fn close_entry(&mut self, id: EntryId) {
+        let result = self.writer.close_entry(id);
+        self.capture(result);
+    }
+
+    /// Flushes the writer, capturing any error, see [`DataLogWriter::flush`]
+    pub fn flush(&mut self) {
+        let result = self.writer.flush();
+        self.capture(result);
+    }
+
+    /// Returns the wrapped writer, dropping any kept errors
+    #[must_use]
+    pub fn into_inner(self) -> DataLogWriter<W> {
+        self.writer
+    }
+}
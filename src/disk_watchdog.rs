@@ -0,0 +1,112 @@
+use std::{fmt::Debug, path::PathBuf, time::{Duration, Instant}};
+
+use crate::DataLogError;
+
+/// How often a [`DiskSpaceWatchdog`] checks the free space by default
+pub const DEFAULT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+type LowSpaceCallback = Box<dyn FnMut(u64) + Send>;
+
+/// What to do when the free space drops below the threshold of a [`DiskSpaceWatchdog`]
+pub enum LowDiskSpaceAction {
+    /// Sync the current file to disk and ignore writes from then on,
+    /// its entries are finished when the writer is closed
+    StopLogging,
+    /// Start the next file, useful when the naming function can move to another drive
+    Rotate,
+    /// Call the callback with the free space in bytes and keep logging
+    Callback(LowSpaceCallback)
+}
+impl Debug for LowDiskSpaceAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::StopLogging => write!(f, "StopLogging"),
+            Self::Rotate => write!(f, "Rotate"),
+            Self::Callback(_) => write!(f, "Callback")
+        }
+    }
+}
+
+/// Watches the free space of the filesystem a log is written to,
+/// so a full log can't fill the flash and break deploys
+///
+/// The action is taken once each time the free space drops below the threshold.
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use frclib_datalog::{disk_watchdog::{DiskSpaceWatchdog, LowDiskSpaceAction}, rotating_writer::RotatingDataLogWriter};
+///
+/// let watchdog = DiskSpaceWatchdog::new("/home/lvuser/logs", 50 * 1024 * 1024, LowDiskSpaceAction::StopLogging);
+/// let mut writer = RotatingDataLogWriter::new(
+///     |index| format!("/home/lvuser/logs/match_{index}.wpilog").into(),
+///     "",
+///     Duration::from_mins(10)
+/// ).expect("Failed to create writer").with_disk_watchdog(watchdog);
+/// ```
+#[derive(Debug)]
+pub struct DiskSpaceWatchdog {
+    path: PathBuf,
+    min_free_bytes: u64,
+    check_interval: Duration,
+    last_check: Option<Instant>,
+    low: bool,
+    action: LowDiskSpaceAction
+}
+
+impl DiskSpaceWatchdog {
+    /// Creates a watchdog for the filesystem containing `path`
+    /// that takes `action` once less than `min_free_bytes` are available
+    pub fn new(path: impl Into<PathBuf>, min_free_bytes: u64, action: LowDiskSpaceAction) -> Self {
+        Self {
+            path: path.into(),
+            min_free_bytes,
+            check_interval: DEFAULT_CHECK_INTERVAL,
+            last_check: None,
+            low: false,
+            action
+        }
+    }
+
+    /// Sets how often the free space is checked, defaults to [`DEFAULT_CHECK_INTERVAL`]
+    #[must_use]
+    pub const fn with_check_interval(mut self, check_interval: Duration) -> Self {
+        self.check_interval = check_interval;
+        self
+    }
+
+    /// The bytes available to the process on the watched filesystem
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if the filesystem can't be queried
+    pub fn available_space(&self) -> Result<u64, DataLogError> {
+        Ok(fs4::available_space(&self.path)?)
+    }
+
+    /// Whether the free space was below the threshold at the last check
+    #[must_use]
+    pub const fn is_low(&self) -> bool {
+        self.low
+    }
+
+    /// The action taken when the free space drops below the threshold
+    pub const fn action_mut(&mut self) -> &mut LowDiskSpaceAction {
+        &mut self.action
+    }
+
+    /// Checks the free space if the check interval has passed,
+    /// returning the free space if it just dropped below the threshold
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if the filesystem can't be queried
+    pub fn poll(&mut self) -> Result<Option<u64>, DataLogError> {
+        if self.last_check.is_some_and(|last| last.elapsed() < self.check_interval) {
+            return Ok(None);
+        }
+        self.last_check = Some(Instant::now());
+
+        let available = self.available_space()?;
+        let was_low = self.low;
+        self.low = available < self.min_free_bytes;
+        Ok((self.low && !was_low).then_some(available))
+    }
+}
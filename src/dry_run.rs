@@ -0,0 +1,61 @@
+use std::io::Write;
+
+/// A sink that counts the bytes written through it, discarding them by default
+///
+/// A writer over the default sink runs every type check and encodes every record without producing a file,
+/// for checking logging code paths in simulation or CI and measuring how much a log would take.
+/// Create one with [`DataLogWriter::dry_run`](crate::DataLogWriter::dry_run).
+/// # Example
+/// ```rust
+/// use frclib_datalog::DataLogWriter;
+///
+/// let mut writer = DataLogWriter::dry_run("").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+/// println!("{} bytes", writer.bytes_written());
+/// ```
+#[derive(Debug, Default)]
+pub struct CountingSink<W: Write = std::io::Sink> {
+    inner: W,
+    bytes_written: u64
+}
+
+impl <W: Write> CountingSink<W> {
+    /// Counts the bytes written to `inner`
+    #[must_use]
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            bytes_written: 0
+        }
+    }
+
+    /// The number of bytes that reached the inner sink
+    #[must_use]
+    pub const fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    /// The inner sink
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Returns the inner sink
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl <W: Write> Write for CountingSink<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.bytes_written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,353 @@
+use std::io::Write;
+
+use frclib_core::value::{FrcTimestamp, FrcValue};
+
+use crate::{
+    proto::records::{header_len, parse_records, ControlRecord},
+    reader::DataLogReaderConfig, DataLogError, DataLogReader, DataLogWriter, Record
+};
+
+/// The only version of the `.dslog` and `.dsevents` formats this reads
+const DS_LOG_VERSION: i32 = 4;
+/// The length of the version and start time at the start of both files
+const DS_HEADER_LEN: usize = 20;
+/// Seconds between the `LabVIEW` epoch of 1904-01-01 the files count from and the unix epoch
+const LABVIEW_EPOCH_OFFSET: i64 = 2_082_844_800;
+/// The time between two `.dslog` records, in microseconds
+const DS_LOG_PERIOD: FrcTimestamp = 20_000;
+/// The length of a `.dslog` record without power distribution data
+const DS_LOG_RECORD_LEN: usize = 14;
+/// The key of the entry events are imported to
+pub const DS_EVENTS_KEY: &str = "/DSEvents";
+/// The entry holding the unix time in microseconds `WPILib`'s `DataLogManager` logs once the time is known
+const SYSTEM_TIME_KEY: &str = "systemTime";
+
+/// Reads one number channel of a [`DsLogRecord`]
+type NumberChannel = fn(&DsLogRecord) -> f64;
+
+/// The number channels of a [`DsLogRecord`] and the keys they are imported to
+const NUMBER_CHANNELS: [(&str, NumberChannel); 7] = [
+    ("/DSLog/TripTimeMS", |record| record.trip_time_ms),
+    ("/DSLog/PacketLoss", |record| record.packet_loss),
+    ("/DSLog/BatteryVoltage", |record| record.battery_voltage),
+    ("/DSLog/CPUUtilization", |record| record.cpu_utilization),
+    ("/DSLog/CANUtilization", |record| record.can_utilization),
+    ("/DSLog/WifiDb", |record| record.wifi_db),
+    ("/DSLog/WifiMb", |record| record.wifi_mb)
+];
+
+/// The status bits of a [`DsLogRecord`] and the keys they are imported to
+const STATUS_CHANNELS: [(&str, DsStatus); 8] = [
+    ("/DSLog/Status/Brownout", DsStatus::BROWNOUT),
+    ("/DSLog/Status/Watchdog", DsStatus::WATCHDOG),
+    ("/DSLog/Status/DSTeleop", DsStatus::DS_TELEOP),
+    ("/DSLog/Status/DSAuto", DsStatus::DS_AUTO),
+    ("/DSLog/Status/DSDisabled", DsStatus::DS_DISABLED),
+    ("/DSLog/Status/RobotTeleop", DsStatus::ROBOT_TELEOP),
+    ("/DSLog/Status/RobotAuto", DsStatus::ROBOT_AUTO),
+    ("/DSLog/Status/RobotDisabled", DsStatus::ROBOT_DISABLED)
+];
+
+bitflags! {
+    /// The state of the robot and Driver Station in a [`DsLogRecord`]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct DsStatus: u8 {
+        /// The roboRIO was in brownout
+        const BROWNOUT = 0x80;
+        /// The motor safety watchdog had disabled the outputs
+        const WATCHDOG = 0x40;
+        /// The Driver Station was in teleop
+        const DS_TELEOP = 0x20;
+        /// The Driver Station was in autonomous
+        const DS_AUTO = 0x10;
+        /// The Driver Station was disabled
+        const DS_DISABLED = 0x08;
+        /// The robot code reported teleop
+        const ROBOT_TELEOP = 0x04;
+        /// The robot code reported autonomous
+        const ROBOT_AUTO = 0x02;
+        /// The robot code reported disabled
+        const ROBOT_DISABLED = 0x01;
+    }
+}
+
+/// One 20ms sample of a `.dslog` file
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DsLogRecord {
+    /// The unix time of the sample in microseconds
+    pub timestamp: FrcTimestamp,
+    /// The round trip time of the control packets in milliseconds
+    pub trip_time_ms: f64,
+    /// The fraction of control packets lost, from 0 to 1
+    pub packet_loss: f64,
+    /// The battery voltage the roboRIO measured
+    pub battery_voltage: f64,
+    /// The roboRIO CPU utilization, from 0 to 1
+    pub cpu_utilization: f64,
+    /// The CAN bus utilization, from 0 to 1
+    pub can_utilization: f64,
+    /// The radio signal strength in dB
+    pub wifi_db: f64,
+    /// The radio bandwidth in Mb/s
+    pub wifi_mb: f64,
+    /// The state of the robot and Driver Station
+    pub status: DsStatus
+}
+
+/// The samples of a `.dslog` file
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DsLog {
+    /// The unix time the Driver Station started the file at in microseconds
+    pub start_time: FrcTimestamp,
+    /// The samples, 20ms apart
+    pub records: Vec<DsLogRecord>
+}
+
+/// One message of a `.dsevents` file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DsEvent {
+    /// The unix time of the event in microseconds
+    pub timestamp: FrcTimestamp,
+    /// The message, with the `<TagVersion>` style tags the Driver Station adds
+    pub text: String
+}
+
+/// What [`write_ds_logs`] wrote
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct DsImport {
+    /// The `.dslog` samples that were written
+    pub records: usize,
+    /// The events that were written
+    pub events: usize,
+    /// The samples and events before the start time, which a log can't hold
+    pub skipped: usize
+}
+
+/// Reads the version and start time both files begin with, returning the start time as unix microseconds
+fn read_ds_header(bytes: &[u8]) -> Result<FrcTimestamp, DataLogError> {
+    let header = bytes.get(..DS_HEADER_LEN)
+        .ok_or(DataLogError::RecordReaderOutOfBounds("Driver Station log header"))?;
+    let (version, time) = header.split_at(4);
+    if i32::from_be_bytes(version.try_into().map_err(|_| DataLogError::RecordDeserialize("Driver Station log version"))?) != DS_LOG_VERSION {
+        return Err(DataLogError::VersionMismatch);
+    }
+    read_labview_time(time)
+}
+
+/// Converts a `LabVIEW` timestamp, big endian seconds since 1904 followed by a fraction of 2^-64 seconds,
+/// to unix microseconds
+fn read_labview_time(bytes: &[u8]) -> Result<FrcTimestamp, DataLogError> {
+    let bytes: [u8; 16] = bytes.try_into()
+        .map_err(|_| DataLogError::RecordDeserialize("Driver Station timestamp"))?;
+    let (seconds, fraction) = bytes.split_at(8);
+    let seconds = i64::from_be_bytes(seconds.try_into().map_err(|_| DataLogError::RecordDeserialize("Driver Station timestamp"))?);
+    let fraction = u64::from_be_bytes(fraction.try_into().map_err(|_| DataLogError::RecordDeserialize("Driver Station timestamp"))?);
+    let seconds = seconds.checked_sub(LABVIEW_EPOCH_OFFSET)
+        .ok_or(DataLogError::RecordDeserialize("Driver Station timestamp"))?;
+    let seconds = u64::try_from(seconds)?;
+    let micros = u64::try_from((u128::from(fraction) * 1_000_000) >> 64)?;
+    Ok(seconds.saturating_mul(1_000_000).saturating_add(micros))
+}
+
+/// Parses a `.dslog` file, the 50Hz telemetry the Driver Station records while connected to a robot
+///
+/// Power distribution currents are skipped. A record cut off at the end of the file,
+/// like the last one of a file the Driver Station is still writing, is left out.
+///
+/// # Errors
+/// - [`DataLogError::RecordReaderOutOfBounds`] if the file is shorter than its header
+/// - [`DataLogError::VersionMismatch`] if the file isn't version 4
+/// - [`DataLogError::IntCast`] if the start time is before the unix epoch
+/// - [`DataLogError::RecordDeserialize`] if the start time is out of range, like in a corrupt file
+pub fn parse_dslog(bytes: &[u8]) -> Result<DsLog, DataLogError> {
+    let start_time = read_ds_header(bytes)?;
+    let mut records = Vec::new();
+    let mut offset = DS_HEADER_LEN;
+    let mut timestamp = start_time;
+    while let Some(record) = bytes.get(offset..).and_then(|rest| rest.get(..DS_LOG_RECORD_LEN)) {
+        let Ok([trip_time, packet_loss, battery_high, battery_low, cpu, status, can, wifi_db, wifi_mb_high, wifi_mb_low, _, _, _, pd_type]) = <[u8; DS_LOG_RECORD_LEN]>::try_from(record) else {
+            break;
+        };
+        let pd_len = match pd_type {
+            // REV Power Distribution Hub
+            33 => 36,
+            // CTRE Power Distribution Panel
+            25 => 25,
+            _ => 0
+        };
+        let len = DS_LOG_RECORD_LEN + pd_len;
+        if bytes.len() < offset + len {
+            break;
+        }
+        records.push(DsLogRecord {
+            timestamp,
+            trip_time_ms: f64::from(trip_time) * 0.5,
+            packet_loss: (f64::from(i8::from_be_bytes([packet_loss])) * 0.04).clamp(0.0, 1.0),
+            battery_voltage: f64::from(u16::from_be_bytes([battery_high, battery_low])) / 256.0,
+            cpu_utilization: f64::from(cpu) * 0.005,
+            can_utilization: f64::from(can) * 0.005,
+            wifi_db: f64::from(wifi_db) * 0.5,
+            wifi_mb: f64::from(u16::from_be_bytes([wifi_mb_high, wifi_mb_low])) / 256.0,
+            // the bits are set while the state is off
+            status: DsStatus::from_bits_retain(!status)
+        });
+        offset += len;
+        timestamp += DS_LOG_PERIOD;
+    }
+    Ok(DsLog { start_time, records })
+}
+
+/// Parses a `.dsevents` file, the messages the Driver Station shows in its console
+///
+/// An event cut off at the end of the file is left out.
+///
+/// # Errors
+/// - [`DataLogError::RecordReaderOutOfBounds`] if the file is shorter than its header
+/// - [`DataLogError::VersionMismatch`] if the file isn't version 4
+/// - [`DataLogError::IntCast`] if a timestamp is before the unix epoch
+/// - [`DataLogError::RecordDeserialize`] if a timestamp is out of range, like in a corrupt file
+pub fn parse_dsevents(bytes: &[u8]) -> Result<Vec<DsEvent>, DataLogError> {
+    let _ = read_ds_header(bytes)?;
+    let mut events = Vec::new();
+    let mut rest = bytes.get(DS_HEADER_LEN..).unwrap_or_default();
+    while let Some((time, after_time)) = rest.split_at_checked(16) {
+        let Some((len, after_len)) = after_time.split_at_checked(4) else {
+            break;
+        };
+        let len = usize::try_from(i32::from_be_bytes(len.try_into().map_err(|_| DataLogError::RecordDeserialize("Driver Station event length"))?))?;
+        let Some((text, after_text)) = after_len.split_at_checked(len) else {
+            break;
+        };
+        events.push(DsEvent {
+            timestamp: read_labview_time(time)?,
+            text: String::from_utf8_lossy(text).trim().to_string()
+        });
+        rest = after_text;
+    }
+    Ok(events)
+}
+
+/// Writes the channels of a `.dslog` and the messages of a `.dsevents` file into a log
+///
+/// Numbers go to `/DSLog/BatteryVoltage`, `/DSLog/TripTimeMS`, `/DSLog/CPUUtilization` and the other
+/// `/DSLog/...` doubles every sample, the status bits to `/DSLog/Status/...` booleans whenever they change
+/// and events to the [`DS_EVENTS_KEY`] string entry.
+///
+/// Both files are stamped with unix time while logs count from robot start, so `start_time` is the
+/// unix time in microseconds timestamp 0 of the log is at, see [`robot_start_time`] for finding it in a robot log.
+/// Samples and events before it are skipped. Without it the earliest sample or event is timestamp 0.
+///
+/// # Errors
+/// - [`DataLogError::EntryTypeMismatch`] if the log already has one of the entries with another type
+/// - Any error of [`DataLogWriter::write_timestamped`]
+pub fn write_ds_logs<W: Write>(
+    writer: &mut DataLogWriter<W>,
+    log: Option<&DsLog>,
+    events: &[DsEvent],
+    start_time: Option<FrcTimestamp>
+) -> Result<DsImport, DataLogError> {
+    let start_time = start_time.unwrap_or_else(|| {
+        log.map(|log| log.start_time).into_iter()
+            .chain(events.iter().map(|event| event.timestamp))
+            .min()
+            .unwrap_or_default()
+    });
+    let mut import = DsImport::default();
+
+    if let Some(log) = log {
+        let mut numbers = Vec::with_capacity(NUMBER_CHANNELS.len());
+        for (key, value) in NUMBER_CHANNELS {
+            numbers.push((writer.get_entry::<f64>(key, None)?, value));
+        }
+        let mut statuses = Vec::with_capacity(STATUS_CHANNELS.len());
+        for (key, flag) in STATUS_CHANNELS {
+            statuses.push((writer.get_entry::<bool>(key, None)?, flag));
+        }
+
+        let mut last_status = None;
+        for record in &log.records {
+            let Some(timestamp) = record.timestamp.checked_sub(start_time) else {
+                import.skipped += 1;
+                continue;
+            };
+            for (id, value) in &numbers {
+                writer.write_timestamped(*id, value(record), timestamp)?;
+            }
+            if last_status != Some(record.status) {
+                for (id, flag) in &statuses {
+                    writer.write_timestamped(*id, record.status.contains(*flag), timestamp)?;
+                }
+                last_status = Some(record.status);
+            }
+            import.records += 1;
+        }
+    }
+
+    if !events.is_empty() {
+        let id = writer.get_entry::<String>(DS_EVENTS_KEY, None)?;
+        let mut events = events.iter().collect::<Vec<_>>();
+        events.sort_by_key(|event| event.timestamp);
+        for event in events {
+            let Some(timestamp) = event.timestamp.checked_sub(start_time) else {
+                import.skipped += 1;
+                continue;
+            };
+            writer.write_timestamped(id, event.text.clone(), timestamp)?;
+            import.events += 1;
+        }
+    }
+    Ok(import)
+}
+
+/// The unix time in microseconds robot start was at, from the first value of the `systemTime` entry
+/// `WPILib`'s `DataLogManager` logs once the roboRIO knows the time
+#[must_use]
+pub fn robot_start_time(reader: &DataLogReader) -> Option<FrcTimestamp> {
+    reader.read_entry(SYSTEM_TIME_KEY).into_iter()
+        .find_map(|value| match value.value {
+            FrcValue::Int(unix_time) => u64::try_from(unix_time).ok()?.checked_sub(value.timestamp),
+            _ => None
+        })
+}
+
+/// Copies a robot log into `out` and adds the Driver Station channels to it, time aligned with the robot's own entries
+///
+/// The records of the robot log are copied as is and the Driver Station channels are written like
+/// [`write_ds_logs`] writes them, with `start_time` defaulting to [`robot_start_time`] of the robot log.
+///
+/// # Errors
+/// - [`DataLogError::MagicMismatch`] if `robot_log` isn't a log
+/// - [`DataLogError::NoSuchEntry`] if there is no `start_time` and the robot log has no `systemTime` entry
+/// - [`DataLogError::Io`] if an IO error occurs
+/// - Any error of [`write_ds_logs`]
+pub fn merge_ds_logs<W: Write>(
+    robot_log: &[u8],
+    log: Option<&DsLog>,
+    events: &[DsEvent],
+    start_time: Option<FrcTimestamp>,
+    out: W
+) -> Result<W, DataLogError> {
+    let header = DataLogReader::peek_header_from(robot_log)?;
+    let start_time = if let Some(start_time) = start_time {
+        start_time
+    } else {
+        let reader = DataLogReader::try_new(robot_log, DataLogReaderConfig::default())?;
+        robot_start_time(&reader).ok_or(DataLogError::NoSuchEntry)?
+    };
+    let records = robot_log.get(header_len(robot_log).ok_or(DataLogError::InvalidDataLog)?..)
+        .map(|bytes| parse_records(bytes, &mut std::collections::HashMap::new()))
+        .transpose()?
+        .unwrap_or_default();
+
+    let mut writer = DataLogWriter::new(out, header.metadata)?;
+    // keep the entries this writer starts off the ids of the copied entries
+    writer.reserve_entry_ids(records.iter().filter_map(|record| match record {
+        Record::Control(ControlRecord::Start(..), _, id) => Some(*id),
+        _ => None
+    }))?;
+    for record in records {
+        writer.write_record(record)?;
+    }
+    let _ = write_ds_logs(&mut writer, log, events, Some(start_time))?;
+    writer.close()
+}
@@ -0,0 +1,230 @@
+use core::fmt::Display;
+
+use embedded_io::Write;
+
+use crate::{proto::records::{EncodedRecordHeader, HeaderWidth}, writer::{WPILOG_MAGIC, WPILOG_VERSION}, FrcTimestamp};
+
+/// Errors an [`EmbeddedDataLogWriter`] can return, generic over the error of its sink
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddedDataLogError<E> {
+    /// The sink failed
+    Io(E),
+    /// A payload, name or metadata is longer than [`u32::MAX`] bytes
+    RecordTooLarge,
+    /// Every entry id has been used
+    OutOfEntryIds
+}
+
+impl <E: core::fmt::Debug> Display for EmbeddedDataLogError<E> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "DataLog io error: {err:?}"),
+            Self::RecordTooLarge => f.write_str("Record too large"),
+            Self::OutOfEntryIds => f.write_str("Ran out of entry ids")
+        }
+    }
+}
+
+/// A minimal writer over an [`embedded_io::Write`] sink that doesn't allocate,
+/// so coprocessors like vision boards can produce logs that are merged with the robot's later
+///
+/// The writer only uses `core`, but the rest of the crate needs `std`,
+/// so it can't be built for `no_std` targets yet.
+///
+/// Unlike [`DataLogWriter`](crate::DataLogWriter) it keeps no entry table and has no clock:
+/// entries are given increasing ids as they are started, values are written as already encoded payloads
+/// or with the typed helpers, and every record takes its timestamp explicitly.
+/// # Example
+/// ```rust
+/// use frclib_datalog::embedded::EmbeddedDataLogWriter;
+///
+/// let mut buffer = [0u8; 256];
+/// let mut writer = EmbeddedDataLogWriter::new(&mut buffer[..], "").expect("Failed to write header");
+/// let entry = writer.start_entry("/vision/latency", "double", "", 0).expect("Failed to start entry");
+/// writer.append_double(entry, 12.5, 1_000).expect("Failed to write value");
+/// ```
+#[derive(Debug)]
+pub struct EmbeddedDataLogWriter<W: Write> {
+    out: W,
+    next_id: u32,
+    width: HeaderWidth
+}
+
+impl <W: Write> EmbeddedDataLogWriter<W> {
+    /// Writes the log header with `metadata` to `out`
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::RecordTooLarge`] if the metadata is too large
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn new(mut out: W, metadata: &str) -> Result<Self, EmbeddedDataLogError<W::Error>> {
+        let (major, minor) = WPILOG_VERSION;
+        let metadata_len = u32::try_from(metadata.len()).map_err(|_| EmbeddedDataLogError::RecordTooLarge)?;
+        out.write_all(&WPILOG_MAGIC).map_err(EmbeddedDataLogError::Io)?;
+        // the version is a little endian u16 with the major version in the high byte
+        out.write_all(&[minor, major]).map_err(EmbeddedDataLogError::Io)?;
+        out.write_all(&metadata_len.to_le_bytes()).map_err(EmbeddedDataLogError::Io)?;
+        out.write_all(metadata.as_bytes()).map_err(EmbeddedDataLogError::Io)?;
+        Ok(Self {
+            out,
+            next_id: 1,
+            width: HeaderWidth::Minimal
+        })
+    }
+
+    /// Writes every following record header with a 4 byte id, 4 byte size and 8 byte timestamp,
+    /// see [`DataLogWriterConfig::fixed_width_headers`](crate::writer::DataLogWriterConfig::fixed_width_headers)
+    #[must_use]
+    pub const fn with_fixed_width_headers(mut self) -> Self {
+        self.width = HeaderWidth::Full;
+        self
+    }
+
+    /// Starts an entry, returning its id
+    ///
+    /// `entry_type` is the type string as written in the log, like `double` or `int64[]`.
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::OutOfEntryIds`] if every id has been used
+    /// - [`EmbeddedDataLogError::RecordTooLarge`] if a string is too large
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn start_entry(
+        &mut self,
+        name: &str,
+        entry_type: &str,
+        metadata: &str,
+        timestamp: FrcTimestamp
+    ) -> Result<u32, EmbeddedDataLogError<W::Error>> {
+        let id = self.next_id;
+        let next_id = id.checked_add(1).ok_or(EmbeddedDataLogError::OutOfEntryIds)?;
+        let strings = [name, entry_type, metadata];
+        let strings_len = strings.iter().try_fold(0usize, |len, string| len.checked_add(4 + string.len()))
+            .ok_or(EmbeddedDataLogError::RecordTooLarge)?;
+        // control type and entry id before the strings
+        self.write_header(0, strings_len.saturating_add(5), timestamp)?;
+        self.write_bytes(&[0])?;
+        self.write_bytes(&id.to_le_bytes())?;
+        for string in strings {
+            self.write_string(string)?;
+        }
+        self.next_id = next_id;
+        Ok(id)
+    }
+
+    /// Finishes an entry, later values of it aren't read
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn finish_entry(&mut self, id: u32, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.write_header(0, 5, timestamp)?;
+        self.write_bytes(&[1])?;
+        self.write_bytes(&id.to_le_bytes())
+    }
+
+    /// Replaces the metadata of an entry
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::RecordTooLarge`] if the metadata is too large
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn set_metadata(&mut self, id: u32, metadata: &str, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.write_header(0, metadata.len().saturating_add(9), timestamp)?;
+        self.write_bytes(&[2])?;
+        self.write_bytes(&id.to_le_bytes())?;
+        self.write_string(metadata)
+    }
+
+    /// Writes a value of an entry whose payload is already encoded
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::RecordTooLarge`] if the payload is too large
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn append_raw(&mut self, id: u32, payload: &[u8], timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.write_header(id, payload.len(), timestamp)?;
+        self.write_bytes(payload)
+    }
+
+    /// Writes a value of a `boolean` entry
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn append_boolean(&mut self, id: u32, value: bool, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.append_raw(id, &[u8::from(value)], timestamp)
+    }
+
+    /// Writes a value of an `int64` entry
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn append_int(&mut self, id: u32, value: i64, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.append_raw(id, &value.to_le_bytes(), timestamp)
+    }
+
+    /// Writes a value of a `float` entry
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn append_float(&mut self, id: u32, value: f32, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.append_raw(id, &value.to_le_bytes(), timestamp)
+    }
+
+    /// Writes a value of a `double` entry
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn append_double(&mut self, id: u32, value: f64, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.append_raw(id, &value.to_le_bytes(), timestamp)
+    }
+
+    /// Writes a value of a `string` entry
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::RecordTooLarge`] if the string is too large
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn append_string(&mut self, id: u32, value: &str, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.append_raw(id, value.as_bytes(), timestamp)
+    }
+
+    /// Writes a value of a `double[]` entry
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::RecordTooLarge`] if the array is too large
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn append_double_array(&mut self, id: u32, values: &[f64], timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.write_header(id, values.len().saturating_mul(8), timestamp)?;
+        values.iter().try_for_each(|value| self.write_bytes(&value.to_le_bytes()))
+    }
+
+    /// Flushes the sink
+    ///
+    /// # Errors
+    /// - [`EmbeddedDataLogError::Io`] if the sink fails
+    pub fn flush(&mut self) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.out.flush().map_err(EmbeddedDataLogError::Io)
+    }
+
+    /// The sink
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    /// Returns the sink
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn write_header(&mut self, id: u32, payload_len: usize, timestamp: FrcTimestamp) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        let payload_len = u32::try_from(payload_len).map_err(|_| EmbeddedDataLogError::RecordTooLarge)?;
+        let header = EncodedRecordHeader::new(self.width, timestamp, id, payload_len);
+        self.write_bytes(header.as_bytes())
+    }
+
+    fn write_string(&mut self, string: &str) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        let len = u32::try_from(string.len()).map_err(|_| EmbeddedDataLogError::RecordTooLarge)?;
+        self.write_bytes(&len.to_le_bytes())?;
+        self.write_bytes(string.as_bytes())
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), EmbeddedDataLogError<W::Error>> {
+        self.out.write_all(bytes).map_err(EmbeddedDataLogError::Io)
+    }
+}
@@ -0,0 +1,214 @@
+use std::io::{Read, Write};
+
+use chacha20poly1305::{aead::{rand_core::RngCore, AeadInPlace, KeyInit, OsRng}, ChaCha20Poly1305, Key, Nonce};
+
+use crate::{proto::util::StreamingRecordByteReader, DataLogError};
+
+/// The magic bytes at the start of an encrypted log
+pub const ENCRYPTED_MAGIC: [u8; 6] = *b"WPIENC";
+/// The version of the encrypted format
+const ENCRYPTED_VERSION: u8 = 1;
+/// The magic, version and nonce prefix, authenticated with every chunk
+const HEADER_LEN: usize = 14;
+/// The random part of every nonce, the rest is the chunk counter and whether the chunk is the last
+const NONCE_PREFIX_LEN: usize = 7;
+/// How much plaintext an [`EncryptedSink`] holds before encrypting it as a chunk, 64 KiB
+pub const CHUNK_SIZE: usize = 64 * 1024;
+/// The length of the authentication tag after every chunk
+const TAG_LEN: usize = 16;
+/// The bit of a chunk length marking the last chunk of the stream
+const FINAL_CHUNK_BIT: u32 = 1 << 31;
+
+/// A sink that encrypts everything written to it with `ChaCha20-Poly1305`,
+/// for logs holding scouting or tuning data that end up on shared USB drives
+///
+/// The log is split into chunks that are each encrypted and authenticated, so a changed,
+/// reordered or dropped chunk fails to decrypt. The stream must be ended with [`EncryptedSink::finish`]
+/// to be complete, every flush ends a chunk so the log can still be read up to the last flush after a crash
+/// with [`Truncation::Allow`].
+/// Each sink picks a random nonce prefix so the same key can be used for every log.
+///
+/// The key is 32 bytes that should come from a random source, like a file that is kept off the robot's USB drive.
+/// Logs are read back with [`DataLogReader::try_new_encrypted`](crate::DataLogReader::try_new_encrypted) or [`decrypt`].
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::DataLogWriter;
+///
+/// let key: [u8; 32] = std::fs::read("/home/lvuser/log.key").expect("Failed to read key")
+///     .try_into().expect("Key isn't 32 bytes");
+/// let mut writer = DataLogWriter::create_encrypted("/u/logs/match.wpilog.enc", &key, "").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+/// writer.close_encrypted().expect("Failed to close writer");
+/// ```
+pub struct EncryptedSink<W: Write> {
+    inner: W,
+    cipher: ChaCha20Poly1305,
+    header: [u8; HEADER_LEN],
+    header_written: bool,
+    /// Plaintext that hasn't been encrypted yet, encrypted in place when a chunk is written
+    buffer: Vec<u8>,
+    counter: u32
+}
+
+impl <W: Write> std::fmt::Debug for EncryptedSink<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EncryptedSink")
+            .field("buffered", &self.buffer.len())
+            .field("chunks", &self.counter)
+            .finish_non_exhaustive()
+    }
+}
+
+impl <W: Write> EncryptedSink<W> {
+    /// Creates a sink that encrypts with `key` before writing to `inner`
+    ///
+    /// # Errors
+    /// - [`std::io::Error`] if the system random source fails
+    pub fn new(inner: W, key: &[u8; 32]) -> std::io::Result<Self> {
+        let mut header = [0u8; HEADER_LEN];
+        let (magic, rest) = header.split_at_mut(ENCRYPTED_MAGIC.len());
+        magic.copy_from_slice(&ENCRYPTED_MAGIC);
+        let (version, prefix) = rest.split_at_mut(1);
+        version[0] = ENCRYPTED_VERSION;
+        OsRng.try_fill_bytes(prefix).map_err(|err| std::io::Error::other(err.to_string()))?;
+        Ok(Self {
+            inner,
+            cipher: ChaCha20Poly1305::new(Key::from_slice(key)),
+            header,
+            header_written: false,
+            buffer: Vec::with_capacity(CHUNK_SIZE + TAG_LEN),
+            counter: 0
+        })
+    }
+
+    /// The inner sink
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Encrypts what is left as the last chunk and returns the inner sink
+    ///
+    /// # Errors
+    /// - [`std::io::Error`] if writing to the inner sink fails
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.write_chunk(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    fn write_chunk(&mut self, last: bool) -> std::io::Result<()> {
+        if !self.header_written {
+            self.inner.write_all(&self.header)?;
+            self.header_written = true;
+        }
+        let nonce = chunk_nonce(&self.header, self.counter, last);
+        self.cipher.encrypt_in_place(Nonce::from_slice(&nonce), &self.header, &mut self.buffer)
+            .map_err(|_| std::io::Error::other("failed to encrypt chunk"))?;
+        let len = u32::try_from(self.buffer.len()).map_err(std::io::Error::other)?;
+        let len = if last { len | FINAL_CHUNK_BIT } else { len };
+        self.inner.write_all(&len.to_le_bytes())?;
+        self.inner.write_all(&self.buffer)?;
+        self.buffer.clear();
+        self.counter = self.counter.checked_add(1)
+            .ok_or_else(|| std::io::Error::other("too many chunks for one nonce prefix"))?;
+        Ok(())
+    }
+}
+
+impl <W: Write> Write for EncryptedSink<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(CHUNK_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..len]);
+        if self.buffer.len() == CHUNK_SIZE {
+            self.write_chunk(false)?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.buffer.is_empty() {
+            self.write_chunk(false)?;
+        }
+        self.inner.flush()
+    }
+}
+
+/// The nonce of a chunk, the prefix from the header followed by the big endian counter and whether it is the last
+fn chunk_nonce(header: &[u8; HEADER_LEN], counter: u32, last: bool) -> [u8; 12] {
+    let mut nonce = [0u8; 12];
+    let (prefix, rest) = nonce.split_at_mut(NONCE_PREFIX_LEN);
+    prefix.copy_from_slice(&header[HEADER_LEN - NONCE_PREFIX_LEN..]);
+    let (count, flag) = rest.split_at_mut(4);
+    count.copy_from_slice(&counter.to_be_bytes());
+    flag[0] = u8::from(last);
+    nonce
+}
+
+/// Whether [`decrypt`] accepts a stream that ends before its last chunk
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Truncation {
+    /// A stream without its last chunk fails to decrypt, so cutting chunks off the end of a log is noticed
+    #[default]
+    Reject,
+    /// A stream without its last chunk yields every whole chunk, for recovering what was flushed before a crash.
+    /// A log cut off on purpose can't be told apart from one cut off by a crash.
+    Allow
+}
+
+/// Decrypts a log written through an [`EncryptedSink`]
+///
+/// A stream that wasn't ended with [`EncryptedSink::finish`], like the log of a robot that crashed,
+/// only decrypts with [`Truncation::Allow`].
+///
+/// # Errors
+/// - [`DataLogError::Decryption`] if the data isn't an encrypted log, the key is wrong, the log was changed
+///   or it ends before its last chunk with [`Truncation::Reject`]
+/// - [`DataLogError::Io`] if there is an error reading the data
+pub fn decrypt(data: impl Read, key: &[u8; 32], truncation: Truncation) -> Result<Vec<u8>, DataLogError> {
+    let mut data = StreamingRecordByteReader::new(data);
+    let mut header = [0u8; HEADER_LEN];
+    header.copy_from_slice(data.bytes(HEADER_LEN)?);
+    if !header.starts_with(&ENCRYPTED_MAGIC) {
+        return Err(DataLogError::Decryption("not an encrypted log"));
+    }
+    if header[ENCRYPTED_MAGIC.len()] != ENCRYPTED_VERSION {
+        return Err(DataLogError::Decryption("unsupported encryption version"));
+    }
+    let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+    let mut decrypted = Vec::new();
+    let mut chunk = Vec::new();
+    let mut counter = 0u32;
+    let mut finished = false;
+    // a stream that ends before a whole chunk was cut off
+    while data.fill(4)? {
+        let len = data.u32()?;
+        let last = len & FINAL_CHUNK_BIT != 0;
+        let len = (len & !FINAL_CHUNK_BIT) as usize;
+        if !(TAG_LEN..=CHUNK_SIZE + TAG_LEN).contains(&len) {
+            return Err(DataLogError::Decryption("invalid chunk length"));
+        }
+        if !data.fill(len)? {
+            break;
+        }
+        chunk.clear();
+        chunk.extend_from_slice(data.bytes(len)?);
+        let nonce = chunk_nonce(&header, counter, last);
+        cipher.decrypt_in_place(Nonce::from_slice(&nonce), &header, &mut chunk)
+            .map_err(|_| DataLogError::Decryption("wrong key or changed log"))?;
+        decrypted.extend_from_slice(&chunk);
+        if last {
+            if data.fill(1)? {
+                return Err(DataLogError::Decryption("data after the last chunk"));
+            }
+            finished = true;
+            break;
+        }
+        counter = counter.checked_add(1).ok_or(DataLogError::Decryption("too many chunks"))?;
+    }
+    if !finished && truncation == Truncation::Reject {
+        return Err(DataLogError::Decryption("log ends before its last chunk"));
+    }
+    Ok(decrypted)
+}
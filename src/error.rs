@@ -44,4 +44,35 @@ pub enum DataLogError {
     RecordTooLarge,
     #[error("Metadata too large")]
     MetadataTooLarge,
+    #[error("Unit conversion error: {0:?}")]
+    UnitConversion(&'static str),
+    #[error("Query parse error: {0:?}")]
+    QueryParse(&'static str),
+    #[error("Analysis error: {0:?}")]
+    Analysis(&'static str),
+    #[error("Import error: {0:?}")]
+    Import(&'static str),
+    #[error("Background writer queue is full")]
+    BackgroundWriterFull,
+    #[error("Background writer has stopped")]
+    BackgroundWriterClosed,
+    #[error("Timestamp is older than the latest one of the entry")]
+    OutOfOrderTimestamp,
+    #[error("Value is NaN or infinite")]
+    NonFiniteValue,
+    #[error("File is locked by another process")]
+    FileLocked,
+    #[error("Decryption error: {0:?}")]
+    Decryption(&'static str),
+    #[error("Rename rule error: {0:?}")]
+    RenameRule(&'static str),
+    #[cfg(feature = "arrow")]
+    #[error("Arrow error: {0}")]
+    Arrow(#[from] arrow_schema::ArrowError),
+    #[cfg(feature = "rosbag2")]
+    #[error("Sqlite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[cfg(feature = "parquet")]
+    #[error("Parquet error: {0}")]
+    Parquet(#[from] parquet::errors::ParquetError),
 }
@@ -0,0 +1,224 @@
+use std::{io::Write, sync::Arc};
+
+use arrow_array::{builder::{ArrayBuilder, BinaryBuilder, BooleanBuilder, Float32Builder, Float64Builder, Int64Builder, ListBuilder, StringBuilder, UInt64Builder}, ArrayRef, RecordBatch};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{DataType, Field, Schema, SchemaRef};
+use frclib_core::value::{FrcTimestamp, FrcValue};
+
+use crate::{DataLogError, DataLogReader};
+
+use super::{for_each_row, read_columns, RowAlignment};
+
+/// How many rows are collected into one record batch
+pub(super) const ROWS_PER_BATCH: usize = 64 * 1024;
+
+/// Configuration for [`write_arrow_ipc`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ArrowExportConfig {
+    /// How values logged at different timestamps are put into rows
+    pub alignment: RowAlignment,
+    /// Only write values between these timestamps
+    pub window: Option<(FrcTimestamp, FrcTimestamp)>
+}
+
+/// The values of one entry collected into an arrow array of the type of the entry
+#[derive(Debug)]
+enum Column {
+    Boolean(BooleanBuilder),
+    Int(Int64Builder),
+    Float(Float32Builder),
+    Double(Float64Builder),
+    String(StringBuilder),
+    Binary(BinaryBuilder),
+    BooleanArray(ListBuilder<BooleanBuilder>),
+    IntArray(ListBuilder<Int64Builder>),
+    FloatArray(ListBuilder<Float32Builder>),
+    DoubleArray(ListBuilder<Float64Builder>),
+    StringArray(ListBuilder<StringBuilder>)
+}
+
+impl Column {
+    /// A column for an entry of `type_str`, types without an arrow equivalent like structs are kept as bytes
+    fn for_type(type_str: &str) -> Self {
+        match type_str {
+            "boolean" => Self::Boolean(BooleanBuilder::new()),
+            "int64" => Self::Int(Int64Builder::new()),
+            "float" => Self::Float(Float32Builder::new()),
+            "double" => Self::Double(Float64Builder::new()),
+            "string" | "json" => Self::String(StringBuilder::new()),
+            "boolean[]" => Self::BooleanArray(ListBuilder::new(BooleanBuilder::new())),
+            "int64[]" => Self::IntArray(ListBuilder::new(Int64Builder::new())),
+            "float[]" => Self::FloatArray(ListBuilder::new(Float32Builder::new())),
+            "double[]" => Self::DoubleArray(ListBuilder::new(Float64Builder::new())),
+            "string[]" => Self::StringArray(ListBuilder::new(StringBuilder::new())),
+            _ => Self::Binary(BinaryBuilder::new())
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        let list = |item: DataType| DataType::List(Arc::new(Field::new("item", item, true)));
+        match self {
+            Self::Boolean(_) => DataType::Boolean,
+            Self::Int(_) => DataType::Int64,
+            Self::Float(_) => DataType::Float32,
+            Self::Double(_) => DataType::Float64,
+            Self::String(_) => DataType::Utf8,
+            Self::Binary(_) => DataType::Binary,
+            Self::BooleanArray(_) => list(DataType::Boolean),
+            Self::IntArray(_) => list(DataType::Int64),
+            Self::FloatArray(_) => list(DataType::Float32),
+            Self::DoubleArray(_) => list(DataType::Float64),
+            Self::StringArray(_) => list(DataType::Utf8)
+        }
+    }
+
+    /// Appends a value, missing values and values of another type than the column are null
+    fn append(&mut self, value: Option<&FrcValue>) {
+        match (&mut *self, value) {
+            (Self::Boolean(builder), Some(FrcValue::Boolean(value))) => builder.append_value(*value),
+            (Self::Int(builder), Some(FrcValue::Int(value))) => builder.append_value(*value),
+            (Self::Float(builder), Some(FrcValue::Float(value))) => builder.append_value(*value),
+            (Self::Double(builder), Some(FrcValue::Double(value))) => builder.append_value(*value),
+            (Self::String(builder), Some(FrcValue::String(value))) => builder.append_value(value),
+            (Self::Binary(builder), Some(FrcValue::Raw(value))) => builder.append_value(value),
+            (Self::Binary(builder), Some(FrcValue::Struct(value) | FrcValue::StructArray(value))) => builder.append_value(&value.data),
+            (Self::BooleanArray(builder), Some(FrcValue::BooleanArray(values))) => {
+                builder.values().append_slice(values);
+                builder.append(true);
+            }
+            (Self::IntArray(builder), Some(FrcValue::IntArray(values))) => {
+                builder.values().append_slice(values);
+                builder.append(true);
+            }
+            (Self::FloatArray(builder), Some(FrcValue::FloatArray(values))) => {
+                builder.values().append_slice(values);
+                builder.append(true);
+            }
+            (Self::DoubleArray(builder), Some(FrcValue::DoubleArray(values))) => {
+                builder.values().append_slice(values);
+                builder.append(true);
+            }
+            (Self::StringArray(builder), Some(FrcValue::StringArray(values))) => {
+                for value in values {
+                    builder.values().append_value(value);
+                }
+                builder.append(true);
+            }
+            _ => self.append_null()
+        }
+    }
+
+    fn append_null(&mut self) {
+        match self {
+            Self::Boolean(builder) => builder.append_null(),
+            Self::Int(builder) => builder.append_null(),
+            Self::Float(builder) => builder.append_null(),
+            Self::Double(builder) => builder.append_null(),
+            Self::String(builder) => builder.append_null(),
+            Self::Binary(builder) => builder.append_null(),
+            Self::BooleanArray(builder) => builder.append_null(),
+            Self::IntArray(builder) => builder.append_null(),
+            Self::FloatArray(builder) => builder.append_null(),
+            Self::DoubleArray(builder) => builder.append_null(),
+            Self::StringArray(builder) => builder.append_null()
+        }
+    }
+
+    fn finish(&mut self) -> ArrayRef {
+        match self {
+            Self::Boolean(builder) => Arc::new(builder.finish()),
+            Self::Int(builder) => Arc::new(builder.finish()),
+            Self::Float(builder) => Arc::new(builder.finish()),
+            Self::Double(builder) => Arc::new(builder.finish()),
+            Self::String(builder) => Arc::new(builder.finish()),
+            Self::Binary(builder) => Arc::new(builder.finish()),
+            Self::BooleanArray(builder) => Arc::new(builder.finish()),
+            Self::IntArray(builder) => Arc::new(builder.finish()),
+            Self::FloatArray(builder) => Arc::new(builder.finish()),
+            Self::DoubleArray(builder) => Arc::new(builder.finish()),
+            Self::StringArray(builder) => Arc::new(builder.finish())
+        }
+    }
+}
+
+/// Builds record batches with a `timestamp` column of microseconds followed by one typed column per entry
+pub(super) struct RecordBatches {
+    schema: SchemaRef,
+    timestamps: UInt64Builder,
+    columns: Vec<Column>
+}
+
+impl RecordBatches {
+    pub(super) fn new(reader: &DataLogReader, keys: &[&str]) -> Self {
+        let columns = keys.iter()
+            .map(|key| Column::for_type(reader.read_entry_type_str(key).last().map_or("", |type_str| &type_str.value)))
+            .collect::<Vec<_>>();
+        let schema = Arc::new(Schema::new(
+            std::iter::once(Field::new("timestamp", DataType::UInt64, false))
+                .chain(keys.iter().zip(&columns).map(|(key, column)| Field::new(*key, column.data_type(), true)))
+                .collect::<Vec<_>>()
+        ));
+        Self {
+            schema,
+            timestamps: UInt64Builder::new(),
+            columns
+        }
+    }
+
+    pub(super) fn schema(&self) -> SchemaRef {
+        Arc::clone(&self.schema)
+    }
+
+    /// Builds the rows of the entries into batches of up to 65536 rows and hands each to `batch`
+    pub(super) fn for_each(
+        mut self,
+        reader: &DataLogReader,
+        keys: &[&str],
+        alignment: RowAlignment,
+        window: Option<(FrcTimestamp, FrcTimestamp)>,
+        mut batch: impl FnMut(&RecordBatch) -> Result<(), DataLogError>
+    ) -> Result<(), DataLogError> {
+        let values = read_columns(reader, keys, window)?;
+        for_each_row(&values, alignment, |timestamp, row| {
+            self.timestamps.append_value(timestamp);
+            for (column, value) in self.columns.iter_mut().zip(row) {
+                column.append(value.map(|value| &value.value));
+            }
+            if self.timestamps.len() >= ROWS_PER_BATCH {
+                batch(&self.finish()?)?;
+            }
+            Ok(())
+        })?;
+        if !self.timestamps.is_empty() {
+            batch(&self.finish()?)?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<RecordBatch, DataLogError> {
+        let timestamps: ArrayRef = Arc::new(self.timestamps.finish());
+        let arrays = std::iter::once(timestamps)
+            .chain(self.columns.iter_mut().map(Column::finish))
+            .collect();
+        Ok(RecordBatch::try_new(self.schema(), arrays)?)
+    }
+}
+
+/// Writes entries as an Arrow IPC stream, for handing logs to `pyarrow` and other Arrow tools
+///
+/// The record batches have a `timestamp` column of microseconds followed by one column per entry, named by its key.
+/// Columns are typed by the type of their entry, arrays become lists and types without an equivalent,
+/// like raw bytes and structs, are kept as binary. Rows are built like [`write_csv`](super::csv::write_csv),
+/// with null for missing values and values of another type than their entry, and sent in batches
+/// of up to 65536 rows.
+///
+/// # Errors
+/// - [`DataLogError::NoSuchEntry`] if one of the keys isn't an entry of the log
+/// - [`DataLogError::Arrow`] if encoding or writing the stream fails
+pub fn write_arrow_ipc(reader: &DataLogReader, keys: &[&str], config: ArrowExportConfig, out: impl Write) -> Result<(), DataLogError> {
+    let batches = RecordBatches::new(reader, keys);
+    let mut writer = StreamWriter::try_new(out, &batches.schema())?;
+    batches.for_each(reader, keys, config.alignment, config.window, |batch| Ok(writer.write(batch)?))?;
+    writer.finish()?;
+    Ok(())
+}
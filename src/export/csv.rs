@@ -0,0 +1,89 @@
+use std::io::Write;
+
+use frclib_core::value::{FrcTimestamp, FrcValue};
+
+use crate::{time::format_seconds, DataLogError, DataLogReader};
+
+use super::{for_each_row, read_columns, RowAlignment};
+
+/// Configuration for [`write_csv`]
+#[derive(Debug, Clone, Copy)]
+pub struct CsvExportConfig {
+    /// How values logged at different timestamps are put into rows
+    pub alignment: RowAlignment,
+    /// Write the timestamp column in seconds, like `83.250000`, instead of microseconds
+    pub timestamps_in_seconds: bool,
+    /// Only write values between these timestamps
+    pub window: Option<(FrcTimestamp, FrcTimestamp)>
+}
+impl Default for CsvExportConfig {
+    fn default() -> Self {
+        Self {
+            alignment: RowAlignment::Sparse,
+            timestamps_in_seconds: true,
+            window: None
+        }
+    }
+}
+
+/// Writes entries as csv with a `timestamp` column followed by one column per entry, named by its key,
+/// for opening logs in spreadsheets
+///
+/// There is a row for every timestamp any of the entries has a value at, if an entry has several values
+/// at one timestamp the last one is written. Arrays are written like `[1.0, 2.0]` and fields holding
+/// commas, quotes or line breaks are quoted.
+/// # Example
+/// ```rust
+/// use frclib_datalog::{export::{csv::{write_csv, CsvExportConfig}, RowAlignment}, reader::DataLogReaderConfig, DataLogReader, DataLogWriter};
+///
+/// let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+/// let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+/// let enabled = writer.get_entry::<bool>("enabled", None).expect("Failed to get entry");
+/// writer.write_timestamped(enabled, true, 1_000_000).expect("Failed to write entry");
+/// writer.write_timestamped(speed, 2.5, 1_500_000).expect("Failed to write entry");
+/// let bytes = writer.into_inner().expect("Failed to finish log");
+///
+/// let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default()).expect("Failed to read log");
+/// let config = CsvExportConfig { alignment: RowAlignment::SampleAndHold, ..Default::default() };
+/// let mut csv = Vec::new();
+/// write_csv(&reader, &["speed", "enabled"], config, &mut csv).expect("Failed to write csv");
+/// assert_eq!(String::from_utf8(csv).expect("Invalid utf8"), "timestamp,speed,enabled\n1.000000,,true\n1.500000,2.5,true\n");
+/// ```
+///
+/// # Errors
+/// - [`DataLogError::NoSuchEntry`] if one of the keys isn't an entry of the log
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn write_csv(reader: &DataLogReader, keys: &[&str], config: CsvExportConfig, mut out: impl Write) -> Result<(), DataLogError> {
+    let columns = read_columns(reader, keys, config.window)?;
+
+    write!(out, "timestamp")?;
+    for key in keys {
+        write!(out, ",{}", escape(key))?;
+    }
+    writeln!(out)?;
+
+    for_each_row(&columns, config.alignment, |timestamp, row| {
+        if config.timestamps_in_seconds {
+            write!(out, "{}", format_seconds(timestamp))?;
+        } else {
+            write!(out, "{timestamp}")?;
+        }
+        for value in row {
+            match value.map(|value| &value.value) {
+                None | Some(FrcValue::Void) => write!(out, ",")?,
+                Some(value) => write!(out, ",{}", escape(&value.to_string()))?
+            }
+        }
+        writeln!(out)?;
+        Ok(())
+    })
+}
+
+/// Quotes a field if it holds a comma, quote or line break, doubling the quotes in it
+fn escape(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
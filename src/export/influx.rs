@@ -0,0 +1,141 @@
+use std::{fmt::Write as _, io::Write};
+
+use frclib_core::value::{FrcTimestamp, FrcValue};
+
+use crate::{DataLogError, DataLogReader};
+
+use super::read_columns;
+
+/// Configuration for [`write_influx_lines`]
+#[derive(Debug, Clone, Copy)]
+pub struct InfluxExportConfig<'a> {
+    /// The measurement every line is written to
+    pub measurement: &'a str,
+    /// Tags added to every line, like the event and match the log was recorded in
+    pub tags: &'a [(&'a str, &'a str)],
+    /// The unix time robot start was at in microseconds, added to every timestamp
+    /// as the timestamps of a log count from robot start while `InfluxDB` expects unix time
+    pub start_time: FrcTimestamp,
+    /// Only write values between these timestamps
+    pub window: Option<(FrcTimestamp, FrcTimestamp)>
+}
+impl Default for InfluxExportConfig<'_> {
+    fn default() -> Self {
+        Self {
+            measurement: "datalog",
+            tags: &[],
+            start_time: 0,
+            window: None
+        }
+    }
+}
+
+/// Escapes the characters line protocol gives a meaning to in tags and field keys
+fn escape_name(name: &str, out: &mut String) {
+    for char in name.chars() {
+        if matches!(char, ',' | '=' | ' ' | '\\') {
+            out.push('\\');
+        }
+        out.push(char);
+    }
+}
+
+/// Writes the fields of a value, returning whether it had any
+///
+/// Arrays become one field per element named by its index, non finite floats are left out
+/// as `InfluxDB` can't store them and raw bytes and structs have no field type to be written as.
+fn write_fields(value: &FrcValue, out: &mut String) -> bool {
+    fn field(out: &mut String, key: &str, value: std::fmt::Arguments<'_>) {
+        // the series before the fields ends with the space separating them
+        if !out.ends_with(' ') {
+            out.push(',');
+        }
+        escape_name(key, out);
+        let _ = write!(out, "={value}");
+    }
+    fn elements<T>(out: &mut String, values: &[T], mut write_element: impl FnMut(&mut String, &str, &T)) {
+        for (index, value) in values.iter().enumerate() {
+            write_element(out, &index.to_string(), value);
+        }
+    }
+    let start = out.len();
+    match value {
+        FrcValue::Boolean(value) => field(out, "value", format_args!("{value}")),
+        FrcValue::Int(value) => field(out, "value", format_args!("{value}i")),
+        FrcValue::Float(value) if value.is_finite() => field(out, "value", format_args!("{value}")),
+        FrcValue::Double(value) if value.is_finite() => field(out, "value", format_args!("{value}")),
+        FrcValue::String(value) => field(out, "value", format_args!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))),
+        FrcValue::BooleanArray(values) => elements(out, values, |out, key, value| field(out, key, format_args!("{value}"))),
+        FrcValue::IntArray(values) => elements(out, values, |out, key, value| field(out, key, format_args!("{value}i"))),
+        FrcValue::FloatArray(values) => elements(out, values, |out, key, value| if value.is_finite() {
+            field(out, key, format_args!("{value}"));
+        }),
+        FrcValue::DoubleArray(values) => elements(out, values, |out, key, value| if value.is_finite() {
+            field(out, key, format_args!("{value}"));
+        }),
+        FrcValue::StringArray(values) => elements(out, values, |out, key, value| {
+            field(out, key, format_args!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\"")));
+        }),
+        _ => {}
+    }
+    out.len() > start
+}
+
+/// Writes entries as `InfluxDB` line protocol, for bulk loading logs into a time series database behind Grafana
+///
+/// Every value becomes a line of `measurement` tagged with `entry=<key>` and the configured tags,
+/// with a `value` field for single values and a field per element named by its index for arrays,
+/// timestamped in nanoseconds. Values with nothing to write, like raw bytes or NaN, are skipped.
+/// # Example
+/// ```rust
+/// use frclib_datalog::{export::influx::{write_influx_lines, InfluxExportConfig}, reader::DataLogReaderConfig, DataLogReader, DataLogWriter};
+///
+/// let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+/// let speed = writer.get_entry::<f64>("drive/speed", None).expect("Failed to get entry");
+/// writer.write_timestamped(speed, 2.5, 1_500_000).expect("Failed to write entry");
+/// let bytes = writer.into_inner().expect("Failed to finish log");
+///
+/// let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default()).expect("Failed to read log");
+/// let config = InfluxExportConfig { tags: &[("match", "Q12")], ..Default::default() };
+/// let mut lines = Vec::new();
+/// write_influx_lines(&reader, &["drive/speed"], config, &mut lines).expect("Failed to write lines");
+/// assert_eq!(String::from_utf8(lines).expect("Invalid utf8"), "datalog,entry=drive/speed,match=Q12 value=2.5 1500000000\n");
+/// ```
+///
+/// # Errors
+/// - [`DataLogError::NoSuchEntry`] if one of the keys isn't an entry of the log
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn write_influx_lines(reader: &DataLogReader, keys: &[&str], config: InfluxExportConfig<'_>, mut out: impl Write) -> Result<(), DataLogError> {
+    let columns = read_columns(reader, keys, config.window)?;
+    let mut line = String::new();
+    for (key, values) in keys.iter().zip(columns) {
+        let mut series = String::new();
+        for char in config.measurement.chars() {
+            // measurements can hold an `=`, only commas and spaces end them
+            if matches!(char, ',' | ' ') {
+                series.push('\\');
+            }
+            series.push(char);
+        }
+        series.push_str(",entry=");
+        escape_name(key, &mut series);
+        for (tag, value) in config.tags {
+            series.push(',');
+            escape_name(tag, &mut series);
+            series.push('=');
+            escape_name(value, &mut series);
+        }
+        series.push(' ');
+
+        for value in values {
+            line.clear();
+            line.push_str(&series);
+            if !write_fields(&value.value, &mut line) {
+                continue;
+            }
+            let nanos = config.start_time.saturating_add(value.timestamp).saturating_mul(1_000);
+            writeln!(out, "{line} {nanos}")?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,127 @@
+use std::io::Write;
+
+use frclib_core::value::{FrcTimestamp, FrcValue};
+use serde_json::{json, Map, Value};
+
+use crate::{time::to_seconds, DataLogError, DataLogReader};
+
+use super::read_columns;
+
+/// The shape of the json written by [`write_json`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonLayout {
+    /// One document with an object per entry, keyed by the entry key,
+    /// like `{"speed": {"type": "double", "values": [{"t": 1.5, "value": 2.0}]}}`
+    #[default]
+    Document,
+    /// One `{"t": 1.5, "key": "speed", "value": 2.0}` object per line for every value
+    /// of every entry in timestamp order, for streaming into tools like `jq`
+    Lines
+}
+
+/// Configuration for [`write_json`]
+#[derive(Debug, Clone, Copy)]
+pub struct JsonExportConfig {
+    /// The shape of the json
+    pub layout: JsonLayout,
+    /// Indent the document, lines are always written compact as each value must be on one line
+    pub pretty: bool,
+    /// Write timestamps in seconds instead of microseconds
+    pub timestamps_in_seconds: bool,
+    /// Only write values between these timestamps
+    pub window: Option<(FrcTimestamp, FrcTimestamp)>
+}
+impl Default for JsonExportConfig {
+    fn default() -> Self {
+        Self {
+            layout: JsonLayout::Document,
+            pretty: false,
+            timestamps_in_seconds: true,
+            window: None
+        }
+    }
+}
+
+/// Converts a value to json, raw bytes and structs become arrays of bytes and non finite floats become `null`
+fn value_to_json(value: &FrcValue) -> Value {
+    match value {
+        FrcValue::Void => Value::Null,
+        FrcValue::Raw(bytes) => json!(bytes),
+        FrcValue::Boolean(value) => json!(value),
+        FrcValue::Int(value) => json!(value),
+        FrcValue::Double(value) => json!(value),
+        FrcValue::Float(value) => json!(f64::from(*value)),
+        FrcValue::String(value) => json!(value),
+        FrcValue::BooleanArray(values) => json!(values),
+        FrcValue::IntArray(values) => json!(values),
+        FrcValue::FloatArray(values) => Value::Array(values.iter().map(|value| json!(f64::from(*value))).collect()),
+        FrcValue::DoubleArray(values) => json!(values),
+        FrcValue::StringArray(values) => json!(values),
+        FrcValue::Struct(structure) | FrcValue::StructArray(structure) => json!(structure.data)
+    }
+}
+
+/// Writes entries as json for web dashboards and scripts, see [`JsonLayout`] for the shapes it can take
+///
+/// # Example
+/// ```rust
+/// use frclib_datalog::{export::json::{write_json, JsonExportConfig, JsonLayout}, reader::DataLogReaderConfig, DataLogReader, DataLogWriter};
+///
+/// let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+/// let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+/// writer.write_timestamped(speed, 2.5, 1_500_000).expect("Failed to write entry");
+/// let bytes = writer.into_inner().expect("Failed to finish log");
+///
+/// let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default()).expect("Failed to read log");
+/// let config = JsonExportConfig { layout: JsonLayout::Lines, ..Default::default() };
+/// let mut json = Vec::new();
+/// write_json(&reader, &["speed"], config, &mut json).expect("Failed to write json");
+/// assert_eq!(String::from_utf8(json).expect("Invalid utf8"), "{\"key\":\"speed\",\"t\":1.5,\"value\":2.5}\n");
+/// ```
+///
+/// # Errors
+/// - [`DataLogError::NoSuchEntry`] if one of the keys isn't an entry of the log
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn write_json(reader: &DataLogReader, keys: &[&str], config: JsonExportConfig, mut out: impl Write) -> Result<(), DataLogError> {
+    let columns = read_columns(reader, keys, config.window)?;
+    let entries = keys.iter().copied().zip(columns);
+    let timestamp = |timestamp: FrcTimestamp| if config.timestamps_in_seconds {
+        json!(to_seconds(timestamp))
+    } else {
+        json!(timestamp)
+    };
+
+    match config.layout {
+        JsonLayout::Document => {
+            let mut document = Map::new();
+            for (key, values) in entries {
+                let type_str = reader.read_entry_type_str(key).last()
+                    .map(|type_str| type_str.value.clone())
+                    .unwrap_or_default();
+                let values = values.iter()
+                    .map(|value| json!({ "t": timestamp(value.timestamp), "value": value_to_json(&value.value) }))
+                    .collect::<Vec<_>>();
+                let _ = document.insert(key.to_string(), json!({ "type": type_str, "values": values }));
+            }
+            let document = Value::Object(document);
+            if config.pretty {
+                serde_json::to_writer_pretty(&mut out, &document)
+            } else {
+                serde_json::to_writer(&mut out, &document)
+            }.map_err(std::io::Error::from)?;
+        }
+        JsonLayout::Lines => {
+            let mut lines = entries
+                .flat_map(|(key, values)| values.into_iter().map(move |value| (key, value)))
+                .collect::<Vec<_>>();
+            // stable, so values at the same timestamp keep the order of the keys
+            lines.sort_by_key(|(_, value)| value.timestamp);
+            for (key, value) in lines {
+                let line = json!({ "t": timestamp(value.timestamp), "key": key, "value": value_to_json(&value.value) });
+                serde_json::to_writer(&mut out, &line).map_err(std::io::Error::from)?;
+                writeln!(out)?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,78 @@
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue};
+
+use crate::{DataLogError, DataLogReader};
+
+/// Exporting pose entries as paths for field overlay tools
+pub mod path;
+/// Exporting entries as csv for spreadsheets
+pub mod csv;
+/// Exporting entries as json documents or json lines for dashboards and scripts
+pub mod json;
+/// Exporting entries as `InfluxDB` line protocol for time series databases
+pub mod influx;
+/// Exporting entries as Arrow record batches for Arrow native tools, requires the `arrow` feature
+#[cfg(feature = "arrow")]
+pub mod arrow;
+/// Exporting entries as parquet for analysis over many logs, requires the `parquet` feature
+#[cfg(feature = "parquet")]
+pub mod parquet;
+
+/// How the values of entries logged at different timestamps are put into rows
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RowAlignment {
+    /// A row only has the values logged at its timestamp, the other columns are left empty
+    #[default]
+    Sparse,
+    /// A row has the latest value of every entry at its timestamp,
+    /// columns are only empty before the first value of their entry
+    SampleAndHold
+}
+
+/// The values of every entry between `start` and `end`, in the order of `keys`
+fn read_columns<'a>(reader: &'a DataLogReader, keys: &[&str], window: Option<(FrcTimestamp, FrcTimestamp)>) -> Result<Vec<Vec<&'a FrcTimestampedValue>>, DataLogError> {
+    let (start, end) = window.unwrap_or((0, FrcTimestamp::MAX));
+    let existing = reader.get_all_entry_keys();
+    keys.iter()
+        .map(|key| {
+            if !existing.iter().any(|existing| existing == key) {
+                return Err(DataLogError::NoSuchEntry);
+            }
+            Ok(reader.read_entry_between(key, start, end))
+        })
+        .collect()
+}
+
+/// Calls `row` with every timestamp any of the columns has a value at and the value of each column there,
+/// if a column has several values at one timestamp the last one is used
+fn for_each_row<'a>(
+    columns: &[Vec<&'a FrcTimestampedValue>],
+    alignment: RowAlignment,
+    mut row: impl FnMut(FrcTimestamp, &[Option<&'a FrcTimestampedValue>]) -> Result<(), DataLogError>
+) -> Result<(), DataLogError> {
+    let mut cursors = vec![0usize; columns.len()];
+    let mut held: Vec<Option<&FrcTimestampedValue>> = vec![None; columns.len()];
+    let mut values = Vec::with_capacity(columns.len());
+    while let Some(timestamp) = columns.iter()
+        .zip(&cursors)
+        .filter_map(|(values, cursor)| values.get(*cursor).map(|value| value.timestamp))
+        .min()
+    {
+        values.clear();
+        for ((column, cursor), held) in columns.iter().zip(&mut cursors).zip(&mut held) {
+            let mut current = None;
+            while let Some(value) = column.get(*cursor).filter(|value| value.timestamp == timestamp) {
+                current = Some(*value);
+                *cursor += 1;
+            }
+            if current.is_some() {
+                *held = current;
+            }
+            values.push(match alignment {
+                RowAlignment::Sparse => current,
+                RowAlignment::SampleAndHold => *held
+            });
+        }
+        row(timestamp, &values)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use std::io::Write;
+
+use frclib_core::value::FrcTimestamp;
+use parquet::{arrow::ArrowWriter, file::properties::WriterProperties};
+
+use crate::{DataLogError, DataLogReader};
+
+use super::{arrow::{RecordBatches, ROWS_PER_BATCH}, RowAlignment};
+
+/// Configuration for [`write_parquet`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParquetExportConfig {
+    /// How values logged at different timestamps are put into rows
+    pub alignment: RowAlignment,
+    /// Only write values between these timestamps
+    pub window: Option<(FrcTimestamp, FrcTimestamp)>
+}
+
+/// Writes entries as a parquet file with a `timestamp` column of microseconds followed by one column
+/// per entry, named by its key, for querying many logs at once with tools like `DuckDB` or Spark
+///
+/// Columns are typed like [`write_arrow_ipc`](super::arrow::write_arrow_ipc) types them,
+/// with null for missing values and values of another type than their entry, and written in row groups
+/// of 65536 rows.
+///
+/// # Errors
+/// - [`DataLogError::NoSuchEntry`] if one of the keys isn't an entry of the log
+/// - [`DataLogError::Parquet`] if encoding or writing the file fails
+pub fn write_parquet<W: Write + Send>(reader: &DataLogReader, keys: &[&str], config: ParquetExportConfig, out: W) -> Result<(), DataLogError> {
+    let batches = RecordBatches::new(reader, keys);
+    let properties = WriterProperties::builder().set_max_row_group_size(ROWS_PER_BATCH).build();
+    let mut writer = ArrowWriter::try_new(out, batches.schema(), Some(properties))?;
+    batches.for_each(reader, keys, config.alignment, config.window, |batch| Ok(writer.write(batch)?))?;
+    let _ = writer.close()?;
+    Ok(())
+}
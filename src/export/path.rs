@@ -0,0 +1,146 @@
+use std::io::Write;
+
+use frclib_core::value::{FrcTimestamp, FrcValue};
+
+use crate::{analysis::{interpolate, numeric_series}, DataLogError, DataLogReader, TimestampedValue};
+
+/// Where the poses of a path are read from
+#[derive(Debug, Clone, Copy)]
+pub enum PoseSource<'a> {
+    /// A single entry holding a `struct:Pose2d`, a `struct:Pose3d` of which the yaw is the rotation,
+    /// or a `double[]` of `[x, y, rotation]`
+    Entry(&'a str),
+    /// Three numeric entries, `y` and `rotation` are interpolated to the timestamps of `x`
+    Components {
+        /// The x position entry
+        x: &'a str,
+        /// The y position entry
+        y: &'a str,
+        /// The rotation entry, in radians
+        rotation: &'a str
+    }
+}
+
+/// A single pose along a path
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathPoint {
+    /// The timestamp of the pose, in microseconds
+    pub timestamp: FrcTimestamp,
+    /// The x position, in meters
+    pub x: f64,
+    /// The y position, in meters
+    pub y: f64,
+    /// The rotation, in radians
+    pub rotation: f64,
+}
+
+fn decode_pose(timestamp: FrcTimestamp, value: &FrcValue, entry_type: &str) -> Option<PathPoint> {
+    let (x, y, rotation) = match value {
+        FrcValue::DoubleArray(array) => match **array {
+            [x, y, rotation, ..] => (x, y, rotation),
+            _ => return None
+        },
+        FrcValue::Raw(bytes) => decode_pose_bytes(entry_type.strip_prefix("struct:")?, bytes)?,
+        FrcValue::Struct(structure) => decode_pose_bytes(structure.desc.type_str, &structure.data)?,
+        _ => return None
+    };
+    Some(PathPoint { timestamp, x, y, rotation })
+}
+
+/// Decodes a `Pose2d` or the position on the field and yaw of a `Pose3d`
+fn decode_pose_bytes(struct_name: &str, bytes: &[u8]) -> Option<(f64, f64, f64)> {
+    let double = |index: usize| -> Option<f64> {
+        bytes.get(index * 8..(index + 1) * 8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map(f64::from_le_bytes)
+    };
+    match (struct_name, bytes.len()) {
+        // a Translation2d of two doubles followed by a Rotation2d of one double
+        ("Pose2d", 24) => Some((double(0)?, double(1)?, double(2)?)),
+        // a Translation3d of three doubles followed by a Rotation3d holding a quaternion of w, x, y and z
+        ("Pose3d", 56) => {
+            let (w, qx, qy, qz) = (double(3)?, double(4)?, double(5)?, double(6)?);
+            let yaw = f64::atan2(2.0 * w.mul_add(qz, qx * qy), (-2.0f64).mul_add(qy.mul_add(qy, qz * qz), 1.0));
+            Some((double(0)?, double(1)?, yaw))
+        }
+        _ => None
+    }
+}
+
+/// The type of an entry when a value was logged, entries can be started again with another type
+fn entry_type_at<'a>(types: &[&'a TimestampedValue<String>], timestamp: FrcTimestamp) -> &'a str {
+    types.iter()
+        .take_while(|entry_type| entry_type.timestamp <= timestamp)
+        .last()
+        .or_else(|| types.first())
+        .map_or("", |entry_type| entry_type.value.as_str())
+}
+
+/// Reads the poses of a path from a log, values that can't be decoded as a pose are skipped
+///
+/// # Errors
+/// - [`DataLogError::NoSuchEntry`] if no poses could be read
+pub fn read_path(reader: &DataLogReader, source: PoseSource<'_>) -> Result<Vec<PathPoint>, DataLogError> {
+    let points = match source {
+        PoseSource::Entry(key) => {
+            let types = reader.read_entry_type_str(key);
+            reader.read_entry(key)
+                .into_iter()
+                .filter_map(|value| decode_pose(value.timestamp, &value.value, entry_type_at(&types, value.timestamp)))
+                .collect::<Vec<_>>()
+        }
+        PoseSource::Components { x, y, rotation } => {
+            let y = numeric_series(reader, y);
+            let rotation = numeric_series(reader, rotation);
+            numeric_series(reader, x)
+                .into_iter()
+                .filter_map(|(timestamp, x)| Some(PathPoint {
+                    timestamp,
+                    x,
+                    y: interpolate(&y, timestamp)?,
+                    rotation: interpolate(&rotation, timestamp)?
+                }))
+                .collect()
+        }
+    };
+    if points.is_empty() {
+        return Err(DataLogError::NoSuchEntry);
+    }
+    Ok(points)
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn seconds(timestamp: FrcTimestamp) -> f64 {
+    timestamp as f64 / 1e6
+}
+
+/// Writes a path as csv with a `t,x,y,rotation` header, `t` is in seconds
+///
+/// # Errors
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn write_path_csv(points: &[PathPoint], mut out: impl Write) -> Result<(), DataLogError> {
+    writeln!(out, "t,x,y,rotation")?;
+    for point in points {
+        writeln!(out, "{},{},{},{}", seconds(point.timestamp), point.x, point.y, point.rotation)?;
+    }
+    Ok(())
+}
+
+/// Writes a path as a json trajectory of the form
+/// `{"points": [{"t": 0.0, "x": 0.0, "y": 0.0, "rotation": 0.0}]}`, `t` is in seconds
+///
+/// # Errors
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn write_path_json(points: &[PathPoint], mut out: impl Write) -> Result<(), DataLogError> {
+    let points = points.iter()
+        .map(|point| serde_json::json!({
+            "t": seconds(point.timestamp),
+            "x": point.x,
+            "y": point.y,
+            "rotation": point.rotation
+        }))
+        .collect::<Vec<_>>();
+    serde_json::to_writer(&mut out, &serde_json::json!({ "points": points }))
+        .map_err(std::io::Error::from)?;
+    Ok(())
+}
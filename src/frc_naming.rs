@@ -0,0 +1,238 @@
+use std::{fmt::{Debug, Write}, fs::File, path::{Path, PathBuf}, time::{Duration, SystemTime, UNIX_EPOCH}};
+
+use crate::{DataLogError, DataLogWriter};
+
+/// The earliest system time trusted as a real date, the roboRIO clock starts at the unix epoch
+/// until the driver station sets it
+const EARLIEST_VALID_TIME: Duration = Duration::from_hours(438_288); // 2020-01-01
+
+/// The kind of match a log was recorded in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchType {
+    /// A practice match
+    Practice,
+    /// A qualification match
+    Qualification,
+    /// An elimination match
+    Elimination
+}
+impl MatchType {
+    const fn prefix(self) -> char {
+        match self {
+            Self::Practice => 'P',
+            Self::Qualification => 'Q',
+            Self::Elimination => 'E'
+        }
+    }
+}
+
+/// The match a log was recorded in, as reported by the FMS
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchInfo {
+    /// The event name, like `CASJ`
+    pub event_name: String,
+    /// The kind of match
+    pub match_type: MatchType,
+    /// The match number
+    pub match_number: u16
+}
+
+/// The name `WPILib` gives a log started at `time`, `FRC_yyyyMMdd_HHmmss.wpilog` in UTC,
+/// with `_<event>_<match>` added before the extension when the match is known
+/// # Example
+/// ```rust
+/// use std::time::{Duration, UNIX_EPOCH};
+/// use frclib_datalog::frc_naming::{frc_log_name, MatchInfo, MatchType};
+///
+/// let time = UNIX_EPOCH + Duration::from_secs(1_710_000_000);
+/// assert_eq!(frc_log_name(time, None), "FRC_20240309_160000.wpilog");
+///
+/// let info = MatchInfo { event_name: "CASJ".to_string(), match_type: MatchType::Qualification, match_number: 12 };
+/// assert_eq!(frc_log_name(time, Some(&info)), "FRC_20240309_160000_CASJ_Q12.wpilog");
+/// ```
+#[must_use]
+pub fn frc_log_name(time: SystemTime, match_info: Option<&MatchInfo>) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    let (year, month, day) = civil_from_days(secs / 86_400);
+    let secs_of_day = secs % 86_400;
+    let mut name = format!(
+        "FRC_{year:04}{month:02}{day:02}_{:02}{:02}{:02}",
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60,
+        secs_of_day % 60
+    );
+    if let Some(info) = match_info {
+        let _ = write!(name, "_{}_{}{}", info.event_name, info.match_type.prefix(), info.match_number);
+    }
+    name.push_str(".wpilog");
+    name
+}
+
+/// Converts days since the unix epoch to a (year, month, day) date in the proleptic gregorian calendar
+const fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    // shifted so eras of 400 years start on march 1st, 0000
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = era * 400 + year_of_era + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// A datalog writer for a file named like `WPILib` names its logs
+///
+/// The file starts as `FRC_TBD_<n>.wpilog` as the real time usually isn't known when logging starts,
+/// it is renamed to `FRC_yyyyMMdd_HHmmss.wpilog` once the time is set and renamed again
+/// to include the event and match once those are known, see [`frc_log_name`].
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::frc_naming::FrcNamedDataLogWriter;
+///
+/// let mut log = FrcNamedDataLogWriter::create("/home/lvuser/logs", "").expect("Failed to create writer");
+/// let entry = log.writer_mut().get_entry::<f64>("test", None).expect("Failed to get entry");
+/// log.writer_mut().write(entry, 10.0).expect("Failed to write entry");
+///
+/// // once the driver station has set the clock
+/// log.sync_to_system_clock().expect("Failed to rename log");
+/// ```
+pub struct FrcNamedDataLogWriter {
+    writer: DataLogWriter<File>,
+    dir: PathBuf,
+    path: PathBuf,
+    start_time: Option<SystemTime>,
+    match_info: Option<MatchInfo>
+}
+impl Debug for FrcNamedDataLogWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FrcNamedDataLogWriter")
+            .field("writer", &self.writer)
+            .field("path", &self.path)
+            .field("start_time", &self.start_time)
+            .field("match_info", &self.match_info)
+            .finish_non_exhaustive()
+    }
+}
+
+impl FrcNamedDataLogWriter {
+    /// Creates a log named `FRC_TBD_<n>.wpilog` in `dir`, using the lowest `n` that isn't taken
+    ///
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn create(dir: impl Into<PathBuf>, metadata: impl ToString) -> Result<Self, DataLogError> {
+        let dir = dir.into();
+        let metadata = metadata.to_string();
+        let mut index = 0u32;
+        loop {
+            let path = dir.join(format!("FRC_TBD_{index}.wpilog"));
+            match DataLogWriter::create(&path, &metadata) {
+                Ok(writer) => return Ok(Self {
+                    writer,
+                    dir,
+                    path,
+                    start_time: None,
+                    match_info: None
+                }),
+                Err(DataLogError::FileAlreadyExists) => index += 1,
+                Err(err) => return Err(err)
+            }
+        }
+    }
+
+    /// The current path of the log
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the log has been given its final date based name
+    #[must_use]
+    pub const fn is_named(&self) -> bool {
+        self.start_time.is_some()
+    }
+
+    /// The writer for the log
+    #[must_use]
+    pub const fn writer(&self) -> &DataLogWriter<File> {
+        &self.writer
+    }
+
+    /// The writer for the log
+    pub const fn writer_mut(&mut self) -> &mut DataLogWriter<File> {
+        &mut self.writer
+    }
+
+    /// Names the log after `start_time`, returning whether it was renamed.
+    ///
+    /// The time is only set once, later calls are ignored.
+    ///
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists with the new name
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn set_start_time(&mut self, start_time: SystemTime) -> Result<bool, DataLogError> {
+        if self.start_time.is_some() {
+            return Ok(false);
+        }
+        self.start_time = Some(start_time);
+        self.rename()?;
+        Ok(true)
+    }
+
+    /// Names the log after the current system time if the clock has been set,
+    /// returning whether it was renamed, see [`FrcNamedDataLogWriter::set_start_time`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists with the new name
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn sync_to_system_clock(&mut self) -> Result<bool, DataLogError> {
+        let now = SystemTime::now();
+        if now.duration_since(UNIX_EPOCH).unwrap_or_default() < EARLIEST_VALID_TIME {
+            return Ok(false);
+        }
+        self.set_start_time(now)
+    }
+
+    /// Adds the match to the name of the log, returning whether it was renamed.
+    ///
+    /// The log is only renamed once the start time is set, the match is kept until then.
+    ///
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists with the new name
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn set_match_info(&mut self, match_info: MatchInfo) -> Result<bool, DataLogError> {
+        if self.match_info.as_ref() == Some(&match_info) {
+            return Ok(false);
+        }
+        self.match_info = Some(match_info);
+        if self.start_time.is_none() {
+            return Ok(false);
+        }
+        self.rename()?;
+        Ok(true)
+    }
+
+    fn rename(&mut self) -> Result<(), DataLogError> {
+        let Some(start_time) = self.start_time else {
+            return Ok(());
+        };
+        let path = self.dir.join(frc_log_name(start_time, self.match_info.as_ref()));
+        if path.exists() {
+            return Err(DataLogError::FileAlreadyExists);
+        }
+        // the file stays open, only its directory entry moves
+        std::fs::rename(&self.path, &path)?;
+        self.path = path;
+        Ok(())
+    }
+
+    /// Returns the writer, the log keeps its current name
+    #[must_use]
+    pub fn into_inner(self) -> DataLogWriter<File> {
+        self.writer
+    }
+}
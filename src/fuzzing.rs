@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::{
+    proto::{entries::SUPPORTED_TYPES_SERIALS, records::split_record, util::StreamingRecordByteReader},
+    reader::DataLogReaderConfig,
+    writer::{WPILOG_MAGIC, WPILOG_VERSION},
+    DataLogReader, DataRecord, DataRecordRef, Record
+};
+
+/// Parses `data` as a single record, the first byte picks the type its entry is read as
+///
+/// Records that parse are checked to serialize into bytes that parse the same again,
+/// and the borrowed and owned decoders are checked to agree, anything else only has to not panic.
+/// The `fuzz` directory of the repository runs this with `cargo fuzz run parse_record`.
+///
+/// # Panics
+/// If one of the checks fails, which is a bug for the fuzzer to report
+pub fn fuzz_parse_record(data: &[u8]) {
+    let Some((type_index, bytes)) = data.split_first() else {
+        return;
+    };
+    let type_serial = SUPPORTED_TYPES_SERIALS[usize::from(*type_index) % SUPPORTED_TYPES_SERIALS.len()];
+
+    // the payload on its own with both decoders
+    if let (Ok(borrowed), Ok(owned)) = (DataRecordRef::from_binary(bytes, type_serial), DataRecord::from_binary(bytes, type_serial)) {
+        let mut from_borrowed = Vec::new();
+        let mut from_owned = Vec::new();
+        // borrowed boolean arrays keep their bytes as is, so the values are compared rather than the payloads
+        let borrowed = DataRecord::from(borrowed).write_to(0, 1, &mut from_borrowed).map(|()| from_borrowed);
+        let owned = owned.write_to(0, 1, &mut from_owned).map(|()| from_owned);
+        assert_eq!(borrowed.ok(), owned.ok(), "the borrowed and owned decoders disagree");
+    }
+
+    let Some((id, .., len)) = split_record(bytes) else {
+        return;
+    };
+    let type_map = HashMap::from([(id, type_serial)]);
+    let Ok(record) = Record::from_binary(&bytes[..len], &type_map) else {
+        return;
+    };
+    let Ok(encoded) = record.to_bytes() else {
+        return;
+    };
+    assert_eq!(Some(encoded.len()), record.serialized_size(), "the serialized size is wrong");
+    // arrays cut short can serialize to an empty payload, which isn't read back
+    if let Ok(again) = Record::from_binary(&encoded, &type_map) {
+        assert_eq!(again.to_bytes().ok().as_ref(), Some(&encoded), "a record changed after a round trip");
+    }
+}
+
+/// Reads `data` as a whole log, both as is and after a valid header so the records are reached
+/// even when the fuzzer hasn't found the header
+///
+/// The log only has to not panic.
+/// The `fuzz` directory of the repository runs this with `cargo fuzz run parse_log`.
+pub fn fuzz_parse_log(data: &[u8]) {
+    let config = DataLogReaderConfig::default();
+    let _ = DataLogReader::try_new(data, config);
+
+    let (major, minor) = WPILOG_VERSION;
+    let mut log = WPILOG_MAGIC.to_vec();
+    log.extend_from_slice(&[minor, major, 0, 0, 0, 0]);
+    log.extend_from_slice(data);
+    if let Ok(reader) = DataLogReader::try_new(log.as_slice(), config) {
+        for key in reader.get_all_entry_keys() {
+            let _ = reader.read_entry(key);
+        }
+    }
+
+    let mut stream = StreamingRecordByteReader::new(data);
+    while let Ok(Some(_)) = stream.record() {}
+}
@@ -0,0 +1,346 @@
+use std::{io::Write, ops::Deref, sync::{Arc, Mutex, MutexGuard, PoisonError, Weak}, time::{Duration, Instant}};
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, IntoFrcValue, StaticallyFrcTyped};
+
+use crate::{units::UNIT_METADATA_KEY, writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+/// A cheap to clone, thread safe handle to a [`DataLogWriter`]
+/// 
+/// Every clone writes to the same log, so subsystems can each own a handle
+/// instead of passing `&mut DataLogWriter` around.
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::{handle::DataLogHandle, DataLogWriter};
+/// 
+/// let writer = DataLogWriter::new(File::create("path/to/file").unwrap(), "")
+///     .expect("Failed to create writer");
+/// let handle = DataLogHandle::new(writer);
+/// 
+/// let drivetrain = handle.clone();
+/// std::thread::spawn(move || {
+///     let entry = drivetrain.get_entry::<f64>("/drivetrain/velocity", None).expect("Failed to get entry");
+///     drivetrain.write(entry, 1.5).expect("Failed to write entry");
+/// });
+/// ```
+#[derive(Debug)]
+pub struct DataLogHandle<W: Write = std::fs::File> {
+    writer: Arc<Mutex<DataLogWriter<W>>>
+}
+
+impl <W: Write> Clone for DataLogHandle<W> {
+    fn clone(&self) -> Self {
+        Self {
+            writer: Arc::clone(&self.writer)
+        }
+    }
+}
+
+impl <W: Write> From<DataLogWriter<W>> for DataLogHandle<W> {
+    fn from(writer: DataLogWriter<W>) -> Self {
+        Self::new(writer)
+    }
+}
+
+impl <W: Write> DataLogHandle<W> {
+    /// Wraps a writer in a handle
+    #[must_use]
+    pub fn new(writer: DataLogWriter<W>) -> Self {
+        Self {
+            writer: Arc::new(Mutex::new(writer))
+        }
+    }
+
+    /// Locks the writer for a series of operations without other threads interleaving
+    /// 
+    /// A panic while another thread held the lock doesn't poison the handle,
+    /// every operation on the writer leaves it in a usable state.
+    pub fn lock(&self) -> MutexGuard<'_, DataLogWriter<W>> {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// A reference to the writer that doesn't keep it alive
+    pub(crate) fn downgrade(&self) -> Weak<Mutex<DataLogWriter<W>>> {
+        Arc::downgrade(&self.writer)
+    }
+
+    /// Returns the writer if this is the last handle to it, otherwise returns the handle back
+    /// 
+    /// # Errors
+    /// - The handle itself if other clones of it are still alive
+    pub fn try_into_writer(self) -> Result<DataLogWriter<W>, Self> {
+        Arc::try_unwrap(self.writer)
+            .map(|writer| writer.into_inner().unwrap_or_else(PoisonError::into_inner))
+            .map_err(|writer| Self { writer })
+    }
+
+    /// Gets an [`Entry`] for a key that can be appended to without the writer, creating it if it doesn't exist
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn entry<T: StaticallyFrcTyped>(&self, key: &str, metadata: Option<String>) -> Result<Entry<T, W>, DataLogError> {
+        let key = key.to_string();
+        let id = self.get_entry(&key, metadata)?;
+        Ok(Entry {
+            handle: self.clone(),
+            id,
+            key
+        })
+    }
+
+    /// Gets a [`ScopedEntry`] for a key, which finishes the entry when it's dropped
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn scoped_entry<T: StaticallyFrcTyped>(&self, key: &str, metadata: Option<String>) -> Result<ScopedEntry<T, W>, DataLogError> {
+        self.entry(key, metadata).map(Entry::scoped)
+    }
+
+    /// Starts a [`ScopedTimer`] that appends the milliseconds until it's dropped to a double entry,
+    /// created with `"ms"` as its unit if it doesn't exist
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry already exists with a type other than double
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn timer(&self, key: &str) -> Result<ScopedTimer<W>, DataLogError> {
+        let metadata = format!(r#"{{"{UNIT_METADATA_KEY}":"ms"}}"#);
+        self.entry::<f64>(key, Some(metadata)).map(ScopedTimer::new)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry<T: StaticallyFrcTyped>(&self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.lock().get_entry(key, metadata)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry_dynamic`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry_dynamic(&self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.lock().get_entry_dynamic(key, entry_type, metadata)
+    }
+
+    /// Writes a value to the datalog, see [`DataLogWriter::write`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write<T: IntoFrcValue>(&self, id: TypedEntryId<T>, value: T) -> Result<(), DataLogError> {
+        self.lock().write(id, value)
+    }
+
+    /// Writes a value to the datalog with a specific timestamp, see [`DataLogWriter::write_timestamped`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write_timestamped<T: IntoFrcValue>(&self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.lock().write_timestamped(id, value, timestamp)
+    }
+
+    /// Writes a value to the datalog, see [`DataLogWriter::write_dynamic`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::EntryTypeMismatch`] if the value type doesn't match the entry type
+    pub fn write_dynamic(&self, id: EntryId, value: FrcTimestampedValue) -> Result<(), DataLogError> {
+        self.lock().write_dynamic(id, value)
+    }
+
+    /// Closes an entry, see [`DataLogWriter::close_entry`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    pub fn close_entry(&self, id: EntryId) -> Result<(), DataLogError> {
+        self.lock().close_entry(id)
+    }
+
+    /// Flushes the writer
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn flush(&self) -> Result<(), DataLogError> {
+        self.lock().flush()
+    }
+}
+
+/// A typed entry that carries its own handle to the log, like wpilib's `DoubleLogEntry`
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{handle::DataLogHandle, DataLogWriter};
+/// 
+/// let handle = DataLogHandle::new(DataLogWriter::create("path/to/file", "").unwrap());
+/// let velocity = handle.entry::<f64>("/drivetrain/velocity", None).expect("Failed to get entry");
+/// velocity.append(1.5).expect("Failed to append value");
+/// ```
+#[derive(Debug)]
+pub struct Entry<T: IntoFrcValue, W: Write = std::fs::File> {
+    handle: DataLogHandle<W>,
+    id: TypedEntryId<T>,
+    key: String
+}
+
+impl <T: IntoFrcValue, W: Write> Clone for Entry<T, W> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone(),
+            id: self.id,
+            key: self.key.clone()
+        }
+    }
+}
+
+impl <T: IntoFrcValue, W: Write> Entry<T, W> {
+    /// The key of the entry
+    #[must_use]
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// The id of the entry, for use with the writer directly
+    #[must_use]
+    pub const fn id(&self) -> TypedEntryId<T> {
+        self.id
+    }
+
+    /// Appends a value timestamped with the current time
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append(&self, value: T) -> Result<(), DataLogError> {
+        self.handle.write(self.id, value)
+    }
+
+    /// Appends a value with a specific timestamp
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_at(&self, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.write_timestamped(self.id, value, timestamp)
+    }
+
+    /// Replaces the metadata of the entry
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn set_metadata(&self, metadata: impl ToString) -> Result<(), DataLogError> {
+        self.handle.lock().set_entry_metadata(self.id.into(), metadata)
+    }
+
+    /// Turns the entry into a guard that finishes it when dropped
+    #[must_use]
+    pub const fn scoped(self) -> ScopedEntry<T, W> {
+        ScopedEntry { entry: self }
+    }
+
+    /// Finishes the entry, clones of it will fail to append afterwards
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is already closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn close(self) -> Result<(), DataLogError> {
+        self.handle.close_entry(self.id.into())
+    }
+}
+
+/// An [`Entry`] that is finished when it goes out of scope, even on early returns,
+/// useful for temporary diagnostic channels
+#[derive(Debug)]
+pub struct ScopedEntry<T: IntoFrcValue, W: Write = std::fs::File> {
+    entry: Entry<T, W>
+}
+
+impl <T: IntoFrcValue, W: Write> Deref for ScopedEntry<T, W> {
+    type Target = Entry<T, W>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.entry
+    }
+}
+
+impl <T: IntoFrcValue, W: Write> Drop for ScopedEntry<T, W> {
+    fn drop(&mut self) {
+        // best effort, the entry may have already been closed through a clone
+        let _ = self.entry.handle.close_entry(self.entry.id.into());
+    }
+}
+
+impl <W: Write> Entry<f64, W> {
+    /// Starts a [`ScopedTimer`] appending to this entry,
+    /// cheaper than [`DataLogHandle::timer`] for code that runs every loop
+    #[must_use]
+    pub fn start_timer(&self) -> ScopedTimer<W> {
+        ScopedTimer::new(self.clone())
+    }
+}
+
+/// A guard that appends the milliseconds since it was started to a double entry when it's dropped,
+/// for profiling robot code in the log
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{handle::DataLogHandle, DataLogWriter};
+/// 
+/// let handle = DataLogHandle::new(DataLogWriter::create("path/to/file", "").expect("Failed to create writer"));
+/// {
+///     let _timer = handle.timer("/profiling/drive_loop_ms").expect("Failed to start timer");
+///     // the drive loop
+/// }
+/// ```
+#[derive(Debug)]
+pub struct ScopedTimer<W: Write = std::fs::File> {
+    entry: Entry<f64, W>,
+    start: Instant
+}
+
+impl <W: Write> ScopedTimer<W> {
+    fn new(entry: Entry<f64, W>) -> Self {
+        Self {
+            entry,
+            start: Instant::now()
+        }
+    }
+
+    /// The time since the timer was started
+    #[must_use]
+    pub fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+}
+
+impl <W: Write> Drop for ScopedTimer<W> {
+    fn drop(&mut self) {
+        // drop can't fail, a lost sample only costs one point of the profile
+        let _ = self.entry.append(self.start.elapsed().as_secs_f64() * 1000.0);
+    }
+}
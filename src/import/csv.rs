@@ -0,0 +1,210 @@
+use std::io::{Read, Write};
+
+use frclib_core::value::{FrcTimestamp, FrcType, FrcValue};
+
+use crate::{time::from_seconds, DataLogError, DataLogWriter};
+
+/// Configuration for [`import_csv`]
+#[derive(Debug, Clone, Copy)]
+pub struct CsvImportConfig<'a> {
+    /// The column holding the timestamps, the first column if [`None`]
+    pub timestamp_column: Option<&'a str>,
+    /// Timestamps are seconds, like `83.250000`, instead of microseconds
+    pub timestamps_in_seconds: bool,
+    /// The types of columns that shouldn't get the type their values look like,
+    /// like a `double` column that only holds whole numbers
+    pub column_types: &'a [(&'a str, FrcType)]
+}
+impl Default for CsvImportConfig<'_> {
+    fn default() -> Self {
+        Self {
+            timestamp_column: None,
+            timestamps_in_seconds: true,
+            column_types: &[]
+        }
+    }
+}
+
+/// Splits csv text into rows of fields, unquoting quoted fields and leaving out blank lines
+fn parse_rows(text: &str) -> Result<Vec<Vec<String>>, DataLogError> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(char) = chars.next() {
+        match (quoted, char) {
+            (true, '"') if chars.peek() == Some(&'"') => {
+                let _ = chars.next();
+                field.push('"');
+            }
+            (true, '"') => quoted = false,
+            (false, '"') if field.is_empty() => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') if chars.peek() == Some(&'\n') => {}
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                if row.iter().any(|field| !field.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            (_, char) => field.push(char)
+        }
+    }
+    if quoted {
+        return Err(DataLogError::Import("CSV field is missing its closing quote"));
+    }
+    row.push(field);
+    if row.iter().any(|field| !field.is_empty()) {
+        rows.push(row);
+    }
+    Ok(rows)
+}
+
+/// The elements of an array written like `[1.0, 2.0]`
+fn array_elements(field: &str) -> Option<Vec<&str>> {
+    let inner = field.strip_prefix('[')?.strip_suffix(']')?.trim();
+    if inner.is_empty() {
+        return Some(Vec::new());
+    }
+    Some(inner.split(',').map(str::trim).collect())
+}
+
+/// The narrowest of `boolean`, `int64` and `double` all `fields` parse as
+fn scalar_type<'a>(mut fields: impl Iterator<Item = &'a str> + Clone) -> Option<FrcType> {
+    if fields.clone().all(|field| field.parse::<bool>().is_ok()) {
+        Some(FrcType::Boolean)
+    } else if fields.clone().all(|field| field.parse::<i64>().is_ok()) {
+        Some(FrcType::Int)
+    } else if fields.all(|field| field.parse::<f64>().is_ok()) {
+        Some(FrcType::Double)
+    } else {
+        None
+    }
+}
+
+/// The type the values of a column look like, arrays of booleans or numbers or else strings
+fn infer_type(fields: &[&str]) -> FrcType {
+    if let Some(scalar) = scalar_type(fields.iter().copied()) {
+        return scalar;
+    }
+    let arrays = fields.iter().map(|field| array_elements(field)).collect::<Option<Vec<_>>>();
+    match arrays.and_then(|arrays| scalar_type(arrays.iter().flatten().copied())) {
+        Some(FrcType::Boolean) => FrcType::BooleanArray,
+        Some(FrcType::Int) => FrcType::IntArray,
+        Some(FrcType::Double) => FrcType::DoubleArray,
+        _ => FrcType::String
+    }
+}
+
+/// Parses a field as a value of `ty`
+fn parse_value(field: &str, ty: &FrcType) -> Option<FrcValue> {
+    fn elements<T: std::str::FromStr>(field: &str) -> Option<Box<[T]>> {
+        array_elements(field)?.into_iter().map(|element| element.parse().ok()).collect()
+    }
+    Some(match ty {
+        FrcType::Boolean => FrcValue::Boolean(field.parse().ok()?),
+        FrcType::Int => FrcValue::Int(field.parse().ok()?),
+        FrcType::Float => FrcValue::Float(field.parse().ok()?),
+        FrcType::Double => FrcValue::Double(field.parse().ok()?),
+        FrcType::String => FrcValue::String(field.into()),
+        FrcType::BooleanArray => FrcValue::BooleanArray(elements(field)?),
+        FrcType::IntArray => FrcValue::IntArray(elements(field)?),
+        FrcType::FloatArray => FrcValue::FloatArray(elements(field)?),
+        FrcType::DoubleArray => FrcValue::DoubleArray(elements(field)?),
+        _ => return None
+    })
+}
+
+/// Writes the columns of a csv table with a timestamp column into a log, for viewing data captured by
+/// other tools, like bench tests, alongside robot logs
+///
+/// Every column other than the timestamp column becomes an entry keyed by its header. Columns get the
+/// narrowest type all their values parse as, `boolean`, `int64` or `double`, or arrays of them written
+/// like `[1.0, 2.0]`, and are `string` otherwise, unless [`CsvImportConfig::column_types`] says otherwise.
+/// Empty fields are left out, so tables written by [`write_csv`](crate::export::csv::write_csv) import back.
+/// Rows don't have to be in timestamp order.
+///
+/// Returns how many values were written.
+/// # Example
+/// ```rust
+/// use frclib_datalog::{import::csv::{import_csv, CsvImportConfig}, reader::DataLogReaderConfig, DataLogReader, DataLogWriter};
+///
+/// let csv = "time,current,label\n0.5,12.5,start\n0.0,3,\n";
+/// let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+/// let values = import_csv(csv.as_bytes(), CsvImportConfig::default(), &mut writer).expect("Failed to import csv");
+/// assert_eq!(values, 3);
+/// let bytes = writer.into_inner().expect("Failed to finish log");
+///
+/// let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default()).expect("Failed to read log");
+/// assert_eq!(reader.read_entry_type_str("current")[0].value, "double");
+/// assert_eq!(reader.read_entry("current")[0].timestamp, 0);
+/// ```
+///
+/// # Errors
+/// - [`DataLogError::Import`] if the table has no timestamp column, a timestamp or a field of a column
+///   with a configured type doesn't parse, a row has more fields than the header or a quote isn't closed
+/// - [`DataLogError::RecordType`] if a configured column type can't be written from csv, like a struct
+/// - [`DataLogError::Io`] if reading the table fails
+/// - Any error of [`DataLogWriter::get_entry_dynamic`] and [`DataLogWriter::write_dynamic`]
+pub fn import_csv<W: Write>(mut input: impl Read, config: CsvImportConfig<'_>, writer: &mut DataLogWriter<W>) -> Result<usize, DataLogError> {
+    let mut text = String::new();
+    let _ = input.read_to_string(&mut text)?;
+    let mut rows = parse_rows(&text)?.into_iter();
+    let Some(header) = rows.next() else {
+        return Ok(0);
+    };
+    let timestamp_index = match config.timestamp_column {
+        Some(column) => header.iter().position(|name| name == column),
+        None => (!header.is_empty()).then_some(0)
+    }.ok_or(DataLogError::Import("CSV has no timestamp column"))?;
+
+    let mut rows = rows
+        .map(|row| {
+            if row.len() > header.len() {
+                return Err(DataLogError::Import("CSV row has more fields than the header"));
+            }
+            let timestamp = row.get(timestamp_index).map(|field| field.trim()).unwrap_or_default();
+            let timestamp = if config.timestamps_in_seconds {
+                timestamp.parse::<f64>().ok().map(from_seconds)
+            } else {
+                timestamp.parse::<FrcTimestamp>().ok()
+            }.ok_or(DataLogError::Import("CSV timestamp is not a number"))?;
+            Ok((timestamp, row))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    // stable, so values at the same timestamp keep the order of the table
+    rows.sort_by_key(|(timestamp, _)| *timestamp);
+
+    let mut written = 0;
+    for (index, key) in header.iter().enumerate() {
+        if index == timestamp_index {
+            continue;
+        }
+        let fields = rows.iter()
+            .filter_map(|(timestamp, row)| Some((*timestamp, row.get(index)?.as_str())))
+            .filter(|(_, field)| !field.is_empty())
+            .collect::<Vec<_>>();
+        let configured = config.column_types.iter().find(|(column, _)| column == key);
+        let ty = match configured {
+            Some((_, ty)) => *ty,
+            None if fields.is_empty() => continue,
+            None => infer_type(&fields.iter().map(|(_, field)| field.trim()).collect::<Vec<_>>())
+        };
+        if !matches!(ty, FrcType::Boolean | FrcType::Int | FrcType::Float | FrcType::Double | FrcType::String
+            | FrcType::BooleanArray | FrcType::IntArray | FrcType::FloatArray | FrcType::DoubleArray) {
+            return Err(DataLogError::RecordType("CSV columns can't hold values of this type"));
+        }
+
+        let id = writer.get_entry_dynamic(key, ty, None)?;
+        for (timestamp, field) in fields {
+            let field = if ty == FrcType::String { field } else { field.trim() };
+            let value = parse_value(field, &ty)
+                .ok_or(DataLogError::Import("CSV field doesn't parse as the type of its column"))?;
+            writer.write_dynamic(id, value.to_timestamped(timestamp))?;
+            written += 1;
+        }
+    }
+    Ok(written)
+}
@@ -0,0 +1,239 @@
+use std::{collections::HashMap, io::{Read, Write}};
+
+use frclib_core::value::{FrcTimestamp, FrcType, FrcValue};
+use serde_json::Value;
+
+use crate::{proto::entries::get_type_from_str, time::from_seconds, writer::EntryId, DataLogError, DataLogWriter};
+
+/// Configuration for [`import_json`]
+#[derive(Debug, Clone, Copy)]
+pub struct JsonImportConfig {
+    /// Timestamps are seconds, like `83.25`, instead of microseconds
+    pub timestamps_in_seconds: bool
+}
+impl Default for JsonImportConfig {
+    fn default() -> Self {
+        Self {
+            timestamps_in_seconds: true
+        }
+    }
+}
+
+/// One value to write, before it is converted to the type of its entry
+struct JsonRecord {
+    timestamp: FrcTimestamp,
+    key: String,
+    type_str: Option<String>,
+    value: Value
+}
+
+/// How the values of an entry are written
+enum Target {
+    /// Converted to a value of a type with an [`FrcValue`] variant
+    Value(FrcType),
+    /// Written as bytes, for raw, struct and types this crate doesn't know
+    Raw
+}
+
+/// The entry type a value looks like, [`None`] for `null`, objects and mixed arrays
+fn infer_type(value: &Value) -> Option<&'static str> {
+    fn scalar(value: &Value) -> Option<&'static str> {
+        match value {
+            Value::Bool(_) => Some("boolean"),
+            Value::Number(number) if number.is_i64() => Some("int64"),
+            Value::Number(_) => Some("double"),
+            Value::String(_) => Some("string"),
+            _ => None
+        }
+    }
+    match value {
+        Value::Array(values) => {
+            let mut types = values.iter().map(scalar);
+            let first = types.next().unwrap_or(Some("double"))?;
+            // ints widen to doubles, anything else has to be the same type throughout
+            let element = types.try_fold(first, |element, ty| match (element, ty?) {
+                (element, ty) if element == ty => Some(element),
+                ("int64" | "double", "int64" | "double") => Some("double"),
+                _ => None
+            })?;
+            Some(match element {
+                "boolean" => "boolean[]",
+                "int64" => "int64[]",
+                "double" => "double[]",
+                _ => "string[]"
+            })
+        }
+        value => scalar(value)
+    }
+}
+
+/// Converts a value to `ty`, `null` becomes NaN for floats like [`write_json`](crate::export::json::write_json) writes it
+#[allow(clippy::cast_possible_truncation)]
+fn convert_value(value: &Value, ty: &FrcType) -> Option<FrcValue> {
+    fn float(value: &Value) -> Option<f64> {
+        if value.is_null() {
+            Some(f64::NAN)
+        } else {
+            value.as_f64()
+        }
+    }
+    fn elements<T>(value: &Value, convert: impl Fn(&Value) -> Option<T>) -> Option<Box<[T]>> {
+        value.as_array()?.iter().map(convert).collect()
+    }
+    Some(match ty {
+        FrcType::Boolean => FrcValue::Boolean(value.as_bool()?),
+        FrcType::Int => FrcValue::Int(value.as_i64()?),
+        FrcType::Float => FrcValue::Float(float(value)? as f32),
+        FrcType::Double => FrcValue::Double(float(value)?),
+        FrcType::String => FrcValue::String(value.as_str()?.into()),
+        FrcType::BooleanArray => FrcValue::BooleanArray(elements(value, Value::as_bool)?),
+        FrcType::IntArray => FrcValue::IntArray(elements(value, Value::as_i64)?),
+        FrcType::FloatArray => FrcValue::FloatArray(elements(value, |value| float(value).map(|value| value as f32))?),
+        FrcType::DoubleArray => FrcValue::DoubleArray(elements(value, float)?),
+        FrcType::StringArray => FrcValue::StringArray(elements(value, |value| value.as_str().map(Box::from))?),
+        _ => return None
+    })
+}
+
+/// The bytes of a raw value, an array of bytes, the text of a string or for `json` entries the json itself
+fn convert_bytes(value: &Value, type_str: &str) -> Option<Vec<u8>> {
+    match value {
+        Value::String(text) => Some(text.clone().into_bytes()),
+        Value::Array(bytes) if type_str != "json" => bytes.iter()
+            .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+            .collect(),
+        value if type_str == "json" => Some(value.to_string().into_bytes()),
+        _ => None
+    }
+}
+
+/// Reads a `{"t", "key", "type", "value"}` object
+fn read_record(object: &Value, config: JsonImportConfig) -> Result<JsonRecord, DataLogError> {
+    let t = object.get("t").ok_or(DataLogError::Import("JSON record has no timestamp"))?;
+    let timestamp = if config.timestamps_in_seconds {
+        t.as_f64().map(from_seconds)
+    } else {
+        t.as_u64()
+    }.ok_or(DataLogError::Import("JSON timestamp is not a number"))?;
+    let key = object.get("key").and_then(Value::as_str)
+        .ok_or(DataLogError::Import("JSON record has no key"))?;
+    let type_str = match object.get("type") {
+        None => None,
+        Some(Value::String(type_str)) => Some(type_str.clone()),
+        Some(_) => return Err(DataLogError::Import("JSON record type is not a string"))
+    };
+    Ok(JsonRecord {
+        timestamp,
+        key: key.to_string(),
+        type_str,
+        value: object.get("value").cloned().unwrap_or(Value::Null)
+    })
+}
+
+/// Reads the records of one top level json value, an array of records, a record
+/// or a document like [`JsonLayout::Document`](crate::export::json::JsonLayout::Document) writes
+fn read_records(value: Value, config: JsonImportConfig, records: &mut Vec<JsonRecord>) -> Result<(), DataLogError> {
+    match value {
+        Value::Array(values) => {
+            for value in &values {
+                records.push(read_record(value, config)?);
+            }
+        }
+        Value::Object(object) if object.contains_key("key") => records.push(read_record(&Value::Object(object), config)?),
+        Value::Object(entries) => {
+            for (key, entry) in entries {
+                let type_str = entry.get("type").and_then(Value::as_str).map(String::from);
+                let values = entry.get("values").and_then(Value::as_array)
+                    .ok_or(DataLogError::Import("JSON document entry has no values"))?;
+                for value in values {
+                    let mut record = read_record(&serde_json::json!({ "key": key, "t": value.get("t"), "value": value.get("value") }), config)?;
+                    record.type_str.clone_from(&type_str);
+                    records.push(record);
+                }
+            }
+        }
+        _ => return Err(DataLogError::Import("JSON value is not a record"))
+    }
+    Ok(())
+}
+
+/// Writes values described as json into a log, so scripts in any language can make logs
+/// without implementing the binary format
+///
+/// Values are `{"t": 1.5, "key": "speed", "type": "double", "value": 2.5}` objects, given as
+/// json lines, one array of them or the two layouts [`write_json`](crate::export::json::write_json) writes.
+/// `type` is an entry type string and can be left out, the entry then gets the type of its first value:
+/// `boolean`, `int64`, `double`, `string` or arrays of them. Types without a value variant, like `raw`,
+/// `struct:Pose2d` or `json`, take an array of bytes or a string, and `json` entries any json.
+/// Values don't have to be in timestamp order.
+///
+/// Returns how many values were written.
+/// # Example
+/// ```rust
+/// use frclib_datalog::{import::json::{import_json, JsonImportConfig}, reader::DataLogReaderConfig, DataLogReader, DataLogWriter};
+///
+/// let json = r#"
+///     {"t": 1.0, "key": "speed", "type": "double", "value": 2}
+///     {"t": 0.5, "key": "mode", "value": "auto"}
+/// "#;
+/// let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+/// let values = import_json(json.as_bytes(), JsonImportConfig::default(), &mut writer).expect("Failed to import json");
+/// assert_eq!(values, 2);
+/// let bytes = writer.into_inner().expect("Failed to finish log");
+///
+/// let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default()).expect("Failed to read log");
+/// assert_eq!(reader.read_entry_type_str("speed")[0].value, "double");
+/// assert_eq!(reader.read_entry("mode")[0].timestamp, 500_000);
+/// ```
+///
+/// # Errors
+/// - [`DataLogError::Import`] if a value is missing its timestamp or key, has no type and no type can be
+///   told from it or doesn't convert to the type of its entry
+/// - [`DataLogError::EntryTypeMismatch`] if values of one key are given different types
+/// - [`DataLogError::Io`] if reading the input fails or it isn't json
+/// - Any error of [`DataLogWriter::write_dynamic`] and [`DataLogWriter::write_raw`]
+pub fn import_json<W: Write>(input: impl Read, config: JsonImportConfig, writer: &mut DataLogWriter<W>) -> Result<usize, DataLogError> {
+    let mut records = Vec::new();
+    for value in serde_json::Deserializer::from_reader(input).into_iter::<Value>() {
+        read_records(value.map_err(std::io::Error::from)?, config, &mut records)?;
+    }
+    // stable, so values at the same timestamp keep the order of the input
+    records.sort_by_key(|record| record.timestamp);
+
+    let mut entries = HashMap::<String, (String, Target, EntryId)>::new();
+    for record in &records {
+        let type_str = match (&record.type_str, entries.get(&record.key)) {
+            (Some(type_str), _) | (None, Some((type_str, _, _))) => type_str.clone(),
+            (None, None) => infer_type(&record.value)
+                .ok_or(DataLogError::Import("JSON value has no type and its type can't be told from it"))?
+                .to_string()
+        };
+        if !entries.contains_key(&record.key) {
+            let (target, id) = match get_type_from_str(&type_str) {
+                Some(FrcType::Raw | FrcType::Struct(_) | FrcType::StructArray(_)) | None => {
+                    (Target::Raw, writer.get_entry_raw(&record.key, &type_str, None)?)
+                }
+                Some(ty) => (Target::Value(ty), writer.get_entry_dynamic(&record.key, ty, None)?)
+            };
+            let _ = entries.insert(record.key.clone(), (type_str.clone(), target, id));
+        }
+        let (entry_type, target, id) = entries.get(&record.key).ok_or(DataLogError::NoSuchEntry)?;
+        if *entry_type != type_str {
+            return Err(DataLogError::EntryTypeMismatch);
+        }
+
+        match target {
+            Target::Value(ty) => {
+                let value = convert_value(&record.value, ty)
+                    .ok_or(DataLogError::Import("JSON value doesn't convert to the type of its entry"))?;
+                writer.write_dynamic(*id, value.to_timestamped(record.timestamp))?;
+            }
+            Target::Raw => {
+                let bytes = convert_bytes(&record.value, entry_type)
+                    .ok_or(DataLogError::Import("JSON value doesn't convert to the type of its entry"))?;
+                writer.write_raw(*id, &bytes, record.timestamp)?;
+            }
+        }
+    }
+    Ok(records.len())
+}
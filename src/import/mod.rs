@@ -0,0 +1,4 @@
+/// Importing csv tables of timestamped columns
+pub mod csv;
+/// Importing values described as json for scripts that make logs
+pub mod json;
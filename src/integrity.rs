@@ -0,0 +1,238 @@
+use std::io::Write;
+
+use frclib_core::value::FrcTimestamp;
+
+use crate::{proto::records::{header_len, split_record, write_encoded_data_record, ControlRecord}, DataLogError};
+
+/// The key of the entry checkpoints are written to
+pub const CHECKPOINT_KEY: &str = "/.checkpoint";
+/// The type of the checkpoint entry, its records hold the crc32 and the length of the bytes they cover
+pub const CHECKPOINT_TYPE: &str = "checkpoint:crc32";
+/// The id of the checkpoint entry, reserved as writers hand out ids counting up from 1
+pub const CHECKPOINT_ENTRY_ID: u32 = u32::MAX;
+/// How many bytes an [`IntegritySink`] writes between checkpoints by default, 64 KiB
+pub const DEFAULT_CHECKPOINT_INTERVAL: usize = 64 * 1024;
+
+/// The crc32 and little endian length of the covered bytes
+const CHECKPOINT_PAYLOAD_LEN: usize = 12;
+
+/// A sink that adds checkpoint records to the log, each holding the crc32 of the bytes since the previous one
+///
+/// Checkpoints are data records of the reserved [`CHECKPOINT_KEY`] entry so the log stays readable by any tool,
+/// [`verify_checkpoints`] checks them to find silent corruption from flaky storage like SD cards.
+/// A checkpoint is written once the interval has been covered, on
+/// [`DataLogWriter::write_checkpoint`](crate::DataLogWriter::write_checkpoint) and by [`IntegritySink::finish`].
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::{integrity::IntegritySink, DataLogWriter};
+///
+/// let file = File::create("/home/lvuser/logs/match.wpilog").expect("Failed to create file");
+/// let mut writer = DataLogWriter::new(IntegritySink::new(file), "").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+///
+/// let file = writer.close().expect("Failed to close writer").finish().expect("Failed to finish log");
+/// ```
+#[derive(Debug)]
+pub struct IntegritySink<W: Write> {
+    inner: W,
+    /// The start of a record cut off between writes
+    pending: Vec<u8>,
+    scratch: Vec<u8>,
+    header_written: bool,
+    hasher: crc32fast::Hasher,
+    covered: u64,
+    interval: usize,
+    last_timestamp: FrcTimestamp,
+    checkpoints: u64
+}
+
+impl <W: Write> IntegritySink<W> {
+    /// Creates a sink that writes a checkpoint every [`DEFAULT_CHECKPOINT_INTERVAL`] bytes
+    #[must_use]
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+            scratch: Vec::new(),
+            header_written: false,
+            hasher: crc32fast::Hasher::new(),
+            covered: 0,
+            interval: DEFAULT_CHECKPOINT_INTERVAL,
+            last_timestamp: 0,
+            checkpoints: 0
+        }
+    }
+
+    /// Sets how many bytes are written between checkpoints, defaults to [`DEFAULT_CHECKPOINT_INTERVAL`]
+    #[must_use]
+    pub const fn with_interval(mut self, interval: usize) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// The number of checkpoints written
+    #[must_use]
+    pub const fn checkpoints(&self) -> u64 {
+        self.checkpoints
+    }
+
+    /// The inner sink
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Writes a checkpoint for everything since the previous one, if anything was written since
+    ///
+    /// # Errors
+    /// - an IO error if writing to the inner sink fails
+    pub(crate) fn checkpoint(&mut self) -> std::io::Result<()> {
+        self.pass_on()?;
+        if self.covered > 0 {
+            self.scratch.clear();
+            self.push_checkpoint()?;
+            self.inner.write_all(&self.scratch)?;
+        }
+        Ok(())
+    }
+
+    /// Writes a final checkpoint and returns the inner sink
+    ///
+    /// # Errors
+    /// - an IO error if writing to the inner sink fails
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.checkpoint()?;
+        self.inner.write_all(&self.pending)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Adds a checkpoint record to `scratch` and starts covering anew
+    fn push_checkpoint(&mut self) -> std::io::Result<()> {
+        let hasher = std::mem::take(&mut self.hasher);
+        let mut payload = [0u8; CHECKPOINT_PAYLOAD_LEN];
+        payload[..4].copy_from_slice(&hasher.finalize().to_le_bytes());
+        payload[4..].copy_from_slice(&self.covered.to_le_bytes());
+        write_encoded_data_record(self.last_timestamp, CHECKPOINT_ENTRY_ID, &payload, &mut self.scratch)
+            .map_err(std::io::Error::other)?;
+        self.covered = 0;
+        self.checkpoints += 1;
+        Ok(())
+    }
+
+    /// Writes the header and the start of the checkpoint entry once `pending` holds the whole header,
+    /// returning its length
+    fn pass_on_header(&mut self) -> std::io::Result<Option<usize>> {
+        let Some(len) = header_len(&self.pending) else {
+            return Ok(None);
+        };
+        self.inner.write_all(&self.pending[..len])?;
+        self.header_written = true;
+
+        self.scratch.clear();
+        ControlRecord::Start(CHECKPOINT_KEY.to_string(), CHECKPOINT_TYPE.to_string(), String::new())
+            .write_to(0, CHECKPOINT_ENTRY_ID, &mut self.scratch)
+            .map_err(std::io::Error::other)?;
+        self.hasher.update(&self.scratch);
+        self.covered += self.scratch.len() as u64;
+        self.inner.write_all(&self.scratch)?;
+        Ok(Some(len))
+    }
+
+    /// Writes every whole record in `pending` to the inner sink, adding checkpoints along the way
+    fn pass_on(&mut self) -> std::io::Result<()> {
+        let mut consumed = if self.header_written {
+            0
+        } else {
+            match self.pass_on_header()? {
+                Some(len) => len,
+                None => return Ok(())
+            }
+        };
+
+        self.scratch.clear();
+        while let Some((_, timestamp, _, len)) = split_record(&self.pending[consumed..]) {
+            let record = &self.pending[consumed..consumed + len];
+            self.hasher.update(record);
+            self.scratch.extend_from_slice(record);
+            self.covered += len as u64;
+            self.last_timestamp = timestamp;
+            consumed += len;
+            if self.covered >= self.interval as u64 {
+                self.push_checkpoint()?;
+            }
+        }
+        self.inner.write_all(&self.scratch)?;
+        let _ = self.pending.drain(..consumed);
+        Ok(())
+    }
+}
+
+impl <W: Write> Write for IntegritySink<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.pass_on()?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.pass_on()?;
+        self.inner.flush()
+    }
+}
+
+/// The result of checking the checkpoints of a log, see [`verify_checkpoints`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckpointReport {
+    /// The number of checkpoints whose bytes are intact
+    pub passed: usize,
+    /// The byte offsets of the checkpoints whose bytes are corrupted
+    pub failed: Vec<usize>,
+    /// The number of bytes after the last checkpoint, which can't be checked
+    pub unchecked_bytes: usize
+}
+impl CheckpointReport {
+    /// Whether every checkpoint passed
+    #[must_use]
+    pub const fn is_intact(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// Checks every checkpoint written by an [`IntegritySink`] against the bytes it covers
+///
+/// Corruption that breaks the framing of a record also fails the checkpoint after it,
+/// the rest of the log can't be split into records once that happens and is counted as unchecked.
+///
+/// # Errors
+/// - [`DataLogError::InvalidDataLog`] if `bytes` doesn't start with a whole header
+pub fn verify_checkpoints(bytes: &[u8]) -> Result<CheckpointReport, DataLogError> {
+    let mut offset = header_len(bytes).ok_or(DataLogError::InvalidDataLog)?;
+    let mut report = CheckpointReport::default();
+    let mut hasher = crc32fast::Hasher::new();
+    let mut covered = 0u64;
+    let mut segment_start = offset;
+
+    while let Some((id, _, payload, len)) = split_record(&bytes[offset..]) {
+        if id == CHECKPOINT_ENTRY_ID && payload.len() == CHECKPOINT_PAYLOAD_LEN {
+            let crc = payload.get(..4).and_then(|crc| <[u8; 4]>::try_from(crc).ok()).map(u32::from_le_bytes);
+            let expected_len = payload.get(4..).and_then(|len| <[u8; 8]>::try_from(len).ok()).map(u64::from_le_bytes);
+            let hasher = std::mem::take(&mut hasher);
+            if crc == Some(hasher.finalize()) && expected_len == Some(covered) {
+                report.passed += 1;
+            } else {
+                report.failed.push(offset);
+            }
+            covered = 0;
+            segment_start = offset + len;
+        } else {
+            hasher.update(&bytes[offset..offset + len]);
+            covered += len as u64;
+        }
+        offset += len;
+    }
+    report.unchecked_bytes = bytes.len() - segment_start;
+    Ok(report)
+}
@@ -15,7 +15,6 @@
     unused_lifetimes,
     unused_unsafe,
     useless_ptr_null_checks,
-    cenum_impl_drop_cast,
     while_true,
     unused_features,
     absolute_paths_not_starting_with_crate,
@@ -30,7 +29,6 @@
     clippy::unwrap_used,
     clippy::panicking_unwrap,
     missing_abi,
-    missing_fragment_specifier,
     clippy::missing_safety_doc,
     clippy::missing_asserts_for_indexing,
     clippy::missing_assert_message,
@@ -69,10 +67,242 @@ pub mod reader;
 /// TODO
 pub mod writer;
 
+/// # Shared Writing
+/// 
+/// A cloneable handle for writing to one log from many threads
+pub mod handle;
+
+/// # Background Writing
+/// 
+/// A writer that does serialization and IO on its own thread
+pub mod background_writer;
+
+/// # Channel Writing
+/// 
+/// A writer driven by a channel of keyed values on its own thread
+pub mod channel_writer;
+
+/// # Real Time Writing
+/// 
+/// A lock free, allocation free append path for real time threads
+pub mod realtime;
+
+/// # Rotating Writing
+/// 
+/// A writer that starts a new file on an interval
+pub mod rotating_writer;
+
+/// # Atomic Finalizing
+/// 
+/// A file that only gets its final name once the log is complete
+pub mod temp_file;
+
+/// # Dry Runs
+/// 
+/// A sink that only counts bytes, for checking logging code without producing a file
+pub mod dry_run;
+
+/// # Embedded Writing
+/// 
+/// A minimal, allocation free writer over `embedded-io` sinks for coprocessors.
+/// The crate itself still needs `std`, so this doesn't build for `no_std` targets yet
+#[cfg(feature = "embedded-io")]
+pub mod embedded;
+
+/// # Deferred Errors
+/// 
+/// A writer whose appends capture errors instead of returning them
+pub mod deferred_writer;
+
+/// # Structured Logging
+/// 
+/// Logging whole structs with one entry per field
+pub mod loggable;
+
+/// # Scoped Writing
+/// 
+/// A view of a writer that puts its keys under a prefix
+pub mod scoped;
+
+/// # `WPILib` Compatibility
+/// 
+/// The `DataLog` and `*LogEntry` API of `wpiutil` for porting Java and C++ logging code
+pub mod wpilib_compat;
+
+/// # Console Messages
+/// 
+/// Leveled messages in the `messages` entry like `WPILib`'s console capture
+pub mod messages;
+
+/// # FRC File Naming
+/// 
+/// Log file names following the `WPILib` convention of `FRC_yyyyMMdd_HHmmss.wpilog`
+pub mod frc_naming;
+
+/// # Match Segments
+/// 
+/// Starting a new log file every time the robot is enabled
+pub mod match_segments;
+
+/// # Session Metadata
+/// 
+/// A typed builder for the header metadata of a log
+pub mod session;
+
+/// # Entry Manifests
+/// 
+/// Declaring the entries of a program in one place
+pub mod manifest;
+
+/// # Log Directory Management
+/// 
+/// Retention limits for a directory of logs
+pub mod log_dir;
+
+/// # Error Recovery
+/// 
+/// A writer that continues the log in a new sink when writing fails
+pub mod recovery;
+
+/// # File Locking
+/// 
+/// Advisory locks that keep other processes from touching logs that are still being written
+pub mod locking;
+
+/// # Shutdown
+/// 
+/// Finalizing registered writers when the program panics or is interrupted
+pub mod shutdown;
+
+/// # Disk Space Watchdog
+/// 
+/// Watches free disk space while logging, requires the `disk-watchdog` feature
+#[cfg(feature = "disk-watchdog")]
+pub mod disk_watchdog;
+
+/// # Async Writing
+/// 
+/// A writer for tokio runtimes, requires the `tokio` feature
+#[cfg(feature = "tokio")]
+pub mod async_writer;
+
+/// # Network Streaming
+/// 
+/// A sink that streams the log over TCP
+pub mod network;
+
+/// # Network Tables Recording
+/// 
+/// Records `NetworkTables` topic updates into a log, requires the `networktables` feature
+#[cfg(feature = "networktables")]
+pub mod nt_bridge;
+
+/// # Mirrored Writing
+/// 
+/// A sink that writes the same log to several sinks
+pub mod tee;
+
+/// # Text Mirror
+/// 
+/// Mirroring the records of a log as readable lines while it is written
+pub mod text_mirror;
+
+/// # Driver Station Logs
+/// 
+/// Importing the `.dslog` and `.dsevents` files of the Driver Station into logs
+pub mod ds_logs;
+
+/// # Time Conversions
+/// 
+/// Converting timestamps to and from durations, seconds and, with the `chrono` feature, dates
+pub mod time;
+
+/// # Time Synchronization
+/// 
+/// A sink that moves timestamps onto a time base that is only known after logging starts
+pub mod time_sync;
+
+/// # Integrity Checkpoints
+/// 
+/// Checksum records for detecting corrupted logs, requires the `integrity` feature
+#[cfg(feature = "integrity")]
+pub mod integrity;
+
+/// # Encryption
+/// 
+/// Encrypting logs at rest and reading them back, requires the `encryption` feature
+#[cfg(feature = "encryption")]
+pub mod encryption;
+
+/// # Tracing
+/// 
+/// A `tracing` layer that records events and spans into a log, requires the `tracing` feature
+#[cfg(feature = "tracing")]
+pub mod tracing_layer;
+
+/// # Pre-Enable Buffering
+/// 
+/// A sink that only keeps the latest records until logging is armed
+pub mod arming;
+
+/// # Units
+/// 
+/// Unit conversion for entries that declare a `"unit"` in their metadata
+pub mod units;
+
+/// # Querying
+/// 
+/// A small query language for selecting entries and values from a [`DataLogReader`]
+pub mod query;
+
+/// # Statistics
+/// 
+/// Per entry aggregates computed while reading, and rolling aggregates computed while writing
+pub mod statistics;
+
+/// # Analysis
+/// 
+/// Helpers for common post match analysis of entries
+pub mod analysis;
+
+/// # Exporting
+/// 
+/// Converting entries into formats other tools understand
+pub mod export;
+
+/// # Importing
+/// 
+/// Converting files other tools write into logs
+pub mod import;
+
+/// # Rewriting
+/// 
+/// Copying logs record by record while trimming or combining them
+pub mod rewrite;
+
+/// # rosbag2 Conversion
+/// 
+/// Converting between logs and rosbag2 bags for ROS tools, requires the `rosbag2` feature
+#[cfg(feature = "rosbag2")]
+pub mod rosbag2;
+
+/// # Property Testing
+/// 
+/// `proptest` strategies for records and whole logs, requires the `proptest` feature
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
+/// # Fuzzing
+/// 
+/// Entry points for fuzzing the parser, requires the `fuzzing` feature
+#[cfg(feature = "fuzzing")]
+pub mod fuzzing;
+
 #[cfg(test)]
 mod test;
 
 pub use error::DataLogError;
+pub use proto::records::{ControlRecord, DataRecord, DataRecordRef, PackedArrayRef, PackedElement, Record, StringArrayRef};
 pub use reader::DataLogReader;
 pub use writer::DataLogWriter;
 
@@ -97,6 +327,7 @@ pub(crate) fn now() -> FrcTimestamp {
 
 /// A timestamped value
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct TimestampedValue<T> {
     /// The timestamp of the value
     pub timestamp: FrcTimestamp,
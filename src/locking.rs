@@ -0,0 +1,55 @@
+use std::{fs::{File, TryLockError}, path::Path};
+
+use crate::DataLogError;
+
+/// What [`DataLogReader::open`](crate::DataLogReader::open) does with a log another process is still writing
+///
+/// Writers created from a path hold an exclusive advisory lock (`flock` on unix) on their file until
+/// they are dropped, so processes that respect it, like log uploaders, can tell which logs are finished.
+/// On Windows the lock is mandatory, no other process can read the file while it is held,
+/// so there a snapshot can't be read and fails with [`DataLogError::Io`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LockedFilePolicy {
+    /// Fail with [`DataLogError::FileLocked`]
+    #[default]
+    Reject,
+    /// Block until the writer releases the file
+    Wait,
+    /// Read the records written so far, dropping a record that is cut off at the end
+    ReadSnapshot
+}
+
+/// Whether a process holds an exclusive lock on the file at `path`, like a writer that is still open
+///
+/// # Errors
+/// - [`DataLogError::FileDoesNotExist`] if there is no file at `path`
+/// - [`DataLogError::Io`] if the file can't be opened or locked
+pub fn is_locked(path: impl AsRef<Path>) -> Result<bool, DataLogError> {
+    let file = open_existing(path.as_ref())?;
+    // the shared lock is released when the file is closed
+    match file.try_lock_shared() {
+        Ok(()) => Ok(false),
+        Err(TryLockError::WouldBlock) => Ok(true),
+        Err(TryLockError::Error(err)) => Err(DataLogError::Io(err))
+    }
+}
+
+/// Opens the file at `path` for reading
+pub(crate) fn open_existing(path: &Path) -> Result<File, DataLogError> {
+    File::open(path).map_err(|err| match err.kind() {
+        std::io::ErrorKind::NotFound => DataLogError::FileDoesNotExist,
+        _ => DataLogError::Io(err)
+    })
+}
+
+/// Creates a new file at `path` holding an exclusive lock on it, which is released once it is closed
+pub(crate) fn create_locked(path: &Path) -> std::io::Result<File> {
+    let file = File::create_new(path)?;
+    if let Err(err) = file.try_lock() {
+        // the file was only just created, so nothing is lost by removing it
+        drop(file);
+        let _ = std::fs::remove_file(path);
+        return Err(err.into());
+    }
+    Ok(file)
+}
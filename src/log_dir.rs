@@ -0,0 +1,192 @@
+use std::{fs::File, path::{Path, PathBuf}, time::{Duration, SystemTime}};
+
+use crate::{frc_naming::FrcNamedDataLogWriter, locking::is_locked, DataLogError, DataLogWriter};
+
+/// A log file in a [`LogDirManager`] directory
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFile {
+    /// The path of the file
+    pub path: PathBuf,
+    /// The size of the file in bytes
+    pub size: u64,
+    /// When the file was last written
+    pub modified: SystemTime
+}
+
+/// Whether `path` is named like a log, `.wpilog` or a compressed `.wpilog.zst`
+fn is_log(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| name.ends_with(".wpilog") || name.ends_with(".wpilog.zst"))
+}
+
+/// Owns a directory of logs, deleting old logs to keep it within its retention limits
+/// and creating the writers for new ones
+///
+/// Logs are deleted oldest first by modification time.
+/// The newest log is never deleted as it is usually the one being written,
+/// and files that aren't logs are left alone.
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use frclib_datalog::log_dir::LogDirManager;
+///
+/// let manager = LogDirManager::new("/home/lvuser/logs")
+///     .with_max_total_size(500 * 1024 * 1024)
+///     .with_max_age(Duration::from_hours(24 * 30))
+///     .with_max_logs(50);
+///
+/// let mut log = manager.create_frc_writer("").expect("Failed to create writer");
+/// let entry = log.writer_mut().get_entry::<f64>("test", None).expect("Failed to get entry");
+/// log.writer_mut().write(entry, 10.0).expect("Failed to write entry");
+/// ```
+#[derive(Debug, Clone)]
+pub struct LogDirManager {
+    dir: PathBuf,
+    max_total_size: Option<u64>,
+    max_age: Option<Duration>,
+    max_logs: Option<usize>,
+    #[cfg(feature = "disk-watchdog")]
+    min_free_space: Option<u64>
+}
+
+impl LogDirManager {
+    /// Creates a manager for `dir` without any retention limits
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_total_size: None,
+            max_age: None,
+            max_logs: None,
+            #[cfg(feature = "disk-watchdog")]
+            min_free_space: None
+        }
+    }
+
+    /// Deletes the oldest logs while all logs together are larger than `max_total_size` bytes
+    #[must_use]
+    pub const fn with_max_total_size(mut self, max_total_size: u64) -> Self {
+        self.max_total_size = Some(max_total_size);
+        self
+    }
+
+    /// Deletes logs that haven't been written for longer than `max_age`
+    #[must_use]
+    pub const fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Keeps only the newest `max_logs` logs
+    #[must_use]
+    pub const fn with_max_logs(mut self, max_logs: usize) -> Self {
+        self.max_logs = Some(max_logs);
+        self
+    }
+
+    /// Deletes the oldest logs while less than `min_free_space` bytes are available on the filesystem,
+    /// requires the `disk-watchdog` feature
+    #[cfg(feature = "disk-watchdog")]
+    #[must_use]
+    pub const fn with_min_free_space(mut self, min_free_space: u64) -> Self {
+        self.min_free_space = Some(min_free_space);
+        self
+    }
+
+    /// The managed directory
+    #[must_use]
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Every log in the directory, oldest first
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if the directory can't be read
+    pub fn logs(&self) -> Result<Vec<LogFile>, DataLogError> {
+        let mut logs = Vec::new();
+        for dir_entry in std::fs::read_dir(&self.dir)? {
+            let dir_entry = dir_entry?;
+            let metadata = dir_entry.metadata()?;
+            if metadata.is_file() && is_log(&dir_entry.path()) {
+                logs.push(LogFile {
+                    path: dir_entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified()?
+                });
+            }
+        }
+        logs.sort_by(|a, b| a.modified.cmp(&b.modified).then_with(|| a.path.cmp(&b.path)));
+        Ok(logs)
+    }
+
+    /// Deletes logs until the directory is within every limit, returning the deleted logs
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if the directory can't be read or a log can't be deleted
+    pub fn enforce(&self) -> Result<Vec<LogFile>, DataLogError> {
+        let mut logs = self.logs()?;
+        // the newest log is kept no matter what
+        let newest = logs.pop();
+        let now = SystemTime::now();
+        let mut total_size = logs.iter().chain(newest.as_ref()).map(|log| log.size).sum::<u64>();
+        let mut count = logs.len() + usize::from(newest.is_some());
+
+        let mut deleted = Vec::new();
+        for log in logs {
+            let too_old = self.max_age.is_some_and(|max_age| {
+                now.duration_since(log.modified).is_ok_and(|age| age > max_age)
+            });
+            let too_large = self.max_total_size.is_some_and(|max| total_size > max);
+            let too_many = self.max_logs.is_some_and(|max| count > max);
+            // a log another process is still writing is never deleted
+            if !(too_old || too_large || too_many || self.is_space_low()?) || is_locked(&log.path)? {
+                continue;
+            }
+            std::fs::remove_file(&log.path)?;
+            total_size -= log.size;
+            count -= 1;
+            deleted.push(log);
+        }
+        Ok(deleted)
+    }
+
+    #[cfg(feature = "disk-watchdog")]
+    fn is_space_low(&self) -> Result<bool, DataLogError> {
+        match self.min_free_space {
+            Some(min_free_space) => Ok(fs4::available_space(&self.dir)? < min_free_space),
+            None => Ok(false)
+        }
+    }
+
+    #[cfg(not(feature = "disk-watchdog"))]
+    #[allow(clippy::unused_self, clippy::unnecessary_wraps)]
+    const fn is_space_low(&self) -> Result<bool, DataLogError> {
+        Ok(false)
+    }
+
+    /// Creates a writer for a new log named `name` in the directory and enforces the limits
+    /// with the new log counted, see [`DataLogWriter::create`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file named `name` already exists
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn create_writer(&self, name: impl AsRef<Path>, metadata: impl ToString) -> Result<DataLogWriter<File>, DataLogError> {
+        let writer = DataLogWriter::create(self.dir.join(name), metadata)?;
+        let _ = self.enforce()?;
+        Ok(writer)
+    }
+
+    /// Creates a writer for a new log named by the FRC convention and enforces the limits
+    /// with the new log counted, see [`FrcNamedDataLogWriter`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn create_frc_writer(&self, metadata: impl ToString) -> Result<FrcNamedDataLogWriter, DataLogError> {
+        let writer = FrcNamedDataLogWriter::create(&self.dir, metadata)?;
+        let _ = self.enforce()?;
+        Ok(writer)
+    }
+}
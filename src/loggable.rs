@@ -0,0 +1,73 @@
+use std::io::Write;
+
+use frclib_core::value::{FrcTimestamp, StaticallyFrcTyped};
+
+use crate::{DataLogError, DataLogWriter};
+
+/// A value that can be logged under a key in one call,
+/// structs log each of their fields as a sub-entry of the key
+///
+/// Every value with a static type is logged as a single entry at the key,
+/// structs implement it with [`impl_loggable!`](crate::impl_loggable) to log `<key>/<field>` for each field.
+/// Log it with [`DataLogWriter::put`].
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{impl_loggable, DataLogWriter};
+///
+/// struct ArmState {
+///     angle: f64,
+///     velocity: f64,
+///     at_setpoint: bool
+/// }
+/// impl_loggable!(ArmState { angle, velocity, at_setpoint });
+///
+/// let mut writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create writer");
+/// let arm_state = ArmState { angle: 0.5, velocity: 0.0, at_setpoint: true };
+/// // writes /arm/angle, /arm/velocity and /arm/at_setpoint
+/// writer.put("/arm", &arm_state).expect("Failed to log arm state");
+/// ```
+pub trait Loggable {
+    /// Writes the value under `key` at `timestamp`, creating any entries that don't exist
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if an entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    fn log<W: Write>(&self, writer: &mut DataLogWriter<W>, key: &str, timestamp: FrcTimestamp) -> Result<(), DataLogError>;
+}
+
+impl <T: StaticallyFrcTyped + Clone> Loggable for T {
+    fn log<W: Write>(&self, writer: &mut DataLogWriter<W>, key: &str, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        let id = writer.get_entry::<T>(key, None)?;
+        writer.write_timestamped(id, self.clone(), timestamp)
+    }
+}
+
+/// Implements [`Loggable`] for a struct by logging each listed field as `<key>/<field>`,
+/// fields can be of any [`Loggable`] type including other structs
+///
+/// See [`Loggable`] for an example.
+#[macro_export]
+macro_rules! impl_loggable {
+    ($type:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::loggable::Loggable for $type {
+            fn log<W: ::std::io::Write>(
+                &self,
+                writer: &mut $crate::DataLogWriter<W>,
+                key: &str,
+                // the FrcTimestamp of frclib-core
+                timestamp: u64
+            ) -> ::std::result::Result<(), $crate::DataLogError> {
+                $(
+                    $crate::loggable::Loggable::log(
+                        &self.$field,
+                        writer,
+                        &::std::format!("{}/{}", key, ::std::stringify!($field)),
+                        timestamp
+                    )?;
+                )*
+                ::std::result::Result::Ok(())
+            }
+        }
+    };
+}
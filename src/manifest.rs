@@ -0,0 +1,101 @@
+use serde_json::Value;
+
+use crate::DataLogError;
+
+/// An entry to create up front, see [`EntryManifest`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryDeclaration {
+    /// The key of the entry
+    pub key: String,
+    /// The entry type string, like `"double"`, `"int64[]"` or `"struct:Pose2d"`
+    #[cfg_attr(feature = "serde", serde(rename = "type"))]
+    pub entry_type: String,
+    /// The metadata of the entry
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub metadata: Option<String>
+}
+
+impl EntryDeclaration {
+    /// Declares an entry without metadata
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(key: impl ToString, entry_type: impl ToString) -> Self {
+        Self {
+            key: key.to_string(),
+            entry_type: entry_type.to_string(),
+            metadata: None
+        }
+    }
+
+    /// Sets the metadata
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_metadata(mut self, metadata: impl ToString) -> Self {
+        self.metadata = Some(metadata.to_string());
+        self
+    }
+}
+
+/// The entries of a robot program declared in one place,
+/// created together with [`DataLogWriter::declare_entries`](crate::DataLogWriter::declare_entries) right after the writer
+///
+/// With the `serde` feature the manifest can be loaded from any format serde supports like TOML,
+/// [`EntryManifest::from_json`] reads json without it.
+/// # Example
+/// ```rust,no_run
+/// use frclib_core::value::{FrcTimestampedValue, FrcValue};
+/// use frclib_datalog::{manifest::EntryManifest, DataLogWriter};
+///
+/// let manifest = EntryManifest::from_json(r#"{"entries": [
+///     {"key": "/drivetrain/velocity", "type": "double", "metadata": "{\"unit\":\"m/s\"}"},
+///     {"key": "/arm/angle", "type": "double"}
+/// ]}"#).expect("Invalid manifest");
+///
+/// let mut writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create writer");
+/// let entries = writer.declare_entries(&manifest).expect("Failed to declare entries");
+/// writer.write_dynamic(entries["/arm/angle"], FrcTimestampedValue::new(1_000, FrcValue::Double(0.5)))
+///     .expect("Failed to write entry");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EntryManifest {
+    /// The declared entries
+    pub entries: Vec<EntryDeclaration>
+}
+
+impl EntryManifest {
+    /// Creates a manifest without entries
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an entry
+    #[must_use]
+    pub fn with_entry(mut self, entry: EntryDeclaration) -> Self {
+        self.entries.push(entry);
+        self
+    }
+
+    /// Reads a json object with an `entries` array of objects with `key`, `type` and an optional `metadata`
+    ///
+    /// # Errors
+    /// - [`DataLogError::RecordDeserialize`] if the json doesn't have that shape
+    pub fn from_json(json: &str) -> Result<Self, DataLogError> {
+        const INVALID: DataLogError = DataLogError::RecordDeserialize("Manifest isn't an object with an entries array");
+        let manifest: Value = serde_json::from_str(json).map_err(|_| INVALID)?;
+        let entries = manifest.get("entries").and_then(Value::as_array).ok_or(INVALID)?;
+        entries.iter()
+            .map(|entry| {
+                let field = |name: &str| entry.get(name).and_then(Value::as_str).map(ToString::to_string);
+                Ok(EntryDeclaration {
+                    key: field("key").ok_or(DataLogError::RecordDeserialize("Manifest entry has no key"))?,
+                    entry_type: field("type").ok_or(DataLogError::RecordDeserialize("Manifest entry has no type"))?,
+                    metadata: field("metadata")
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(|entries| Self { entries })
+    }
+}
@@ -0,0 +1,238 @@
+use std::{fmt::Debug, fs::File, path::{Path, PathBuf}, time::SystemTime};
+
+#[cfg(feature = "networktables")]
+use frclib_core::value::FrcValue;
+
+#[cfg(feature = "networktables")]
+use crate::{frc_naming::MatchType, nt_bridge::TopicUpdate};
+use crate::{frc_naming::{frc_log_name, MatchInfo}, locking::create_locked, shutdown::Finalize, DataLogError, DataLogWriter};
+
+/// A datalog writer that starts a new file every time the robot is enabled,
+/// so each match or practice run ends up in its own file
+///
+/// Files are named like `WPILib` names its logs, see [`frc_log_name`], with the match added when it is known.
+/// The first file holds everything logged before the first enable. Enabling again during the match
+/// a file was started for, like after the pause between autonomous and teleop, doesn't start a new file.
+/// Open entries are started again in each new file so their ids stay valid.
+///
+/// The enabled state and match are either set directly or taken from the `FMSInfo` topics
+/// the robot publishes with [`MatchSegmentedDataLogWriter::observe`].
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::match_segments::MatchSegmentedDataLogWriter;
+///
+/// let mut log = MatchSegmentedDataLogWriter::create("/home/lvuser/logs", "").expect("Failed to create writer");
+/// let entry = log.writer_mut().get_entry::<f64>("test", None).expect("Failed to get entry");
+///
+/// // every robot loop
+/// log.set_enabled(true).expect("Failed to start a new log");
+/// log.writer_mut().write(entry, 10.0).expect("Failed to write entry");
+/// ```
+pub struct MatchSegmentedDataLogWriter {
+    writer: DataLogWriter<File>,
+    dir: PathBuf,
+    path: PathBuf,
+    metadata: String,
+    enabled: bool,
+    match_info: Option<MatchInfo>,
+    /// The match known when the current file was started
+    segment_match: Option<MatchInfo>,
+    segment: u32,
+    #[cfg(feature = "networktables")]
+    fms: FmsInfo
+}
+impl Debug for MatchSegmentedDataLogWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MatchSegmentedDataLogWriter")
+            .field("writer", &self.writer)
+            .field("path", &self.path)
+            .field("enabled", &self.enabled)
+            .field("match_info", &self.match_info)
+            .field("segment", &self.segment)
+            .finish_non_exhaustive()
+    }
+}
+
+impl MatchSegmentedDataLogWriter {
+    /// Creates the first log in `dir`, named after the current time
+    ///
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn create(dir: impl Into<PathBuf>, metadata: impl ToString) -> Result<Self, DataLogError> {
+        let dir = dir.into();
+        let metadata = metadata.to_string();
+        let (path, file) = create_segment_file(&dir, None)?;
+        Ok(Self {
+            writer: DataLogWriter::new(file, &metadata)?,
+            dir,
+            path,
+            metadata,
+            enabled: false,
+            match_info: None,
+            segment_match: None,
+            segment: 0,
+            #[cfg(feature = "networktables")]
+            fms: FmsInfo::default()
+        })
+    }
+
+    /// The path of the current log
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The number of files started after the first one
+    #[must_use]
+    pub const fn segment(&self) -> u32 {
+        self.segment
+    }
+
+    /// Whether the robot was last reported as enabled
+    #[must_use]
+    pub const fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// The match the robot was last reported to be in
+    #[must_use]
+    pub const fn match_info(&self) -> Option<&MatchInfo> {
+        self.match_info.as_ref()
+    }
+
+    /// The writer for the current log
+    #[must_use]
+    pub const fn writer(&self) -> &DataLogWriter<File> {
+        &self.writer
+    }
+
+    /// The writer for the current log
+    pub const fn writer_mut(&mut self) -> &mut DataLogWriter<File> {
+        &mut self.writer
+    }
+
+    /// Sets whether the robot is enabled, starting a new file when it becomes enabled.
+    /// Returns whether a new file was started.
+    ///
+    /// Call this every robot loop, only changes of the state have an effect.
+    ///
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn set_enabled(&mut self, enabled: bool) -> Result<bool, DataLogError> {
+        if !enabled || self.enabled {
+            self.enabled = enabled;
+            return Ok(false);
+        }
+        if self.segment_match.is_some() && self.segment_match == self.match_info {
+            self.enabled = true;
+            return Ok(false);
+        }
+        // only marked enabled once the file exists, so a failed start is tried again on the next call
+        self.start_segment()?;
+        self.enabled = true;
+        Ok(true)
+    }
+
+    /// Sets the match the robot is in, used to name the files started from then on
+    pub fn set_match_info(&mut self, match_info: Option<MatchInfo>) {
+        self.match_info = match_info;
+    }
+
+    /// Takes the enabled state and match from an update of the `FMSInfo` topics,
+    /// returning whether a new file was started. Updates of other topics are ignored.
+    ///
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[cfg(feature = "networktables")]
+    pub fn observe(&mut self, update: &TopicUpdate) -> Result<bool, DataLogError> {
+        match (update.topic.as_str(), &update.value.value) {
+            ("/FMSInfo/FMSControlData", FrcValue::Int(control)) => {
+                // the first bit of the control word is whether the robot is enabled
+                return self.set_enabled(control & 1 != 0);
+            }
+            ("/FMSInfo/EventName", FrcValue::String(event_name)) => self.fms.event_name = event_name.to_string(),
+            ("/FMSInfo/MatchType", FrcValue::Int(match_type)) => self.fms.match_type = *match_type,
+            ("/FMSInfo/MatchNumber", FrcValue::Int(match_number)) => {
+                self.fms.match_number = u16::try_from(*match_number).unwrap_or_default();
+            }
+            _ => return Ok(false)
+        }
+        self.match_info = self.fms.match_info();
+        Ok(false)
+    }
+
+    fn start_segment(&mut self) -> Result<(), DataLogError> {
+        let (path, file) = create_segment_file(&self.dir, self.match_info.as_ref())?;
+        let old = self.writer.rotate(file, &self.metadata)?;
+        old.sync_data()?;
+        self.path = path;
+        self.segment_match.clone_from(&self.match_info);
+        self.segment += 1;
+        Ok(())
+    }
+
+    /// Finishes every open entry and returns the current file, see [`DataLogWriter::close`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn close(self) -> Result<File, DataLogError> {
+        self.writer.close()
+    }
+}
+
+/// Creates a file named after the current time and `match_info` in `dir`,
+/// adding `_<n>` before the extension if a file already has the name
+fn create_segment_file(dir: &Path, match_info: Option<&MatchInfo>) -> Result<(PathBuf, File), DataLogError> {
+    let name = frc_log_name(SystemTime::now(), match_info);
+    let stem = name.trim_end_matches(".wpilog");
+    let mut index = 1u32;
+    loop {
+        let path = if index == 1 {
+            dir.join(&name)
+        } else {
+            dir.join(format!("{stem}_{index}.wpilog"))
+        };
+        match create_locked(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => index += 1,
+            Err(err) => return Err(DataLogError::Io(err))
+        }
+    }
+}
+
+impl Finalize for MatchSegmentedDataLogWriter {
+    fn finalize(&mut self) -> Result<(), DataLogError> {
+        self.writer.close_in_place()
+    }
+}
+
+/// The match as last published in the `FMSInfo` topics
+#[cfg(feature = "networktables")]
+#[derive(Debug, Default)]
+struct FmsInfo {
+    event_name: String,
+    match_type: i64,
+    match_number: u16
+}
+
+#[cfg(feature = "networktables")]
+impl FmsInfo {
+    fn match_info(&self) -> Option<MatchInfo> {
+        // the driver station publishes 0 when there is no match
+        let match_type = match self.match_type {
+            1 => MatchType::Practice,
+            2 => MatchType::Qualification,
+            3 => MatchType::Elimination,
+            _ => return None
+        };
+        Some(MatchInfo {
+            event_name: self.event_name.clone(),
+            match_type,
+            match_number: self.match_number
+        })
+    }
+}
@@ -0,0 +1,81 @@
+use std::fmt::Display;
+
+use frclib_core::value::FrcTimestamp;
+
+/// The key of the entry console messages are written to, the same as `WPILib`'s `DataLogManager`
+pub const MESSAGES_KEY: &str = "messages";
+
+/// How severe a console message is
+///
+/// Messages are written with the level in brackets before the text, like `[WARN] Brownout`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MessageLevel {
+    /// Details only needed when debugging
+    Debug,
+    /// Normal output, messages without a level are read as this
+    Info,
+    /// Something unexpected the robot can keep running through
+    Warning,
+    /// Something failed
+    Error
+}
+
+impl MessageLevel {
+    /// The tag of the level as written in brackets
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Debug => "DEBUG",
+            Self::Info => "INFO",
+            Self::Warning => "WARN",
+            Self::Error => "ERROR"
+        }
+    }
+
+    /// Parses the tag of a level
+    #[must_use]
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        [Self::Debug, Self::Info, Self::Warning, Self::Error]
+            .into_iter()
+            .find(|level| level.as_str() == tag)
+    }
+}
+
+impl Display for MessageLevel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A message of the [`MESSAGES_KEY`] entry,
+/// read with [`DataLogReader::read_messages`](crate::DataLogReader::read_messages)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsoleMessage {
+    /// When the message was written
+    pub timestamp: FrcTimestamp,
+    /// The level of the message
+    pub level: MessageLevel,
+    /// The text without the level
+    pub text: String
+}
+
+impl ConsoleMessage {
+    /// Splits a message written with a level in brackets, messages without one are [`MessageLevel::Info`]
+    #[must_use]
+    pub fn parse(timestamp: FrcTimestamp, message: &str) -> Self {
+        let leveled = message.strip_prefix('[')
+            .and_then(|rest| rest.split_once("] "))
+            .and_then(|(tag, text)| Some((MessageLevel::from_tag(tag)?, text)));
+        let (level, text) = leveled.unwrap_or((MessageLevel::Info, message));
+        Self {
+            timestamp,
+            level,
+            text: text.to_string()
+        }
+    }
+}
+
+/// Formats a message the way [`ConsoleMessage::parse`] reads it
+pub(crate) fn format_message(level: MessageLevel, text: &str) -> String {
+    format!("[{level}] {text}")
+}
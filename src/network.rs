@@ -0,0 +1,245 @@
+use std::{collections::BTreeMap, io::{ErrorKind, Write}, net::{SocketAddr, TcpStream}, time::{Duration, Instant}};
+
+use crate::proto::records::{header_len, split_record};
+
+/// How much a [`TcpSink`] buffers while disconnected by default, 16 MiB
+pub const DEFAULT_SPILL_CAPACITY: usize = 16 * 1024 * 1024;
+/// How long a [`TcpSink`] waits between connection attempts by default
+pub const DEFAULT_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a [`TcpSink`] waits for a connection attempt by default
+pub const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_millis(50);
+
+/// A sink that streams the log over TCP, like to a driver station laptop
+/// capturing the log live alongside the file on the robot
+///
+/// Network failures never reach the writer, so a flaky radio can't break logging.
+/// While disconnected the stream is buffered in memory and sent once the connection is back.
+/// Every connection is sent a whole log: a new connection first gets the header and the Start
+/// records of the entries that are alive, then the buffered records starting with the first one
+/// the last connection didn't take whole. Records the last connection took but didn't deliver
+/// before it broke are lost. If the buffer would overflow the stream can't be continued without
+/// a gap, so the sink gives up and drops everything after, see [`TcpSink::is_lost`].
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{network::TcpSink, DataLogWriter};
+///
+/// let sink = TcpSink::new("10.0.0.5:5810".parse().expect("Invalid address"));
+/// let mut writer = DataLogWriter::new(sink, "").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+/// ```
+#[derive(Debug)]
+pub struct TcpSink {
+    address: SocketAddr,
+    stream: Option<TcpStream>,
+    /// The bytes not yet sent, starting with a whole header or record
+    spill: Vec<u8>,
+    /// How many bytes of `spill` the current connection took
+    sent: usize,
+    /// Whether `spill` starts with the header
+    at_header: bool,
+    /// The header once sent, for sending again to the next connection
+    header: Option<Vec<u8>>,
+    /// The Start and metadata records of the entries alive as of the bytes sent
+    live: BTreeMap<u32, Vec<u8>>,
+    spill_capacity: usize,
+    connect_timeout: Duration,
+    reconnect_interval: Duration,
+    last_attempt: Option<Instant>,
+    lost: bool
+}
+
+impl TcpSink {
+    /// Creates a sink that connects to `address` on the first write
+    #[must_use]
+    pub const fn new(address: SocketAddr) -> Self {
+        Self {
+            address,
+            stream: None,
+            spill: Vec::new(),
+            sent: 0,
+            at_header: true,
+            header: None,
+            live: BTreeMap::new(),
+            spill_capacity: DEFAULT_SPILL_CAPACITY,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            reconnect_interval: DEFAULT_RECONNECT_INTERVAL,
+            last_attempt: None,
+            lost: false
+        }
+    }
+
+    /// Sets how many bytes are buffered while disconnected, defaults to [`DEFAULT_SPILL_CAPACITY`]
+    #[must_use]
+    pub const fn with_spill_capacity(mut self, spill_capacity: usize) -> Self {
+        self.spill_capacity = spill_capacity;
+        self
+    }
+
+    /// Sets how long to wait between connection attempts, defaults to [`DEFAULT_RECONNECT_INTERVAL`]
+    #[must_use]
+    pub const fn with_reconnect_interval(mut self, reconnect_interval: Duration) -> Self {
+        self.reconnect_interval = reconnect_interval;
+        self
+    }
+
+    /// Sets how long a connection attempt can block the writer, defaults to [`DEFAULT_CONNECT_TIMEOUT`]
+    #[must_use]
+    pub const fn with_connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = connect_timeout;
+        self
+    }
+
+    /// Whether the sink is currently connected
+    #[must_use]
+    pub const fn is_connected(&self) -> bool {
+        self.stream.is_some()
+    }
+
+    /// Whether the buffer overflowed while disconnected and the stream was given up
+    #[must_use]
+    pub const fn is_lost(&self) -> bool {
+        self.lost
+    }
+
+    /// The number of bytes waiting to be sent
+    #[must_use]
+    pub const fn spilled_len(&self) -> usize {
+        self.spill.len() - self.sent
+    }
+
+    fn try_connect(&mut self) {
+        if self.stream.is_some() || self.lost {
+            return;
+        }
+        if self.last_attempt.is_some_and(|last| last.elapsed() < self.reconnect_interval) {
+            return;
+        }
+        self.last_attempt = Some(Instant::now());
+
+        if let Ok(stream) = TcpStream::connect_timeout(&self.address, self.connect_timeout) {
+            // writes that would block are spilled instead so a slow receiver can't stall the writer
+            if stream.set_nonblocking(true).is_ok() {
+                let _ = stream.set_nodelay(true);
+                self.stream = Some(stream);
+                self.resync();
+            }
+        }
+    }
+
+    /// Starts the buffer of a new connection with the header and the Start records of the
+    /// alive entries, so the receiver can read the records after them
+    fn resync(&mut self) {
+        let Some(header) = &self.header else {
+            return;
+        };
+        let preamble = header.iter()
+            .chain(self.live.values().flatten())
+            .copied()
+            .collect::<Vec<_>>();
+        let _ = self.spill.splice(..0, preamble);
+        self.at_header = true;
+    }
+
+    /// The length of the header or record at the start of `bytes`, [`None`] if it isn't whole yet
+    fn unit_len(&self, bytes: &[u8]) -> Option<usize> {
+        if self.at_header {
+            header_len(bytes)
+        } else {
+            split_record(bytes).map(|(_, _, _, len)| len)
+        }
+    }
+
+    /// Keeps track of the header and of the entries a sent header or record starts and finishes
+    fn track(&mut self, unit: &[u8]) {
+        if self.at_header {
+            self.header = Some(unit.to_vec());
+            self.at_header = false;
+            return;
+        }
+        let Some((0, _, payload, _)) = split_record(unit) else {
+            return;
+        };
+        let Some(id) = payload.get(1..5).and_then(|id| id.try_into().ok()).map(u32::from_le_bytes) else {
+            return;
+        };
+        match payload.first() {
+            Some(0) => {
+                let _ = self.live.insert(id, unit.to_vec());
+            }
+            Some(1) => {
+                let _ = self.live.remove(&id);
+            }
+            Some(2) => {
+                if let Some(records) = self.live.get_mut(&id) {
+                    records.extend_from_slice(unit);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Drops the connection, the next one is sent the record it was in the middle of again
+    fn disconnect(&mut self) {
+        self.stream = None;
+        self.sent = 0;
+    }
+
+    /// Sends as much of the buffer as the connection takes without blocking
+    fn drain_spill(&mut self) {
+        while self.sent < self.spill.len() {
+            let Some(stream) = self.stream.as_mut() else {
+                break;
+            };
+            match stream.write(&self.spill[self.sent..]) {
+                Ok(0) => self.disconnect(),
+                Ok(written) => self.sent += written,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+                Err(_) => self.disconnect()
+            }
+        }
+
+        // only whole headers and records leave the buffer, so a new connection can be sent them again
+        let mut spill = std::mem::take(&mut self.spill);
+        let mut consumed = 0;
+        while let Some(len) = self.unit_len(&spill[consumed..]).filter(|len| consumed + len <= self.sent) {
+            self.track(&spill[consumed..consumed + len]);
+            consumed += len;
+        }
+        let _ = spill.drain(..consumed);
+        self.spill = spill;
+        self.sent -= consumed;
+    }
+
+    fn spill(&mut self, buf: &[u8]) {
+        if self.spill.len() + buf.len() > self.spill_capacity {
+            self.lost = true;
+            self.stream = None;
+            self.spill = Vec::new();
+            self.sent = 0;
+        } else {
+            self.spill.extend_from_slice(buf);
+        }
+    }
+}
+
+impl Write for TcpSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.lost {
+            return Ok(buf.len());
+        }
+        self.spill(buf);
+        self.try_connect();
+        self.drain_spill();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if !self.lost {
+            self.try_connect();
+            self.drain_spill();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,117 @@
+use std::io::Write;
+
+use frclib_core::value::FrcTimestampedValue;
+
+use crate::{DataLogError, DataLogWriter};
+
+/// The prefix `WPILib` gives the keys of recorded topics
+pub const DEFAULT_KEY_PREFIX: &str = "NT:";
+
+/// A value published to a `NetworkTables` topic
+#[derive(Debug, Clone)]
+pub struct TopicUpdate {
+    /// The full name of the topic, like `/SmartDashboard/speed`
+    pub topic: String,
+    /// The value and the time it was published at
+    pub value: FrcTimestampedValue,
+    /// The properties of the topic as json, recorded as the metadata of the entry
+    pub properties: Option<String>
+}
+
+/// A `NetworkTables` client the recorder can take topic updates from
+///
+/// Implement this for whichever NT4 client the robot program uses,
+/// subscribing to every topic that should end up in the log.
+pub trait NetworkTablesConnector {
+    /// Takes the next update received since the last call, [`None`] once there are no more
+    fn next_update(&mut self) -> Option<TopicUpdate>;
+}
+
+/// Records every `NetworkTables` topic update into a datalog, like `DataLogManager.logNetworkTables(true)`
+///
+/// Each topic becomes an entry keyed by its name with a prefix, `NT:` by default.
+/// Updates whose type doesn't match the type the topic was first recorded with
+/// are dropped as an entry can't change its type.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{nt_bridge::{NetworkTablesConnector, NetworkTablesRecorder, TopicUpdate}, DataLogWriter};
+///
+/// struct Client;
+/// impl NetworkTablesConnector for Client {
+///     fn next_update(&mut self) -> Option<TopicUpdate> {
+///         None
+///     }
+/// }
+///
+/// let mut writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create writer");
+/// let mut recorder = NetworkTablesRecorder::new(Client);
+///
+/// // every robot loop
+/// recorder.record(&mut writer).expect("Failed to record updates");
+/// ```
+#[derive(Debug)]
+pub struct NetworkTablesRecorder<C: NetworkTablesConnector> {
+    connector: C,
+    prefix: String,
+    dropped_updates: u64
+}
+
+impl <C: NetworkTablesConnector> NetworkTablesRecorder<C> {
+    /// Creates a recorder for the updates of `connector`
+    pub fn new(connector: C) -> Self {
+        Self {
+            connector,
+            prefix: DEFAULT_KEY_PREFIX.to_string(),
+            dropped_updates: 0
+        }
+    }
+
+    /// Sets the prefix of the keys of recorded topics, defaults to [`DEFAULT_KEY_PREFIX`]
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_prefix(mut self, prefix: impl ToString) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// The number of updates dropped because their type changed
+    #[must_use]
+    pub const fn dropped_updates(&self) -> u64 {
+        self.dropped_updates
+    }
+
+    /// The connector updates are taken from
+    pub const fn connector_mut(&mut self) -> &mut C {
+        &mut self.connector
+    }
+
+    /// Writes every pending update to `writer`, returning how many were written
+    ///
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if an update has no value
+    /// - [`DataLogError::MetadataTooLarge`] if the properties of a topic are too large
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry of a topic was closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn record<W: Write>(&mut self, writer: &mut DataLogWriter<W>) -> Result<usize, DataLogError> {
+        let mut written = 0;
+        while let Some(update) = self.connector.next_update() {
+            let key = format!("{}{}", self.prefix, update.topic);
+            let id = match writer.get_entry_dynamic(&key, update.value.value.get_type(), update.properties) {
+                Ok(id) => id,
+                Err(DataLogError::EntryTypeMismatch) => {
+                    self.dropped_updates += 1;
+                    continue;
+                }
+                Err(err) => return Err(err)
+            };
+            writer.write_dynamic(id, update.value)?;
+            written += 1;
+        }
+        Ok(written)
+    }
+
+    /// Returns the connector
+    pub fn into_inner(self) -> C {
+        self.connector
+    }
+}
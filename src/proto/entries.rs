@@ -1,7 +1,7 @@
 
 use std::num::NonZeroU32;
 
-use frclib_core::value::FrcType;
+use frclib_core::{structure::FrcStructDescDB, value::FrcType};
 
 #[inline]
 #[allow(clippy::cast_possible_truncation)]
@@ -90,9 +90,52 @@ pub const fn get_data_type(ty: &FrcType) -> Option<&'static str> {
     }
 }
 
+/// The full type string of an entry holding `ty`, like `struct:Name[]` for struct arrays
+pub fn get_full_type_str(ty: &FrcType) -> Option<String> {
+    match ty {
+        FrcType::Struct(desc) => Some(format!("struct:{}", desc.type_str)),
+        FrcType::StructArray(desc) => Some(format!("struct:{}[]", desc.type_str)),
+        _ => get_data_type(ty).map(str::to_string)
+    }
+}
+
+/// Whether `type_str` is the full type string of `ty`, without allocating one to compare against
+pub fn type_str_matches(ty: &FrcType, type_str: &str) -> bool {
+    match ty {
+        FrcType::Struct(desc) => type_str.strip_prefix("struct:") == Some(desc.type_str),
+        FrcType::StructArray(desc) => type_str.strip_prefix("struct:")
+            .and_then(|name| name.strip_suffix("[]")) == Some(desc.type_str),
+        _ => get_data_type(ty) == Some(type_str)
+    }
+}
+
+/// The type an entry type string stands for, struct types have to be registered in the [`FrcStructDescDB`]
+pub fn get_type_from_str(ty: &str) -> Option<FrcType> {
+    if let Some(name) = ty.strip_prefix("struct:") {
+        return match name.strip_suffix("[]") {
+            Some(name) => FrcStructDescDB::get(name).map(FrcType::StructArray),
+            None => FrcStructDescDB::get(name).map(FrcType::Struct)
+        };
+    }
+    match ty {
+        "raw" => Some(FrcType::Raw),
+        "boolean" => Some(FrcType::Boolean),
+        "int64" => Some(FrcType::Int),
+        "float" => Some(FrcType::Float),
+        "double" => Some(FrcType::Double),
+        "string" => Some(FrcType::String),
+        "boolean[]" => Some(FrcType::BooleanArray),
+        "int64[]" => Some(FrcType::IntArray),
+        "float[]" => Some(FrcType::FloatArray),
+        "double[]" => Some(FrcType::DoubleArray),
+        "string[]" => Some(FrcType::StringArray),
+        _ => None
+    }
+}
+
 /// A faster way of comparing type equality, is not immune to hash collisions
 pub fn get_data_type_serial(ty: &FrcType) -> NonZeroU32 {
-    const INVALID: NonZeroU32 = unsafe { NonZeroU32::new_unchecked(u32::MAX) };
+    const INVALID: NonZeroU32 = NonZeroU32::MAX;
     match ty {
         FrcType::Raw => unsafe { NonZeroU32::new_unchecked(RAW_TYPE_SERIAL) },
         FrcType::Boolean => unsafe { NonZeroU32::new_unchecked(BOOLEAN_TYPE_SERIAL) },
@@ -1,13 +1,13 @@
 #![allow(dead_code)]
 
-use std::{collections::HashMap, hash::BuildHasher, io::Write};
+use std::{collections::HashMap, hash::BuildHasher, io::{Read, Write}};
 use byteorder::{LittleEndian, WriteBytesExt};
 
 use frclib_core::value::{FrcValue, IntoFrcValue};
 
 use crate::{
     error::DataLogError,
-    proto::util::{RecordByteReader, UInt},
+    proto::util::{RecordByteReader, StreamingRecordByteReader, UInt},
     EntryId, EntryMetadata, EntryName, EntryType, FrcTimestamp,
 };
 
@@ -48,23 +48,87 @@ fn chunk_by_record(bytes: &[u8]) -> Result<Vec<&[u8]>, DataLogError> {
     Ok(chunks)
 }
 
+/// The length of the log header at the start of `bytes`,
+/// [`None`] if `bytes` doesn't hold all of it yet
+pub fn header_len(bytes: &[u8]) -> Option<usize> {
+    // magic, version and the metadata length followed by the metadata
+    let metadata_len = u32::from_le_bytes(bytes.get(8..12)?.try_into().ok()?);
+    let len = 12 + usize::try_from(metadata_len).ok()?;
+    (bytes.len() >= len).then_some(len)
+}
+
+/// Splits the first record off `bytes` into its entry id, timestamp, payload and total length,
+/// returning [`None`] if `bytes` doesn't hold a whole record yet
+pub fn split_record(bytes: &[u8]) -> Option<(EntryId, FrcTimestamp, &[u8], usize)> {
+    let bit_field = RecordElementBitfield::from_bits_truncate(*bytes.first()?);
+    let header = bytes.get(..1 + bit_field.total_length())?;
+    let (id, rest) = header[1..].split_at(bit_field.id_length());
+    let (payload_size, timestamp) = rest.split_at(bit_field.payload_length());
+
+    let id = EntryId::try_from(*UInt::from_binary(id)?).ok()?;
+    let payload_size = usize::try_from(*UInt::from_binary(payload_size)?).ok()?;
+    let timestamp = *UInt::from_binary(timestamp)?;
+    let total_size = header.len().checked_add(payload_size)?;
+    let payload = bytes.get(header.len()..total_size)?;
+    Some((id, timestamp, payload, total_size))
+}
+
 pub fn parse_records<H: BuildHasher>(bytes: &[u8], type_map: &mut HashMap<u32, u32, H>) -> Result<Vec<Record>, DataLogError> {
-    let chunks = chunk_by_record(bytes)?;
     let mut records = Vec::new();
+    for_each_record(bytes, type_map, |record, _| {
+        records.push(record);
+        Ok(())
+    })?;
+    Ok(records)
+}
+
+/// Parses records one at a time and hands them to `visitor` without collecting them,
+/// the visitor is also given the type map so it can update entry types as it goes
+pub fn for_each_record<H: BuildHasher>(
+    bytes: &[u8],
+    type_map: &mut HashMap<u32, u32, H>,
+    mut visitor: impl FnMut(Record, &mut HashMap<u32, u32, H>) -> Result<(), DataLogError>
+) -> Result<(), DataLogError> {
+    let chunks = chunk_by_record(bytes)?;
     for chunk in chunks {
-        if let Ok(record) = Record::from_binary(chunk, type_map) {
-            if let Record::Control(control, _, _) = &record {
-                if let Some(entry_type) = control.get_entry_type() {
-                    #[allow(unused_results)]
-                    {
-                        type_map.insert(record.get_id(), get_str_type_serial(entry_type));
-                    }
+        visit_record(chunk, type_map, &mut visitor)?;
+    }
+    Ok(())
+}
+
+/// Like [`for_each_record`] but pulls the records from `stream` one at a time,
+/// so the records never have to be held in memory all at once
+pub fn for_each_record_from<H: BuildHasher>(
+    stream: &mut StreamingRecordByteReader<impl Read>,
+    type_map: &mut HashMap<u32, u32, H>,
+    mut visitor: impl FnMut(Record, &mut HashMap<u32, u32, H>) -> Result<(), DataLogError>
+) -> Result<(), DataLogError> {
+    while let Some(chunk) = stream.record()? {
+        visit_record(chunk, type_map, &mut visitor)?;
+    }
+    if stream.has_partial_record() {
+        return Err(DataLogError::RecordReaderOutOfBounds("bytes"));
+    }
+    Ok(())
+}
+
+fn visit_record<H: BuildHasher>(
+    chunk: &[u8],
+    type_map: &mut HashMap<u32, u32, H>,
+    visitor: &mut impl FnMut(Record, &mut HashMap<u32, u32, H>) -> Result<(), DataLogError>
+) -> Result<(), DataLogError> {
+    if let Ok(record) = Record::from_binary(chunk, type_map) {
+        if let Record::Control(control, _, _) = &record {
+            if let Some(entry_type) = control.get_entry_type() {
+                #[allow(unused_results)]
+                {
+                    type_map.insert(record.get_id(), get_str_type_serial(entry_type));
                 }
             }
-            records.push(record);
         }
+        visitor(record, type_map)?;
     }
-    Ok(records)
+    Ok(())
 }
 
 bitflags! {
@@ -128,6 +192,17 @@ impl RecordElementBitfield {
     }
 }
 
+/// How wide the id, size and timestamp fields of record headers are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeaderWidth {
+    /// As few bytes as the values need
+    #[default]
+    Minimal,
+    /// Always 4 bytes for the id and size and 8 for the timestamp,
+    /// so they can be patched in place later
+    Full
+}
+
 #[derive(Debug, Clone)]
 struct RecordElementSizes {
     pub bit_field: RecordElementBitfield,
@@ -136,10 +211,11 @@ struct RecordElementSizes {
     pub payload: UInt,
 }
 impl RecordElementSizes {
-    fn create(timestamp: FrcTimestamp, id: EntryId, payload: u32) -> Self {
-        let wrapped_timestamp = UInt::from(timestamp).shrink();
-        let wrapped_id = UInt::from(id).shrink();
-        let wrapped_payload = UInt::from(payload).shrink();
+    fn create(timestamp: FrcTimestamp, id: EntryId, payload: u32, width: HeaderWidth) -> Self {
+        let (wrapped_timestamp, wrapped_id, wrapped_payload) = match width {
+            HeaderWidth::Minimal => (UInt::from(timestamp), UInt::from(id), UInt::from(payload)),
+            HeaderWidth::Full => (UInt::with_size(8, timestamp), UInt::with_size(4, id.into()), UInt::with_size(4, payload.into()))
+        };
         // create bitfield as little endian byte
         // let bit_field = ((wrapped_id.get_byte_count() - 1) as LeByte) & 0b11
         //     | (((wrapped_payload.get_byte_count() - 1) as LeByte) & 0b11) << 2
@@ -158,25 +234,72 @@ impl RecordElementSizes {
     }
 }
 
+/// The longest a record header can be, a bitfield byte with a 4 byte id, 4 byte size and 8 byte timestamp
+pub const MAX_RECORD_HEADER_LEN: usize = 17;
+
+/// A record header encoded into a fixed buffer, shared by every writer so none of them need `std` to encode one
+#[derive(Debug, Clone, Copy)]
+pub struct EncodedRecordHeader {
+    bytes: [u8; MAX_RECORD_HEADER_LEN],
+    len: usize
+}
+
+impl EncodedRecordHeader {
+    pub fn new(width: HeaderWidth, timestamp: FrcTimestamp, id: EntryId, payload: u32) -> Self {
+        let element_sizes = RecordElementSizes::create(timestamp, id, payload, width);
+        let mut header = Self {
+            bytes: [0; MAX_RECORD_HEADER_LEN],
+            len: 0
+        };
+        for part in [
+            &[element_sizes.bit_field.bits()][..], //1-byte header length bitfield
+            element_sizes.id.as_binary(), //1 to 4-byte (32-bit) entry ID
+            element_sizes.payload.as_binary(), // 1 to 4-byte (32-bit) payload size (in bytes)
+            element_sizes.timestamp.as_binary() // 1 to 8-byte (64-bit) timestamp (in microseconds)
+        ] {
+            if let Some(out) = header.bytes.get_mut(header.len..header.len + part.len()) {
+                out.copy_from_slice(part);
+                header.len += part.len();
+            }
+        }
+        header
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        self.bytes.get(..self.len).unwrap_or_default()
+    }
+}
+
+/// A single record of a datalog, with its timestamp and entry id
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Record {
+    /// A value of an entry
     Data(DataRecord, FrcTimestamp, EntryId),
+    /// A change to the lifetime or metadata of an entry, written with entry id 0
+    /// and the id of the entry it changes in the payload
     Control(ControlRecord, FrcTimestamp, EntryId),
 }
 
 impl Record {
+    /// The timestamp of the record
+    #[must_use]
     pub const fn get_timestamp(&self) -> FrcTimestamp {
         match self {
             Self::Data(_, timestamp, _) | Self::Control(_, timestamp, _)=> *timestamp
         }
     }
 
+    /// The id of the entry the record belongs to
+    #[must_use]
     pub const fn get_id(&self) -> EntryId {
         match self {
             Self::Control(_, _, id) | Self::Data(_, _, id) => *id
         }
     }
 
+    /// Whether the record holds a value
+    #[must_use]
     pub const fn is_data(&self) -> bool {
         match self {
             Self::Data(_, _, _) => true,
@@ -184,6 +307,8 @@ impl Record {
         }
     }
 
+    /// Whether the record is a control record
+    #[must_use]
     pub const fn is_control(&self) -> bool {
         match self {
             Self::Data(_, _, _) => false,
@@ -191,6 +316,8 @@ impl Record {
         }
     }
 
+    /// The value of the record if it is a data record
+    #[must_use]
     pub const fn as_data(&self) -> Option<&DataRecord> {
         match self {
             Self::Data(data, _, _) => Some(data),
@@ -198,6 +325,8 @@ impl Record {
         }
     }
 
+    /// The control record if it is one
+    #[must_use]
     pub const fn as_control(&self) -> Option<&ControlRecord> {
         match self {
             Self::Data(_, _, _) => None,
@@ -205,13 +334,51 @@ impl Record {
         }
     }
 
+    /// Serializes the record to `out_buffer`
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordTooLarge`] if the payload doesn't fit in a record
+    /// - [`DataLogError::Io`] if an IO error occurs
     pub fn write_to(self, out_buffer: &mut impl Write) -> Result<(), DataLogError> {
+        self.write_to_with_width(HeaderWidth::Minimal, out_buffer)
+    }
+
+    /// The exact number of bytes [`Record::write_to`] writes for the record, header included,
+    /// or [`None`] if the payload is too large to be written
+    #[must_use]
+    pub fn serialized_size(&self) -> Option<usize> {
+        let (id, payload_size) = match self {
+            // control records are written with entry id 0
+            Self::Control(control, _, _) => (0, control.binary_payload_size()?),
+            Self::Data(data, _, id) => (*id, data.binary_payload_size()?),
+        };
+        let header = EncodedRecordHeader::new(HeaderWidth::Minimal, self.get_timestamp(), id, payload_size);
+        header.as_bytes().len().checked_add(usize::try_from(payload_size).ok()?)
+    }
+
+    /// Serializes the record into a new buffer of exactly [`Record::serialized_size`] bytes
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordTooLarge`] if the payload doesn't fit in a record
+    pub fn to_bytes(&self) -> Result<Vec<u8>, DataLogError> {
+        let mut bytes = Vec::with_capacity(self.serialized_size().ok_or(DataLogError::RecordTooLarge)?);
+        self.write_to_with_width(HeaderWidth::Minimal, &mut bytes)?;
+        Ok(bytes)
+    }
+
+    /// Serializes the record to `out_buffer` with headers of the given width
+    pub(crate) fn write_to_with_width(&self, width: HeaderWidth, out_buffer: &mut impl Write) -> Result<(), DataLogError> {
         match self {
-            Self::Control(control, timestamp, id) => control.write_to(timestamp, id, out_buffer),
-            Self::Data(data, timestamp, id) => data.write_to(timestamp, id, out_buffer),
+            Self::Control(control, timestamp, id) => control.write_to_with_width(width, *timestamp, *id, out_buffer),
+            Self::Data(data, timestamp, id) => data.write_to_with_width(width, *timestamp, *id, out_buffer),
         }
     }
 
+    /// Parses a whole record, `type_map` maps entry ids to the serial of their type
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordDeserialize`] if the record is malformed
+    /// - [`DataLogError::RecordType`] if the entry type is unknown
     pub fn from_binary<H: BuildHasher>(bytes: &[u8], type_map: &HashMap<u32, u32, H>) -> Result<Self, DataLogError> {
         let mut reader = RecordByteReader::new(bytes);
         let bit_field = reader.byte().unwrap_or_default();
@@ -225,7 +392,7 @@ impl Record {
             id = UInt::from_binary(bin_int)
                 // .map_or_else(|| 0u32, |f| *f as u32);
                 .and_then(|f| u32::try_from(*f).ok())
-                .ok_or_else(|| DataLogError::RecordDeserialize("Failed to read entry id"))?;
+                .ok_or(DataLogError::RecordDeserialize("Failed to read entry id"))?;
         } else {
             return Err(DataLogError::RecordReaderOutOfBounds("Entry id"));
         }
@@ -271,53 +438,172 @@ impl Record {
     }
 }
 
+/// Encodes the payload of a data record for a value into a reusable buffer
+#[allow(unused_results)]
+pub fn encode_payload(value: &FrcValue, out_buffer: &mut Vec<u8>) {
+    match value {
+        FrcValue::Void => {}
+        FrcValue::Raw(data) => out_buffer.extend_from_slice(data),
+        FrcValue::Boolean(data) => out_buffer.push(u8::from(*data)),
+        FrcValue::Int(data) => out_buffer.extend_from_slice(&data.to_le_bytes()),
+        FrcValue::Float(data) => out_buffer.extend_from_slice(&data.to_le_bytes()),
+        FrcValue::Double(data) => out_buffer.extend_from_slice(&data.to_le_bytes()),
+        FrcValue::String(data) => out_buffer.extend_from_slice(data.as_bytes()),
+        FrcValue::BooleanArray(data) => out_buffer.extend(data.iter().map(|b| u8::from(*b))),
+        FrcValue::IntArray(data) => {
+            for i in &**data {
+                out_buffer.extend_from_slice(&i.to_le_bytes());
+            }
+        }
+        FrcValue::FloatArray(data) => {
+            for f in &**data {
+                out_buffer.extend_from_slice(&f.to_le_bytes());
+            }
+        }
+        FrcValue::DoubleArray(data) => {
+            for d in &**data {
+                out_buffer.extend_from_slice(&d.to_le_bytes());
+            }
+        }
+        FrcValue::StringArray(data) => {
+            for s in &**data {
+                if let Ok(len) = u32::try_from(s.len()) {
+                    out_buffer.extend_from_slice(&len.to_le_bytes());
+                    out_buffer.extend_from_slice(s.as_bytes());
+                }
+            }
+        }
+        FrcValue::Struct(data) | FrcValue::StructArray(data) => out_buffer.extend_from_slice(&data.data),
+    }
+}
+
+/// Writes a data record whose payload is already encoded
+#[allow(unused_results)]
+pub fn write_encoded_data_record(timestamp: FrcTimestamp, id: EntryId, payload: &[u8], out_buffer: &mut impl Write) -> Result<(), DataLogError> {
+    write_encoded_data_record_with_width(HeaderWidth::Minimal, timestamp, id, payload, out_buffer)
+}
+
+/// Writes a data record whose payload is already encoded with headers of the given width
+#[allow(unused_results)]
+pub fn write_encoded_data_record_with_width(
+    width: HeaderWidth,
+    timestamp: FrcTimestamp,
+    id: EntryId,
+    payload: &[u8],
+    out_buffer: &mut impl Write
+) -> Result<(), DataLogError> {
+    let payload_size = u32::try_from(payload.len()).map_err(|_| DataLogError::RecordTooLarge)?;
+    let header = EncodedRecordHeader::new(width, timestamp, id, payload_size);
+
+    out_buffer.write_all(header.as_bytes())?; // bitfield, entry id, payload size and timestamp
+    out_buffer.write_all(payload)?;
+
+    Ok(())
+}
+
+/// The payload of a control record
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ControlRecord {
+    /// Starts an entry with a key, type and metadata
     Start(EntryName, EntryType, EntryMetadata),
+    /// Finishes an entry
     Finish,
+    /// Replaces the metadata of an entry
     Metadata(EntryMetadata),
+    /// A control record of a type this crate doesn't know, like one added by a later version of the format,
+    /// kept so copying a log doesn't drop it
+    /// 
+    /// `kind` shouldn't be one of the known types, the record would be read back as that type.
+    Unknown {
+        /// The control type byte
+        kind: u8,
+        /// The payload after the entry id
+        payload: Box<[u8]>
+    },
 }
 
 
 impl ControlRecord {
+    /// The control type byte of the record
+    #[must_use]
     pub const fn get_control_type(&self) -> u8 {
         match self {
             Self::Start(_, _, _) => 0,
             Self::Finish => 1,
             Self::Metadata(_) => 2,
+            Self::Unknown { kind, .. } => *kind,
         }
     }
 
+    /// Whether the record starts an entry
+    #[must_use]
     pub const fn is_start(&self) -> bool {
         match self {
             Self::Start(_, _, _) => true,
-            Self::Finish | Self::Metadata(_) => false,
+            Self::Finish | Self::Metadata(_) | Self::Unknown { .. } => false,
         }
     }
 
+    /// The key of the entry if the record starts one
+    #[must_use]
     pub const fn get_entry_name(&self) -> Option<&EntryName> {
         match self {
             Self::Start(name, _, _) => Some(name),
-            Self::Finish | Self::Metadata(_) => None,
+            Self::Finish | Self::Metadata(_) | Self::Unknown { .. } => None,
         }
     }
 
+    /// The type of the entry if the record starts one
+    #[must_use]
     pub const fn get_entry_type(&self) -> Option<&EntryType> {
         match self {
             Self::Start(_, entry_type, _) => Some(entry_type),
-            Self::Finish | Self::Metadata(_) => None,
+            Self::Finish | Self::Metadata(_) | Self::Unknown { .. } => None,
         }
     }
 
+    /// The metadata the record gives the entry
+    #[must_use]
     pub const fn get_entry_metadata(&self) -> Option<&EntryMetadata> {
         match self {
             Self::Start(_, _, entry_metadata) | Self::Metadata(entry_metadata) => Some(entry_metadata),
-            Self::Finish => None,
+            Self::Finish | Self::Unknown { .. } => None,
         }
     }
 
-    #[allow(unused_results)]
+    /// The size of the payload of the record in bytes,
+    /// or [`None`] if it is too large to be represented in a u32
+    #[must_use]
+    pub fn binary_payload_size(&self) -> Option<u32> {
+        fn len_u32(string: &str) -> Option<u32> {
+            string.len().try_into().ok()
+        }
+        match self {
+            // control type, entry id and the three length prefixed strings
+            Self::Start(name, entry_type, entry_metadata) => 17u32
+                .checked_add(len_u32(name)?)?
+                .checked_add(len_u32(entry_type)?)?
+                .checked_add(len_u32(entry_metadata)?),
+            Self::Finish => Some(5),
+            Self::Metadata(entry_metadata) => 9u32.checked_add(len_u32(entry_metadata)?),
+            Self::Unknown { payload, .. } => 5u32.checked_add(payload.len().try_into().ok()?),
+        }
+    }
+
+    /// Serializes the record for entry `id` to `out_buffer`
+    /// 
+    /// # Errors
+    /// - [`DataLogError::IntCast`] if a string is longer than [`u32::MAX`]
+    /// - [`DataLogError::RecordTooLarge`] if the payload of an unknown record doesn't fit in a record
+    /// - [`DataLogError::Io`] if an IO error occurs
     pub fn write_to(self, timestamp: FrcTimestamp, id: EntryId, out_buffer: &mut impl Write) -> Result<(), DataLogError> {
+        self.write_to_with_width(HeaderWidth::Minimal, timestamp, id, out_buffer)
+    }
+
+    /// Serializes the record for entry `id` to `out_buffer` with headers of the given width
+    #[allow(unused_results)]
+    pub(crate) fn write_to_with_width(&self, width: HeaderWidth, timestamp: FrcTimestamp, id: EntryId, out_buffer: &mut impl Write) -> Result<(), DataLogError> {
         match self {
             Self::Start(name, entry_type, entry_metadata) => {
                 let name_len = u32::try_from(name.len())?;
@@ -326,12 +612,9 @@ impl ControlRecord {
 
                 let payload_len = 17 + name_len + entry_type_len + entry_metadata_len;
 
-                let element_sizes = RecordElementSizes::create(timestamp, 0, payload_len);
+                let header = EncodedRecordHeader::new(width, timestamp, 0, payload_len);
 
-                out_buffer.write_u8(element_sizes.bit_field.bits())?;                             //1-byte header length bitfield
-                out_buffer.write_u8(0u8)?;                                                        //1 to 4-byte (32-bit) entry ID (0 int for control records)
-                out_buffer.write_all(element_sizes.payload.as_binary())?;                        // 1 to 4-byte (32-bit) payload size (in bytes)
-                out_buffer.write_all(element_sizes.timestamp.as_binary())?;                      // 1 to 8-byte (64-bit) timestamp (in microseconds)
+                out_buffer.write_all(header.as_bytes())?; // bitfield, entry id, payload size and timestamp
                 out_buffer.write_u8(0u8)?;                                                        // 1-byte control record type (0 for Start control records)
                 out_buffer.write_all(&id.to_le_bytes())?;                             // 4-byte (32-bit) entry ID of entry being started
                 out_buffer.write_u32::<LittleEndian>(name_len)?;            // 4-byte (32-bit) length of entry name string
@@ -344,12 +627,9 @@ impl ControlRecord {
             Self::Finish => {
                 let payload_len = 5u32;
 
-                let element_sizes = RecordElementSizes::create(timestamp, 0, payload_len);
+                let header = EncodedRecordHeader::new(width, timestamp, 0, payload_len);
 
-                out_buffer.write_u8(element_sizes.bit_field.bits())?;             //1-byte header length bitfield
-                out_buffer.write_u8(0u8)?;                                        //1 to 4-byte (32-bit) entry ID (0 int for control records)
-                out_buffer.write_all(element_sizes.payload.as_binary())?;        // 1 to 4-byte (32-bit) payload size (in bytes)
-                out_buffer.write_all(element_sizes.timestamp.as_binary())?;      // 1 to 8-byte (64-bit) timestamp (in microseconds)
+                out_buffer.write_all(header.as_bytes())?; // bitfield, entry id, payload size and timestamp
                 out_buffer.write_u8(1u8)?;                                          // 1-byte control record type (1 for Finish control records)
                 out_buffer.write_all(&id.to_le_bytes())?;             // 4-byte (32-bit) entry ID of entry being finished
             }
@@ -358,20 +638,32 @@ impl ControlRecord {
 
                 let payload_len = 9 + entry_metadata_len;
 
-                let element_sizes = RecordElementSizes::create(timestamp, 0, payload_len );
+                let header = EncodedRecordHeader::new(width, timestamp, 0, payload_len);
 
-                out_buffer.write_u8(element_sizes.bit_field.bits())?;                             //1-byte header length bitfield
-                out_buffer.write_u8(0u8)?;                                                        //1 to 4-byte (32-bit) entry ID (0 int for control records)
-                out_buffer.write_all(element_sizes.payload.as_binary())?;                        // 1 to 4-byte (32-bit) payload size (in bytes)
-                out_buffer.write_all(element_sizes.timestamp.as_binary())?;                      // 1 to 8-byte (64-bit) timestamp (in microseconds)
+                out_buffer.write_all(header.as_bytes())?; // bitfield, entry id, payload size and timestamp
                 out_buffer.write_u8(2u8)?;                                                          // 1-byte control record type (2 for Metadata control records)
+                out_buffer.write_all(&id.to_le_bytes())?;                             // 4-byte (32-bit) entry ID of entry being updated
                 out_buffer.write_u32::<LittleEndian>(entry_metadata_len)?;  // 4-byte (32-bit) length of entry metadata string
-                out_buffer.write_all(&id.to_le_bytes())?;                             // 4-byte (32-bit) entry ID of entry being finished
+                out_buffer.write_all(entry_metadata.as_bytes())?;                     // UTF-8 encoded entry metadata string
             }
-        };
+            Self::Unknown { kind, payload } => {
+                let payload_len = self.binary_payload_size().ok_or(DataLogError::RecordTooLarge)?;
+
+                let header = EncodedRecordHeader::new(width, timestamp, 0, payload_len);
+
+                out_buffer.write_all(header.as_bytes())?; // bitfield, entry id, payload size and timestamp
+                out_buffer.write_u8(*kind)?;                                        // 1-byte control record type
+                out_buffer.write_all(&id.to_le_bytes())?;             // 4-byte (32-bit) entry ID
+                out_buffer.write_all(payload)?;                                     // the rest as it was read
+            }
+        }
         Ok(())
     }
 
+    /// Parses the payload of a control record, returning it with the id of the entry it changes
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordDeserialize`] if the payload is malformed
     pub fn from_binary(bytes: &[u8]) -> Result<(Self, EntryId), DataLogError> {
         let mut reader = RecordByteReader::new(bytes);
         let control_type = reader.byte()?;
@@ -436,12 +728,15 @@ impl ControlRecord {
                     ))
                 }
             }
-            _ => Err(DataLogError::RecordDeserialize("Unsupported control record type")),
+            kind => Ok((Self::Unknown { kind, payload: Box::from(reader.the_rest()) }, entry_id)),
         }
     }
 }
 
+/// The value of a data record
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[allow(missing_docs)]
 pub enum DataRecord {
     Raw(Box<[u8]>),
     Boolean(bool),
@@ -457,6 +752,8 @@ pub enum DataRecord {
 }
 
 impl DataRecord {
+    /// The entry type string of the value
+    #[must_use]
     pub fn get_data_type(&self) -> EntryType {
         match self {
             Self::Raw(_) => "raw".to_string(),
@@ -473,6 +770,8 @@ impl DataRecord {
         }
     }
 
+    /// Whether the value can be written to an entry of type `e_type`
+    #[must_use]
     pub fn matches_type(&self, e_type: &EntryType) -> bool {
         match self {
             Self::Raw(_) => e_type == "raw",
@@ -489,6 +788,8 @@ impl DataRecord {
         }
     }
 
+    /// The serial of the entry type of the value
+    #[must_use]
     pub const fn get_type_serial(&self) -> u32 {
         match self {
             Self::Raw(_) => RAW_TYPE_SERIAL,
@@ -505,53 +806,60 @@ impl DataRecord {
         }
     }
 
-    #[allow(unused_results)]
+    /// Serializes the value as a record of entry `id` to `out_buffer`
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordTooLarge`] if the payload doesn't fit in a record
+    /// - [`DataLogError::Io`] if an IO error occurs
     #[inline]
     pub fn write_to(self, timestamp: FrcTimestamp, id: EntryId, out_buffer: &mut impl Write) -> Result<(), DataLogError> {
+        self.write_to_with_width(HeaderWidth::Minimal, timestamp, id, out_buffer)
+    }
+
+    /// Serializes the value as a record of entry `id` to `out_buffer` with headers of the given width
+    #[allow(unused_results)]
+    pub(crate) fn write_to_with_width(&self, width: HeaderWidth, timestamp: FrcTimestamp, id: EntryId, out_buffer: &mut impl Write) -> Result<(), DataLogError> {
         let payload_size = self.binary_payload_size().ok_or(DataLogError::RecordTooLarge)?;
-        let element_sizes = RecordElementSizes::create(timestamp, id, payload_size);
+        let header = EncodedRecordHeader::new(width, timestamp, id, payload_size);
 
-        out_buffer.write_u8(element_sizes.bit_field.bits())?; //1-byte header length bitfield
-        out_buffer.write_all(element_sizes.id.as_binary())?; //1 to 4-byte (32-bit) entry ID
-        out_buffer.write_all(element_sizes.payload.as_binary())?; // 1 to 4-byte (32-bit) payload size (in bytes)
-        out_buffer.write_all(element_sizes.timestamp.as_binary())?; // 1 to 8-byte (64-bit) timestamp (in microseconds)
+        out_buffer.write_all(header.as_bytes())?; // bitfield, entry id, payload size and timestamp
 
         match self {
             Self::Raw(data) => out_buffer.write_all(data.iter().as_slice())?,
-            Self::Boolean(data) => out_buffer.write_u8(u8::from(data))?,
+            Self::Boolean(data) => out_buffer.write_u8(u8::from(*data))?,
             Self::Integer(data) => out_buffer.write_all(&data.to_le_bytes())?,
             Self::Float(data) => out_buffer.write_all(&data.to_le_bytes())?,
-            Self::Double(data) => out_buffer.write_f64::<LittleEndian>(data)?,
+            Self::Double(data) => out_buffer.write_f64::<LittleEndian>(*data)?,
             Self::String(data) => out_buffer.write_all(data.as_bytes())?,
             Self::BooleanArray(data) => {
-                for b in data.iter() {
+                for b in data {
                     out_buffer.write_u8(u8::from(*b))?;
                 }
             },
             Self::IntegerArray(data) => {
-                for i in data.iter() {
+                for i in data {
                     out_buffer.write_i64::<LittleEndian>(*i)?;
                 }
             },
             Self::FloatArray(data) => {
-                for f in data.iter() {
+                for f in data {
                     out_buffer.write_f32::<LittleEndian>(*f)?;
                 }
             },
             Self::DoubleArray(data) => {
-                for d in data.iter() {
+                for d in data {
                     out_buffer.write_f64::<LittleEndian>(*d)?;
                 }
             },
             Self::StringArray(data) => {
-                for s in data.iter() {
+                for s in data {
                     if let Ok(len) = <usize as TryInto<u32>>::try_into(s.len()) {
                         out_buffer.write_u32::<LittleEndian>(len)?;
                         out_buffer.write_all(s.as_bytes())?;
                     }
                 }
             },
-        };
+        }
 
         Ok(())
     }
@@ -560,6 +868,7 @@ impl DataRecord {
     /// The size of the binary representation of the record in bytes
     /// or None if the record is too large to be represented in a u32
     #[inline]
+    #[must_use]
     #[allow(clippy::cast_possible_truncation)]
     pub fn binary_payload_size(&self) -> Option<u32> {
         const BOOL_SIZE: u32 = core::mem::size_of::<bool>() as u32;
@@ -587,7 +896,7 @@ impl DataRecord {
                 .and_then(|len| len.checked_mul(DOUBLE_SIZE)),
             Self::StringArray(data) => {
                 let mut size: usize = 0;
-                for s in data.iter() {
+                for s in data {
                     size = size.checked_add(core::mem::size_of::<u32>() + s.len())?;
                 }
                 size.try_into().ok()
@@ -595,6 +904,11 @@ impl DataRecord {
         }
     }
 
+    /// Parses a record payload of an entry whose type has the serial `type_serial`
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordReaderOutOfBounds`] if the payload is too short
+    /// - [`DataLogError::RecordType`] if the type is unsupported
     pub fn from_binary(bytes: &[u8], type_serial: u32) -> Result<Self, DataLogError> {
         if bytes.is_empty() {
             return Err(DataLogError::RecordReaderOutOfBounds("Bytes len is 0"));
@@ -619,40 +933,16 @@ impl DataRecord {
             BOOLEAN_TYPE_SERIAL => {
                 Ok(Self::Boolean(reader.bool()?))
             }
-            DOUBLE_ARRAY_TYPE_SERIAL => {
-                let mut doubles = Vec::new();
-                while reader.bytes_left() >= 8 {
-                    doubles.push(reader.f64()?);
-                }
-                Ok(Self::DoubleArray(doubles.into_boxed_slice()))
-            }
+            DOUBLE_ARRAY_TYPE_SERIAL => Ok(Self::DoubleArray(f64::decode_all(reader.the_rest()))),
             INT_TYPE_SERIAL => {
                 Ok(Self::Integer(reader.i64()?))
             }
             FLOAT_TYPE_SERIAL => {
                 Ok(Self::Float(reader.f32()?))
             }
-            BOOLEAN_ARRAY_TYPE_SERIAL => {
-                let mut bools = Vec::new();
-                while !reader.is_empty() {
-                    bools.push(reader.bool()?);
-                }
-                Ok(Self::BooleanArray(bools.into_boxed_slice()))
-            }
-            INT_ARRAY_TYPE_SERIAL => {
-                let mut ints = Vec::new();
-                while reader.bytes_left() >= 8 {
-                    ints.push(reader.i64()?);
-                }
-                Ok(Self::IntegerArray(ints.into_boxed_slice()))
-            }
-            FLOAT_ARRAY_TYPE_SERIAL => {
-                let mut floats = Vec::new();
-                while reader.bytes_left() >= 4 {
-                    floats.push(reader.f32()?);
-                }
-                Ok(Self::FloatArray(floats.into_boxed_slice()))
-            }
+            BOOLEAN_ARRAY_TYPE_SERIAL => Ok(Self::BooleanArray(bool::decode_all(reader.the_rest()))),
+            INT_ARRAY_TYPE_SERIAL => Ok(Self::IntegerArray(i64::decode_all(reader.the_rest()))),
+            FLOAT_ARRAY_TYPE_SERIAL => Ok(Self::FloatArray(f32::decode_all(reader.the_rest()))),
             STRING_ARRAY_TYPE_SERIAL => {
                 let mut strings = Vec::new();
                 while reader.bytes_left() >= 4 {
@@ -709,3 +999,270 @@ impl From<FrcValue> for DataRecord {
         }
     }
 }
+
+/// The value of a data record borrowing its payload from the buffer it was parsed from,
+/// so records that are only inspected or written again aren't copied
+///
+/// Strings and raw payloads are borrowed as is and numeric arrays are decoded as they are read,
+/// a value can be turned into an owned [`DataRecord`] with [`From`].
+#[derive(Debug, Clone, Copy)]
+#[allow(missing_docs)]
+pub enum DataRecordRef<'a> {
+    Raw(&'a [u8]),
+    Boolean(bool),
+    Integer(i64),
+    Float(f32),
+    Double(f64),
+    String(&'a str),
+    BooleanArray(PackedArrayRef<'a, bool>),
+    IntegerArray(PackedArrayRef<'a, i64>),
+    FloatArray(PackedArrayRef<'a, f32>),
+    DoubleArray(PackedArrayRef<'a, f64>),
+    StringArray(StringArrayRef<'a>),
+}
+
+impl<'a> DataRecordRef<'a> {
+    /// Parses a record payload of an entry whose type has the serial `type_serial`, see [`DataRecord::from_binary`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordReaderOutOfBounds`] if the payload is too short or a string isn't valid utf8
+    /// - [`DataLogError::RecordType`] if the type is unsupported
+    pub fn from_binary(bytes: &'a [u8], type_serial: u32) -> Result<Self, DataLogError> {
+        if bytes.is_empty() {
+            return Err(DataLogError::RecordReaderOutOfBounds("Bytes len is 0"));
+        }
+        let mut reader = RecordByteReader::new(bytes);
+        // ordered by most to least used, structs fall under raw
+        match type_serial {
+            DOUBLE_TYPE_SERIAL => Ok(Self::Double(reader.f64()?)),
+            STRING_TYPE_SERIAL => Ok(Self::String(reader.string(bytes.len())?)),
+            RAW_TYPE_SERIAL => Ok(Self::Raw(bytes)),
+            BOOLEAN_TYPE_SERIAL => Ok(Self::Boolean(reader.bool()?)),
+            DOUBLE_ARRAY_TYPE_SERIAL => Ok(Self::DoubleArray(PackedArrayRef::new(bytes))),
+            INT_TYPE_SERIAL => Ok(Self::Integer(reader.i64()?)),
+            FLOAT_TYPE_SERIAL => Ok(Self::Float(reader.f32()?)),
+            BOOLEAN_ARRAY_TYPE_SERIAL => Ok(Self::BooleanArray(PackedArrayRef::new(bytes))),
+            INT_ARRAY_TYPE_SERIAL => Ok(Self::IntegerArray(PackedArrayRef::new(bytes))),
+            FLOAT_ARRAY_TYPE_SERIAL => Ok(Self::FloatArray(PackedArrayRef::new(bytes))),
+            STRING_ARRAY_TYPE_SERIAL => Ok(Self::StringArray(StringArrayRef::new(bytes)?)),
+            _ => Err(DataLogError::RecordType("Unsupported type")),
+        }
+    }
+
+    /// The serial of the entry type of the value
+    #[must_use]
+    pub const fn get_type_serial(&self) -> u32 {
+        match self {
+            Self::Raw(_) => RAW_TYPE_SERIAL,
+            Self::Boolean(_) => BOOLEAN_TYPE_SERIAL,
+            Self::Integer(_) => INT_TYPE_SERIAL,
+            Self::Float(_) => FLOAT_TYPE_SERIAL,
+            Self::Double(_) => DOUBLE_TYPE_SERIAL,
+            Self::String(_) => STRING_TYPE_SERIAL,
+            Self::BooleanArray(_) => BOOLEAN_ARRAY_TYPE_SERIAL,
+            Self::IntegerArray(_) => INT_ARRAY_TYPE_SERIAL,
+            Self::FloatArray(_) => FLOAT_ARRAY_TYPE_SERIAL,
+            Self::DoubleArray(_) => DOUBLE_ARRAY_TYPE_SERIAL,
+            Self::StringArray(_) => STRING_ARRAY_TYPE_SERIAL,
+        }
+    }
+
+    /// Serializes the value as a record of entry `id` to `out_buffer` without decoding it
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordTooLarge`] if the payload doesn't fit in a record
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write_to(self, timestamp: FrcTimestamp, id: EntryId, out_buffer: &mut impl Write) -> Result<(), DataLogError> {
+        match self {
+            Self::Raw(payload) => write_encoded_data_record(timestamp, id, payload, out_buffer),
+            Self::Boolean(data) => write_encoded_data_record(timestamp, id, &[u8::from(data)], out_buffer),
+            Self::Integer(data) => write_encoded_data_record(timestamp, id, &data.to_le_bytes(), out_buffer),
+            Self::Float(data) => write_encoded_data_record(timestamp, id, &data.to_le_bytes(), out_buffer),
+            Self::Double(data) => write_encoded_data_record(timestamp, id, &data.to_le_bytes(), out_buffer),
+            Self::String(data) => write_encoded_data_record(timestamp, id, data.as_bytes(), out_buffer),
+            Self::BooleanArray(data) => write_encoded_data_record(timestamp, id, data.as_bytes(), out_buffer),
+            Self::IntegerArray(data) => write_encoded_data_record(timestamp, id, data.as_bytes(), out_buffer),
+            Self::FloatArray(data) => write_encoded_data_record(timestamp, id, data.as_bytes(), out_buffer),
+            Self::DoubleArray(data) => write_encoded_data_record(timestamp, id, data.as_bytes(), out_buffer),
+            Self::StringArray(data) => write_encoded_data_record(timestamp, id, data.as_bytes(), out_buffer),
+        }
+    }
+}
+
+impl From<DataRecordRef<'_>> for DataRecord {
+    fn from(value: DataRecordRef<'_>) -> Self {
+        match value {
+            DataRecordRef::Raw(data) => Self::Raw(Box::from(data)),
+            DataRecordRef::Boolean(data) => Self::Boolean(data),
+            DataRecordRef::Integer(data) => Self::Integer(data),
+            DataRecordRef::Float(data) => Self::Float(data),
+            DataRecordRef::Double(data) => Self::Double(data),
+            DataRecordRef::String(data) => Self::String(Box::from(data)),
+            DataRecordRef::BooleanArray(data) => Self::BooleanArray(data.to_boxed_slice()),
+            DataRecordRef::IntegerArray(data) => Self::IntegerArray(data.to_boxed_slice()),
+            DataRecordRef::FloatArray(data) => Self::FloatArray(data.to_boxed_slice()),
+            DataRecordRef::DoubleArray(data) => Self::DoubleArray(data.to_boxed_slice()),
+            DataRecordRef::StringArray(data) => Self::StringArray(data.iter().map(Box::from).collect()),
+        }
+    }
+}
+
+/// An element of a numeric array payload, stored as little endian bytes of a fixed size
+pub trait PackedElement: Copy + 'static {
+    /// The size of an element in bytes
+    const SIZE: usize;
+
+    /// Decodes an element from exactly [`PackedElement::SIZE`] bytes
+    fn from_le_slice(bytes: &[u8]) -> Self;
+
+    /// Decodes every whole element of `bytes`
+    fn decode_all(bytes: &[u8]) -> Box<[Self]> {
+        bytes.chunks_exact(Self::SIZE).map(Self::from_le_slice).collect()
+    }
+}
+
+impl PackedElement for bool {
+    const SIZE: usize = 1;
+
+    fn from_le_slice(bytes: &[u8]) -> Self {
+        bytes.iter().any(|byte| *byte != 0)
+    }
+}
+
+macro_rules! packed_element {
+    ($($ty:ty),*) => {
+        $(
+            impl PackedElement for $ty {
+                const SIZE: usize = core::mem::size_of::<$ty>();
+
+                fn from_le_slice(bytes: &[u8]) -> Self {
+                    let mut buf = [0u8; core::mem::size_of::<$ty>()];
+                    buf.copy_from_slice(bytes);
+                    Self::from_le_bytes(buf)
+                }
+
+                fn decode_all(bytes: &[u8]) -> Box<[Self]> {
+                    let bytes = &bytes[..bytes.len() - bytes.len() % Self::SIZE];
+                    if cfg!(target_endian = "little") {
+                        // the payload is already laid out like the array, it only needs to be copied to aligned memory
+                        bytemuck::pod_collect_to_vec(bytes).into_boxed_slice()
+                    } else {
+                        bytes.chunks_exact(Self::SIZE).map(Self::from_le_slice).collect()
+                    }
+                }
+            }
+        )*
+    };
+}
+packed_element!(i64, f32, f64);
+
+/// A numeric array payload that decodes its elements as they are read,
+/// bytes after the last whole element are ignored like when the array is decoded into a [`DataRecord`]
+#[derive(Debug, Clone, Copy)]
+pub struct PackedArrayRef<'a, T: PackedElement> {
+    bytes: &'a [u8],
+    element: core::marker::PhantomData<T>
+}
+
+impl<'a, T: PackedElement> PackedArrayRef<'a, T> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self {
+            bytes: &bytes[..bytes.len() - bytes.len() % T::SIZE],
+            element: core::marker::PhantomData
+        }
+    }
+
+    /// The number of elements
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.bytes.len() / T::SIZE
+    }
+
+    /// Whether the array has no elements
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    /// The element at `index`, [`None`] if it is out of bounds
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<T> {
+        let start = index.checked_mul(T::SIZE)?;
+        let end = start.checked_add(T::SIZE)?;
+        self.bytes.get(start..end).map(T::from_le_slice)
+    }
+
+    /// Decodes the elements in order
+    #[must_use]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = T> + 'a {
+        self.bytes.chunks_exact(T::SIZE).map(T::from_le_slice)
+    }
+
+    /// Decodes every element at once, faster than collecting [`PackedArrayRef::iter`] for large arrays
+    #[must_use]
+    pub fn to_boxed_slice(&self) -> Box<[T]> {
+        T::decode_all(self.bytes)
+    }
+
+    /// The encoded elements
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
+
+/// A string array payload whose strings are borrowed from it,
+/// the lengths and utf8 are checked once when it is parsed
+#[derive(Debug, Clone, Copy)]
+pub struct StringArrayRef<'a> {
+    bytes: &'a [u8],
+    len: usize
+}
+
+impl<'a> StringArrayRef<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Self, DataLogError> {
+        let mut reader = RecordByteReader::new(bytes);
+        let mut len = 0;
+        while reader.bytes_left() >= 4 {
+            let string_len = usize::try_from(reader.u32()?)?;
+            if reader.bytes_left() < string_len {
+                return Err(DataLogError::RecordReaderOutOfBounds("String[]"));
+            }
+            let _ = reader.string(string_len)?;
+            len += 1;
+        }
+        Ok(Self {
+            bytes: &bytes[..bytes.len() - reader.bytes_left()],
+            len
+        })
+    }
+
+    /// The number of strings
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the array has no strings
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The strings in order
+    #[must_use]
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = &'a str> + 'a {
+        let mut reader = RecordByteReader::new(self.bytes);
+        (0..self.len).map(move |_| {
+            // checked when the array was parsed
+            let len = reader.u32().map_or(0, |len| len as usize);
+            reader.string(len).unwrap_or_default()
+        })
+    }
+
+    /// The encoded strings
+    #[must_use]
+    pub const fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+}
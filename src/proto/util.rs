@@ -1,6 +1,6 @@
-use std::ops::Deref;
+use std::{io::Read, ops::Deref};
 
-use crate::error::DataLogError;
+use crate::{error::DataLogError, proto::records::RecordElementBitfield};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -28,6 +28,11 @@ impl UInt {
         Self { size, value }
     }
 
+    /// An int written with `size` bytes even if it fits in fewer, `value` has to fit in them
+    pub const fn with_size(size: u8, value: u64) -> Self {
+        Self::new_unchecked(size, value)
+    }
+
     #[inline]
     pub const fn shrink(&self) -> Self {
         let universal_int = self.value;
@@ -58,7 +63,7 @@ impl UInt {
     #[allow(trivial_casts)]
     pub const fn as_binary(&self) -> &[u8] {
         unsafe {
-            std::slice::from_raw_parts(
+            core::slice::from_raw_parts(
                 std::ptr::from_ref::<u64>(&self.value).cast::<u8>(),
                 self.size as usize
             )
@@ -116,7 +121,7 @@ impl<'buf> RecordByteReader<'buf> {
         Self { bytes }
     }
 
-    pub fn byte(&mut self) -> Result<u8, DataLogError> {
+    pub const fn byte(&mut self) -> Result<u8, DataLogError> {
         if self.bytes.is_empty() {
             return Err(DataLogError::RecordReaderOutOfBounds("u8"));
         }
@@ -163,7 +168,7 @@ impl<'buf> RecordByteReader<'buf> {
     }
 
     #[allow(unused)]
-    pub fn i32(&mut self) -> Result<i32, DataLogError> {
+    pub const fn i32(&mut self) -> Result<i32, DataLogError> {
         if self.bytes.len() < 4 {
             return Err(DataLogError::RecordReaderOutOfBounds("i32"));
         }
@@ -174,7 +179,7 @@ impl<'buf> RecordByteReader<'buf> {
         Ok(i32::from_le_bytes(bytes))
     }
 
-    pub fn i64(&mut self) -> Result<i64, DataLogError> {
+    pub const fn i64(&mut self) -> Result<i64, DataLogError> {
         if self.bytes.len() < 8 {
             return Err(DataLogError::RecordReaderOutOfBounds("i64"));
         }
@@ -185,7 +190,7 @@ impl<'buf> RecordByteReader<'buf> {
         Ok(i64::from_le_bytes(bytes))
     }
 
-    pub fn u32(&mut self) -> Result<u32, DataLogError> {
+    pub const fn u32(&mut self) -> Result<u32, DataLogError> {
         if self.bytes.len() < 4 {
             return Err(DataLogError::RecordReaderOutOfBounds("u32"));
         }
@@ -196,7 +201,7 @@ impl<'buf> RecordByteReader<'buf> {
         Ok(u32::from_le_bytes(bytes))
     }
 
-    pub fn bool(&mut self) -> Result<bool, DataLogError> {
+    pub const fn bool(&mut self) -> Result<bool, DataLogError> {
         if self.bytes.is_empty() {
             return Err(DataLogError::RecordReaderOutOfBounds("bool"));
         }
@@ -207,7 +212,7 @@ impl<'buf> RecordByteReader<'buf> {
         Ok(u8::from_le_bytes(bytes) != 0)
     }
 
-    pub fn f32(&mut self) -> Result<f32, DataLogError> {
+    pub const fn f32(&mut self) -> Result<f32, DataLogError> {
         if self.bytes.len() < 4 {
             return Err(DataLogError::RecordReaderOutOfBounds("f32"));
         }
@@ -218,7 +223,7 @@ impl<'buf> RecordByteReader<'buf> {
         Ok(f32::from_le_bytes(bytes))
     }
 
-    pub fn f64(&mut self) -> Result<f64, DataLogError> {
+    pub const fn f64(&mut self) -> Result<f64, DataLogError> {
         if self.bytes.len() < 8 {
             return Err(DataLogError::RecordReaderOutOfBounds("f64"));
         }
@@ -262,3 +267,160 @@ impl<'buf> RecordByteReader<'buf> {
         self.bytes.len()
     }
 }
+
+/// How many bytes a [`StreamingRecordByteReader`] asks its source for at least
+const READ_AHEAD: usize = 8 * 1024;
+/// How many bytes a [`StreamingRecordByteReader`] asks its source for at most,
+/// so a corrupted length doesn't allocate more than the source holds
+const MAX_READ: usize = 1024 * 1024;
+
+/// A [`RecordByteReader`] that pulls its bytes from an [`io::Read`](Read) as they are needed,
+/// only buffering what hasn't been read yet
+///
+/// Bytes read before the source ran out stay buffered, so a reader following a file that
+/// is still being written can try again once more has been written.
+#[derive(Debug)]
+pub struct StreamingRecordByteReader<R: Read> {
+    inner: R,
+    buffer: Vec<u8>,
+    /// The start of the unread bytes in `buffer`
+    start: usize
+}
+
+impl<R: Read> StreamingRecordByteReader<R> {
+    #[inline]
+    pub const fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+            start: 0
+        }
+    }
+
+    /// Reads until at least `len` bytes are buffered, returning `false` if the source ran out first
+    pub fn fill(&mut self, len: usize) -> Result<bool, DataLogError> {
+        while self.bytes_buffered() < len {
+            if self.start > 0 {
+                let _ = self.buffer.drain(..self.start);
+                self.start = 0;
+            }
+            let filled = self.buffer.len();
+            self.buffer.resize(filled + (len - filled).clamp(READ_AHEAD, MAX_READ), 0);
+            let read = loop {
+                match self.inner.read(&mut self.buffer[filled..]) {
+                    Err(err) if err.kind() == std::io::ErrorKind::Interrupted => {}
+                    read => break read
+                }
+            };
+            self.buffer.truncate(filled + read.as_ref().map_or(0, |read| *read));
+            if read? == 0 {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    #[inline]
+    pub const fn bytes_buffered(&self) -> usize {
+        self.buffer.len() - self.start
+    }
+
+    /// Takes the next `len` bytes as a [`RecordByteReader`]
+    pub fn take(&mut self, len: usize) -> Result<RecordByteReader<'_>, DataLogError> {
+        if !self.fill(len)? {
+            return Err(DataLogError::RecordReaderOutOfBounds("stream"));
+        }
+        let bytes = &self.buffer[self.start..self.start + len];
+        self.start += len;
+        Ok(RecordByteReader::new(bytes))
+    }
+
+    #[allow(unused)]
+    pub fn byte(&mut self) -> Result<u8, DataLogError> {
+        self.take(1)?.byte()
+    }
+
+    pub fn inspect_byte(&mut self) -> Result<u8, DataLogError> {
+        if !self.fill(1)? {
+            return Err(DataLogError::RecordReaderOutOfBounds("u8"));
+        }
+        Ok(self.buffer[self.start])
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&[u8], DataLogError> {
+        Ok(self.take(len)?.the_rest())
+    }
+
+    #[allow(unused)]
+    pub fn string(&mut self, len: usize) -> Result<&str, DataLogError> {
+        self.take(len)?.string(len)
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DataLogError> {
+        self.take(4)?.u32()
+    }
+
+    #[allow(unused)]
+    pub fn i64(&mut self) -> Result<i64, DataLogError> {
+        self.take(8)?.i64()
+    }
+
+    #[allow(unused)]
+    pub fn f32(&mut self) -> Result<f32, DataLogError> {
+        self.take(4)?.f32()
+    }
+
+    #[allow(unused)]
+    pub fn f64(&mut self) -> Result<f64, DataLogError> {
+        self.take(8)?.f64()
+    }
+
+    #[allow(unused)]
+    pub fn bool(&mut self) -> Result<bool, DataLogError> {
+        self.take(1)?.bool()
+    }
+
+    #[allow(unused)]
+    pub fn uint(&mut self, size: usize) -> Result<UInt, DataLogError> {
+        self.take(size)?.uint(size)
+    }
+
+    #[allow(unused)]
+    pub fn skip(&mut self, len: usize) -> Result<(), DataLogError> {
+        self.take(len).map(|_| ())
+    }
+
+    /// The next whole record with its header, [`None`] if the source runs out before it ends
+    pub fn record(&mut self) -> Result<Option<&[u8]>, DataLogError> {
+        if !self.fill(1)? {
+            return Ok(None);
+        }
+        let bit_field = RecordElementBitfield::from_bits_truncate(self.inspect_byte()?);
+        let header_len = 1 + bit_field.total_length();
+        if !self.fill(header_len)? {
+            return Ok(None);
+        }
+        let payload_size = &self.buffer[self.start + 1 + bit_field.id_length()..][..bit_field.payload_length()];
+        let payload_size = UInt::from_binary(payload_size)
+            .and_then(|size| usize::try_from(*size).ok())
+            .ok_or(DataLogError::RecordDeserialize("Failed to read payload size"))?;
+        if !self.fill(header_len + payload_size)? {
+            return Ok(None);
+        }
+        self.bytes(header_len + payload_size).map(Some)
+    }
+
+    /// Whether a whole record header is buffered, after [`StreamingRecordByteReader::record`]
+    /// returned [`None`] this means the source ended in the middle of a payload
+    pub fn has_partial_record(&self) -> bool {
+        self.buffer.get(self.start).is_some_and(|bit_field| {
+            self.bytes_buffered() > RecordElementBitfield::from_bits_truncate(*bit_field).total_length()
+        })
+    }
+
+    #[allow(unused)]
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+}
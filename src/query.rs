@@ -0,0 +1,323 @@
+use std::cmp::Ordering;
+
+use frclib_core::value::{FrcTimestamp, FrcValue};
+
+use crate::{reader::EntryFilterReader, DataLogError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Comparison {
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual
+}
+
+impl Comparison {
+    fn accepts(self, ordering: Ordering) -> bool {
+        match self {
+            Self::Less => ordering == Ordering::Less,
+            Self::LessEqual => ordering != Ordering::Greater,
+            Self::Greater => ordering == Ordering::Greater,
+            Self::GreaterEqual => ordering != Ordering::Less,
+            Self::Equal => ordering == Ordering::Equal,
+            Self::NotEqual => ordering != Ordering::Equal
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Literal {
+    Number(f64),
+    Bool(bool),
+    String(String)
+}
+
+impl Literal {
+    #[allow(clippy::cast_precision_loss)]
+    fn compare(&self, value: &FrcValue) -> Option<Ordering> {
+        match (value, self) {
+            (FrcValue::Double(v), Self::Number(n)) => v.partial_cmp(n),
+            (FrcValue::Float(v), Self::Number(n)) => f64::from(*v).partial_cmp(n),
+            (FrcValue::Int(v), Self::Number(n)) => (*v as f64).partial_cmp(n),
+            (FrcValue::Boolean(v), Self::Bool(b)) => Some(v.cmp(b)),
+            (FrcValue::String(v), Self::String(s)) => Some(v.as_ref().cmp(s.as_str())),
+            _ => None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct ValueCondition {
+    comparison: Comparison,
+    literal: Literal
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Word(String),
+    Number(f64, Option<String>),
+    String(String),
+    Comparison(Comparison),
+    OpenBracket,
+    CloseBracket,
+    Comma
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, DataLogError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            let _ = chars.next();
+        } else if c == '[' {
+            let _ = chars.next();
+            tokens.push(Token::OpenBracket);
+        } else if c == ']' {
+            let _ = chars.next();
+            tokens.push(Token::CloseBracket);
+        } else if c == ',' {
+            let _ = chars.next();
+            tokens.push(Token::Comma);
+        } else if c == '"' {
+            let _ = chars.next();
+            let mut string = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => string.push(c),
+                    None => return Err(DataLogError::QueryParse("Unterminated string literal"))
+                }
+            }
+            tokens.push(Token::String(string));
+        } else if "<>=!".contains(c) {
+            let _ = chars.next();
+            let followed_by_eq = chars.next_if_eq(&'=').is_some();
+            let comparison = match (c, followed_by_eq) {
+                ('<', false) => Comparison::Less,
+                ('<', true) => Comparison::LessEqual,
+                ('>', false) => Comparison::Greater,
+                ('>', true) => Comparison::GreaterEqual,
+                ('=', _) => Comparison::Equal,
+                ('!', true) => Comparison::NotEqual,
+                _ => return Err(DataLogError::QueryParse("Unknown operator"))
+            };
+            tokens.push(Token::Comparison(comparison));
+        } else if c.is_ascii_digit() || c == '-' || c == '.' {
+            let mut number = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '-' || *c == '.' || *c == '_') {
+                number.push(c);
+            }
+            let mut suffix = String::new();
+            while let Some(c) = chars.next_if(char::is_ascii_alphabetic) {
+                suffix.push(c);
+            }
+            let number = number.replace('_', "").parse()
+                .map_err(|_| DataLogError::QueryParse("Invalid number"))?;
+            tokens.push(Token::Number(number, (!suffix.is_empty()).then_some(suffix)));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_alphanumeric() || *c == '_') {
+                word.push(c);
+            }
+            tokens.push(Token::Word(word));
+        } else {
+            return Err(DataLogError::QueryParse("Unexpected character"));
+        }
+    }
+    Ok(tokens)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn time_literal(number: f64, suffix: Option<&str>) -> Result<FrcTimestamp, DataLogError> {
+    let micros = match suffix {
+        None | Some("s") => number * 1e6,
+        Some("ms") => number * 1e3,
+        Some("us") => number,
+        Some("min") => number * 60e6,
+        Some(_) => return Err(DataLogError::QueryParse("Unknown time unit"))
+    };
+    if !micros.is_finite() || micros < 0.0 {
+        return Err(DataLogError::QueryParse("Time can't be negative"));
+    }
+    Ok(micros.round() as FrcTimestamp)
+}
+
+/// A selection of entries and values compiled from a query string
+///
+/// # Syntax
+/// ```text
+/// <key pattern> [where <condition> [and <condition>]...]
+/// ```
+/// The key pattern matches entry keys, `*` matches any run of characters and `?` any single character.
+///
+/// Conditions can be
+/// - `value <op> <literal>` where `<op>` is one of `<`, `<=`, `>`, `>=`, `==`, `!=`
+///   and `<literal>` is a number, `true`/`false` or a `"quoted string"`
+/// - `t <op> <time>` or `t in [<time>, <time>]` where `<time>` is a number with an optional
+///   `us`, `ms`, `s` or `min` suffix, bare numbers are seconds
+/// - `type == <type string>`
+///
+/// # Example
+/// ```rust
+/// use frclib_datalog::query::Query;
+///
+/// let query = Query::parse("/drivetrain/* where value > 3 and t in [10s, 25s]")
+///     .expect("Failed to parse query");
+/// assert!(query.matches_key("/drivetrain/velocity"));
+/// assert!(!query.matches_key("/arm/angle"));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    key_pattern: String,
+    after: Option<FrcTimestamp>,
+    before: Option<FrcTimestamp>,
+    required_type: Option<String>,
+    value_conditions: Vec<ValueCondition>
+}
+
+impl Query {
+    /// Parses a query string
+    ///
+    /// # Errors
+    /// - [`DataLogError::QueryParse`] if the query is malformed
+    pub fn parse(source: &str) -> Result<Self, DataLogError> {
+        let (pattern, conditions) = match source.find(" where ") {
+            Some(index) => (&source[..index], Some(&source[index + " where ".len()..])),
+            None => (source, None)
+        };
+        let pattern = pattern.trim();
+        let pattern = pattern.strip_prefix('"')
+            .and_then(|pattern| pattern.strip_suffix('"'))
+            .unwrap_or(pattern);
+        if pattern.is_empty() {
+            return Err(DataLogError::QueryParse("Missing key pattern"));
+        }
+
+        let mut query = Self {
+            key_pattern: pattern.to_string(),
+            after: None,
+            before: None,
+            required_type: None,
+            value_conditions: Vec::new()
+        };
+
+        if let Some(conditions) = conditions {
+            let tokens = tokenize(conditions)?;
+            for condition in tokens.split(|token| matches!(token, Token::Word(word) if word == "and")) {
+                query.add_condition(condition)?;
+            }
+        }
+
+        Ok(query)
+    }
+
+    fn add_condition(&mut self, condition: &[Token]) -> Result<(), DataLogError> {
+        match condition {
+            [Token::Word(field), Token::Comparison(comparison), literal] if field == "value" => {
+                let literal = match literal {
+                    Token::Number(number, None) => Literal::Number(*number),
+                    Token::Word(word) if word == "true" => Literal::Bool(true),
+                    Token::Word(word) if word == "false" => Literal::Bool(false),
+                    Token::String(string) => Literal::String(string.clone()),
+                    _ => return Err(DataLogError::QueryParse("Invalid value literal"))
+                };
+                self.value_conditions.push(ValueCondition { comparison: *comparison, literal });
+            }
+            [Token::Word(field), Token::Comparison(comparison), Token::Number(number, suffix)] if field == "t" => {
+                let time = time_literal(*number, suffix.as_deref())?;
+                match comparison {
+                    Comparison::Greater => self.restrict_after(time.saturating_add(1)),
+                    Comparison::GreaterEqual => self.restrict_after(time),
+                    Comparison::Less => self.restrict_before(time.checked_sub(1)
+                        .ok_or(DataLogError::QueryParse("No time is before 0"))?),
+                    Comparison::LessEqual => self.restrict_before(time),
+                    Comparison::Equal => {
+                        self.restrict_after(time);
+                        self.restrict_before(time);
+                    }
+                    Comparison::NotEqual => return Err(DataLogError::QueryParse("Time does not support !="))
+                }
+            }
+            [Token::Word(field), Token::Word(keyword), Token::OpenBracket,
+                Token::Number(start, start_suffix), Token::Comma,
+                Token::Number(end, end_suffix), Token::CloseBracket] if field == "t" && keyword == "in" => {
+                self.restrict_after(time_literal(*start, start_suffix.as_deref())?);
+                self.restrict_before(time_literal(*end, end_suffix.as_deref())?);
+            }
+            [Token::Word(field), Token::Comparison(Comparison::Equal), Token::Word(type_str) | Token::String(type_str)] if field == "type" => {
+                self.required_type = Some(type_str.clone());
+            }
+            [] => return Err(DataLogError::QueryParse("Empty condition")),
+            _ => return Err(DataLogError::QueryParse("Unrecognized condition"))
+        }
+        Ok(())
+    }
+
+    fn restrict_after(&mut self, timestamp: FrcTimestamp) {
+        self.after = Some(self.after.map_or(timestamp, |after| after.max(timestamp)));
+    }
+
+    fn restrict_before(&mut self, timestamp: FrcTimestamp) {
+        self.before = Some(self.before.map_or(timestamp, |before| before.min(timestamp)));
+    }
+
+    /// Returns true if the key matches the key pattern of the query
+    #[must_use]
+    pub fn matches_key(&self, key: &str) -> bool {
+        // only the last `*` is ever backtracked to, a later `*` can match anything an earlier one could,
+        // so matching takes at most the product of the lengths instead of growing exponentially
+        fn glob(pattern: &[char], key: &[char]) -> bool {
+            let (mut p, mut k) = (0, 0);
+            // the position after the last `*` and how much of the key it has taken
+            let mut last_star = None;
+            while let Some(c) = key.get(k) {
+                match pattern.get(p) {
+                    Some('*') => {
+                        last_star = Some((p + 1, k));
+                        p += 1;
+                    }
+                    Some(expected) if *expected == '?' || expected == c => {
+                        p += 1;
+                        k += 1;
+                    }
+                    _ => match last_star {
+                        Some((after_star, taken)) => {
+                            last_star = Some((after_star, taken + 1));
+                            p = after_star;
+                            k = taken + 1;
+                        }
+                        None => return false
+                    }
+                }
+            }
+            pattern.iter().skip(p).all(|c| *c == '*')
+        }
+        let pattern = self.key_pattern.chars().collect::<Vec<_>>();
+        let key = key.chars().collect::<Vec<_>>();
+        glob(&pattern, &key)
+    }
+
+    /// Applies the conditions of the query to an entry filter
+    pub fn apply(&self, filter: &mut EntryFilterReader<'_>) {
+        if let Some(after) = self.after {
+            let _ = filter.after(after);
+        }
+        if let Some(before) = self.before {
+            let _ = filter.before(before);
+        }
+        if let Some(type_str) = &self.required_type {
+            let _ = filter.required_type(type_str.clone());
+        }
+        if !self.value_conditions.is_empty() {
+            let conditions = self.value_conditions.clone();
+            let _ = filter.value_predicate(Box::new(move |value| {
+                conditions.iter().all(|condition| {
+                    condition.literal.compare(value)
+                        .is_some_and(|ordering| condition.comparison.accepts(ordering))
+                })
+            }));
+        }
+    }
+}
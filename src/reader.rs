@@ -1,15 +1,75 @@
-use std::{collections::HashMap, fmt::Debug, hash::BuildHasherDefault, io::Read, mem::swap};
+use std::{collections::HashMap, fmt::Debug, fs::TryLockError, hash::BuildHasherDefault, io::{BufReader, Read}, mem::swap, path::Path};
 
-use crate::{proto::{entries::{get_str_type_serial, EntryLifeStatus, RAW_TYPE_SERIAL, SUPPORTED_TYPES_SERIALS}, records::{parse_records, ControlRecord, Record}}, DataLogError, EntryId, TimestampedValue};
-use byteorder::ReadBytesExt;
+use crate::{locking::{open_existing, LockedFilePolicy}, messages::{ConsoleMessage, MESSAGES_KEY}, proto::{util::StreamingRecordByteReader, entries::{get_str_type_serial, EntryLifeStatus, RAW_TYPE_SERIAL, SUPPORTED_TYPES_SERIALS}, records::{for_each_record_from, header_len, split_record, ControlRecord, Record}}, query::Query, statistics::EntryStatistics, units::{convert_value, unit_from_metadata, UnitTable}, DataLogError, EntryId, TimestampedValue};
 use frclib_core::{structure::FrcStructureBytes, value::{FrcTimestamp, FrcTimestampedValue, FrcValue, IntoFrcValue}};
 use nohash::NoHashHasher;
 
+/// The magic bytes at the start of a zstd frame
+#[cfg(feature = "zstd")]
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Decompresses a zstd stream, a stream cut off by a crash yields everything before the cut
+#[cfg(feature = "zstd")]
+fn decompress(data: impl Read) -> Result<Vec<u8>, DataLogError> {
+    let mut decoder = zstd::Decoder::new(data)?;
+    let mut decompressed = Vec::new();
+    match decoder.read_to_end(&mut decompressed) {
+        Err(err) if err.kind() != std::io::ErrorKind::UnexpectedEof => Err(err.into()),
+        _ => Ok(decompressed)
+    }
+}
+
+/// The length of the header and the whole records at the start of a log that is still being written
+fn complete_len(bytes: &[u8]) -> usize {
+    // logs that aren't plain, like encrypted ones, are passed on whole
+    let Some(mut len) = header_len(bytes).filter(|_| bytes.starts_with(b"WPILOG")) else {
+        return bytes.len();
+    };
+    while let Some((.., record_len)) = split_record(&bytes[len..]) {
+        len += record_len;
+    }
+    len
+}
+
+/// The header at the start of a log, read without any of its records by [`DataLogReader::peek_header`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataLogHeader {
+    /// The version of the file format as (major, minor)
+    pub format_version: (u8, u8),
+    /// The metadata the log was created with
+    pub metadata: String
+}
+
+/// Reads the magic, version and metadata at the start of a log
+fn read_header(file: &mut StreamingRecordByteReader<impl Read>, require_magic: bool) -> Result<DataLogHeader, DataLogError> {
+    // Validate Magic, input too short to hold it isn't a log either
+    let magic = match file.bytes(6) {
+        Err(DataLogError::RecordReaderOutOfBounds(_)) if require_magic => return Err(DataLogError::MagicMismatch),
+        magic => magic?
+    };
+    if require_magic && magic != b"WPILOG" {
+        return Err(DataLogError::MagicMismatch);
+    }
+
+    let version = file.bytes(2)?;
+    let format_version = (version[1], version[0]);
+
+    // Read Metadata
+    let metadata_len = file.u32()? as usize;
+    let metadata = String::from_utf8(file.bytes(metadata_len)?.to_vec())
+        .unwrap_or_default();
+    Ok(DataLogHeader {
+        format_version,
+        metadata
+    })
+}
+
 #[derive(Debug, Clone)]
 struct EntryData {
     values: Vec<FrcTimestampedValue>,
     metadata: Vec<TimestampedValue<String>>,
-    type_str: Vec<TimestampedValue<String>>
+    type_str: Vec<TimestampedValue<String>>,
+    statistics: EntryStatistics
 }
 
 /// Configuration for the [`DataLogReader`]
@@ -17,19 +77,29 @@ struct EntryData {
 pub struct DataLogReaderConfig {
     /// Require the magic bytes at the start of the file to be `WPILOG`
     pub require_magic: bool,
-    /// Require a specific version of the file format
+    /// Require a compatible version of the file format as (major, minor),
+    /// files with the same major version and at least this minor version are read
+    /// as newer minor versions only add records older readers can skip
     pub required_version: Option<(u8, u8)>,
+    /// Keep every value in memory, when `false` only the [`EntryStatistics`]
+    /// of each entry are kept which is much cheaper for summarizing large numbers of logs
+    pub retain_values: bool,
+    /// What [`DataLogReader::open`] does with a file another process is still writing
+    pub locked_files: LockedFilePolicy,
 }
 impl Default for DataLogReaderConfig {
     fn default() -> Self {
         Self {
             require_magic: true,
-            required_version: Some((1, 0))
+            required_version: Some((1, 0)),
+            retain_values: true,
+            locked_files: LockedFilePolicy::Reject
         }
     }
 }
 
 type StringPredicate = Box<dyn Fn(&str) -> bool>;
+type ValuePredicate = Box<dyn Fn(&FrcValue) -> bool>;
 
 /// A reader that can filter entries based on certain criteria
 pub struct EntryFilterReader<'a> {
@@ -37,7 +107,8 @@ pub struct EntryFilterReader<'a> {
     before: Option<u64>,
     after: Option<u64>,
     required_metadata_predicate: Option<StringPredicate>,
-    required_type_predicate: Option<StringPredicate>
+    required_type_predicate: Option<StringPredicate>,
+    value_predicate: Option<ValuePredicate>
 }
 impl Debug for EntryFilterReader<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -46,14 +117,15 @@ impl Debug for EntryFilterReader<'_> {
             .field("after", &self.after)
             .field("required_metadata_predicate", &self.required_metadata_predicate.is_some())
             .field("required_type_predicate", &self.required_type_predicate.is_some())
+            .field("value_predicate", &self.value_predicate.is_some())
             .finish()
     }
 }
 
 /// An all in one reader for a datalog file
 /// # Example
-/// ```rust
-/// use std::{path::PathBuf, fs:File};
+/// ```rust,no_run
+/// use std::{path::PathBuf, fs::File};
 /// use frclib_datalog::DataLogReader;
 /// use frclib_core::value::FrcValue;
 /// 
@@ -81,9 +153,12 @@ pub struct DataLogReader {
 impl DataLogReader {
     /// Will create a new reader that reads the buffer
     /// 
+    /// With the `zstd` feature logs compressed with zstd, like `.wpilog.zst` files,
+    /// are decompressed transparently.
+    /// 
     /// # Errors
     /// - [`DataLogError::MagicMismatch`] if the magic bytes at the start of the file do not match `WPILOG` and [`DataLogReaderConfig::require_magic`] is `true`
-    /// - [`DataLogError::VersionMismatch`] if the version of the file does not match the required version in [`DataLogReaderConfig::required_version`]
+    /// - [`DataLogError::VersionMismatch`] if the version of the file isn't compatible with the required version in [`DataLogReaderConfig::required_version`]
     /// - [`DataLogError::Io`] if there is an error reading the file
     /// - [`DataLogError::Utf8`] if there is an error reading the metadata or any string entries in the file
     /// - [`DataLogError::IntCast`] if there is an error reading records
@@ -98,51 +173,125 @@ impl DataLogReader {
             keys: HashMap::new(),
             data: HashMap::with_hasher(nohash::BuildNoHashHasher::default())
         };
+        #[cfg(feature = "zstd")]
+        {
+            let mut data = data;
+            // input shorter than the zstd magic is left for the header check to reject
+            let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+            let _ = data.by_ref().take(ZSTD_MAGIC.len() as u64).read_to_end(&mut magic)?;
+            let data = magic.as_slice().chain(data);
+            if magic == ZSTD_MAGIC {
+                reader.deserialize(decompress(data)?.as_slice())?;
+            } else {
+                reader.deserialize(data)?;
+            }
+        }
+        #[cfg(not(feature = "zstd"))]
         reader.deserialize(data)?;
         reader.sort_data();
         Ok(reader)
     }
 
+    /// Will create a new reader that decrypts and reads a log written through an
+    /// [`EncryptedSink`](crate::encryption::EncryptedSink), see [`decrypt`](crate::encryption::decrypt)
+    /// 
+    /// A log that wasn't finished, like the log of a robot that crashed, is only read
+    /// up to its last flush with [`Truncation::Allow`](crate::encryption::Truncation::Allow).
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Decryption`] if the data isn't an encrypted log, the key is wrong, the log was changed
+    ///   or it wasn't finished and truncation isn't allowed
+    /// - Any error of [`DataLogReader::try_new`]
+    #[cfg(feature = "encryption")]
+    pub fn try_new_encrypted(
+        data: impl Read,
+        key: &[u8; 32],
+        truncation: crate::encryption::Truncation,
+        config: DataLogReaderConfig
+    ) -> Result<Self, DataLogError> {
+        Self::try_new(crate::encryption::decrypt(data, key, truncation)?.as_slice(), config)
+    }
+
+    /// Opens and reads the log at `path`, see [`DataLogReader::try_new`]
+    ///
+    /// If a writer in another process still holds the file it is handled by [`DataLogReaderConfig::locked_files`].
+    ///
+    /// # Errors
+    /// - [`DataLogError::FileDoesNotExist`] if there is no file at `path`
+    /// - [`DataLogError::FileLocked`] if the file is being written and the policy is [`LockedFilePolicy::Reject`]
+    /// - Any error of [`DataLogReader::try_new`]
+    pub fn open(path: impl AsRef<Path>, config: DataLogReaderConfig) -> Result<Self, DataLogError> {
+        let file = open_existing(path.as_ref())?;
+        // the shared lock is held until the file is read and closed
+        match (file.try_lock_shared(), config.locked_files) {
+            (Ok(()), _) => {}
+            (Err(TryLockError::WouldBlock), LockedFilePolicy::Reject) => return Err(DataLogError::FileLocked),
+            (Err(TryLockError::WouldBlock), LockedFilePolicy::Wait) => file.lock_shared()?,
+            (Err(TryLockError::WouldBlock), LockedFilePolicy::ReadSnapshot) => {
+                let mut bytes = Vec::new();
+                let _ = BufReader::new(file).read_to_end(&mut bytes)?;
+                // a compressed log ends wherever its last frame was flushed, which can be inside a record
+                #[cfg(feature = "zstd")]
+                if bytes.starts_with(&ZSTD_MAGIC) {
+                    bytes = decompress(bytes.as_slice())?;
+                }
+                return Self::try_new(&bytes[..complete_len(&bytes)], config);
+            }
+            (Err(TryLockError::Error(err)), _) => return Err(DataLogError::Io(err))
+        }
+        Self::try_new(BufReader::new(file), config)
+    }
+
+    /// Reads only the header of the log at `path`, for directory scanners that classify
+    /// and list many logs without the cost of parsing their records
+    /// 
+    /// The file is read without taking a lock, as a writer of the file never changes the header.
+    /// Compressed and encrypted logs don't start with the header and fail with [`DataLogError::MagicMismatch`].
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileDoesNotExist`] if there is no file at `path`
+    /// - [`DataLogError::MagicMismatch`] if the file doesn't start with `WPILOG`
+    /// - [`DataLogError::Io`] if there is an error reading the file
+    /// - [`DataLogError::RecordReaderOutOfBounds`] if the file ends before the end of the header
+    pub fn peek_header(path: impl AsRef<Path>) -> Result<DataLogHeader, DataLogError> {
+        Self::peek_header_from(open_existing(path.as_ref())?)
+    }
+
+    /// Reads only the header at the start of `data`, see [`DataLogReader::peek_header`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MagicMismatch`] if the data doesn't start with `WPILOG`
+    /// - [`DataLogError::Io`] if there is an error reading the data
+    /// - [`DataLogError::RecordReaderOutOfBounds`] if the data ends before the end of the header
+    pub fn peek_header_from(data: impl Read) -> Result<DataLogHeader, DataLogError> {
+        read_header(&mut StreamingRecordByteReader::new(data), true)
+    }
+
     #[allow(clippy::map_entry)]
     fn get_entry_data(&mut self, id: EntryId) -> &mut EntryData {
         self.data.entry(id)
             .or_insert_with(|| EntryData {
                 values: Vec::new(),
                 metadata: Vec::new(),
-                type_str: Vec::new()
+                type_str: Vec::new(),
+                statistics: EntryStatistics::default()
             })
     }
 
     #[allow(unused_results, clippy::collection_is_never_read)]
-    fn deserialize(&mut self, mut file: impl Read) -> Result<(), DataLogError> {
-
-        // Validate Magic
-        let mut magic = [0u8; 6];
-        file.read_exact(&mut magic)?;
-        if  self.config.require_magic && magic != *b"WPILOG" {
-            return Err(DataLogError::MagicMismatch);
-        }
+    fn deserialize(&mut self, file: impl Read) -> Result<(), DataLogError> {
+        let mut file = StreamingRecordByteReader::new(file);
 
-        // Validate Version
-        let mut version = [0u8; 2];
-        file.read_exact(&mut version)?;
-        self.format_version = (version[1], version[0]);
-        if let Some(version) = self.config.required_version {
-            if version != self.format_version{
+        let header = read_header(&mut file, self.config.require_magic)?;
+        self.format_version = header.format_version;
+        if let Some((major, minor)) = self.config.required_version {
+            if self.format_version.0 != major || self.format_version.1 < minor {
                 return Err(DataLogError::VersionMismatch);
             }
         }
-
-        // Read Metadata
-        let metadata_len = file.read_u32::<byteorder::LittleEndian>()?;
-        let mut metadata = vec![0u8; metadata_len as usize];
-        file.read_exact(&mut metadata)?;
-        self.header_metadata = String::from_utf8(metadata)
-            .unwrap_or_default();
+        self.header_metadata = header.metadata;
 
         // Read Records
-        let mut file_buffer = Vec::new();
-        file.read_to_end(&mut file_buffer)?;
         // let mut entry_types: HashMap<EntryId, String, BuildHasherDefault<NoHashHasher<EntryId>>> = {
         //     HashMap::with_capacity_and_hasher(128, nohash::BuildNoHashHasher::default())
         // };
@@ -152,15 +301,15 @@ impl DataLogReader {
         let mut entry_status: HashMap<EntryId, EntryLifeStatus, BuildHasherDefault<NoHashHasher<EntryId>>> = {
             HashMap::with_capacity_and_hasher(128, nohash::BuildNoHashHasher::default())
         };
-        let all_records = parse_records(&file_buffer, &mut entry_type_serials)?;
-        for record in all_records {
+        let retain_values = self.config.retain_values;
+        for_each_record_from(&mut file, &mut entry_type_serials, |record, entry_type_serials| {
             match record {
                 Record::Control(inner, timestamp, id) => {
                     match inner {
                         ControlRecord::Start(name, type_str, metadata) => {
                             if let Some(EntryLifeStatus::Alive { .. }) = entry_status.get(&id) {
                                 // Got a start record for an already started entry
-                                continue;
+                                return Ok(());
                             }
                             entry_status.insert(id, EntryLifeStatus::Alive { start: timestamp });
                             let type_serial = get_str_type_serial(&type_str);
@@ -186,6 +335,8 @@ impl DataLogReader {
                                 self.get_entry_data(id).metadata.push(TimestampedValue::new(timestamp, metadata));
                             }
                         }
+                        // nothing is known about what it changes
+                        ControlRecord::Unknown { .. } => {}
                     }
                 },
                 Record::Data(value, timestamp, id) => {
@@ -193,14 +344,27 @@ impl DataLogReader {
                         let type_serial = entry_type_serials.get(&id)
                             .ok_or(DataLogError::NoSuchEntry)?;
                         if value.get_type_serial() != *type_serial {
-                            continue;
+                            return Ok(());
                         }
 
                         let value = FrcTimestampedValue::new(timestamp, value.into_frc_value());
-                        self.get_entry_data(id).values.push(value);
+                        let data = self.get_entry_data(id);
+                        if retain_values {
+                            data.statistics.update_aggregates(&value);
+                            data.values.push(value);
+                        } else {
+                            data.statistics.update(value);
+                        }
                     }
                 }
             }
+            Ok(())
+        })?;
+        if retain_values {
+            // the latest value is already retained, so it is only copied once per entry
+            for data in self.data.values_mut() {
+                data.statistics.last = data.values.iter().max_by_key(|value| value.timestamp).cloned();
+            }
         }
         Ok(())
     }
@@ -304,6 +468,52 @@ impl DataLogReader {
         Vec::new()
     }
 
+    /// Returns the values from the entry with the given key converted into `unit`,
+    /// if no entry with the given key exists an empty `Vec` is returned.
+    /// 
+    /// The source unit of each value is the `"unit"` field of the entry metadata at the time of the value.
+    /// Integer values are converted into doubles.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::UnitConversion`] if a value has no unit metadata, is not numeric or the units are not convertible with the given [`UnitTable`]
+    pub fn read_entry_in_unit(&self, entry_key: &str, unit: &str, table: &UnitTable) -> Result<Vec<FrcTimestampedValue>, DataLogError> {
+        let Some(data) = self.keys.get(entry_key).and_then(|id| self.data.get(id)) else {
+            return Ok(Vec::new());
+        };
+        data.values.iter()
+            .map(|value| {
+                let source_unit = data.metadata.iter()
+                    .rev()
+                    .find(|metadata| metadata.timestamp <= value.timestamp)
+                    .and_then(|metadata| unit_from_metadata(&metadata.value))
+                    .ok_or(DataLogError::UnitConversion("Entry has no unit metadata"))?;
+                let converter = table.converter(&source_unit, unit)?;
+                Ok(FrcTimestampedValue::new(value.timestamp, convert_value(&value.value, converter)?))
+            })
+            .collect()
+    }
+
+    /// Returns the console messages of the [`MESSAGES_KEY`] entry split into their level and text,
+    /// messages written without a level are [`MessageLevel::Info`](crate::messages::MessageLevel::Info)
+    #[must_use]
+    pub fn read_messages(&self) -> Vec<ConsoleMessage> {
+        self.read_entry(MESSAGES_KEY)
+            .into_iter()
+            .filter_map(|value| match &value.value {
+                FrcValue::String(message) => Some(ConsoleMessage::parse(value.timestamp, message)),
+                _ => None
+            })
+            .collect()
+    }
+
+    /// Returns the aggregates of the entry with the given key,
+    /// these are available even if [`DataLogReaderConfig::retain_values`] is `false`
+    #[must_use]
+    pub fn get_entry_statistics(&self, entry_key: &str) -> Option<&EntryStatistics> {
+        self.data.get(self.keys.get(entry_key)?)
+            .map(|data| &data.statistics)
+    }
+
     /// Get all the keys for the entries in the `DataLog`
     /// 
     /// # Memory
@@ -323,6 +533,25 @@ impl DataLogReader {
         Some(EntryFilterReader::new(self.data.get(self.keys.get(entry_key)?)?))
     }
 
+    /// Runs a [`Query`] string against every entry in the `DataLog`,
+    /// returning the matching values of each matching entry sorted by key
+    /// 
+    /// # Errors
+    /// - [`DataLogError::QueryParse`] if the query is malformed
+    pub fn query(&self, query: &str) -> Result<Vec<(&str, Vec<&FrcTimestampedValue>)>, DataLogError> {
+        let query = Query::parse(query)?;
+        let mut results = self.keys.iter()
+            .filter(|(key, _)| query.matches_key(key))
+            .filter_map(|(key, id)| {
+                let mut filter = EntryFilterReader::new(self.data.get(id)?);
+                query.apply(&mut filter);
+                Some((key.as_str(), filter.collect()))
+            })
+            .collect::<Vec<_>>();
+        results.sort_by_key(|(key, _)| *key);
+        Ok(results)
+    }
+
     /// Converts any [`FrcValue::Raw`] entries that have a type string
     /// matching something in the [`frclib_core::structure::FrcStructDescDB`]
     /// into [`FrcValue::Struct`] or [`FrcValue::StructArray`]
@@ -353,18 +582,33 @@ impl DataLogReader {
             for value in &mut data.values {
                 if let FrcValue::Raw(raw_bytes) = &mut value.value {
                     update_type_str_for_timestamp(value.timestamp, &type_history, &mut type_str, &mut expiration_timestamp);
-                    if let Some(struct_desc) = frclib_core::structure::FrcStructDescDB::get(&type_str) {
+                    // logs from wpilib and this crates writer prefix struct types with `struct:`
+                    let struct_type = type_str.strip_prefix("struct:").unwrap_or(&type_str);
+                    let (struct_type, is_array) = match struct_type.strip_suffix("[]") {
+                        Some(struct_type) => (struct_type, true),
+                        None => (struct_type, false)
+                    };
+                    if let Some(struct_desc) = frclib_core::structure::FrcStructDescDB::get(struct_type) {
+                        let count = if is_array {
+                            if struct_desc.size == 0 || raw_bytes.len() % struct_desc.size != 0 {
+                                continue;
+                            }
+                            raw_bytes.len() / struct_desc.size
+                        } else {
+                            1
+                        };
                         let mut new_struct_inner = FrcStructureBytes {
                             desc: struct_desc,
-                            count: 1,
+                            count,
                             data: Box::default()
                         };
                         swap(raw_bytes, &mut new_struct_inner.data);
-                        let mut new_struct = FrcValue::Struct(
-                            Box::new(
-                                new_struct_inner
-                            )
-                        );
+                        let new_struct_inner = Box::new(new_struct_inner);
+                        let mut new_struct = if is_array {
+                            FrcValue::StructArray(new_struct_inner)
+                        } else {
+                            FrcValue::Struct(new_struct_inner)
+                        };
                         swap(&mut value.value, &mut new_struct);
                     }
                 }
@@ -380,14 +624,15 @@ impl <'a> EntryFilterReader<'a> {
             before: None,
             after: None,
             required_metadata_predicate: None,
-            required_type_predicate: None
+            required_type_predicate: None,
+            value_predicate: None
         }
     }
 
     /// Filters the values to only include those before the given timestamp
     /// 
     /// This method is chainable and mutates the original filter
-    pub fn before(&mut self, timestamp: u64) -> &mut Self {
+    pub const fn before(&mut self, timestamp: u64) -> &mut Self {
         self.before = Some(timestamp);
         self
     }
@@ -395,7 +640,7 @@ impl <'a> EntryFilterReader<'a> {
     /// Filters the values to only include those after the given timestamp
     /// 
     /// This method is chainable and mutates the original filter
-    pub fn after(&mut self, timestamp: u64) -> &mut Self {
+    pub const fn after(&mut self, timestamp: u64) -> &mut Self {
         self.after = Some(timestamp);
         self
     }
@@ -425,6 +670,14 @@ impl <'a> EntryFilterReader<'a> {
         self
     }
 
+    /// Filters the values to only include those whose value complies with the predicate
+    /// 
+    /// This method is chainable and mutates the original filter
+    pub fn value_predicate(&mut self, predicate: Box<dyn Fn(&FrcValue) -> bool>) -> &mut Self {
+        self.value_predicate = Some(predicate);
+        self
+    }
+
     fn get_metadata_at_timestamp(&self, timestamp: u64) -> Option<&str> {
         self.data.metadata.iter()
             .rev()
@@ -441,7 +694,7 @@ impl <'a> EntryFilterReader<'a> {
 
     /// Collects all the values that match the filter criteria
     #[must_use]
-    pub fn collect(&self) -> Vec<&FrcTimestampedValue> {
+    pub fn collect(&self) -> Vec<&'a FrcTimestampedValue> {
         self.data.values.iter()
             .filter(|value| {
                 if let Some(before) = self.before {
@@ -472,6 +725,11 @@ impl <'a> EntryFilterReader<'a> {
                         return false;
                     }
                 }
+                if let Some(predicate) = &self.value_predicate {
+                    if !predicate(&value.value) {
+                        return false;
+                    }
+                }
                 true
             })
             .collect()
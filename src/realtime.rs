@@ -0,0 +1,312 @@
+use std::{io::Write, sync::{atomic::{AtomicBool, AtomicU64, AtomicU8, AtomicUsize, Ordering}, Arc}, thread::JoinHandle, time::Duration};
+
+use frclib_core::value::FrcTimestamp;
+
+use crate::{writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+/// entry id, payload length and timestamp
+const FRAME_HEADER_SIZE: usize = 16;
+/// How long the consumer sleeps when the ring buffer is empty
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// A single producer, single consumer byte ring buffer
+/// 
+/// Positions only ever increase, the index into `bytes` is the position modulo the capacity.
+/// The producer owns `head` and the consumer owns `tail`.
+#[derive(Debug)]
+struct RingBuffer {
+    bytes: Box<[AtomicU8]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped_records: AtomicU64,
+    dropped_bytes: AtomicU64,
+    closed: AtomicBool
+}
+
+impl RingBuffer {
+    fn new(capacity: usize) -> Self {
+        Self {
+            bytes: (0..capacity).map(|_| AtomicU8::new(0)).collect(),
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            dropped_records: AtomicU64::new(0),
+            dropped_bytes: AtomicU64::new(0),
+            closed: AtomicBool::new(false)
+        }
+    }
+
+    fn store(&self, position: usize, data: &[u8]) {
+        for (offset, byte) in data.iter().enumerate() {
+            self.bytes[position.wrapping_add(offset) % self.bytes.len()].store(*byte, Ordering::Relaxed);
+        }
+    }
+
+    fn load(&self, position: usize, data: &mut [u8]) {
+        for (offset, byte) in data.iter_mut().enumerate() {
+            *byte = self.bytes[position.wrapping_add(offset) % self.bytes.len()].load(Ordering::Relaxed);
+        }
+    }
+}
+
+/// Where the next bytes of a frame being appended go
+#[derive(Debug)]
+struct FrameCursor<'a> {
+    ring: &'a RingBuffer,
+    position: usize
+}
+
+impl FrameCursor<'_> {
+    fn put(&mut self, data: &[u8]) {
+        self.ring.store(self.position, data);
+        self.position = self.position.wrapping_add(data.len());
+    }
+}
+
+/// A datalog writer for real time threads
+/// 
+/// Values are encoded by the [`RealtimeAppender`] straight into a fixed size ring buffer,
+/// appending never allocates, locks or makes a syscall.
+/// A consumer thread moves the encoded records from the ring buffer into the [`DataLogWriter`].
+/// 
+/// When the ring buffer is full the value is dropped and counted,
+/// see [`RealtimeDataLogWriter::dropped_records`].
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::{realtime::RealtimeDataLogWriter, DataLogWriter};
+/// 
+/// let mut writer = DataLogWriter::new(File::create("path/to/file").unwrap(), "")
+///     .expect("Failed to create writer");
+/// // entries are created up front, appending can't create them
+/// let entry = writer.get_entry::<f64>("/arm/position", None).expect("Failed to get entry");
+/// 
+/// let (writer, mut appender) = RealtimeDataLogWriter::new(writer, 64 * 1024)
+///     .expect("Failed to start writer");
+/// appender.append_f64(entry, 0.25, 1_000).expect("Failed to append value");
+/// 
+/// let file = writer.finish().expect("Failed to finish log");
+/// ```
+#[derive(Debug)]
+pub struct RealtimeDataLogWriter<W: Write + Send + 'static> {
+    ring: Arc<RingBuffer>,
+    /// Only [`None`] once [`RealtimeDataLogWriter::finish`] has taken it
+    worker: Option<JoinHandle<Result<W, DataLogError>>>
+}
+
+impl <W: Write + Send + 'static> RealtimeDataLogWriter<W> {
+    /// Starts the consumer thread for a writer with a ring buffer of `capacity` bytes,
+    /// every record takes 16 bytes plus its payload in the ring buffer
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if the thread can't be spawned
+    pub fn new(writer: DataLogWriter<W>, capacity: usize) -> Result<(Self, RealtimeAppender), DataLogError> {
+        let ring = Arc::new(RingBuffer::new(capacity));
+        let appender = RealtimeAppender {
+            ring: Arc::clone(&ring),
+            datalog_id: writer.datalog_id()
+        };
+        let consumer_ring = Arc::clone(&ring);
+        let worker = std::thread::Builder::new()
+            .name("datalog-realtime-writer".to_string())
+            .spawn(move || {
+                let result = run_consumer(writer, &consumer_ring);
+                consumer_ring.closed.store(true, Ordering::Release);
+                result
+            })?;
+        Ok((Self { ring, worker: Some(worker) }, appender))
+    }
+
+    /// The number of records dropped because the ring buffer was full
+    #[must_use]
+    pub fn dropped_records(&self) -> u64 {
+        self.ring.dropped_records.load(Ordering::Relaxed)
+    }
+
+    /// The number of bytes dropped because the ring buffer was full
+    #[must_use]
+    pub fn dropped_bytes(&self) -> u64 {
+        self.ring.dropped_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Writes everything left in the ring buffer, stops the consumer thread and returns the underlying sink
+    /// 
+    /// Appends after this fail with [`DataLogError::BackgroundWriterClosed`].
+    /// 
+    /// # Errors
+    /// - Any error the consumer stopped on
+    /// - [`DataLogError::BackgroundWriterClosed`] if the consumer thread panicked
+    pub fn finish(mut self) -> Result<W, DataLogError> {
+        self.stop().ok_or(DataLogError::BackgroundWriterClosed)?
+    }
+
+    /// Closes the ring buffer and waits for the consumer to write what is left in it
+    fn stop(&mut self) -> Option<Result<W, DataLogError>> {
+        self.ring.closed.store(true, Ordering::Release);
+        let worker = self.worker.take()?;
+        worker.thread().unpark();
+        Some(worker.join().unwrap_or(Err(DataLogError::BackgroundWriterClosed)))
+    }
+}
+
+impl <W: Write + Send + 'static> Drop for RealtimeDataLogWriter<W> {
+    /// Writes everything left in the ring buffer and stops the consumer thread like
+    /// [`RealtimeDataLogWriter::finish`], dropping the sink and any error
+    fn drop(&mut self) {
+        let _ = self.stop();
+    }
+}
+
+fn run_consumer<W: Write>(mut writer: DataLogWriter<W>, ring: &RingBuffer) -> Result<W, DataLogError> {
+    let mut payload = Vec::new();
+    loop {
+        // read closed before head so nothing appended before closing is missed
+        let closed = ring.closed.load(Ordering::Acquire);
+        let head = ring.head.load(Ordering::Acquire);
+        let mut tail = ring.tail.load(Ordering::Relaxed);
+        if head == tail {
+            if closed {
+                break;
+            }
+            let _ = writer.maybe_flush()?;
+            std::thread::park_timeout(POLL_INTERVAL);
+            continue;
+        }
+
+        while tail != head {
+            let mut header = [0u8; FRAME_HEADER_SIZE];
+            ring.load(tail, &mut header);
+            let [i0, i1, i2, i3, l0, l1, l2, l3, timestamp @ ..] = header;
+            payload.resize(u32::from_le_bytes([l0, l1, l2, l3]) as usize, 0);
+            ring.load(tail.wrapping_add(FRAME_HEADER_SIZE), &mut payload);
+            writer.write_encoded(u32::from_le_bytes([i0, i1, i2, i3]), u64::from_le_bytes(timestamp), &payload)?;
+            tail = tail.wrapping_add(FRAME_HEADER_SIZE + payload.len());
+            ring.tail.store(tail, Ordering::Release);
+        }
+    }
+    writer.into_inner()
+}
+
+/// The producer side of a [`RealtimeDataLogWriter`]
+/// 
+/// There is only ever one appender per writer, appending takes `&mut self` so it can't be shared
+/// between threads without synchronization.
+#[derive(Debug)]
+pub struct RealtimeAppender {
+    ring: Arc<RingBuffer>,
+    datalog_id: u32
+}
+
+impl Drop for RealtimeAppender {
+    /// Closes the ring buffer, the consumer writes what is left in it and stops
+    fn drop(&mut self) {
+        self.ring.closed.store(true, Ordering::Release);
+    }
+}
+
+impl RealtimeAppender {
+    // `&mut self` keeps there from ever being more than one producer
+    #[allow(clippy::needless_pass_by_ref_mut)]
+    fn push(&mut self, id: EntryId, timestamp: FrcTimestamp, payload_len: usize, fill: impl FnOnce(&mut FrameCursor<'_>)) -> Result<(), DataLogError> {
+        if id.datalog_id() != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+        if self.ring.closed.load(Ordering::Acquire) {
+            return Err(DataLogError::BackgroundWriterClosed);
+        }
+        let len = u32::try_from(payload_len).map_err(|_| DataLogError::RecordTooLarge)?;
+
+        let frame_len = FRAME_HEADER_SIZE + payload_len;
+        let head = self.ring.head.load(Ordering::Relaxed);
+        let used = head.wrapping_sub(self.ring.tail.load(Ordering::Acquire));
+        if frame_len > self.ring.bytes.len() - used {
+            let _ = self.ring.dropped_records.fetch_add(1, Ordering::Relaxed);
+            let _ = self.ring.dropped_bytes.fetch_add(frame_len as u64, Ordering::Relaxed);
+            return Err(DataLogError::BackgroundWriterFull);
+        }
+
+        let mut cursor = FrameCursor {
+            ring: &self.ring,
+            position: head
+        };
+        cursor.put(&id.entry_id().to_le_bytes());
+        cursor.put(&len.to_le_bytes());
+        cursor.put(&timestamp.to_le_bytes());
+        fill(&mut cursor);
+        self.ring.head.store(head.wrapping_add(frame_len), Ordering::Release);
+        Ok(())
+    }
+
+    /// The number of records dropped because the ring buffer was full
+    #[must_use]
+    pub fn dropped_records(&self) -> u64 {
+        self.ring.dropped_records.load(Ordering::Relaxed)
+    }
+
+    /// Appends a boolean
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the ring buffer is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the consumer has stopped
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    pub fn append_bool(&mut self, id: TypedEntryId<bool>, value: bool, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.push(id.into(), timestamp, 1, |cursor| cursor.put(&[u8::from(value)]))
+    }
+
+    /// Appends an integer
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the ring buffer is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the consumer has stopped
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    pub fn append_i64(&mut self, id: TypedEntryId<i64>, value: i64, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.push(id.into(), timestamp, 8, |cursor| cursor.put(&value.to_le_bytes()))
+    }
+
+    /// Appends a float
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the ring buffer is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the consumer has stopped
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    pub fn append_f32(&mut self, id: TypedEntryId<f32>, value: f32, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.push(id.into(), timestamp, 4, |cursor| cursor.put(&value.to_le_bytes()))
+    }
+
+    /// Appends a double
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the ring buffer is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the consumer has stopped
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    pub fn append_f64(&mut self, id: TypedEntryId<f64>, value: f64, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.push(id.into(), timestamp, 8, |cursor| cursor.put(&value.to_le_bytes()))
+    }
+
+    /// Appends a double array
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the ring buffer is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the consumer has stopped
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::RecordTooLarge`] if the array is too large for a record
+    pub fn append_f64_slice(&mut self, id: TypedEntryId<Vec<f64>>, values: &[f64], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.push(id.into(), timestamp, values.len() * 8, |cursor| {
+            for value in values {
+                cursor.put(&value.to_le_bytes());
+            }
+        })
+    }
+
+    /// Appends an already encoded payload, like a string, raw bytes or a packed struct.
+    /// 
+    /// The payload isn't checked against the type of the entry.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::BackgroundWriterFull`] if the ring buffer is full
+    /// - [`DataLogError::BackgroundWriterClosed`] if the consumer has stopped
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::RecordTooLarge`] if the payload is too large for a record
+    pub fn append_raw(&mut self, id: EntryId, payload: &[u8], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.push(id, timestamp, payload.len(), |cursor| cursor.put(payload))
+    }
+}
@@ -0,0 +1,252 @@
+use std::{fmt::Debug, fs::File, io::Write, path::PathBuf, time::{Duration, Instant}};
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, IntoFrcValue, StaticallyFrcTyped};
+
+use crate::{locking::create_locked, now, writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+/// How long a [`RecoveringDataLogWriter`] waits between recovery attempts by default
+pub const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+type ReopenFn<W> = Box<dyn FnMut(u32) -> std::io::Result<W> + Send>;
+
+/// A datalog writer that replaces its sink when writing to it fails and carries on,
+/// like when a USB stick is pulled or the filesystem is remounted read only
+///
+/// On an IO error the writer asks `reopen` for a new sink, given how many recoveries happened before,
+/// continues the log in it with every open entry started again and retries the failed operation once.
+/// Records that were buffered when the sink failed are lost.
+/// Recovery is attempted at most once per retry interval, errors are returned while it isn't possible.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::recovery::RecoveringDataLogWriter;
+///
+/// let mut writer = RecoveringDataLogWriter::create(
+///     "/u/logs/match.wpilog",
+///     Some("/home/lvuser/logs".into()),
+///     ""
+/// ).expect("Failed to create writer");
+///
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+/// ```
+pub struct RecoveringDataLogWriter<W: Write = File> {
+    writer: DataLogWriter<W>,
+    reopen: ReopenFn<W>,
+    metadata: String,
+    retry_interval: Duration,
+    last_attempt: Option<Instant>,
+    recoveries: u32
+}
+impl <W: Write + Debug> Debug for RecoveringDataLogWriter<W> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RecoveringDataLogWriter")
+            .field("writer", &self.writer)
+            .field("metadata", &self.metadata)
+            .field("retry_interval", &self.retry_interval)
+            .field("recoveries", &self.recoveries)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RecoveringDataLogWriter<File> {
+    /// Creates a new file at `path` and a writer that recovers into `<name>_<n>.<extension>`
+    /// next to it, or in `fallback_dir` if that fails
+    ///
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at `path`
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn create(path: impl Into<PathBuf>, fallback_dir: Option<PathBuf>, metadata: impl ToString) -> Result<Self, DataLogError> {
+        let path = path.into();
+        let metadata = metadata.to_string();
+        let writer = DataLogWriter::create(&path, &metadata)?;
+        let reopen = move |recovery: u32| {
+            let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+            let name = match path.extension() {
+                Some(extension) => format!("{stem}_{}.{}", recovery + 1, extension.to_string_lossy()),
+                None => format!("{stem}_{}", recovery + 1)
+            };
+            create_locked(&path.with_file_name(&name)).or_else(|err| match &fallback_dir {
+                Some(dir) => create_locked(&dir.join(&name)),
+                None => Err(err)
+            })
+        };
+        Ok(Self::new(writer, metadata, reopen))
+    }
+}
+
+impl <W: Write> RecoveringDataLogWriter<W> {
+    /// Wraps a writer, `reopen` creates the sink to continue in after the current one failed
+    /// and `metadata` is the header metadata for it
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(
+        writer: DataLogWriter<W>,
+        metadata: impl ToString,
+        reopen: impl FnMut(u32) -> std::io::Result<W> + Send + 'static
+    ) -> Self {
+        Self {
+            writer,
+            reopen: Box::new(reopen),
+            metadata: metadata.to_string(),
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+            last_attempt: None,
+            recoveries: 0
+        }
+    }
+
+    /// Sets how long to wait between recovery attempts, defaults to [`DEFAULT_RETRY_INTERVAL`]
+    #[must_use]
+    pub const fn with_retry_interval(mut self, retry_interval: Duration) -> Self {
+        self.retry_interval = retry_interval;
+        self
+    }
+
+    /// The number of times the sink has been replaced
+    #[must_use]
+    pub const fn recoveries(&self) -> u32 {
+        self.recoveries
+    }
+
+    /// The wrapped writer
+    #[must_use]
+    pub const fn writer(&self) -> &DataLogWriter<W> {
+        &self.writer
+    }
+
+    /// The wrapped writer, operations on it aren't recovered
+    pub const fn writer_mut(&mut self) -> &mut DataLogWriter<W> {
+        &mut self.writer
+    }
+
+    /// Runs `op`, recovering and running it again if it fails with an IO error
+    fn recovering<R>(&mut self, mut op: impl FnMut(&mut DataLogWriter<W>) -> Result<R, DataLogError>) -> Result<R, DataLogError> {
+        match op(&mut self.writer) {
+            Err(DataLogError::Io(err)) => {
+                if !self.try_recover()? {
+                    return Err(DataLogError::Io(err));
+                }
+                op(&mut self.writer)
+            }
+            result => result
+        }
+    }
+
+    /// Replaces the sink if the retry interval has passed, returning whether it did
+    fn try_recover(&mut self) -> Result<bool, DataLogError> {
+        if self.last_attempt.is_some_and(|last| last.elapsed() < self.retry_interval) {
+            return Ok(false);
+        }
+        self.last_attempt = Some(Instant::now());
+        let sink = (self.reopen)(self.recoveries)?;
+        let _ = self.writer.recover(sink, &self.metadata)?;
+        self.recoveries += 1;
+        Ok(true)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn get_entry<T: StaticallyFrcTyped>(&mut self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        // an entry whose start record failed is still registered and started again on recovery
+        self.recovering(|writer| writer.get_entry(key, metadata.clone()))
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry_dynamic`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn get_entry_dynamic(&mut self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.recovering(|writer| writer.get_entry_dynamic(key, entry_type, metadata.clone()))
+    }
+
+    /// Writes a value, see [`DataLogWriter::write`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    pub fn write<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T) -> Result<(), DataLogError> {
+        self.write_timestamped(id, value, now())
+    }
+
+    /// Writes a value with a specific timestamp, see [`DataLogWriter::write_timestamped`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    pub fn write_timestamped<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.write_dynamic(id.into(), FrcTimestampedValue::new(timestamp, value.into_frc_value()))
+    }
+
+    /// Writes a value, see [`DataLogWriter::write_dynamic`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::EntryTypeMismatch`] if the value type doesn't match the entry type
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn write_dynamic(&mut self, id: EntryId, value: FrcTimestampedValue) -> Result<(), DataLogError> {
+        self.recovering(|writer| writer.write_dynamic(id, value.clone()))
+    }
+
+    /// Replaces the metadata of an entry, see [`DataLogWriter::set_entry_metadata`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn set_entry_metadata(&mut self, id: EntryId, metadata: impl ToString) -> Result<(), DataLogError> {
+        let metadata = metadata.to_string();
+        self.recovering(|writer| writer.set_entry_metadata(id, &metadata))
+    }
+
+    /// Closes an entry, see [`DataLogWriter::close_entry`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    pub fn close_entry(&mut self, id: EntryId) -> Result<(), DataLogError> {
+        match self.writer.close_entry(id) {
+            // the entry is already closed so it isn't started in the new sink, nothing to retry
+            Err(DataLogError::Io(err)) if !self.try_recover()? => Err(DataLogError::Io(err)),
+            Err(DataLogError::Io(_)) => Ok(()),
+            result => result
+        }
+    }
+
+    /// Flushes the writer, see [`DataLogWriter::flush`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs and the sink can't be replaced
+    pub fn flush(&mut self) -> Result<(), DataLogError> {
+        self.recovering(DataLogWriter::flush)
+    }
+
+    /// Finishes every open entry and returns the current sink, see [`DataLogWriter::close`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn close(self) -> Result<W, DataLogError> {
+        self.writer.close()
+    }
+}
@@ -0,0 +1,269 @@
+use std::{collections::{HashMap, HashSet}, io::Write};
+
+use frclib_core::value::FrcTimestamp;
+
+use crate::{
+    proto::records::{for_each_record, header_len},
+    reader::DataLogHeader, ControlRecord, DataLogError, DataLogReader, DataLogWriter, Record
+};
+
+/// The header of a log and the bytes of its records
+fn split_log(log: &[u8]) -> Result<(DataLogHeader, &[u8]), DataLogError> {
+    let header = DataLogReader::peek_header_from(log)?;
+    let records = header_len(log)
+        .and_then(|len| log.get(len..))
+        .ok_or(DataLogError::InvalidDataLog)?;
+    Ok((header, records))
+}
+
+/// Copies the records of a log into `writer` one at a time, writing what `rewrite` returns for each
+///
+/// Checkpoints are left out, their checksums don't cover the rewritten bytes.
+fn copy_records<W: Write>(
+    records: &[u8],
+    writer: &mut DataLogWriter<W>,
+    mut rewrite: impl FnMut(Record) -> Option<Record>
+) -> Result<(), DataLogError> {
+    for_each_record(records, &mut HashMap::new(), |record, _| {
+        #[cfg(feature = "integrity")]
+        if record.get_id() == crate::integrity::CHECKPOINT_ENTRY_ID {
+            return Ok(());
+        }
+        match rewrite(record) {
+            Some(record) => writer.write_record(record),
+            None => Ok(())
+        }
+    })
+}
+
+/// Copies a log into `out` with only the values logged between `start` and `end`, inclusive
+///
+/// Every control record is kept, so entries keep their types and metadata, and so are the values of
+/// `structschema` entries, which struct entries need to be decoded no matter when they were logged.
+/// Timestamps aren't changed.
+///
+/// # Errors
+/// - [`DataLogError::MagicMismatch`] if `log` isn't a log
+/// - [`DataLogError::InvalidDataLog`] if `log` ends inside its header
+/// - Any error of reading the records of `log`
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn trim<W: Write>(log: &[u8], start: FrcTimestamp, end: FrcTimestamp, out: W) -> Result<W, DataLogError> {
+    let (header, records) = split_log(log)?;
+    let mut writer = DataLogWriter::new(out, header.metadata)?;
+    let mut schemas = HashSet::new();
+    copy_records(records, &mut writer, |record| match &record {
+        Record::Control(ControlRecord::Start(_, entry_type, _), _, id) => {
+            if entry_type == "structschema" {
+                let _ = schemas.insert(*id);
+            } else {
+                let _ = schemas.remove(id);
+            }
+            Some(record)
+        }
+        Record::Data(_, timestamp, id) if !(start..=end).contains(timestamp) && !schemas.contains(id) => None,
+        _ => Some(record)
+    })?;
+    writer.close()
+}
+
+/// The ids entries get in a log combined from several logs, one per key and type
+#[derive(Default)]
+struct MergedEntries {
+    ids: HashMap<(String, String), u32>,
+    last_id: u32
+}
+
+impl MergedEntries {
+    /// The id of an entry and whether it is new, [`None`] if there are no ids left
+    fn start(&mut self, key: &str, entry_type: &str) -> Option<(u32, bool)> {
+        if let Some(id) = self.ids.get(&(key.to_string(), entry_type.to_string())) {
+            return Some((*id, false));
+        }
+        self.last_id = self.last_id.checked_add(1)?;
+        let _ = self.ids.insert((key.to_string(), entry_type.to_string()), self.last_id);
+        Some((self.last_id, true))
+    }
+}
+
+/// Combines the entries of logs recorded on the same time base, like a robot log and the log
+/// of a coprocessor, into one log written to `out`
+///
+/// The records of each log are copied after the ones of the log before it with their entries given
+/// new ids so they don't collide, and the header metadata of the first log is kept. Entries with
+/// the same key and type in several logs become one entry, so entries aren't finished in the merged
+/// log as a later log can still add values to them.
+///
+/// # Errors
+/// - [`DataLogError::MagicMismatch`] if one of the logs isn't a log
+/// - [`DataLogError::InvalidDataLog`] if one of the logs ends inside its header
+/// - [`DataLogError::RecordTooLarge`] if the logs have more entries than there are entry ids
+/// - Any error of reading the records of the logs
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn merge<W: Write>(logs: &[&[u8]], out: W) -> Result<W, DataLogError> {
+    let logs = logs.iter().map(|log| split_log(log)).collect::<Result<Vec<_>, _>>()?;
+    let metadata = logs.first().map(|(header, _)| header.metadata.clone()).unwrap_or_default();
+    let mut writer = DataLogWriter::new(out, metadata)?;
+    let mut entries = MergedEntries::default();
+    let mut overflowed = false;
+    for (_, records) in logs {
+        let mut ids = HashMap::new();
+        copy_records(records, &mut writer, |record| match record {
+            Record::Control(ControlRecord::Start(key, entry_type, metadata), timestamp, id) => {
+                let Some((merged, new)) = entries.start(&key, &entry_type) else {
+                    overflowed = true;
+                    return None;
+                };
+                let _ = ids.insert(id, merged);
+                new.then_some(Record::Control(ControlRecord::Start(key, entry_type, metadata), timestamp, merged))
+            }
+            Record::Control(ControlRecord::Finish, _, _) => None,
+            Record::Control(control, timestamp, id) => Some(Record::Control(control, timestamp, *ids.get(&id)?)),
+            Record::Data(data, timestamp, id) => Some(Record::Data(data, timestamp, *ids.get(&id)?))
+        })?;
+        if overflowed {
+            return Err(DataLogError::RecordTooLarge);
+        }
+    }
+    writer.close()
+}
+
+/// Appends `second` after `first` into one log written to `out`, for stitching together the logs
+/// of a robot that rebooted in the middle of a session
+///
+/// `first` is copied as is. The timestamps of `second`, which count from its own robot start,
+/// are moved `offset` later, by default to just after the last record of `first`.
+/// Entries of `second` with the same key and type as an entry of `first` continue it, the others
+/// get new ids, and the Start records of `second` are written again so entries `first` finished
+/// are started again. The header metadata of `first` is kept.
+///
+/// # Errors
+/// - [`DataLogError::MagicMismatch`] if one of the logs isn't a log
+/// - [`DataLogError::InvalidDataLog`] if one of the logs ends inside its header
+/// - [`DataLogError::RecordTooLarge`] if the logs have more entries than there are entry ids
+/// - Any error of reading the records of the logs
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn concat<W: Write>(first: &[u8], second: &[u8], offset: Option<FrcTimestamp>, out: W) -> Result<W, DataLogError> {
+    let (header, first) = split_log(first)?;
+    let (_, second) = split_log(second)?;
+    let mut writer = DataLogWriter::new(out, header.metadata)?;
+
+    let mut entries = MergedEntries::default();
+    let mut end = 0;
+    copy_records(first, &mut writer, |record| {
+        end = end.max(record.get_timestamp());
+        if let Record::Control(ControlRecord::Start(key, entry_type, _), _, id) = &record {
+            let _ = entries.ids.insert((key.clone(), entry_type.clone()), *id);
+            entries.last_id = entries.last_id.max(*id);
+        }
+        Some(record)
+    })?;
+
+    let offset = offset.unwrap_or_else(|| end.saturating_add(1));
+    let mut ids = HashMap::new();
+    let mut overflowed = false;
+    copy_records(second, &mut writer, |record| match record {
+        Record::Control(ControlRecord::Start(key, entry_type, metadata), timestamp, id) => {
+            let Some((continued, _)) = entries.start(&key, &entry_type) else {
+                overflowed = true;
+                return None;
+            };
+            let _ = ids.insert(id, continued);
+            Some(Record::Control(ControlRecord::Start(key, entry_type, metadata), timestamp.saturating_add(offset), continued))
+        }
+        Record::Control(control, timestamp, id) => Some(Record::Control(control, timestamp.saturating_add(offset), *ids.get(&id)?)),
+        Record::Data(data, timestamp, id) => Some(Record::Data(data, timestamp.saturating_add(offset), *ids.get(&id)?))
+    })?;
+    if overflowed {
+        return Err(DataLogError::RecordTooLarge);
+    }
+    writer.close()
+}
+
+/// Checks that either both sides of every rule end in `*` or neither does
+fn validate_renames(renames: &[(&str, &str)]) -> Result<(), DataLogError> {
+    for (from, to) in renames {
+        match (from.ends_with('*'), to.ends_with('*')) {
+            (true, false) => return Err(DataLogError::RenameRule("a prefix rule has to rename to a prefix ending in *")),
+            (false, true) => return Err(DataLogError::RenameRule("a key can only be renamed to a prefix by a prefix rule")),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The key `key` is renamed to by the first rule that matches it exactly or, if none does,
+/// the prefix rule with the longest prefix
+fn renamed(key: &str, renames: &[(&str, &str)]) -> Option<String> {
+    if let Some((_, to)) = renames.iter().find(|(from, _)| !from.ends_with('*') && *from == key) {
+        return Some((*to).to_string());
+    }
+    renames.iter()
+        .filter_map(|(from, to)| Some((from.strip_suffix('*')?, to.strip_suffix('*')?)))
+        .filter(|(from, _)| key.starts_with(from))
+        .max_by_key(|(from, _)| from.len())
+        .map(|(from, to)| format!("{to}{}", &key[from.len()..]))
+}
+
+/// Checks that no renamed entry ends up under the key of another entry that is alive at the same time
+fn check_collisions(records: &[u8], renames: &[(&str, &str)]) -> Result<(), DataLogError> {
+    // the entry alive under every key
+    let mut alive = HashMap::<String, u32>::new();
+    for_each_record(records, &mut HashMap::new(), |record, _| {
+        match record {
+            Record::Control(ControlRecord::Start(key, _, _), _, id) => {
+                let key = renamed(&key, renames).unwrap_or(key);
+                if alive.insert(key, id).is_some_and(|other| other != id) {
+                    return Err(DataLogError::EntryAlreadyExists);
+                }
+            }
+            Record::Control(ControlRecord::Finish, _, id) => alive.retain(|_, alive_id| *alive_id != id),
+            _ => {}
+        }
+        Ok(())
+    })
+}
+
+/// Copies a log into `out` with entries renamed, so logs recorded before a refactor renamed keys
+/// can be viewed and compared with the keys of the new code
+///
+/// A rule is a pair of the old and new key, like `("/drive/speed", "/drivetrain/speed")`, or of
+/// prefixes ending in `*`, like `("/oldSubsystem/*", "/arm/*")` which renames `/oldSubsystem/angle`
+/// to `/arm/angle`. Exact rules are used before prefix rules and longer prefixes before shorter ones.
+/// Only the keys in Start records change, every other record is copied as is.
+/// The rules and the renamed keys are checked before anything is written to `out`.
+///
+/// # Example
+/// ```rust
+/// use frclib_datalog::{rewrite::rename, reader::DataLogReaderConfig, DataLogReader, DataLogWriter};
+///
+/// let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+/// let angle = writer.get_entry::<f64>("/oldSubsystem/angle", None).expect("Failed to get entry");
+/// writer.write_timestamped(angle, 0.5, 1_000).expect("Failed to write entry");
+/// let log = writer.into_inner().expect("Failed to finish log");
+///
+/// let renamed = rename(&log, &[("/oldSubsystem/*", "/arm/*")], Vec::new()).expect("Failed to rename entries");
+/// let reader = DataLogReader::try_new(renamed.as_slice(), DataLogReaderConfig::default()).expect("Failed to read log");
+/// assert_eq!(reader.read_entry("/arm/angle").len(), 1);
+/// ```
+///
+/// # Errors
+/// - [`DataLogError::RenameRule`] if only one side of a rule ends in `*`
+/// - [`DataLogError::MagicMismatch`] if `log` isn't a log
+/// - [`DataLogError::InvalidDataLog`] if `log` ends inside its header
+/// - [`DataLogError::EntryAlreadyExists`] if an entry is renamed to the key of another entry that is alive at the same time
+/// - Any error of reading the records of `log`
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn rename<W: Write>(log: &[u8], renames: &[(&str, &str)], out: W) -> Result<W, DataLogError> {
+    validate_renames(renames)?;
+    let (header, records) = split_log(log)?;
+    check_collisions(records, renames)?;
+    let mut writer = DataLogWriter::new(out, header.metadata)?;
+    copy_records(records, &mut writer, |record| match record {
+        Record::Control(ControlRecord::Start(key, entry_type, metadata), timestamp, id) => {
+            let key = renamed(&key, renames).unwrap_or(key);
+            Some(Record::Control(ControlRecord::Start(key, entry_type, metadata), timestamp, id))
+        }
+        record => Some(record)
+    })?;
+    writer.close()
+}
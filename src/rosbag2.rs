@@ -0,0 +1,570 @@
+use std::{collections::HashMap, fmt::Write as _, io::Write, path::{Path, PathBuf}};
+
+use frclib_core::value::{FrcTimestamp, FrcType, FrcValue};
+use rusqlite::{params, Connection};
+
+use crate::{writer::EntryId, DataLogError, DataLogReader, DataLogWriter};
+
+/// The serialization every message is written with, little endian CDR
+const SERIALIZATION_FORMAT: &str = "cdr";
+/// The encapsulation header at the start of every little endian CDR message
+const CDR_LE_HEADER: [u8; 4] = [0x00, 0x01, 0x00, 0x00];
+
+/// The tables of the `sqlite3` storage, as written by Foxy and read by every later distro
+const SCHEMA: &str = "
+    CREATE TABLE topics(
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL,
+        type TEXT NOT NULL,
+        serialization_format TEXT NOT NULL,
+        offered_qos_profiles TEXT NOT NULL
+    );
+    CREATE TABLE messages(
+        id INTEGER PRIMARY KEY,
+        topic_id INTEGER NOT NULL,
+        timestamp INTEGER NOT NULL,
+        data BLOB NOT NULL
+    );
+    CREATE INDEX timestamp_idx ON messages (timestamp ASC);
+";
+
+/// A channel type both formats have, the wpilog entry type and the ROS message type it converts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Channel {
+    Boolean,
+    Int,
+    Float,
+    Double,
+    String,
+    IntArray,
+    FloatArray,
+    DoubleArray,
+    Pose2d,
+    Pose3d,
+    Translation3d,
+    Rotation3d
+}
+
+impl Channel {
+    const ALL: [Self; 12] = [
+        Self::Boolean, Self::Int, Self::Float, Self::Double, Self::String, Self::IntArray,
+        Self::FloatArray, Self::DoubleArray, Self::Pose2d, Self::Pose3d, Self::Translation3d, Self::Rotation3d
+    ];
+
+    fn from_entry_type(type_str: &str) -> Option<Self> {
+        match type_str {
+            // json is kept as its text
+            "json" => Some(Self::String),
+            _ => Self::ALL.into_iter().find(|channel| channel.entry_type() == type_str)
+        }
+    }
+
+    fn from_message_type(message_type: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|channel| channel.message_type() == message_type)
+    }
+
+    const fn entry_type(self) -> &'static str {
+        match self {
+            Self::Boolean => "boolean",
+            Self::Int => "int64",
+            Self::Float => "float",
+            Self::Double => "double",
+            Self::String => "string",
+            Self::IntArray => "int64[]",
+            Self::FloatArray => "float[]",
+            Self::DoubleArray => "double[]",
+            Self::Pose2d => "struct:Pose2d",
+            Self::Pose3d => "struct:Pose3d",
+            Self::Translation3d => "struct:Translation3d",
+            Self::Rotation3d => "struct:Rotation3d"
+        }
+    }
+
+    const fn message_type(self) -> &'static str {
+        match self {
+            Self::Boolean => "std_msgs/msg/Bool",
+            Self::Int => "std_msgs/msg/Int64",
+            Self::Float => "std_msgs/msg/Float32",
+            Self::Double => "std_msgs/msg/Float64",
+            Self::String => "std_msgs/msg/String",
+            Self::IntArray => "std_msgs/msg/Int64MultiArray",
+            Self::FloatArray => "std_msgs/msg/Float32MultiArray",
+            Self::DoubleArray => "std_msgs/msg/Float64MultiArray",
+            Self::Pose2d => "geometry_msgs/msg/Pose2D",
+            Self::Pose3d => "geometry_msgs/msg/Pose",
+            Self::Translation3d => "geometry_msgs/msg/Point",
+            Self::Rotation3d => "geometry_msgs/msg/Quaternion"
+        }
+    }
+
+    /// The struct schemas the entry type needs in a log, the entry type's own first
+    const fn schemas(self) -> &'static [(&'static str, &'static str)] {
+        const TRANSLATION2D: (&str, &str) = ("Translation2d", "double x;double y");
+        const ROTATION2D: (&str, &str) = ("Rotation2d", "double value");
+        const TRANSLATION3D: (&str, &str) = ("Translation3d", "double x;double y;double z");
+        const ROTATION3D: (&str, &str) = ("Rotation3d", "Quaternion q");
+        const QUATERNION: (&str, &str) = ("Quaternion", "double w;double x;double y;double z");
+        match self {
+            Self::Pose2d => &[("Pose2d", "Translation2d translation;Rotation2d rotation"), TRANSLATION2D, ROTATION2D],
+            Self::Pose3d => &[("Pose3d", "Translation3d translation;Rotation3d rotation"), TRANSLATION3D, ROTATION3D, QUATERNION],
+            Self::Translation3d => &[TRANSLATION3D],
+            Self::Rotation3d => &[ROTATION3D, QUATERNION],
+            _ => &[]
+        }
+    }
+
+    /// Serializes a value of the entry as a message, [`None`] if it isn't a value of the channel
+    fn encode(self, value: &FrcValue) -> Option<Vec<u8>> {
+        let mut cdr = CdrWriter::new();
+        match (self, value) {
+            (Self::Boolean, FrcValue::Boolean(value)) => cdr.bytes(&[u8::from(*value)]),
+            (Self::Int, FrcValue::Int(value)) => cdr.i64(*value),
+            (Self::Float, FrcValue::Float(value)) => cdr.f32(*value),
+            (Self::Double, FrcValue::Double(value)) => cdr.f64(*value),
+            (Self::String, FrcValue::String(value)) => cdr.string(value)?,
+            (Self::IntArray, FrcValue::IntArray(values)) => {
+                cdr.empty_layout();
+                cdr.u32(u32::try_from(values.len()).ok()?);
+                values.iter().for_each(|value| cdr.i64(*value));
+            }
+            (Self::FloatArray, FrcValue::FloatArray(values)) => {
+                cdr.empty_layout();
+                cdr.u32(u32::try_from(values.len()).ok()?);
+                values.iter().for_each(|value| cdr.f32(*value));
+            }
+            (Self::DoubleArray, FrcValue::DoubleArray(values)) => {
+                cdr.empty_layout();
+                cdr.u32(u32::try_from(values.len()).ok()?);
+                values.iter().for_each(|value| cdr.f64(*value));
+            }
+            (_, FrcValue::Raw(bytes)) => self.encode_struct(&mut cdr, bytes)?,
+            (_, FrcValue::Struct(structure)) => self.encode_struct(&mut cdr, &structure.data)?,
+            _ => return None
+        }
+        Some(cdr.finish())
+    }
+
+    /// Serializes the bytes of a `WPILib` struct, whose doubles are in the order of the message
+    /// except for quaternions that put `w` first instead of last
+    fn encode_struct(self, cdr: &mut CdrWriter, bytes: &[u8]) -> Option<()> {
+        let doubles = bytes.chunks_exact(8)
+            .map(|chunk| chunk.try_into().map(f64::from_le_bytes))
+            .collect::<Result<Vec<_>, _>>()
+            .ok()?;
+        let ordered = match (self, doubles.as_slice()) {
+            (Self::Pose2d, [x, y, theta]) => vec![*x, *y, *theta],
+            (Self::Translation3d, [x, y, z]) => vec![*x, *y, *z],
+            (Self::Rotation3d, [w, x, y, z]) => vec![*x, *y, *z, *w],
+            (Self::Pose3d, [px, py, pz, w, x, y, z]) => vec![*px, *py, *pz, *x, *y, *z, *w],
+            _ => return None
+        };
+        if bytes.len() != ordered.len() * 8 {
+            return None;
+        }
+        for value in ordered {
+            cdr.f64(value);
+        }
+        Some(())
+    }
+
+    /// Deserializes a message into the value written to the entry, [`None`] if it is malformed
+    fn decode(self, data: &[u8]) -> Option<FrcValue> {
+        let mut cdr = CdrReader::new(data)?;
+        let value = match self {
+            Self::Boolean => FrcValue::Boolean(cdr.bytes(1)?[0] != 0),
+            Self::Int => FrcValue::Int(cdr.i64()?),
+            Self::Float => FrcValue::Float(cdr.f32()?),
+            Self::Double => FrcValue::Double(cdr.f64()?),
+            Self::String => FrcValue::String(cdr.string()?.into()),
+            Self::IntArray => {
+                cdr.skip_layout()?;
+                let len = cdr.u32()?;
+                FrcValue::IntArray((0..len).map(|_| cdr.i64()).collect::<Option<_>>()?)
+            }
+            Self::FloatArray => {
+                cdr.skip_layout()?;
+                let len = cdr.u32()?;
+                FrcValue::FloatArray((0..len).map(|_| cdr.f32()).collect::<Option<_>>()?)
+            }
+            Self::DoubleArray => {
+                cdr.skip_layout()?;
+                let len = cdr.u32()?;
+                FrcValue::DoubleArray((0..len).map(|_| cdr.f64()).collect::<Option<_>>()?)
+            }
+            Self::Pose2d | Self::Translation3d => decode_struct(&mut cdr, &[0, 1, 2])?,
+            Self::Rotation3d => decode_struct(&mut cdr, &[3, 0, 1, 2])?,
+            Self::Pose3d => decode_struct(&mut cdr, &[0, 1, 2, 6, 3, 4, 5])?
+        };
+        Some(value)
+    }
+}
+
+/// Reads the doubles of a message into the layout of a `WPILib` struct,
+/// `order` holds the index in the message of every double of the struct
+fn decode_struct(cdr: &mut CdrReader<'_>, order: &[usize]) -> Option<FrcValue> {
+    let doubles = order.iter().map(|_| cdr.f64()).collect::<Option<Vec<_>>>()?;
+    let bytes = order.iter()
+        .map(|index| doubles.get(*index).map(|double| double.to_le_bytes()))
+        .collect::<Option<Vec<_>>>()?
+        .concat();
+    Some(FrcValue::Raw(bytes.into()))
+}
+
+/// Writes little endian CDR, aligning every primitive to its size from the end of the encapsulation header
+struct CdrWriter {
+    bytes: Vec<u8>
+}
+
+impl CdrWriter {
+    fn new() -> Self {
+        Self { bytes: CDR_LE_HEADER.to_vec() }
+    }
+
+    fn align(&mut self, size: usize) {
+        while !(self.bytes.len() - CDR_LE_HEADER.len()).is_multiple_of(size) {
+            self.bytes.push(0);
+        }
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn u32(&mut self, value: u32) {
+        self.align(4);
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn i64(&mut self, value: i64) {
+        self.align(8);
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f32(&mut self, value: f32) {
+        self.align(4);
+        self.bytes(&value.to_le_bytes());
+    }
+
+    fn f64(&mut self, value: f64) {
+        self.align(8);
+        self.bytes(&value.to_le_bytes());
+    }
+
+    /// A string is its length with the nul terminator followed by its bytes and the terminator
+    fn string(&mut self, value: &str) -> Option<()> {
+        self.u32(u32::try_from(value.len() + 1).ok()?);
+        self.bytes(value.as_bytes());
+        self.bytes(&[0]);
+        Some(())
+    }
+
+    /// A `MultiArrayLayout` without dimensions, the `dim` sequence is empty and `data_offset` is 0
+    fn empty_layout(&mut self) {
+        self.u32(0);
+        self.u32(0);
+    }
+
+    fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// Reads little endian CDR written by [`CdrWriter`] or any other ROS 2 middleware
+struct CdrReader<'a> {
+    bytes: &'a [u8],
+    position: usize
+}
+
+impl<'a> CdrReader<'a> {
+    /// Starts reading after the encapsulation header, big endian messages aren't supported
+    fn new(data: &'a [u8]) -> Option<Self> {
+        let (header, bytes) = data.split_at_checked(CDR_LE_HEADER.len())?;
+        (header[1] == CDR_LE_HEADER[1]).then_some(Self { bytes, position: 0 })
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.bytes.get(self.position..self.position.checked_add(len)?)?;
+        self.position += len;
+        Some(bytes)
+    }
+
+    fn aligned<const N: usize>(&mut self) -> Option<[u8; N]> {
+        self.position = self.position.next_multiple_of(N);
+        self.bytes(N)?.try_into().ok()
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.aligned().map(u32::from_le_bytes)
+    }
+
+    fn i64(&mut self) -> Option<i64> {
+        self.aligned().map(i64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.aligned().map(f32::from_le_bytes)
+    }
+
+    fn f64(&mut self) -> Option<f64> {
+        self.aligned().map(f64::from_le_bytes)
+    }
+
+    fn string(&mut self) -> Option<&'a str> {
+        let len = self.u32()? as usize;
+        let bytes = self.bytes(len)?;
+        std::str::from_utf8(bytes.strip_suffix(&[0]).unwrap_or(bytes)).ok()
+    }
+
+    /// Skips a `MultiArrayLayout`, a sequence of dimensions with a label, size and stride followed by an offset
+    fn skip_layout(&mut self) -> Option<()> {
+        for _ in 0..self.u32()? {
+            let _ = self.string()?;
+            let _ = self.u32()?;
+            let _ = self.u32()?;
+        }
+        self.u32().map(|_| ())
+    }
+}
+
+/// What a conversion between a log and a bag did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Rosbag2Conversion {
+    /// The entries or topics that were converted
+    pub converted: Vec<String>,
+    /// The entries or topics with a type the other format has no equivalent for
+    pub skipped: Vec<String>,
+    /// The number of values or messages converted
+    pub messages: u64
+}
+
+/// The ROS topic name of an entry key, characters topics can't hold become `_` and the name starts with `/`
+fn topic_name(key: &str) -> String {
+    let mut name = String::from("/");
+    for part in key.split('/').filter(|part| !part.is_empty()) {
+        if name.len() > 1 {
+            name.push('/');
+        }
+        // topic tokens can't start with a digit
+        if part.starts_with(|char: char| char.is_ascii_digit()) {
+            name.push('_');
+        }
+        name.extend(part.chars().map(|char| if char.is_ascii_alphanumeric() { char } else { '_' }));
+    }
+    name
+}
+
+/// Converts the entries of a log with a channel type both formats have to a rosbag2 bag with `sqlite3` storage,
+/// for ROS based simulation and analysis tools
+///
+/// The bag is a new directory at `bag` holding the database and the `metadata.yaml` `ros2 bag` reads.
+/// Every entry becomes a topic named after its key with characters ROS doesn't allow replaced by `_`,
+/// and values become CDR serialized messages:
+///
+/// | Entry type | Message type |
+/// |---|---|
+/// | `boolean` | `std_msgs/msg/Bool` |
+/// | `int64` | `std_msgs/msg/Int64` |
+/// | `float` | `std_msgs/msg/Float32` |
+/// | `double` | `std_msgs/msg/Float64` |
+/// | `string`, `json` | `std_msgs/msg/String` |
+/// | `int64[]`, `float[]`, `double[]` | `std_msgs/msg/Int64MultiArray`, `Float32MultiArray`, `Float64MultiArray` |
+/// | `struct:Pose2d` | `geometry_msgs/msg/Pose2D` |
+/// | `struct:Pose3d` | `geometry_msgs/msg/Pose` |
+/// | `struct:Translation3d` | `geometry_msgs/msg/Point` |
+/// | `struct:Rotation3d` | `geometry_msgs/msg/Quaternion` |
+///
+/// Bags are stamped with unix time in nanoseconds while logs count from robot start,
+/// so `start_time` is the unix time of robot start in microseconds added to every timestamp.
+///
+/// # Errors
+/// - [`DataLogError::FileAlreadyExists`] if there is already something at `bag`
+/// - [`DataLogError::Sqlite`] if writing the database fails
+/// - [`DataLogError::Io`] if an IO error occurs
+pub fn wpilog_to_rosbag2(reader: &DataLogReader, bag: impl AsRef<Path>, start_time: FrcTimestamp) -> Result<Rosbag2Conversion, DataLogError> {
+    let bag = bag.as_ref();
+    std::fs::create_dir(bag).map_err(|err| match err.kind() {
+        std::io::ErrorKind::AlreadyExists => DataLogError::FileAlreadyExists,
+        _ => DataLogError::Io(err)
+    })?;
+    let name = bag.file_name().and_then(|name| name.to_str()).unwrap_or("rosbag2");
+    let file_name = format!("{name}_0.db3");
+    let mut db = Connection::open(bag.join(&file_name))?;
+    db.execute_batch(SCHEMA)?;
+
+    let mut conversion = Rosbag2Conversion::default();
+    let mut topics = Vec::new();
+    let (mut first, mut last) = (u64::MAX, 0u64);
+    let transaction = db.transaction()?;
+    {
+        let mut insert_topic = transaction.prepare("INSERT INTO topics (id, name, type, serialization_format, offered_qos_profiles) VALUES (?1, ?2, ?3, ?4, '')")?;
+        let mut insert_message = transaction.prepare("INSERT INTO messages (topic_id, timestamp, data) VALUES (?1, ?2, ?3)")?;
+        let mut keys = reader.get_all_entry_keys();
+        keys.sort();
+        for key in keys {
+            let channel = reader.read_entry_type_str(key).last()
+                .and_then(|type_str| Channel::from_entry_type(&type_str.value));
+            let Some(channel) = channel else {
+                conversion.skipped.push(key.clone());
+                continue;
+            };
+            let topic_id = i64::try_from(topics.len() + 1)?;
+            let topic = topic_name(key);
+            let _ = insert_topic.execute(params![topic_id, topic, channel.message_type(), SERIALIZATION_FORMAT])?;
+            let mut count = 0u64;
+            for value in reader.read_entry(key) {
+                let Some(data) = channel.encode(&value.value) else {
+                    continue;
+                };
+                let nanos = start_time.saturating_add(value.timestamp).saturating_mul(1_000);
+                let _ = insert_message.execute(params![topic_id, i64::try_from(nanos)?, data])?;
+                (first, last) = (first.min(nanos), last.max(nanos));
+                count += 1;
+            }
+            conversion.converted.push(key.clone());
+            conversion.messages += count;
+            topics.push((topic, channel, count));
+        }
+    }
+    transaction.commit()?;
+    drop(db);
+
+    let first = first.min(last);
+    let metadata = bag_metadata(&file_name, &topics, first, last - first, conversion.messages);
+    std::fs::write(bag.join("metadata.yaml"), metadata)?;
+    Ok(conversion)
+}
+
+/// The `metadata.yaml` of a bag with one database file
+fn bag_metadata(file_name: &str, topics: &[(String, Channel, u64)], start: u64, duration: u64, messages: u64) -> String {
+    let mut yaml = String::new();
+    let _ = writeln!(yaml, "rosbag2_bagfile_information:");
+    let _ = writeln!(yaml, "  version: 5");
+    let _ = writeln!(yaml, "  storage_identifier: sqlite3");
+    let _ = writeln!(yaml, "  duration:\n    nanoseconds: {duration}");
+    let _ = writeln!(yaml, "  starting_time:\n    nanoseconds_since_epoch: {start}");
+    let _ = writeln!(yaml, "  message_count: {messages}");
+    let _ = writeln!(yaml, "  topics_with_message_count:");
+    for (name, channel, count) in topics {
+        let _ = writeln!(yaml, "    - topic_metadata:");
+        let _ = writeln!(yaml, "        name: {name}");
+        let _ = writeln!(yaml, "        type: {}", channel.message_type());
+        let _ = writeln!(yaml, "        serialization_format: {SERIALIZATION_FORMAT}");
+        let _ = writeln!(yaml, "        offered_qos_profiles: \"\"");
+        let _ = writeln!(yaml, "      message_count: {count}");
+    }
+    let _ = writeln!(yaml, "  compression_format: \"\"");
+    let _ = writeln!(yaml, "  compression_mode: \"\"");
+    let _ = writeln!(yaml, "  relative_file_paths:\n    - {file_name}");
+    let _ = writeln!(yaml, "  files:\n    - path: {file_name}");
+    let _ = writeln!(yaml, "      starting_time:\n        nanoseconds_since_epoch: {start}");
+    let _ = writeln!(yaml, "      duration:\n        nanoseconds: {duration}");
+    let _ = writeln!(yaml, "      message_count: {messages}");
+    yaml
+}
+
+/// The database files of a bag, `bag` can also be a single database file
+fn database_files(bag: &Path) -> Result<Vec<PathBuf>, DataLogError> {
+    if bag.is_file() {
+        return Ok(vec![bag.to_path_buf()]);
+    }
+    if !bag.exists() {
+        return Err(DataLogError::FileDoesNotExist);
+    }
+    let mut files = std::fs::read_dir(bag)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .filter(|path| path.as_ref().map_or(true, |path| path.extension().is_some_and(|extension| extension == "db3")))
+        .collect::<Result<Vec<_>, _>>()?;
+    files.sort();
+    Ok(files)
+}
+
+/// Converts the topics of a rosbag2 bag with `sqlite3` storage into entries of a log,
+/// the reverse of [`wpilog_to_rosbag2`]
+///
+/// Topics with a message type in the table of [`wpilog_to_rosbag2`] become entries keyed by the topic name,
+/// with the struct schemas `WPILib` tools need to decode struct entries. Timestamps count from the first
+/// message of the bag, so the log reads as if robot start was when the bag started.
+/// Messages that fail to deserialize are skipped.
+///
+/// # Errors
+/// - [`DataLogError::FileDoesNotExist`] if there is nothing at `bag`
+/// - [`DataLogError::Sqlite`] if reading a database fails
+/// - [`DataLogError::EntryTypeMismatch`] if the log already has an entry keyed by a topic with another type
+/// - [`DataLogError::Io`] if an IO error occurs
+/// - Any error of [`DataLogWriter::write_dynamic`]
+pub fn rosbag2_to_wpilog<W: Write>(bag: impl AsRef<Path>, writer: &mut DataLogWriter<W>) -> Result<Rosbag2Conversion, DataLogError> {
+    let mut conversion = Rosbag2Conversion::default();
+    let mut start = None;
+    let mut published_schemas = Vec::new();
+    for file in database_files(bag.as_ref())? {
+        let db = Connection::open(file)?;
+        if start.is_none() {
+            start = db.query_row("SELECT MIN(timestamp) FROM messages", [], |row| row.get::<_, Option<i64>>(0))?;
+        }
+        let start = start.unwrap_or_default();
+
+        let mut entries = HashMap::new();
+        let mut statement = db.prepare("SELECT id, name, type FROM topics ORDER BY id")?;
+        let topics = statement.query_map([], |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?)))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for (id, name, message_type) in topics {
+            let Some(channel) = Channel::from_message_type(&message_type) else {
+                conversion.skipped.push(name);
+                continue;
+            };
+            for (struct_name, schema) in channel.schemas() {
+                if !published_schemas.contains(struct_name) {
+                    let schema_entry = writer.get_entry_raw(&format!("/.schema/struct:{struct_name}"), "structschema", None)?;
+                    writer.write_raw(schema_entry, schema.as_bytes(), 0)?;
+                    published_schemas.push(*struct_name);
+                }
+            }
+            let entry = match channel {
+                Channel::Pose2d | Channel::Pose3d | Channel::Translation3d | Channel::Rotation3d => {
+                    writer.get_entry_raw(&name, channel.entry_type(), None)?
+                }
+                _ => writer.get_entry_dynamic(&name, entry_frc_type(channel), None)?
+            };
+            let _ = entries.insert(id, (channel, entry));
+            if !conversion.converted.contains(&name) {
+                conversion.converted.push(name);
+            }
+        }
+
+        let mut statement = db.prepare("SELECT topic_id, timestamp, data FROM messages ORDER BY timestamp")?;
+        let mut rows = statement.query([])?;
+        while let Some(row) = rows.next()? {
+            let Some((channel, entry)) = entries.get(&row.get::<_, i64>(0)?) else {
+                continue;
+            };
+            let timestamp = u64::try_from(row.get::<_, i64>(1)?.saturating_sub(start)).unwrap_or_default() / 1_000;
+            let Some(value) = row.get_ref(2)?.as_blob().ok().and_then(|data| channel.decode(data)) else {
+                continue;
+            };
+            write_value(writer, *entry, value, timestamp)?;
+            conversion.messages += 1;
+        }
+    }
+    Ok(conversion)
+}
+
+/// The entry type of the channels that aren't structs
+const fn entry_frc_type(channel: Channel) -> FrcType {
+    match channel {
+        Channel::Boolean => FrcType::Boolean,
+        Channel::Int => FrcType::Int,
+        Channel::Float => FrcType::Float,
+        Channel::String => FrcType::String,
+        Channel::IntArray => FrcType::IntArray,
+        Channel::FloatArray => FrcType::FloatArray,
+        Channel::DoubleArray => FrcType::DoubleArray,
+        _ => FrcType::Double
+    }
+}
+
+fn write_value<W: Write>(writer: &mut DataLogWriter<W>, entry: EntryId, value: FrcValue, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+    match value {
+        FrcValue::Raw(bytes) => writer.write_raw(entry, &bytes, timestamp),
+        value => writer.write_dynamic(entry, value.to_timestamped(timestamp))
+    }
+}
@@ -0,0 +1,283 @@
+use std::{fmt::Debug, fs::File, path::PathBuf, time::{Duration, Instant}};
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, IntoFrcValue, StaticallyFrcTyped};
+
+#[cfg(feature = "disk-watchdog")]
+use crate::disk_watchdog::{DiskSpaceWatchdog, LowDiskSpaceAction};
+use crate::{locking::create_locked, shutdown::Finalize, writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+type NamingFn = Box<dyn FnMut(u32) -> PathBuf + Send>;
+
+/// A datalog writer that starts a new file every time an interval passes
+/// 
+/// Every file is a complete log, entries that are open when the writer rotates
+/// are started again in the new file so their ids stay valid.
+/// # Example
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use frclib_datalog::rotating_writer::RotatingDataLogWriter;
+/// 
+/// let mut writer = RotatingDataLogWriter::new(
+///     |index| format!("logs/bench_{index}.wpilog").into(),
+///     "",
+///     Duration::from_mins(10)
+/// ).expect("Failed to create writer");
+/// 
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+/// ```
+pub struct RotatingDataLogWriter {
+    writer: DataLogWriter<File>,
+    naming: NamingFn,
+    metadata: String,
+    interval: Duration,
+    opened: Instant,
+    index: u32,
+    #[cfg(feature = "disk-watchdog")]
+    watchdog: Option<DiskSpaceWatchdog>,
+    stopped: bool
+}
+impl Debug for RotatingDataLogWriter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RotatingDataLogWriter")
+            .field("writer", &self.writer)
+            .field("metadata", &self.metadata)
+            .field("interval", &self.interval)
+            .field("index", &self.index)
+            .field("stopped", &self.stopped)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RotatingDataLogWriter {
+    /// Creates a new rotating writer, `naming` is given the index of each file starting at 0
+    /// and returns the path to create it at
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at the first path
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn new(
+        mut naming: impl FnMut(u32) -> PathBuf + Send + 'static,
+        metadata: impl ToString,
+        interval: Duration
+    ) -> Result<Self, DataLogError> {
+        let metadata = metadata.to_string();
+        let writer = DataLogWriter::create(naming(0), &metadata)?;
+        Ok(Self {
+            writer,
+            naming: Box::new(naming),
+            metadata,
+            interval,
+            opened: Instant::now(),
+            index: 0,
+            #[cfg(feature = "disk-watchdog")]
+            watchdog: None,
+            stopped: false
+        })
+    }
+
+    /// Watches the free disk space while writing, see [`DiskSpaceWatchdog`]
+    #[cfg(feature = "disk-watchdog")]
+    #[must_use]
+    pub fn with_disk_watchdog(mut self, watchdog: DiskSpaceWatchdog) -> Self {
+        self.watchdog = Some(watchdog);
+        self
+    }
+
+    /// Whether logging was stopped, writes are ignored once it is.
+    /// 
+    /// The file stays open so [`RotatingDataLogWriter::close`] can still finish its entries.
+    #[must_use]
+    pub const fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Runs the disk watchdog and rotation before a write, returning whether the write should happen
+    fn before_write(&mut self) -> Result<bool, DataLogError> {
+        #[cfg(feature = "disk-watchdog")]
+        if !self.stopped {
+            if let Some(mut watchdog) = self.watchdog.take() {
+                // the watchdog is moved out so its action can use the writer
+                let result = self.check_disk_space(&mut watchdog);
+                self.watchdog = Some(watchdog);
+                result?;
+            }
+        }
+        if self.stopped {
+            return Ok(false);
+        }
+        let _ = self.maybe_rotate()?;
+        Ok(true)
+    }
+
+    #[cfg(feature = "disk-watchdog")]
+    fn check_disk_space(&mut self, watchdog: &mut DiskSpaceWatchdog) -> Result<(), DataLogError> {
+        if let Some(available) = watchdog.poll()? {
+            match watchdog.action_mut() {
+                LowDiskSpaceAction::StopLogging => {
+                    self.writer.sync()?;
+                    self.stopped = true;
+                }
+                LowDiskSpaceAction::Rotate => self.rotate()?,
+                LowDiskSpaceAction::Callback(callback) => callback(available)
+            }
+        }
+        Ok(())
+    }
+
+    /// The index of the file currently being written
+    #[must_use]
+    pub const fn current_index(&self) -> u32 {
+        self.index
+    }
+
+    /// Closes the current file and starts the next one, regardless of the interval
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at the next path
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn rotate(&mut self) -> Result<(), DataLogError> {
+        let index = self.index + 1;
+        let file = create_locked(&(self.naming)(index))
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::AlreadyExists => DataLogError::FileAlreadyExists,
+                _ => DataLogError::Io(err)
+            })?;
+        let old = self.writer.rotate(file, &self.metadata)?;
+        old.sync_data()?;
+        self.index = index;
+        self.opened = Instant::now();
+        Ok(())
+    }
+
+    /// Rotates if the interval has passed since the current file was started,
+    /// returning whether it did.
+    /// 
+    /// This is called by every write, call it periodically if writes can be infrequent.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at the next path
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn maybe_rotate(&mut self) -> Result<bool, DataLogError> {
+        let should_rotate = self.opened.elapsed() >= self.interval;
+        if should_rotate {
+            self.rotate()?;
+        }
+        Ok(should_rotate)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry<T: StaticallyFrcTyped>(&mut self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.writer.get_entry(key, metadata)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry_dynamic`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry_dynamic(&mut self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.writer.get_entry_dynamic(key, entry_type, metadata)
+    }
+
+    /// Writes a value to the current file, see [`DataLogWriter::write`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::FileAlreadyExists`] if rotating and a file already exists at the next path
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T) -> Result<(), DataLogError> {
+        if !self.before_write()? {
+            return Ok(());
+        }
+        self.writer.write(id, value)
+    }
+
+    /// Writes a value to the current file with a specific timestamp, see [`DataLogWriter::write_timestamped`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::FileAlreadyExists`] if rotating and a file already exists at the next path
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write_timestamped<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        if !self.before_write()? {
+            return Ok(());
+        }
+        self.writer.write_timestamped(id, value, timestamp)
+    }
+
+    /// Writes a value to the current file, see [`DataLogWriter::write_dynamic`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::EntryTypeMismatch`] if the value type doesn't match the entry type
+    /// - [`DataLogError::FileAlreadyExists`] if rotating and a file already exists at the next path
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write_dynamic(&mut self, id: EntryId, value: FrcTimestampedValue) -> Result<(), DataLogError> {
+        if !self.before_write()? {
+            return Ok(());
+        }
+        self.writer.write_dynamic(id, value)
+    }
+
+    /// Replaces the metadata of an entry, see [`DataLogWriter::set_entry_metadata`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn set_entry_metadata(&mut self, id: EntryId, metadata: impl ToString) -> Result<(), DataLogError> {
+        self.writer.set_entry_metadata(id, metadata)
+    }
+
+    /// Closes an entry, see [`DataLogWriter::close_entry`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    pub fn close_entry(&mut self, id: EntryId) -> Result<(), DataLogError> {
+        self.writer.close_entry(id)
+    }
+
+    /// Flushes the current file
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn flush(&mut self) -> Result<(), DataLogError> {
+        self.writer.flush()
+    }
+
+    /// Finishes every open entry and returns the current file, see [`DataLogWriter::close`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn close(self) -> Result<File, DataLogError> {
+        self.writer.close()
+    }
+}
+
+impl Finalize for RotatingDataLogWriter {
+    fn finalize(&mut self) -> Result<(), DataLogError> {
+        self.writer.close_in_place()
+    }
+}
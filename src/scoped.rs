@@ -0,0 +1,136 @@
+use std::io::Write;
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, IntoFrcValue, StaticallyFrcTyped};
+
+use crate::{loggable::Loggable, writer::{EntryId, TypedEntryId}, DataLogError, DataLogWriter};
+
+/// A view of a [`DataLogWriter`] that puts every key it creates under a prefix
+///
+/// Subsystem code can use keys relative to its own mount point,
+/// created with [`DataLogWriter::scoped`] or [`ScopedDataLogWriter::scoped`] for nesting.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{scoped::ScopedDataLogWriter, DataLogWriter};
+///
+/// fn log_drivetrain(log: &mut ScopedDataLogWriter<'_, std::fs::File>) {
+///     let entry = log.get_entry::<f64>("velocity", None).expect("Failed to get entry");
+///     log.write(entry, 1.5).expect("Failed to write entry");
+/// }
+///
+/// let mut writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create writer");
+/// // writes /drivetrain/velocity
+/// log_drivetrain(&mut writer.scoped("/drivetrain"));
+/// ```
+#[derive(Debug)]
+pub struct ScopedDataLogWriter<'a, W: Write> {
+    writer: &'a mut DataLogWriter<W>,
+    prefix: String
+}
+
+impl <'a, W: Write> ScopedDataLogWriter<'a, W> {
+    pub(crate) fn new(writer: &'a mut DataLogWriter<W>, prefix: &str) -> Self {
+        Self {
+            writer,
+            prefix: prefix.trim_end_matches('/').to_string()
+        }
+    }
+
+    /// The prefix put before every key, without a trailing `/`
+    #[must_use]
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// The full key of `key` in the log
+    #[must_use]
+    pub fn full_key(&self, key: &str) -> String {
+        format!("{}/{}", self.prefix, key.trim_start_matches('/'))
+    }
+
+    /// A view that puts keys under `prefix` within this one's prefix
+    pub fn scoped(&mut self, prefix: &str) -> ScopedDataLogWriter<'_, W> {
+        let prefix = self.full_key(prefix);
+        ScopedDataLogWriter::new(self.writer, &prefix)
+    }
+
+    /// The underlying writer, its keys aren't prefixed
+    pub const fn writer_mut(&mut self) -> &mut DataLogWriter<W> {
+        self.writer
+    }
+
+    /// Gets the entry id for a key under the prefix, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry<T: StaticallyFrcTyped>(&mut self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.writer.get_entry(&self.full_key(key), metadata)
+    }
+
+    /// Gets the entry id for a key under the prefix, creating it if it doesn't exist,
+    /// see [`DataLogWriter::get_entry_dynamic`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn get_entry_dynamic(&mut self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.writer.get_entry_dynamic(&self.full_key(key), entry_type, metadata)
+    }
+
+    /// Writes a value, see [`DataLogWriter::write`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T) -> Result<(), DataLogError> {
+        self.writer.write(id, value)
+    }
+
+    /// Writes a value with a specific timestamp, see [`DataLogWriter::write_timestamped`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write_timestamped<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.writer.write_timestamped(id, value, timestamp)
+    }
+
+    /// Writes a value, see [`DataLogWriter::write_dynamic`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::EntryTypeMismatch`] if the value type doesn't match the entry type
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write_dynamic(&mut self, id: EntryId, value: FrcTimestampedValue) -> Result<(), DataLogError> {
+        self.writer.write_dynamic(id, value)
+    }
+
+    /// Logs a value under a key under the prefix, see [`DataLogWriter::put`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if an entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn put(&mut self, key: &str, value: &impl Loggable) -> Result<(), DataLogError> {
+        self.writer.put(&self.full_key(key), value)
+    }
+
+    /// Logs a value under a key under the prefix with a specific timestamp, see [`DataLogWriter::put_timestamped`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if an entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn put_timestamped(&mut self, key: &str, value: &impl Loggable, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.writer.put_timestamped(&self.full_key(key), value, timestamp)
+    }
+}
@@ -0,0 +1,148 @@
+use std::fmt::Display;
+
+use serde_json::{Map, Value};
+
+use crate::frc_naming::{MatchInfo, MatchType};
+
+/// The header metadata of a log describing the robot and match it was recorded in, serialized as json
+///
+/// It implements [`Display`] so it can be passed as the metadata of any writer.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{session::SessionMetadata, DataLogWriter};
+///
+/// let metadata = SessionMetadata::new()
+///     .with_team_number(1234)
+///     .with_robot_name("Comp Bot")
+///     .with_code_version(env!("CARGO_PKG_VERSION"));
+/// let writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", metadata).expect("Failed to create writer");
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SessionMetadata {
+    /// The team number
+    pub team_number: Option<u16>,
+    /// The match, including the event name
+    pub match_info: Option<MatchInfo>,
+    /// The name of the robot, for teams with more than one
+    pub robot_name: Option<String>,
+    /// The version of the robot code, like a git hash
+    pub code_version: Option<String>,
+    /// Any other fields
+    pub extra: Map<String, Value>
+}
+
+impl SessionMetadata {
+    /// Creates metadata without any fields
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the team number
+    #[must_use]
+    pub const fn with_team_number(mut self, team_number: u16) -> Self {
+        self.team_number = Some(team_number);
+        self
+    }
+
+    /// Sets the match
+    #[must_use]
+    pub fn with_match_info(mut self, match_info: MatchInfo) -> Self {
+        self.match_info = Some(match_info);
+        self
+    }
+
+    /// Sets the robot name
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_robot_name(mut self, robot_name: impl ToString) -> Self {
+        self.robot_name = Some(robot_name.to_string());
+        self
+    }
+
+    /// Sets the code version
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_code_version(mut self, code_version: impl ToString) -> Self {
+        self.code_version = Some(code_version.to_string());
+        self
+    }
+
+    /// Adds a field of any other name, fields named like the typed ones are overwritten by them
+    #[must_use]
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_field(mut self, key: impl ToString, value: impl Into<Value>) -> Self {
+        let _ = self.extra.insert(key.to_string(), value.into());
+        self
+    }
+
+    /// Reads metadata written by [`SessionMetadata::to_json`], [`None`] if `json` isn't a json object
+    #[must_use]
+    pub fn parse(json: &str) -> Option<Self> {
+        let Value::Object(mut fields) = serde_json::from_str(json).ok()? else {
+            return None;
+        };
+        let mut take_str = |key: &str| match fields.remove(key) {
+            Some(Value::String(value)) => Some(value),
+            _ => None
+        };
+        let event_name = take_str("event");
+        let match_type = take_str("match_type").and_then(|match_type| match match_type.as_str() {
+            "Practice" => Some(MatchType::Practice),
+            "Qualification" => Some(MatchType::Qualification),
+            "Elimination" => Some(MatchType::Elimination),
+            _ => None
+        });
+        let robot_name = take_str("robot");
+        let code_version = take_str("code_version");
+        let mut take_u16 = |key: &str| fields.remove(key)
+            .and_then(|value| value.as_u64())
+            .and_then(|value| u16::try_from(value).ok());
+        let team_number = take_u16("team");
+        let match_number = take_u16("match_number");
+
+        let match_info = match (event_name, match_type, match_number) {
+            (Some(event_name), Some(match_type), Some(match_number)) => Some(MatchInfo { event_name, match_type, match_number }),
+            _ => None
+        };
+        Some(Self {
+            team_number,
+            match_info,
+            robot_name,
+            code_version,
+            extra: fields
+        })
+    }
+
+    /// Serializes the metadata to a json object
+    #[must_use]
+    pub fn to_json(&self) -> String {
+        let mut fields = self.extra.clone();
+        if let Some(team_number) = self.team_number {
+            let _ = fields.insert("team".to_string(), team_number.into());
+        }
+        if let Some(info) = &self.match_info {
+            let match_type = match info.match_type {
+                MatchType::Practice => "Practice",
+                MatchType::Qualification => "Qualification",
+                MatchType::Elimination => "Elimination"
+            };
+            let _ = fields.insert("event".to_string(), info.event_name.clone().into());
+            let _ = fields.insert("match_type".to_string(), match_type.into());
+            let _ = fields.insert("match_number".to_string(), info.match_number.into());
+        }
+        if let Some(robot_name) = &self.robot_name {
+            let _ = fields.insert("robot".to_string(), robot_name.clone().into());
+        }
+        if let Some(code_version) = &self.code_version {
+            let _ = fields.insert("code_version".to_string(), code_version.clone().into());
+        }
+        Value::Object(fields).to_string()
+    }
+}
+
+impl Display for SessionMetadata {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_json())
+    }
+}
@@ -0,0 +1,124 @@
+use std::{io::Write, sync::{Arc, Mutex, Once, PoisonError, TryLockError, Weak}};
+
+use crate::{handle::DataLogHandle, DataLogError, DataLogWriter};
+
+/// Finalizes a registered writer, returning whether it should stay registered
+type FinalizeFn = Box<dyn Fn() -> bool + Send>;
+
+static REGISTERED: Mutex<Vec<FinalizeFn>> = Mutex::new(Vec::new());
+static PANIC_HOOK: Once = Once::new();
+
+/// A writer that can be finalized by the shutdown hooks, see [`register_shared`]
+pub trait Finalize: Send {
+    /// Finishes every open entry, flushes and closes the sink, the writer can't be written to afterwards
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    fn finalize(&mut self) -> Result<(), DataLogError>;
+}
+
+impl <W: Write + Send> Finalize for DataLogWriter<W> {
+    fn finalize(&mut self) -> Result<(), DataLogError> {
+        self.close_in_place()
+    }
+}
+
+/// Registers the writer behind `handle` to be finalized when the program exits abnormally,
+/// see [`register_shared`]
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{handle::DataLogHandle, shutdown, DataLogWriter};
+///
+/// let writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create writer");
+/// let handle = DataLogHandle::new(writer);
+/// shutdown::register(&handle);
+/// shutdown::install_panic_hook();
+/// ```
+pub fn register<W: Write + Send + 'static>(handle: &DataLogHandle<W>) {
+    register_weak(handle.downgrade());
+}
+
+/// Registers a shared writer to be finalized when the program exits abnormally
+///
+/// Registered writers are finalized by [`finalize_registered`] and the hooks installed with
+/// [`install_panic_hook`] or `install_ctrlc_handler`, so a crash doesn't lose the buffered records
+/// and the log ends with every entry finished.
+/// The registry only keeps a weak reference, the writer is forgotten once it is dropped or finalized.
+/// # Example
+/// ```rust,no_run
+/// use std::{sync::{Arc, Mutex}, time::Duration};
+/// use frclib_datalog::{rotating_writer::RotatingDataLogWriter, shutdown};
+///
+/// let writer = RotatingDataLogWriter::new(
+///     |index| format!("/home/lvuser/logs/bench_{index}.wpilog").into(),
+///     "",
+///     Duration::from_mins(10)
+/// ).expect("Failed to create writer");
+/// let writer = Arc::new(Mutex::new(writer));
+/// shutdown::register_shared(&writer);
+/// shutdown::install_panic_hook();
+/// ```
+pub fn register_shared<T: Finalize + 'static>(writer: &Arc<Mutex<T>>) {
+    register_weak(Arc::downgrade(writer));
+}
+
+fn register_weak<T: Finalize + 'static>(writer: Weak<Mutex<T>>) {
+    let finalize: FinalizeFn = Box::new(move || {
+        let Some(writer) = writer.upgrade() else {
+            return false;
+        };
+        // the lock is held if the thread that is exiting was writing, the writer may be mid record then
+        let locked = match writer.try_lock() {
+            Ok(mut guard) => {
+                let _ = guard.finalize();
+                true
+            }
+            Err(TryLockError::Poisoned(poisoned)) => {
+                let _ = poisoned.into_inner().finalize();
+                true
+            }
+            Err(TryLockError::WouldBlock) => false
+        };
+        // a finalized writer can't be written to anymore, only a locked one has to be tried again
+        !locked
+    });
+    REGISTERED.lock().unwrap_or_else(PoisonError::into_inner).push(finalize);
+}
+
+/// Finalizes every registered writer that is still alive and isn't locked, see [`register_shared`]
+///
+/// Does nothing if the registry is already being finalized, like when finalizing itself panics.
+pub fn finalize_registered() {
+    let mut registered = match REGISTERED.try_lock() {
+        Ok(registered) => registered,
+        Err(TryLockError::Poisoned(poisoned)) => poisoned.into_inner(),
+        Err(TryLockError::WouldBlock) => return
+    };
+    registered.retain(|finalize| finalize());
+}
+
+/// Installs a panic hook that finalizes every registered writer before running the hook that was set before it,
+/// installing it more than once does nothing
+///
+/// The hook runs for every panic, including ones that are caught or only end a background thread.
+pub fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            finalize_registered();
+            previous(info);
+        }));
+    });
+}
+
+/// Installs a Ctrl-C handler that finalizes every registered writer and exits with status 130
+///
+/// # Errors
+/// - [`DataLogError::Io`] if a handler is already set or can't be installed
+#[cfg(feature = "ctrlc")]
+pub fn install_ctrlc_handler() -> Result<(), DataLogError> {
+    ctrlc::try_set_handler(|| {
+        finalize_registered();
+        std::process::exit(130);
+    }).map_err(|err| std::io::Error::other(err).into())
+}
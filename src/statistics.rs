@@ -0,0 +1,173 @@
+use std::{collections::VecDeque, num::NonZeroUsize, time::Duration};
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcValue};
+
+/// A scalar numeric value as a double, [`None`] for any other value
+#[allow(clippy::cast_precision_loss)]
+pub(crate) fn as_number(value: &FrcValue) -> Option<f64> {
+    match *value {
+        FrcValue::Double(v) => Some(v),
+        FrcValue::Float(v) => Some(f64::from(v)),
+        FrcValue::Int(v) => Some(v as f64),
+        _ => None
+    }
+}
+
+/// Aggregates of an entry that are computed while a log is parsed
+///
+/// `min`, `max` and `mean` only account for scalar numeric values
+/// (doubles, floats and integers), `count` accounts for every value
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EntryStatistics {
+    /// The number of values in the entry
+    pub count: u64,
+    /// The number of numeric values in the entry
+    pub numeric_count: u64,
+    /// The smallest numeric value
+    pub min: Option<f64>,
+    /// The largest numeric value
+    pub max: Option<f64>,
+    /// The mean of the numeric values
+    pub mean: Option<f64>,
+    /// The timestamp of the earliest value
+    pub first_timestamp: Option<FrcTimestamp>,
+    /// The value with the latest timestamp
+    pub last: Option<FrcTimestampedValue>,
+}
+
+impl EntryStatistics {
+    /// Adds a value to the aggregates, keeping it as [`last`](Self::last) if it is the latest so far
+    pub fn update(&mut self, value: FrcTimestampedValue) {
+        self.update_aggregates(&value);
+        if self.last.as_ref().is_none_or(|last| last.timestamp <= value.timestamp) {
+            self.last = Some(value);
+        }
+    }
+
+    /// Adds a value to the aggregates without keeping it as [`last`](Self::last),
+    /// for values that are kept elsewhere anyway
+    #[allow(clippy::cast_precision_loss)]
+    pub fn update_aggregates(&mut self, value: &FrcTimestampedValue) {
+        self.count += 1;
+        self.first_timestamp = Some(
+            self.first_timestamp.map_or(value.timestamp, |first| first.min(value.timestamp))
+        );
+
+        let Some(number) = as_number(&value.value) else {
+            return;
+        };
+        self.numeric_count += 1;
+        self.min = Some(self.min.map_or(number, |min| min.min(number)));
+        self.max = Some(self.max.map_or(number, |max| max.max(number)));
+        let mean = self.mean.unwrap_or_default();
+        self.mean = Some(mean + (number - mean) / self.numeric_count as f64);
+    }
+
+    /// The timestamp of the latest value
+    #[must_use]
+    pub fn last_timestamp(&self) -> Option<FrcTimestamp> {
+        self.last.as_ref().map(|last| last.timestamp)
+    }
+}
+
+/// The values [`RollingStatistics`] are computed over
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollingWindow {
+    /// The latest values, up to this many
+    Samples(NonZeroUsize),
+    /// The values timestamped at most this long before the latest one
+    Duration(Duration)
+}
+
+/// The mean, min and max of the latest values of a signal, updated as values are pushed
+///
+/// Non finite values are ignored. This is what
+/// [`DataLogWriter::add_rolling_statistics`](crate::DataLogWriter::add_rolling_statistics) logs.
+/// # Example
+/// ```rust
+/// use std::num::NonZeroUsize;
+/// use frclib_datalog::statistics::{RollingStatistics, RollingWindow};
+///
+/// let mut statistics = RollingStatistics::new(RollingWindow::Samples(NonZeroUsize::new(2).unwrap()));
+/// statistics.push(1_000, 1.0);
+/// statistics.push(2_000, 3.0);
+/// statistics.push(3_000, 5.0);
+/// assert_eq!(statistics.mean(), Some(4.0));
+/// assert_eq!(statistics.min(), Some(3.0));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RollingStatistics {
+    window: RollingWindow,
+    values: VecDeque<(FrcTimestamp, f64)>
+}
+
+impl RollingStatistics {
+    /// Creates statistics over `window` without any values
+    #[must_use]
+    pub const fn new(window: RollingWindow) -> Self {
+        Self {
+            window,
+            values: VecDeque::new()
+        }
+    }
+
+    /// The window the statistics are computed over
+    #[must_use]
+    pub const fn window(&self) -> RollingWindow {
+        self.window
+    }
+
+    /// Adds a value and drops the values that fall out of the window
+    pub fn push(&mut self, timestamp: FrcTimestamp, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        self.values.push_back((timestamp, value));
+        match self.window {
+            RollingWindow::Samples(count) => {
+                while self.values.len() > count.get() {
+                    let _ = self.values.pop_front();
+                }
+            }
+            RollingWindow::Duration(duration) => {
+                let window = u64::try_from(duration.as_micros()).unwrap_or(u64::MAX);
+                let latest = self.values.iter().map(|(timestamp, _)| *timestamp).max().unwrap_or(timestamp);
+                self.values.retain(|(timestamp, _)| latest - *timestamp <= window);
+            }
+        }
+    }
+
+    /// The number of values in the window
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Whether the window holds no values
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// The mean of the values in the window
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn mean(&self) -> Option<f64> {
+        if self.values.is_empty() {
+            return None;
+        }
+        Some(self.values.iter().map(|(_, value)| value).sum::<f64>() / self.values.len() as f64)
+    }
+
+    /// The smallest value in the window
+    #[must_use]
+    pub fn min(&self) -> Option<f64> {
+        self.values.iter().map(|(_, value)| *value).reduce(f64::min)
+    }
+
+    /// The largest value in the window
+    #[must_use]
+    pub fn max(&self) -> Option<f64> {
+        self.values.iter().map(|(_, value)| *value).reduce(f64::max)
+    }
+}
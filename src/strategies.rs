@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+
+use proptest::{arbitrary::Arbitrary, collection::vec, prelude::*, strategy::BoxedStrategy};
+
+use crate::{writer::{WPILOG_MAGIC, WPILOG_VERSION}, ControlRecord, DataRecord, Record};
+
+/// The type strings of every supported entry type
+const TYPE_STRS: [&str; 11] = [
+    "raw", "boolean", "int64", "float", "double", "string",
+    "boolean[]", "int64[]", "float[]", "double[]", "string[]"
+];
+
+/// The most elements a generated array or bytes a generated string or raw value has
+const MAX_LEN: usize = 16;
+
+/// Values of any supported type
+///
+/// Values of variable length types have at least one element or byte,
+/// as [`DataLogReader`](crate::DataLogReader) doesn't read back empty payloads.
+pub fn data_record() -> impl Strategy<Value = DataRecord> {
+    (0..TYPE_STRS.len()).prop_flat_map(data_record_of)
+}
+
+/// Values of the type at `index` of the supported types, like `4` for `double`
+fn data_record_of(index: usize) -> BoxedStrategy<DataRecord> {
+    match index {
+        0 => vec(any::<u8>(), 1..MAX_LEN).prop_map(|data| DataRecord::Raw(data.into())).boxed(),
+        1 => any::<bool>().prop_map(DataRecord::Boolean).boxed(),
+        2 => any::<i64>().prop_map(DataRecord::Integer).boxed(),
+        3 => any::<f32>().prop_map(DataRecord::Float).boxed(),
+        4 => any::<f64>().prop_map(DataRecord::Double).boxed(),
+        5 => non_empty_string().prop_map(|data| DataRecord::String(data.into())).boxed(),
+        6 => vec(any::<bool>(), 1..MAX_LEN).prop_map(|data| DataRecord::BooleanArray(data.into())).boxed(),
+        7 => vec(any::<i64>(), 1..MAX_LEN).prop_map(|data| DataRecord::IntegerArray(data.into())).boxed(),
+        8 => vec(any::<f32>(), 1..MAX_LEN).prop_map(|data| DataRecord::FloatArray(data.into())).boxed(),
+        9 => vec(any::<f64>(), 1..MAX_LEN).prop_map(|data| DataRecord::DoubleArray(data.into())).boxed(),
+        _ => vec(any::<String>(), 1..MAX_LEN)
+            .prop_map(|data| DataRecord::StringArray(data.into_iter().map(String::into_boxed_str).collect()))
+            .boxed(),
+    }
+}
+
+fn non_empty_string() -> impl Strategy<Value = String> {
+    any::<String>().prop_filter("empty payloads aren't read back", |string| !string.is_empty())
+}
+
+/// Control records of every kind, including [`ControlRecord::Unknown`] ones
+pub fn control_record() -> impl Strategy<Value = ControlRecord> {
+    prop_oneof![
+        (any::<String>(), (0..TYPE_STRS.len()).prop_map(|index| TYPE_STRS[index].to_string()), any::<String>())
+            .prop_map(|(name, entry_type, metadata)| ControlRecord::Start(name, entry_type, metadata)),
+        Just(ControlRecord::Finish),
+        any::<String>().prop_map(ControlRecord::Metadata),
+        (3..=u8::MAX, vec(any::<u8>(), 0..MAX_LEN))
+            .prop_map(|(kind, payload)| ControlRecord::Unknown { kind, payload: payload.into() }),
+    ]
+}
+
+/// Data and control records with any timestamp, data records never use the reserved entry id 0
+pub fn record() -> impl Strategy<Value = Record> {
+    prop_oneof![
+        (data_record(), any::<u64>(), 1..=u32::MAX).prop_map(|(data, timestamp, id)| Record::Data(data, timestamp, id)),
+        (control_record(), any::<u64>(), 1..=u32::MAX).prop_map(|(control, timestamp, id)| Record::Control(control, timestamp, id)),
+    ]
+}
+
+/// A whole log and the records it was written from
+#[derive(Debug, Clone)]
+pub struct GeneratedLog {
+    /// The encoded log, header included
+    pub bytes: Vec<u8>,
+    /// The metadata in the header
+    pub metadata: String,
+    /// The records in the order they were written
+    pub records: Vec<Record>,
+    /// The key and type string of every entry
+    pub entries: HashMap<u32, (String, String)>
+}
+
+impl GeneratedLog {
+    /// The number of values written to the entry with `key`
+    #[must_use]
+    pub fn value_count(&self, key: &str) -> usize {
+        let Some(id) = self.entries.iter().find_map(|(id, (name, _))| (name == key).then_some(*id)) else {
+            return 0;
+        };
+        self.records.iter()
+            .filter(|record| record.is_data() && record.get_id() == id)
+            .count()
+    }
+}
+
+/// Valid logs of up to `max_entries` entries with distinct keys, each started before its values
+/// and finished after them, with timestamps that never go back
+pub fn log(max_entries: usize) -> impl Strategy<Value = GeneratedLog> {
+    let entry_types = vec(0..TYPE_STRS.len(), 1..=max_entries.max(1));
+    (any::<String>(), entry_types)
+        .prop_flat_map(|(metadata, entry_types)| {
+            let values = entry_types.iter()
+                .map(|index| vec((data_record_of(*index), 0..1_000u64), 0..MAX_LEN))
+                .collect::<Vec<_>>();
+            (Just(metadata), Just(entry_types), values)
+        })
+        .prop_map(|(metadata, entry_types, values)| build_log(metadata, &entry_types, values))
+}
+
+fn build_log(metadata: String, entry_types: &[usize], values: Vec<Vec<(DataRecord, u64)>>) -> GeneratedLog {
+    let mut records = Vec::new();
+    let mut entries = HashMap::new();
+    let mut timestamp = 0u64;
+    for (index, (type_index, values)) in entry_types.iter().zip(values).enumerate() {
+        // ids are handed out counting up from 1 like a writer does
+        let id = u32::try_from(index + 1).unwrap_or(u32::MAX);
+        let name = format!("/generated/{index}");
+        let entry_type = TYPE_STRS[*type_index].to_string();
+        records.push(Record::Control(ControlRecord::Start(name.clone(), entry_type.clone(), String::new()), timestamp, id));
+        let _ = entries.insert(id, (name, entry_type));
+        for (value, step) in values {
+            timestamp += step;
+            records.push(Record::Data(value, timestamp, id));
+        }
+        records.push(Record::Control(ControlRecord::Finish, timestamp, id));
+    }
+
+    let (major, minor) = WPILOG_VERSION;
+    let mut bytes = WPILOG_MAGIC.to_vec();
+    bytes.extend_from_slice(&[minor, major]);
+    bytes.extend_from_slice(&u32::try_from(metadata.len()).unwrap_or_default().to_le_bytes());
+    bytes.extend_from_slice(metadata.as_bytes());
+    for record in &records {
+        if let Ok(encoded) = record.to_bytes() {
+            bytes.extend_from_slice(&encoded);
+        }
+    }
+    GeneratedLog {
+        bytes,
+        metadata,
+        records,
+        entries
+    }
+}
+
+/// Logs that are almost valid: a valid log cut off at any point or with one byte changed,
+/// for checking that readers fail cleanly instead of panicking
+pub fn near_valid_log(max_entries: usize) -> impl Strategy<Value = Vec<u8>> {
+    (log(max_entries), any::<prop::sample::Index>(), any::<u8>(), any::<bool>())
+        .prop_map(|(log, index, byte, truncate)| {
+            let mut bytes = log.bytes;
+            let index = index.index(bytes.len());
+            if truncate {
+                bytes.truncate(index);
+            } else {
+                bytes[index] ^= byte.max(1);
+            }
+            bytes
+        })
+}
+
+impl Arbitrary for DataRecord {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        data_record().boxed()
+    }
+}
+
+impl Arbitrary for ControlRecord {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        control_record().boxed()
+    }
+}
+
+impl Arbitrary for Record {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with((): Self::Parameters) -> Self::Strategy {
+        record().boxed()
+    }
+}
+
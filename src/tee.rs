@@ -0,0 +1,91 @@
+use std::{fmt::Debug, io::Write};
+
+struct MirroredSink {
+    sink: Box<dyn Write + Send>,
+    error: Option<std::io::Error>
+}
+
+/// A sink that writes the same log to several sinks, like a local file, a USB stick and the network
+///
+/// Every sink fails on its own, a sink that returns an error is dropped from the mirror
+/// and writing carries on to the rest. Writing only fails once every sink has failed.
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::{tee::TeeSink, DataLogWriter};
+///
+/// let sink = TeeSink::new()
+///     .with_sink(File::create("/home/lvuser/logs/match.wpilog").expect("Failed to create file"))
+///     .with_sink(File::create("/u/logs/match.wpilog").expect("Failed to create file"));
+/// let mut writer = DataLogWriter::new(sink, "").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+/// ```
+#[derive(Default)]
+pub struct TeeSink {
+    sinks: Vec<MirroredSink>
+}
+impl Debug for TeeSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TeeSink")
+            .field("sinks", &self.sinks.len())
+            .field("healthy", &self.healthy_count())
+            .finish()
+    }
+}
+
+impl TeeSink {
+    /// Creates a mirror without any sinks
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            sinks: Vec::new()
+        }
+    }
+
+    /// Adds a sink to the mirror, sinks are indexed in the order they are added
+    #[must_use]
+    pub fn with_sink(mut self, sink: impl Write + Send + 'static) -> Self {
+        self.sinks.push(MirroredSink {
+            sink: Box::new(sink),
+            error: None
+        });
+        self
+    }
+
+    /// The number of sinks that haven't failed
+    #[must_use]
+    pub fn healthy_count(&self) -> usize {
+        self.sinks.iter().filter(|sink| sink.error.is_none()).count()
+    }
+
+    /// The error that made the sink at `index` fail, [`None`] if it is healthy or doesn't exist
+    #[must_use]
+    pub fn error(&self, index: usize) -> Option<&std::io::Error> {
+        self.sinks.get(index).and_then(|sink| sink.error.as_ref())
+    }
+
+    /// Runs `op` on every healthy sink, failing only if no sink is left
+    fn mirror(&mut self, mut op: impl FnMut(&mut Box<dyn Write + Send>) -> std::io::Result<()>) -> std::io::Result<()> {
+        for mirrored in self.sinks.iter_mut().filter(|sink| sink.error.is_none()) {
+            if let Err(err) = op(&mut mirrored.sink) {
+                mirrored.error = Some(err);
+            }
+        }
+        if self.healthy_count() == 0 {
+            return Err(std::io::Error::other("Every mirrored sink has failed"));
+        }
+        Ok(())
+    }
+}
+
+impl Write for TeeSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.mirror(|sink| sink.write_all(buf))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.mirror(Write::flush)
+    }
+}
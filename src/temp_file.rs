@@ -0,0 +1,88 @@
+use std::{ffi::OsString, fs::File, io::Write, path::{Path, PathBuf}};
+
+use crate::locking::create_locked;
+
+/// A file written under a temporary name that only gets its final name once it is complete
+///
+/// The file is created as `<name>.tmp` next to its final path and moved there when persisted,
+/// the move is atomic so tools scanning the log directory never see a half-written log.
+/// A crash leaves the `.tmp` file behind to be recovered by hand.
+/// Use it through [`DataLogWriter::create_atomic`](crate::DataLogWriter::create_atomic).
+#[derive(Debug)]
+pub struct TempFileSink {
+    file: File,
+    temp_path: PathBuf,
+    final_path: PathBuf
+}
+
+impl TempFileSink {
+    /// Creates the temporary file for `final_path`, this will not overwrite an existing file
+    ///
+    /// # Errors
+    /// - an IO error with [`std::io::ErrorKind::AlreadyExists`] if the temporary or the final file exists
+    /// - an IO error if the file can't be created
+    pub fn create(final_path: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let final_path = final_path.into();
+        if final_path.exists() {
+            return Err(std::io::ErrorKind::AlreadyExists.into());
+        }
+        let mut temp_name = final_path.file_name().map(OsString::from).unwrap_or_default();
+        temp_name.push(".tmp");
+        let temp_path = final_path.with_file_name(temp_name);
+        Ok(Self {
+            file: create_locked(&temp_path)?,
+            temp_path,
+            final_path
+        })
+    }
+
+    /// The path the file is written at until it is persisted
+    #[must_use]
+    pub fn temp_path(&self) -> &Path {
+        &self.temp_path
+    }
+
+    /// The path the file gets once it is persisted
+    #[must_use]
+    pub fn final_path(&self) -> &Path {
+        &self.final_path
+    }
+
+    /// Waits for the data to reach the disk and moves the file to its final path,
+    /// this will not overwrite a file another process created there in the meantime
+    ///
+    /// The file is moved by linking it at its final path and then removing the temporary name,
+    /// which fails atomically if the final path is taken. Filesystems without hard links, like the
+    /// FAT32 of most USB sticks, fall back to checking the final path before renaming,
+    /// so a file created in between those two steps is overwritten there.
+    ///
+    /// # Errors
+    /// - an IO error with [`std::io::ErrorKind::AlreadyExists`] if the final file exists,
+    ///   the file is left at its temporary path then
+    /// - an IO error if syncing or moving the file fails
+    pub fn persist(mut self) -> std::io::Result<File> {
+        self.file.flush()?;
+        self.file.sync_all()?;
+        match std::fs::hard_link(&self.temp_path, &self.final_path) {
+            Ok(()) => std::fs::remove_file(&self.temp_path)?,
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => return Err(err),
+            Err(_) => {
+                if self.final_path.exists() {
+                    return Err(std::io::ErrorKind::AlreadyExists.into());
+                }
+                std::fs::rename(&self.temp_path, &self.final_path)?;
+            }
+        }
+        Ok(self.file)
+    }
+}
+
+impl Write for TempFileSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
@@ -2,9 +2,9 @@
 
 use std::{collections::HashMap, fs::File};
 
-use frclib_core::value::IntoFrcValue;
+use frclib_core::value::{FrcValue, IntoFrcValue};
 
-use crate::{now, proto::{entries::{get_data_type_serial, get_str_type_serial, TEST_SERIAL}, records::{DataRecord, Record}, util::UInt}, reader::{DataLogReader, DataLogReaderConfig}, writer::DataLogWriter};
+use crate::{error::DataLogError, analysis::{correlation::{cross_correlate, CrossCorrelationConfig}, power::{BrownoutEvent, PowerAnalysis, PowerAnalysisConfig}}, export::path::{read_path, write_path_csv, PoseSource}, now, proto::{entries::{get_data_type_serial, get_str_type_serial, TEST_SERIAL}, records::{encode_payload, write_encoded_data_record, DataRecord, Record}, util::UInt}, reader::{DataLogReader, DataLogReaderConfig}, units::UnitTable, writer::DataLogWriter};
 
 extern crate test;
 use test::Bencher;
@@ -29,6 +29,13 @@ fn test_record_type(payload: impl IntoFrcValue) {
         + timestamp_size as usize
         + payload_package_size as usize);
 
+    // the writer encodes payloads into reusable buffers instead of going through `DataRecord`
+    let mut payload_buffer = Vec::new();
+    encode_payload(&payload, &mut payload_buffer);
+    let mut encoded_bytes = Vec::new();
+    write_encoded_data_record(timestamp, entry_id, &payload_buffer, &mut encoded_bytes).expect("Failed to write encoded record");
+    assert_eq!(encoded_bytes, bytes);
+
     let entry_type_map = HashMap::from([(entry_id, u32::from(get_data_type_serial(&payload.get_type())))]);
     let rerecord = Record::from_binary(&bytes, &entry_type_map).expect("Failed to read record");
     assert_eq!(rerecord.is_data(), record.is_data());
@@ -77,6 +84,16 @@ fn bench_read(b: &mut Bencher) {
     b.iter(exec);
 }
 
+#[bench]
+fn bench_decode_double_array(b: &mut Bencher) {
+    use crate::proto::entries::DOUBLE_ARRAY_TYPE_SERIAL;
+
+    // a swerve module state array is small, a full pose history is not
+    let mut payload = Vec::new();
+    encode_payload(&FrcValue::DoubleArray((0..4_096).map(f64::from).collect()), &mut payload);
+    b.iter(|| DataRecord::from_binary(&payload, DOUBLE_ARRAY_TYPE_SERIAL).expect("Failed to decode array"));
+}
+
 #[test]
 fn test_write() {
     let mut writer = DataLogWriter::new(
@@ -92,5 +109,3375 @@ fn test_write() {
 
 #[test]
 fn test_type_serial() {
-    assert!(TEST_SERIAL == get_str_type_serial("test"));
-}
\ No newline at end of file
+    assert_eq!(TEST_SERIAL, get_str_type_serial("test"));
+}
+#[test]
+fn test_read_in_unit() {
+    let mut writer = DataLogWriter::new(
+        std::fs::File::create("./test_logs/test_units.wpilog").expect("Failed to create file"),
+        "test"
+    ).expect("Failed to create writer");
+
+    let entry = writer.get_entry::<f64>("distance", Some(r#"{"unit": "meters"}"#.to_string()))
+        .expect("Failed to get entry");
+    writer.write_timestamped(entry, 0.3048, now() + 10).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+
+    let reader = DataLogReader::try_new(
+        File::open("./test_logs/test_units.wpilog").expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+
+    let values = reader.read_entry_in_unit("distance", "feet", &UnitTable::default())
+        .expect("Failed to convert entry");
+    assert_eq!(values.len(), 1);
+    match values[0].value {
+        FrcValue::Double(feet) => assert!((feet - 1.0).abs() < 1e-9, "expected 1 foot, got {feet}"),
+        _ => panic!("Converted value was not a double")
+    }
+    assert!(reader.read_entry_in_unit("distance", "degrees", &UnitTable::default()).is_err());
+}
+
+#[test]
+fn test_query() {
+    let mut writer = DataLogWriter::new(
+        std::fs::File::create("./test_logs/test_query.wpilog").expect("Failed to create file"),
+        "test"
+    ).expect("Failed to create writer");
+
+    let left = writer.get_entry::<f64>("/drivetrain/left", None).expect("Failed to get entry");
+    let right = writer.get_entry::<f64>("/drivetrain/right", None).expect("Failed to get entry");
+    let arm = writer.get_entry::<f64>("/arm/angle", None).expect("Failed to get entry");
+    for (value, second) in (0u8..).zip((5..30u64).step_by(5)) {
+        let timestamp = second * 1_000_000;
+        writer.write_timestamped(left, f64::from(value), timestamp).expect("Failed to write entry");
+        writer.write_timestamped(right, 10.0, timestamp).expect("Failed to write entry");
+        writer.write_timestamped(arm, 10.0, timestamp).expect("Failed to write entry");
+    }
+    writer.flush().expect("Failed to flush");
+
+    let reader = DataLogReader::try_new(
+        File::open("./test_logs/test_query.wpilog").expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+
+    let results = reader.query("/drivetrain/* where value > 3 and t in [10s, 25s]")
+        .expect("Failed to run query");
+    let counts = results.iter()
+        .map(|(key, values)| (*key, values.len()))
+        .collect::<Vec<_>>();
+    assert_eq!(counts, vec![("/drivetrain/left", 1), ("/drivetrain/right", 4)]);
+
+    assert!(reader.query("/drivetrain/* where value >").is_err());
+    assert!(reader.query("/drivetrain/* where t in [10parsecs, 25s]").is_err());
+}
+
+#[test]
+fn test_statistics_only() {
+    let mut writer = DataLogWriter::new(
+        std::fs::File::create("./test_logs/test_statistics.wpilog").expect("Failed to create file"),
+        "test"
+    ).expect("Failed to create writer");
+
+    let entry = writer.get_entry::<f64>("voltage", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 12.0, 100).expect("Failed to write entry");
+    writer.write_timestamped(entry, 8.0, 200).expect("Failed to write entry");
+    writer.write_timestamped(entry, 10.0, 300).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+
+    let reader = DataLogReader::try_new(
+        File::open("./test_logs/test_statistics.wpilog").expect("Failed to open file"),
+        DataLogReaderConfig {
+            retain_values: false,
+            ..Default::default()
+        }
+    ).expect("Failed to create reader");
+
+    assert!(reader.read_entry("voltage").is_empty());
+    let statistics = reader.get_entry_statistics("voltage").expect("Missing statistics");
+    assert_eq!(statistics.count, 3);
+    assert_eq!(statistics.min, Some(8.0));
+    assert_eq!(statistics.max, Some(12.0));
+    assert_eq!(statistics.mean, Some(10.0));
+    assert_eq!(statistics.first_timestamp, Some(100));
+    assert_eq!(statistics.last_timestamp(), Some(300));
+
+    let reader = DataLogReader::try_new(
+        File::open("./test_logs/test_statistics.wpilog").expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+    let statistics = reader.get_entry_statistics("voltage").expect("Missing statistics");
+    assert_eq!(statistics.count, 3);
+    assert_eq!(statistics.last.as_ref().map(|last| (last.timestamp, last.value.clone())), Some((300, FrcValue::Double(10.0))));
+}
+
+#[test]
+fn test_cross_correlation_latency() {
+    let signal = |timestamp: u64| (f64::from(u32::try_from(timestamp).expect("timestamp too large")) / 150_000.0).sin();
+    let commanded = (0..2_000_000u64).step_by(1_000)
+        .map(|t| (t, signal(t)))
+        .collect::<Vec<_>>();
+    let measured = (0..2_000_000u64).step_by(1_000)
+        .map(|t| (t, signal(t.saturating_sub(40_000))))
+        .collect::<Vec<_>>();
+
+    let estimate = cross_correlate(&commanded, &measured, &CrossCorrelationConfig::default())
+        .expect("Failed to correlate");
+    assert_eq!(estimate.lag, 40_000);
+    assert!(estimate.correlation > 0.99, "correlation was {}", estimate.correlation);
+}
+
+#[test]
+fn test_power_analysis() {
+    let voltage = vec![(0, 12.0), (100_000, 6.5), (200_000, 6.0), (300_000, 12.0), (1_000_000, 12.0)];
+    let current = (0..=1_000_000u64).step_by(100_000).map(|t| (t, 10.0)).collect();
+    let analysis = PowerAnalysis::from_series(voltage, current).expect("Failed to create analysis");
+
+    let brownouts = analysis.brownouts(&PowerAnalysisConfig::default());
+    assert_eq!(brownouts, vec![BrownoutEvent { start: 100_000, end: 300_000, min_voltage: 6.0 }]);
+
+    let statistics = analysis.sag_statistics(&PowerAnalysisConfig::default());
+    assert_eq!(statistics.time_below_threshold, 200_000);
+    assert!((statistics.fraction_below_threshold - 0.2).abs() < 1e-9);
+
+    let energy = analysis.energy_per_segment(&[(300_000, 1_000_000)]);
+    assert!((energy[0] - 84.0).abs() < 1e-9, "energy was {}", energy[0]);
+}
+
+#[test]
+fn test_path_export() {
+    let mut writer = DataLogWriter::new(
+        std::fs::File::create("./test_logs/test_path.wpilog").expect("Failed to create file"),
+        "test"
+    ).expect("Failed to create writer");
+
+    let pose = writer.get_entry::<Vec<f64>>("/odometry/pose", None).expect("Failed to get entry");
+    writer.write_timestamped(pose, vec![1.0, 2.0, 0.5], 1_000_000).expect("Failed to write entry");
+    writer.write_timestamped(pose, vec![1.5, 2.5, 0.75], 1_500_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+
+    let reader = DataLogReader::try_new(
+        File::open("./test_logs/test_path.wpilog").expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+
+    let points = read_path(&reader, PoseSource::Entry("/odometry/pose")).expect("Failed to read path");
+    let mut csv = Vec::new();
+    write_path_csv(&points, &mut csv).expect("Failed to write csv");
+    assert_eq!(
+        String::from_utf8(csv).expect("csv was not utf8"),
+        "t,x,y,rotation\n1,1,2,0.5\n1.5,1.5,2.5,0.75\n"
+    );
+
+    // raw payloads are only decoded as poses by the struct type of their entry
+    let doubles = |values: &[f64]| values.iter().flat_map(|value| value.to_le_bytes()).collect::<Vec<_>>();
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let pose_2d = writer.get_entry_raw("pose2d", "struct:Pose2d", None).expect("Failed to get entry");
+    let pose_3d = writer.get_entry_raw("pose3d", "struct:Pose3d", None).expect("Failed to get entry");
+    let other = writer.get_entry_raw("other", "struct:Translation3d", None).expect("Failed to get entry");
+    writer.write_raw(pose_2d, &doubles(&[1.0, 2.0, 0.5]), 1_000).expect("Failed to write entry");
+    // half a turn of yaw, the quaternion of w, x, y and z is (0, 0, 0, 1)
+    writer.write_raw(pose_3d, &doubles(&[1.0, 2.0, 3.0, 0.0, 0.0, 0.0, 1.0]), 1_000).expect("Failed to write entry");
+    writer.write_raw(other, &doubles(&[1.0, 2.0, 3.0]), 1_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let points = read_path(&reader, PoseSource::Entry("pose2d")).expect("Failed to read path");
+    assert_eq!((points[0].x, points[0].y, points[0].rotation), (1.0, 2.0, 0.5));
+    let points = read_path(&reader, PoseSource::Entry("pose3d")).expect("Failed to read path");
+    assert_eq!((points[0].x, points[0].y), (1.0, 2.0));
+    assert!((points[0].rotation - std::f64::consts::PI).abs() < 1e-9);
+    assert!(matches!(read_path(&reader, PoseSource::Entry("other")), Err(DataLogError::NoSuchEntry)));
+}
+
+#[test]
+fn test_create_file_writer() {
+    let path = "./test_logs/test_create.wpilog";
+    let _ = std::fs::remove_file(path);
+    let mut writer = DataLogWriter::create(path, "test").expect("Failed to create writer");
+    writer.sync().expect("Failed to sync");
+    assert!(matches!(DataLogWriter::create(path, "test"), Err(DataLogError::FileAlreadyExists)));
+}
+
+#[test]
+fn test_in_memory_writer() {
+    let mut writer = DataLogWriter::new_in_memory("test").expect("Failed to create writer");
+    let entry = writer.get_entry::<bool>("enabled", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, true, 1_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "test");
+    assert_eq!(reader.read_entry("enabled").len(), 1);
+}
+
+#[cfg(feature = "tokio")]
+#[tokio::test(flavor = "current_thread")]
+async fn test_async_writer() {
+    use crate::async_writer::AsyncDataLogWriter;
+
+    let mut writer = AsyncDataLogWriter::with_buffer_size(Vec::new(), "test", 64)
+        .expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("voltage", None).expect("Failed to get entry");
+    for i in 0..100u32 {
+        writer.write_timestamped(entry, f64::from(i), u64::from(i) * 1_000).await.expect("Failed to write entry");
+    }
+    let bytes = writer.into_inner().await.expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("voltage").len(), 100);
+}
+
+#[test]
+fn test_background_writer() {
+    use crate::background_writer::BackgroundDataLogWriter;
+
+    let writer = BackgroundDataLogWriter::new(Vec::new(), "test").expect("Failed to create writer");
+    let handle = writer.handle();
+    let entry = handle.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    let worker_handle = handle.clone();
+    std::thread::spawn(move || {
+        for i in 0..100 {
+            worker_handle.append_timestamped(entry, i, 1_000 + i.unsigned_abs()).expect("Failed to queue value");
+        }
+    }).join().expect("Appending thread panicked");
+    // a value of the wrong type is reported without stopping the worker
+    handle.append_dynamic(entry.into(), FrcValue::Boolean(true).to_timestamped(2_000)).expect("Failed to queue value");
+    handle.append_timestamped(entry, 100, 3_000).expect("Failed to queue value");
+    let bytes = writer.finish().expect("Failed to finish log");
+    assert!(matches!(handle.append(entry, 0), Err(DataLogError::BackgroundWriterClosed)));
+    assert_eq!(handle.failed(), 1);
+    assert!(matches!(handle.take_error(), Some(DataLogError::EntryTypeMismatch)));
+    assert!(handle.take_error().is_none());
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 101);
+}
+
+#[test]
+fn test_auto_flush() {
+    use crate::writer::DataLogWriterConfig;
+
+    let path = "./test_logs/test_auto_flush.wpilog";
+    let config = DataLogWriterConfig {
+        auto_flush_interval: Some(std::time::Duration::ZERO),
+        ..Default::default()
+    };
+    let mut writer = DataLogWriter::with_config(File::create(path).expect("Failed to create file"), "", config)
+        .expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("voltage", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 12.5, 1_000).expect("Failed to write entry");
+
+    // the writer is still alive and was never explicitly flushed
+    let reader = DataLogReader::try_new(
+        File::open(path).expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+    assert_eq!(reader.read_entry("voltage").len(), 1);
+    drop(writer);
+}
+
+#[test]
+fn test_flush_after_records() {
+    use crate::writer::DataLogWriterConfig;
+
+    let config = DataLogWriterConfig {
+        buffer_capacity: Some(64 * 1024),
+        flush_after_records: Some(3),
+        ..Default::default()
+    };
+    let mut writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(entry, 2, 2_000).expect("Failed to write entry");
+    assert!(!writer.maybe_flush().expect("Failed to check flush"), "Flushed before the record threshold");
+    writer.write_timestamped(entry, 3, 3_000).expect("Failed to write entry");
+    // the third record flushed the buffer, so there is nothing left to flush
+    assert!(!writer.maybe_flush().expect("Failed to check flush"), "Records were left in the buffer");
+    writer.write_timestamped(entry, 4, 4_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 4);
+}
+
+#[test]
+fn test_drop_flushes() {
+    let path = "./test_logs/test_drop_flush.wpilog";
+    {
+        let mut writer = DataLogWriter::new(File::create(path).expect("Failed to create file"), "")
+            .expect("Failed to create writer");
+        let entry = writer.get_entry::<bool>("enabled", None).expect("Failed to get entry");
+        writer.write_timestamped(entry, true, 1_000).expect("Failed to write entry");
+    }
+
+    let reader = DataLogReader::try_new(
+        File::open(path).expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+    assert_eq!(reader.read_entry("enabled").len(), 1);
+}
+
+#[test]
+fn test_close_finishes_entries() {
+    use crate::proto::records::{parse_records, ControlRecord};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let first = writer.get_entry::<f64>("first", None).expect("Failed to get entry");
+    let second = writer.get_entry::<f64>("second", None).expect("Failed to get entry");
+    let third = writer.get_entry::<f64>("third", None).expect("Failed to get entry");
+    writer.write_timestamped(first, 1.0, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(second, 2.0, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(third, 3.0, 1_000).expect("Failed to write entry");
+    writer.close_entry(second.into()).expect("Failed to close entry");
+    let bytes = writer.close().expect("Failed to close writer");
+
+    // magic, version and the empty header metadata length
+    let records = parse_records(&bytes[12..], &mut HashMap::new()).expect("Failed to parse records");
+    let finished = records.iter()
+        .filter(|record| matches!(record, Record::Control(ControlRecord::Finish, _, _)))
+        .count();
+    assert_eq!(finished, 3);
+}
+
+#[test]
+fn test_rotating_writer() {
+    use crate::rotating_writer::RotatingDataLogWriter;
+
+    let path = |index: u32| std::path::PathBuf::from(format!("./test_logs/test_rotating_{index}.wpilog"));
+    for index in 0..3 {
+        let _ = std::fs::remove_file(path(index));
+    }
+    let mut writer = RotatingDataLogWriter::new(path, "bench", std::time::Duration::from_hours(1))
+        .expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.rotate().expect("Failed to rotate");
+    writer.write_timestamped(entry, 2, 2_000).expect("Failed to write entry");
+    writer.write_timestamped(entry, 3, 3_000).expect("Failed to write entry");
+    assert_eq!(writer.current_index(), 1);
+    let _ = writer.close().expect("Failed to close writer");
+
+    for (index, expected) in [(0, 1), (1, 2)] {
+        let reader = DataLogReader::try_new(
+            File::open(path(index)).expect("Failed to open file"),
+            DataLogReaderConfig::default()
+        ).expect("Failed to create reader");
+        assert_eq!(reader.get_header_metadata(), "bench");
+        assert_eq!(reader.read_entry("counter").len(), expected);
+    }
+}
+
+#[test]
+fn test_set_entry_metadata() {
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("arm/angle", Some(r#"{"unit":"deg"}"#.to_string()))
+        .expect("Failed to get entry");
+    writer.write_timestamped(entry, 90.0, 1_000).expect("Failed to write entry");
+    writer.set_entry_metadata(entry.into(), r#"{"unit":"rad"}"#).expect("Failed to set metadata");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let metadata = reader.read_entry_metadata("arm/angle");
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(metadata[metadata.len() - 1].value, r#"{"unit":"rad"}"#);
+}
+
+#[test]
+fn test_struct_schema_publishing() {
+    use frclib_core::{structure::{FrcStructDesc, FrcStructDescDB}, value::FrcType};
+
+    static POINT: FrcStructDesc = FrcStructDesc {
+        schema_supplier: || "float64 x;float64 y".to_string(),
+        type_str: "SchemaTestPoint",
+        size: 16
+    };
+    static SEGMENT: FrcStructDesc = FrcStructDesc {
+        schema_supplier: || "SchemaTestPoint start;SchemaTestPoint end".to_string(),
+        type_str: "SchemaTestSegment",
+        size: 32
+    };
+    FrcStructDescDB::add_ref(&POINT);
+    FrcStructDescDB::add_ref(&SEGMENT);
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let _ = writer.get_entry_dynamic("segment", FrcType::Struct(&SEGMENT), None).expect("Failed to get entry");
+    let _ = writer.get_entry_dynamic("other segment", FrcType::Struct(&SEGMENT), None).expect("Failed to get entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry_type_str("segment")[0].value, "struct:SchemaTestSegment");
+    for (key, schema) in [
+        ("/.schema/struct:SchemaTestPoint", "float64 x;float64 y"),
+        ("/.schema/struct:SchemaTestSegment", "SchemaTestPoint start;SchemaTestPoint end")
+    ] {
+        assert_eq!(reader.read_entry_type_str(key)[0].value, "structschema");
+        let values = reader.read_entry(key);
+        assert_eq!(values.len(), 1, "Schema {key} should be written once");
+        assert_eq!(values[0].value, FrcValue::Raw(schema.as_bytes().into()));
+    }
+}
+
+#[test]
+fn test_struct_array_writing() {
+    use frclib_core::{structure::{FrcStructDesc, FrcStructDescDB, FrcStructureBytes}, value::{FrcTimestampedValue, FrcType}};
+
+    static POINT: FrcStructDesc = FrcStructDesc {
+        schema_supplier: || "float64 x;float64 y".to_string(),
+        type_str: "ArrayTestPoint",
+        size: 16
+    };
+    FrcStructDescDB::add_ref(&POINT);
+    let points = |data: Vec<u8>| FrcValue::StructArray(Box::new(
+        FrcStructureBytes::from_parts(&POINT, data.len() / POINT.size, data.into_boxed_slice())
+    ));
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = writer.get_entry_dynamic("points", FrcType::StructArray(&POINT), None).expect("Failed to get entry");
+    // structify resolves the type of a value by timestamp, so it can't be before the entry start
+    writer.write_dynamic(entry, FrcTimestampedValue::new(now(), points(vec![1; 48]))).expect("Failed to write entry");
+    assert!(matches!(
+        writer.write_dynamic(entry, FrcTimestampedValue::new(now(), points(vec![1; 20]))),
+        Err(DataLogError::RecordSerialize(_))
+    ));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let mut reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry_type_str("points")[0].value, "struct:ArrayTestPoint[]");
+    assert_eq!(reader.read_entry("/.schema/struct:ArrayTestPoint").len(), 1);
+    reader.structify_all_data();
+    let values = reader.read_entry("points");
+    assert_eq!(values.len(), 1);
+    assert!(matches!(&values[0].value, FrcValue::StructArray(bytes) if bytes.count == 3));
+}
+
+#[test]
+fn test_struct_schema_key_taken() {
+    use frclib_core::{structure::FrcStructDesc, value::FrcType};
+
+    static POINT: FrcStructDesc = FrcStructDesc {
+        schema_supplier: || "float64 x;float64 y".to_string(),
+        type_str: "TakenSchemaPoint",
+        size: 16
+    };
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let user = writer.get_entry::<f64>("/.schema/struct:TakenSchemaPoint", None).expect("Failed to get entry");
+    assert!(matches!(
+        writer.get_entry_dynamic("point", FrcType::Struct(&POINT), None),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    writer.write_timestamped(user, 1.0, 1).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry_type_str("/.schema/struct:TakenSchemaPoint")[0].value, "double");
+    assert_eq!(reader.read_entry("/.schema/struct:TakenSchemaPoint")[0].value, FrcValue::Double(1.0));
+}
+
+#[test]
+fn test_publish_registered_schemas() {
+    use frclib_core::structure::{FrcStructDesc, FrcStructDescDB};
+    use crate::writer::DataLogWriterConfig;
+
+    static REGISTERED: FrcStructDesc = FrcStructDesc {
+        schema_supplier: || "float64 value".to_string(),
+        type_str: "RegisteredTestStruct",
+        size: 8
+    };
+    FrcStructDescDB::add_ref(&REGISTERED);
+
+    let config = DataLogWriterConfig {
+        publish_registered_schemas: true,
+        ..Default::default()
+    };
+    let writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/.schema/struct:RegisteredTestStruct").len(), 1);
+}
+
+#[test]
+fn test_log_on_change() {
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = writer.get_entry::<bool>("enabled", None).expect("Failed to get entry");
+    writer.set_log_on_change(entry.into(), true).expect("Failed to set log on change");
+    for (timestamp, value) in [(1_000, false), (2_000, false), (3_000, true), (4_000, true), (5_000, false)] {
+        writer.write_timestamped(entry, value, timestamp).expect("Failed to write entry");
+    }
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let timestamps = reader.read_entry("enabled").iter()
+        .map(|value| value.timestamp)
+        .collect::<Vec<_>>();
+    assert_eq!(timestamps, vec![1_000, 3_000, 5_000]);
+}
+
+#[test]
+fn test_shared_handle() {
+    use crate::handle::DataLogHandle;
+
+    let handle = DataLogHandle::new(DataLogWriter::new_in_memory("").expect("Failed to create writer"));
+    let threads = ["left", "right"].map(|side| {
+        let handle = handle.clone();
+        std::thread::spawn(move || {
+            let entry = handle.get_entry::<f64>(&format!("/drivetrain/{side}"), None).expect("Failed to get entry");
+            for i in 0..50u32 {
+                handle.write_timestamped(entry, f64::from(i), u64::from(i) * 20_000).expect("Failed to write entry");
+            }
+        })
+    });
+    for thread in threads {
+        thread.join().expect("Writing thread panicked");
+    }
+    let writer = handle.try_into_writer().expect("Handle was still shared");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/drivetrain/left").len(), 50);
+    assert_eq!(reader.read_entry("/drivetrain/right").len(), 50);
+}
+
+#[test]
+fn test_realtime_appender() {
+    use crate::realtime::RealtimeDataLogWriter;
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let position = writer.get_entry::<f64>("/arm/position", None).expect("Failed to get entry");
+    let setpoints = writer.get_entry::<Vec<f64>>("/arm/setpoints", None).expect("Failed to get entry");
+
+    // a buffer too small to hold all the records at once exercises wrapping and overflow
+    let (writer, mut appender) = RealtimeDataLogWriter::new(writer, 256).expect("Failed to start writer");
+    let (mut written, mut dropped) = (0u64, 0u64);
+    for i in 0..200u32 {
+        match appender.append_f64(position, f64::from(i), u64::from(i) * 1_000) {
+            Ok(()) => written += 1,
+            Err(DataLogError::BackgroundWriterFull) => dropped += 1,
+            Err(err) => panic!("Unexpected append error {err}")
+        }
+    }
+    while appender.append_f64_slice(setpoints, &[1.0, 2.0, 3.0], 300_000).is_err() {
+        dropped += 1;
+        std::thread::yield_now();
+    }
+    assert_eq!(writer.dropped_records(), dropped);
+    let bytes = writer.finish().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(u64::try_from(reader.read_entry("/arm/position").len()), Ok(written));
+    assert!(reader.read_entry("/arm/setpoints").iter()
+        .any(|value| value.value == FrcValue::DoubleArray(vec![1.0, 2.0, 3.0].into_boxed_slice())));
+
+    // entries that log on change skip repeated values appended from the real time thread too
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let position = writer.get_entry::<f64>("/arm/position", None).expect("Failed to get entry");
+    writer.set_log_on_change(position.into(), true).expect("Failed to set log on change");
+    let (writer, mut appender) = RealtimeDataLogWriter::new(writer, 1024).expect("Failed to start writer");
+    for (i, value) in [1.0, 1.0, 2.0, 2.0, 1.0].into_iter().enumerate() {
+        appender.append_f64(position, value, i as u64 * 1_000).expect("Failed to append value");
+    }
+    let bytes = writer.finish().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/arm/position").len(), 3);
+
+    // dropping the writer without finishing still writes what was appended
+    let path = "./test_logs/test_realtime_drop.wpilog";
+    let mut writer = DataLogWriter::new(File::create(path).expect("Failed to create file"), "")
+        .expect("Failed to create writer");
+    let position = writer.get_entry::<f64>("/arm/position", None).expect("Failed to get entry");
+    let (writer, mut appender) = RealtimeDataLogWriter::new(writer, 1024).expect("Failed to start writer");
+    appender.append_f64(position, 0.5, 1_000).expect("Failed to append value");
+    drop(writer);
+    assert!(matches!(appender.append_f64(position, 1.0, 2_000), Err(DataLogError::BackgroundWriterClosed)));
+    let reader = DataLogReader::try_new(File::open(path).expect("Failed to open file"), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/arm/position").len(), 1);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+fn test_write_batch() {
+    use frclib_core::value::{FrcTimestampedValue, FrcType};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let voltage = writer.get_entry::<f64>("voltage", None).expect("Failed to get entry");
+    let mode = writer.get_entry_dynamic("mode", FrcType::String, None).expect("Failed to get entry");
+    let batch = (0..100u32)
+        .map(|i| (voltage, 12.0 - f64::from(i) / 100.0, u64::from(i) * 1_000))
+        .collect::<Vec<_>>();
+    writer.write_batch(&batch).expect("Failed to write batch");
+    assert!(matches!(
+        writer.write_batch_dynamic(&[
+            (mode, FrcTimestampedValue::new(1_000, FrcValue::String("auto".into()))),
+            (mode, FrcTimestampedValue::new(2_000, FrcValue::Double(1.0)))
+        ]),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("voltage").len(), 100);
+    // the failed batch wrote nothing
+    assert!(reader.read_entry("mode").is_empty());
+}
+
+#[test]
+fn test_entry_append() {
+    use crate::handle::DataLogHandle;
+
+    let handle = DataLogHandle::new(DataLogWriter::new_in_memory("").expect("Failed to create writer"));
+    let velocity = handle.entry::<f64>("/drivetrain/velocity", None).expect("Failed to get entry");
+    velocity.append_at(1.0, 1_000).expect("Failed to append value");
+    velocity.append_at(2.0, 2_000).expect("Failed to append value");
+    assert_eq!(velocity.key(), "/drivetrain/velocity");
+    let closed = velocity.clone();
+    velocity.close().expect("Failed to close entry");
+    assert!(matches!(closed.append_at(3.0, 3_000), Err(DataLogError::OutsideEntryLifetime)));
+    drop(closed);
+
+    let bytes = handle.try_into_writer().expect("Handle was still shared")
+        .into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/drivetrain/velocity").len(), 2);
+}
+
+#[test]
+fn test_scoped_entry() {
+    use crate::{handle::DataLogHandle, proto::records::{parse_records, ControlRecord}};
+
+    let handle = DataLogHandle::new(DataLogWriter::new_in_memory("").expect("Failed to create writer"));
+    let log_diagnostics = || -> Result<(), DataLogError> {
+        let diagnostic = handle.scoped_entry::<i64>("/diagnostics/retries", None)?;
+        diagnostic.append_at(1, 1_000)?;
+        // returns early, the entry still has to be finished
+        Err(DataLogError::NoSuchEntry)
+    };
+    assert!(log_diagnostics().is_err());
+
+    let bytes = handle.try_into_writer().expect("Handle was still shared")
+        .into_inner().expect("Failed to finish log");
+    let records = parse_records(&bytes[12..], &mut HashMap::new()).expect("Failed to parse records");
+    assert!(records.iter().any(|record| matches!(record, Record::Control(ControlRecord::Finish, _, _))));
+}
+
+#[test]
+fn test_reopen_entry() {
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("/vision/latency", Some("{\"unit\":\"ms\"}".to_string()))
+        .expect("Failed to get entry");
+    writer.write_timestamped(entry, 20.0, 1_000).expect("Failed to write entry");
+    assert!(matches!(writer.reopen_entry("/vision/latency"), Err(DataLogError::EntryAlreadyExists)));
+    writer.close_entry(entry.into()).expect("Failed to close entry");
+
+    let reopened = writer.reopen_entry("/vision/latency").expect("Failed to reopen entry");
+    let typed = writer.get_entry::<f64>("/vision/latency", None).expect("Failed to get reopened entry");
+    writer.write_timestamped(typed, 25.0, 2_000).expect("Failed to write entry");
+    writer.close_entry(reopened).expect("Failed to close reopened entry");
+    assert!(matches!(writer.write_timestamped(entry, 30.0, 3_000), Err(DataLogError::OutsideEntryLifetime)));
+    assert!(matches!(writer.reopen_entry("/vision/unknown"), Err(DataLogError::NoSuchEntry)));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = reader.read_entry("/vision/latency");
+    assert_eq!(values.len(), 1, "The reader follows the latest id of a key");
+    assert!(matches!(values[0].value, FrcValue::Double(v) if (v - 25.0).abs() < f64::EPSILON));
+    assert!(reader.read_entry_metadata("/vision/latency").iter().all(|metadata| metadata.value == "{\"unit\":\"ms\"}"));
+}
+
+#[test]
+fn test_deferred_writer() {
+    use std::sync::{atomic::{AtomicBool, AtomicU64, Ordering}, Arc};
+    use crate::deferred_writer::DeferredDataLogWriter;
+
+    struct FailingSink(Arc<AtomicBool>);
+    impl std::io::Write for FailingSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0.load(Ordering::Relaxed) {
+                Err(std::io::ErrorKind::StorageFull.into())
+            } else {
+                Ok(buf.len())
+            }
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut writer = DeferredDataLogWriter::new(
+        DataLogWriter::new_in_memory("").expect("Failed to create writer")
+    );
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000);
+    writer.close_entry(entry.into());
+    writer.write_timestamped(entry, 2, 2_000);
+    assert_eq!(writer.error_count(), 1);
+    assert!(matches!(writer.take_errors().as_slice(), [DataLogError::OutsideEntryLifetime]));
+    assert!(writer.errors().is_empty());
+
+    let failing = Arc::new(AtomicBool::new(false));
+    let reported = Arc::new(AtomicU64::new(0));
+    let callback_count = Arc::clone(&reported);
+    let inner = DataLogWriter::new(FailingSink(Arc::clone(&failing)), "").expect("Failed to create writer");
+    let mut writer = DeferredDataLogWriter::with_callback(inner, move |err| {
+        assert!(matches!(err, DataLogError::Io(_)), "Expected an io error");
+        let _ = callback_count.fetch_add(1, Ordering::Relaxed);
+    });
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.flush();
+    failing.store(true, Ordering::Relaxed);
+    writer.write_timestamped(entry, 1, 1_000);
+    writer.flush();
+    assert_eq!(writer.error_count(), 1);
+    assert_eq!(reported.load(Ordering::Relaxed), 1);
+    assert!(writer.errors().is_empty());
+    failing.store(false, Ordering::Relaxed);
+}
+
+#[test]
+fn test_write_raw() {
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let frames = writer.get_entry_raw("/camera/frames", "jpeg", None).expect("Failed to get entry");
+    let _ = writer.get_entry_raw("/camera/frames", "jpeg", None).expect("Failed to get existing entry");
+    assert!(matches!(writer.get_entry_raw("/camera/frames", "png", None), Err(DataLogError::EntryTypeMismatch)));
+    assert!(matches!(writer.get_entry_raw("/camera/other", "", None), Err(DataLogError::RecordType(_))));
+    writer.write_raw(frames, &[0xFF, 0xD8, 0xFF], now()).expect("Failed to write entry");
+    let number = writer.get_entry::<f64>("/number", None).expect("Failed to get entry");
+    assert!(matches!(writer.write_raw(number.into(), &[1, 2], now()), Err(DataLogError::EntryTypeMismatch)));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let types = reader.read_entry_type_str("/camera/frames");
+    assert_eq!(types[0].value, "jpeg");
+    let values = reader.read_entry("/camera/frames");
+    assert!(matches!(&values[0].value, FrcValue::Raw(bytes) if bytes.as_ref() == [0xFF, 0xD8, 0xFF]));
+}
+
+#[test]
+fn test_write_record() {
+    use crate::proto::records::parse_records;
+
+    let mut source = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = source.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    for value in 0..3 {
+        source.write_timestamped(entry, value, now()).expect("Failed to write entry");
+    }
+    let bytes = source.into_inner().expect("Failed to finish log");
+    let records = parse_records(&bytes[12..], &mut HashMap::new()).expect("Failed to parse records");
+
+    let mut copy = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    for record in records.clone() {
+        copy.write_record(record).expect("Failed to write record");
+    }
+    let copied = copy.into_inner().expect("Failed to finish log");
+    assert_eq!(copied, bytes);
+
+    let reader = DataLogReader::try_new(copied.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 3);
+
+    // a paused writer drops the data records but still starts the entry
+    let mut paused = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    paused.pause();
+    for record in records {
+        paused.write_record(record).expect("Failed to write record");
+    }
+    let bytes = paused.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert!(reader.get_all_entry_keys().iter().any(|key| *key == "counter"));
+    assert!(reader.read_entry("counter").is_empty());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_compressed_writing() {
+    let mut writer = DataLogWriter::new_compressed(Vec::new(), "compressed", 0)
+        .expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("/drive/velocity", None).expect("Failed to get entry");
+    for index in 0..1_000 {
+        writer.write_timestamped(entry, 1.5, 1_000 + index).expect("Failed to write entry");
+    }
+    writer.flush().expect("Failed to flush");
+    let bytes = writer.close_compressed().expect("Failed to finish log");
+    assert!(bytes.len() < 1_000 * 8, "Expected the log to be compressed");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "compressed");
+    assert_eq!(reader.read_entry("/drive/velocity").len(), 1_000);
+
+    // a log cut off before the stream was finished keeps everything that was flushed
+    let mut writer = DataLogWriter::new_compressed(Vec::new(), "", 0).expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    let truncated = writer.into_inner().expect("Failed to take sink").get_ref().clone();
+    let reader = DataLogReader::try_new(truncated.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 1);
+}
+
+#[cfg(feature = "disk-watchdog")]
+#[test]
+fn test_disk_watchdog() {
+    use std::sync::{atomic::{AtomicU64, Ordering}, Arc};
+    use crate::{disk_watchdog::{DiskSpaceWatchdog, LowDiskSpaceAction}, rotating_writer::RotatingDataLogWriter};
+
+    let path = |name: &'static str| move |index: u32| std::path::PathBuf::from(format!("./test_logs/test_watchdog_{name}_{index}.wpilog"));
+    for name in ["stop", "callback"] {
+        for index in 0..2 {
+            let _ = std::fs::remove_file(path(name)(index));
+        }
+    }
+
+    let mut watchdog = DiskSpaceWatchdog::new("./test_logs", 0, LowDiskSpaceAction::StopLogging);
+    assert!(watchdog.available_space().expect("Failed to query disk space") > 0);
+    assert_eq!(watchdog.poll().expect("Failed to poll watchdog"), None);
+
+    // no filesystem has u64::MAX bytes free so the watchdog always trips
+    let watchdog = DiskSpaceWatchdog::new("./test_logs", u64::MAX, LowDiskSpaceAction::StopLogging);
+    let mut writer = RotatingDataLogWriter::new(path("stop"), "", std::time::Duration::from_hours(1))
+        .expect("Failed to create writer")
+        .with_disk_watchdog(watchdog);
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    assert!(writer.is_stopped());
+    let _ = writer.close().expect("Failed to close writer");
+    let reader = DataLogReader::try_new(
+        File::open(path("stop")(0)).expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+    assert!(reader.read_entry("counter").is_empty());
+
+    let calls = Arc::new(AtomicU64::new(0));
+    let counter = Arc::clone(&calls);
+    let watchdog = DiskSpaceWatchdog::new("./test_logs", u64::MAX, LowDiskSpaceAction::Callback(Box::new(move |_| {
+        let _ = counter.fetch_add(1, Ordering::Relaxed);
+    }))).with_check_interval(std::time::Duration::ZERO);
+    let mut writer = RotatingDataLogWriter::new(path("callback"), "", std::time::Duration::from_hours(1))
+        .expect("Failed to create writer")
+        .with_disk_watchdog(watchdog);
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(entry, 2, 2_000).expect("Failed to write entry");
+    assert!(!writer.is_stopped());
+    assert_eq!(calls.load(Ordering::Relaxed), 1, "The callback should only run when the space drops");
+    let _ = writer.close().expect("Failed to close writer");
+}
+
+#[test]
+fn test_tcp_sink() {
+    use std::{io::Read, net::TcpListener};
+    use crate::network::TcpSink;
+
+    // find a free port, then close it so the sink starts out disconnected
+    let address = TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener")
+        .local_addr().expect("Failed to get address");
+    let sink = TcpSink::new(address).with_reconnect_interval(std::time::Duration::ZERO);
+    let mut writer = DataLogWriter::new(sink, "streamed").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, now()).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+
+    let listener = TcpListener::bind(address).expect("Failed to bind listener");
+    writer.write_timestamped(entry, 2, now()).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    let sink = writer.close().expect("Failed to close writer");
+    assert!(sink.is_connected());
+    assert!(!sink.is_lost());
+    assert_eq!(sink.spilled_len(), 0);
+    drop(sink);
+
+    let (mut stream, _) = listener.accept().expect("Failed to accept connection");
+    let mut bytes = Vec::new();
+    let _ = stream.read_to_end(&mut bytes).expect("Failed to read stream");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "streamed");
+    assert_eq!(reader.read_entry("counter").len(), 2);
+
+    // a new connection after the receiver drops out gets a log it can read on its own
+    let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind listener");
+    let sink = TcpSink::new(listener.local_addr().expect("Failed to get address"))
+        .with_reconnect_interval(std::time::Duration::ZERO);
+    let mut writer = DataLogWriter::new(sink, "streamed").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", Some("{\"unit\": \"count\"}".to_string())).expect("Failed to get entry");
+    writer.write_timestamped(entry, 0, now()).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    drop(listener.accept().expect("Failed to accept connection"));
+    for value in 1..=50 {
+        writer.write_timestamped(entry, value, now()).expect("Failed to write entry");
+        writer.flush().expect("Failed to flush");
+        std::thread::sleep(std::time::Duration::from_millis(2));
+    }
+    let sink = writer.close().expect("Failed to close writer");
+    assert!(!sink.is_lost());
+    drop(sink);
+
+    let (mut stream, _) = listener.accept().expect("Failed to accept connection");
+    let mut bytes = Vec::new();
+    let _ = stream.read_to_end(&mut bytes).expect("Failed to read stream");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "streamed");
+    assert_eq!(reader.read_entry_metadata("counter").last().map(|metadata| metadata.value.as_str()), Some("{\"unit\": \"count\"}"));
+    assert_eq!(reader.read_entry("counter").last().map(|value| value.value.clone()), Some(FrcValue::Int(50)));
+}
+
+#[test]
+fn test_tee_sink() {
+    use std::sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex};
+    use crate::tee::TeeSink;
+
+    #[derive(Clone)]
+    struct SharedSink(Arc<Mutex<Vec<u8>>>, Arc<AtomicBool>);
+    impl std::io::Write for SharedSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.1.load(Ordering::Relaxed) {
+                return Err(std::io::ErrorKind::NotConnected.into());
+            }
+            self.0.lock().expect("Sink lock poisoned").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let local = SharedSink(Arc::default(), Arc::default());
+    let usb = SharedSink(Arc::default(), Arc::default());
+    let sink = TeeSink::new().with_sink(local.clone()).with_sink(usb.clone());
+    let mut writer = DataLogWriter::new(sink, "").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, now()).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+
+    // pulling the usb stick doesn't stop the local log
+    usb.1.store(true, Ordering::Relaxed);
+    writer.write_timestamped(entry, 2, now()).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    let sink = writer.close().expect("Failed to close writer");
+    assert_eq!(sink.healthy_count(), 1);
+    assert!(sink.error(0).is_none());
+    assert!(sink.error(1).is_some_and(|err| err.kind() == std::io::ErrorKind::NotConnected));
+
+    let read = |sink: &SharedSink| {
+        let bytes = sink.0.lock().expect("Sink lock poisoned").clone();
+        DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+            .expect("Failed to create reader")
+            .read_entry("counter")
+            .len()
+    };
+    assert_eq!(read(&local), 2);
+    assert_eq!(read(&usb), 1);
+}
+
+#[test]
+fn test_time_base_rebasing() {
+    use crate::time_sync::RebasingSink;
+
+    let mut writer = DataLogWriter::new(RebasingSink::new(Vec::new()), "").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, now()).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    assert!(writer.set_time_base(1_000_000_000).expect("Failed to set time base"));
+    assert!(!writer.set_time_base(5).expect("Failed to set time base"), "The time base can only be set once");
+    writer.write_timestamped(entry, 2, now()).expect("Failed to write entry");
+    let sink = writer.close().expect("Failed to close writer");
+    assert_eq!(sink.offset(), Some(1_000_000_000));
+    let bytes = sink.finish().expect("Failed to finish sink");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = reader.read_entry("counter");
+    assert_eq!(values.len(), 2);
+    assert!(values.iter().all(|value| value.timestamp > 1_000_000_000));
+
+    // a sink that can't hold anything passes records through without an offset
+    let mut writer = DataLogWriter::new(RebasingSink::new(Vec::new()).with_max_held(0), "")
+        .expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    assert!(!writer.set_time_base(1_000_000_000).expect("Failed to set time base"));
+    let bytes = writer.close().expect("Failed to close writer").finish().expect("Failed to finish sink");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter")[0].timestamp, 1_000);
+}
+
+#[test]
+fn test_out_of_order_policy() {
+    use crate::writer::{DataLogWriterConfig, OutOfOrderPolicy};
+
+    let write_log = |policy: OutOfOrderPolicy| {
+        let config = DataLogWriterConfig {
+            out_of_order_policy: policy,
+            ..Default::default()
+        };
+        let mut writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+        let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+        let other = writer.get_entry::<i64>("other", None).expect("Failed to get entry");
+        writer.write_timestamped(entry, 1, 2_000).expect("Failed to write entry");
+        writer.write_timestamped(other, 1, 3_000).expect("Failed to write entry");
+        let result = writer.write_timestamped(entry, 2, 1_000);
+        assert_eq!(writer.out_of_order_count(), 1);
+        let bytes = writer.into_inner().expect("Failed to finish log");
+        let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+            .expect("Failed to create reader");
+        let timestamps = reader.read_entry("counter").iter().map(|value| value.timestamp).collect::<Vec<_>>();
+        (result, timestamps)
+    };
+
+    let (result, timestamps) = write_log(OutOfOrderPolicy::Allow);
+    assert!(result.is_ok());
+    assert_eq!(timestamps, [1_000, 2_000]);
+    let (result, timestamps) = write_log(OutOfOrderPolicy::Clamp);
+    assert!(result.is_ok());
+    assert_eq!(timestamps, [2_000, 2_000]);
+    let (result, timestamps) = write_log(OutOfOrderPolicy::Reject);
+    assert!(matches!(result, Err(DataLogError::OutOfOrderTimestamp)));
+    assert_eq!(timestamps, [2_000]);
+}
+
+#[test]
+fn test_pause_resume() {
+    use crate::background_writer::BackgroundDataLogWriter;
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.pause();
+    assert!(writer.is_paused());
+    writer.write_timestamped(entry, 2, 2_000).expect("Failed to write entry");
+    writer.write_batch(&[(entry, 3, 3_000)]).expect("Failed to write batch");
+    writer.resume();
+    writer.write_timestamped(entry, 4, 4_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 2);
+
+    let writer = BackgroundDataLogWriter::new(Vec::new(), "").expect("Failed to create writer");
+    let handle = writer.handle();
+    let entry = handle.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    handle.pause().expect("Failed to queue pause");
+    handle.append_timestamped(entry, 1, 1_000).expect("Failed to queue value");
+    handle.resume().expect("Failed to queue resume");
+    handle.append_timestamped(entry, 2, 2_000).expect("Failed to queue value");
+    let bytes = writer.finish().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 1);
+}
+
+#[test]
+fn test_armable_sink() {
+    use frclib_core::{structure::FrcStructDesc, value::FrcType};
+    use crate::arming::ArmableSink;
+
+    static POINT: FrcStructDesc = FrcStructDesc {
+        schema_supplier: || "float64 x;float64 y".to_string(),
+        type_str: "ArmingTestPoint",
+        size: 16
+    };
+
+    let mut writer = DataLogWriter::new(ArmableSink::new(Vec::new(), 64), "").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    let _ = writer.get_entry_dynamic("point", FrcType::Struct(&POINT), None)
+        .expect("Failed to get struct entry");
+    for value in 0..100 {
+        writer.write_timestamped(entry, value, now()).expect("Failed to write entry");
+    }
+    writer.arm().expect("Failed to arm writer");
+    writer.write_timestamped(entry, 100, now()).expect("Failed to write entry");
+    let sink = writer.close().expect("Failed to close writer");
+    assert!(sink.is_armed());
+    assert!(sink.dropped_records() > 0);
+
+    let reader = DataLogReader::try_new(sink.get_ref().as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = reader.read_entry("counter");
+    assert_eq!(values.len() as u64 + sink.dropped_records(), 101);
+    assert!(matches!(values[values.len() - 1].value, FrcValue::Int(100)));
+    assert_eq!(reader.read_entry("/.schema/struct:ArmingTestPoint").len(), 1);
+}
+
+#[test]
+fn test_frc_naming() {
+    use std::time::{Duration, UNIX_EPOCH};
+    use crate::frc_naming::{FrcNamedDataLogWriter, MatchInfo, MatchType};
+
+    let dir = std::path::PathBuf::from("./test_logs/frc_naming");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Failed to create directory");
+
+    let mut first = FrcNamedDataLogWriter::create(&dir, "").expect("Failed to create writer");
+    let second = FrcNamedDataLogWriter::create(&dir, "").expect("Failed to create writer");
+    assert_eq!(first.path(), dir.join("FRC_TBD_0.wpilog"));
+    assert_eq!(second.path(), dir.join("FRC_TBD_1.wpilog"));
+
+    let entry = first.writer_mut().get_entry::<i64>("counter", None).expect("Failed to get entry");
+    first.writer_mut().write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    let info = MatchInfo { event_name: "CASJ".to_string(), match_type: MatchType::Elimination, match_number: 3 };
+    assert!(!first.set_match_info(info.clone()).expect("Failed to set match info"));
+    assert!(!first.is_named());
+
+    // 2000-02-29 23:59:59 UTC
+    let start = UNIX_EPOCH + Duration::from_secs(951_868_799);
+    assert!(first.set_start_time(start).expect("Failed to rename log"));
+    assert!(!first.set_start_time(UNIX_EPOCH).expect("Failed to rename log"));
+    assert!(!first.set_match_info(info).expect("Failed to set match info"));
+    let path = dir.join("FRC_20000229_235959_CASJ_E3.wpilog");
+    assert_eq!(first.path(), path);
+    assert!(!dir.join("FRC_TBD_0.wpilog").exists());
+
+    first.writer_mut().write_timestamped(entry, 2, 2_000).expect("Failed to write entry");
+    let _ = first.into_inner().close().expect("Failed to close writer");
+    let reader = DataLogReader::try_new(
+        File::open(path).expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 2);
+}
+
+#[test]
+fn test_log_dir_manager() {
+    use std::time::{Duration, SystemTime};
+    use crate::log_dir::LogDirManager;
+
+    let dir = std::path::PathBuf::from("./test_logs/log_dir");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("Failed to create directory");
+    std::fs::write(dir.join("notes.txt"), "not a log").expect("Failed to write file");
+
+    let manager = LogDirManager::new(&dir).with_max_logs(3);
+    let now = SystemTime::now();
+    for index in 0..5u64 {
+        let writer = manager.create_writer(format!("log_{index}.wpilog"), "").expect("Failed to create writer");
+        let file = writer.close().expect("Failed to close writer");
+        // spread out the modification times so the order doesn't depend on the filesystem
+        file.set_modified(now - Duration::from_hours(10 - index)).expect("Failed to set modified time");
+    }
+    let names = |manager: &LogDirManager| manager.logs().expect("Failed to list logs")
+        .into_iter()
+        .map(|log| log.path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(names(&manager), ["log_2.wpilog", "log_3.wpilog", "log_4.wpilog"]);
+    assert!(manager.enforce().expect("Failed to enforce limits").is_empty());
+
+    let manager = LogDirManager::new(&dir).with_max_age(Duration::from_mins(7 * 60 + 30));
+    let deleted = manager.enforce().expect("Failed to enforce limits");
+    assert_eq!(deleted.len(), 1);
+    assert_eq!(names(&manager), ["log_3.wpilog", "log_4.wpilog"]);
+
+    // the newest log is kept even if it alone is over the limit
+    let manager = LogDirManager::new(&dir).with_max_total_size(0);
+    let _ = manager.enforce().expect("Failed to enforce limits");
+    assert_eq!(names(&manager), ["log_4.wpilog"]);
+    assert!(dir.join("notes.txt").exists());
+}
+
+#[test]
+#[cfg(feature = "networktables")]
+fn test_nt_bridge() {
+    use std::collections::VecDeque;
+    use frclib_core::value::FrcTimestampedValue;
+    use crate::nt_bridge::{NetworkTablesConnector, NetworkTablesRecorder, TopicUpdate};
+
+    struct QueuedUpdates(VecDeque<TopicUpdate>);
+    impl NetworkTablesConnector for QueuedUpdates {
+        fn next_update(&mut self) -> Option<TopicUpdate> {
+            self.0.pop_front()
+        }
+    }
+
+    let update = |topic: &str, value: FrcValue, timestamp: u64| TopicUpdate {
+        topic: topic.to_string(),
+        value: FrcTimestampedValue { timestamp, value },
+        properties: Some("{\"retained\":true}".to_string())
+    };
+    let mut recorder = NetworkTablesRecorder::new(QueuedUpdates(VecDeque::from([
+        update("/SmartDashboard/speed", FrcValue::Double(1.5), 1_000),
+        update("/SmartDashboard/speed", FrcValue::Double(2.5), 2_000),
+        update("/SmartDashboard/speed", FrcValue::Boolean(true), 3_000),
+        update("/FMSInfo/MatchNumber", FrcValue::Int(12), 3_000)
+    ])));
+
+    let mut writer = DataLogWriter::new(Vec::new(), "").expect("Failed to create writer");
+    assert_eq!(recorder.record(&mut writer).expect("Failed to record updates"), 3);
+    assert_eq!(recorder.record(&mut writer).expect("Failed to record updates"), 0);
+    assert_eq!(recorder.dropped_updates(), 1);
+
+    let bytes = writer.close().expect("Failed to close writer");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("NT:/SmartDashboard/speed").len(), 2);
+    assert_eq!(reader.read_entry("NT:/FMSInfo/MatchNumber").len(), 1);
+}
+
+#[test]
+fn test_format_version() {
+    use crate::writer::{DataLogWriterConfig, WPILOG_VERSION};
+
+    let write_log = |format_version: Option<(u8, u8)>| {
+        let config = DataLogWriterConfig {
+            format_version,
+            ..Default::default()
+        };
+        DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer")
+            .close().expect("Failed to close writer")
+    };
+
+    let bytes = write_log(None);
+    // wpiutil stores the version as a little endian u16, 0x0100 for 1.0
+    assert_eq!(bytes[6..8], [0, 1]);
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_format_version(), WPILOG_VERSION);
+
+    let reader = DataLogReader::try_new(write_log(Some((1, 1))).as_slice(), DataLogReaderConfig::default())
+        .expect("A newer minor version should be readable");
+    assert_eq!(reader.get_format_version(), (1, 1));
+
+    for version in [(2, 0), (0, 1)] {
+        let result = DataLogReader::try_new(write_log(Some(version)).as_slice(), DataLogReaderConfig::default());
+        assert!(matches!(result, Err(DataLogError::VersionMismatch)), "{version:?} should be rejected");
+    }
+}
+
+#[test]
+#[cfg(feature = "integrity")]
+fn test_integrity_checkpoints() {
+    use crate::integrity::{verify_checkpoints, IntegritySink, CHECKPOINT_ENTRY_ID, CHECKPOINT_KEY};
+
+    let mut writer = DataLogWriter::new(IntegritySink::new(Vec::new()).with_interval(64), "")
+        .expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    for value in 0..20 {
+        writer.write_timestamped(entry, value, 1_000 * value.unsigned_abs()).expect("Failed to write entry");
+    }
+    writer.write_checkpoint().expect("Failed to write checkpoint");
+    writer.write_timestamped(entry, 20, 20_000).expect("Failed to write entry");
+    // the checkpoint id can't be taken by an entry or a copied record
+    assert!(matches!(
+        writer.get_entry_with_id::<f64>("other", CHECKPOINT_ENTRY_ID, None),
+        Err(DataLogError::EntryAlreadyExists)
+    ));
+    assert!(matches!(
+        writer.write_record(Record::Data(DataRecord::Double(1.5), 21_000, CHECKPOINT_ENTRY_ID)),
+        Err(DataLogError::EntryAlreadyExists)
+    ));
+    let sink = writer.close().expect("Failed to close writer");
+    let checkpoints = sink.checkpoints();
+    let bytes = sink.finish().expect("Failed to finish log");
+
+    let report = verify_checkpoints(&bytes).expect("Failed to verify log");
+    assert!(report.is_intact());
+    // finishing adds one for the last value
+    assert_eq!(report.passed as u64, checkpoints + 1);
+    assert!(report.passed > 2);
+    assert_eq!(report.unchecked_bytes, 0);
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 21);
+    assert_eq!(reader.read_entry(CHECKPOINT_KEY).len(), report.passed);
+
+    // rewriting a log leaves out its checkpoints, they don't cover the rewritten bytes
+    let trimmed = crate::rewrite::trim(&bytes, 0, 5_000, Vec::new()).expect("Failed to trim log");
+    let reader = DataLogReader::try_new(trimmed.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 6);
+    assert!(reader.read_entry(CHECKPOINT_KEY).is_empty());
+
+    // flip a bit in the last value, right before the 20 byte final checkpoint
+    let mut corrupted = bytes.clone();
+    let index = bytes.len() - 24;
+    corrupted[index] ^= 1;
+    let report = verify_checkpoints(&corrupted).expect("Failed to verify log");
+    assert!(!report.is_intact());
+}
+
+#[test]
+fn test_atomic_finalize() {
+    let path = std::path::PathBuf::from("./test_logs/test_atomic.wpilog");
+    let temp_path = std::path::PathBuf::from("./test_logs/test_atomic.wpilog.tmp");
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&temp_path);
+
+    let mut writer = DataLogWriter::create_atomic(&path, "").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush writer");
+    assert!(temp_path.exists());
+    assert!(!path.exists());
+    assert!(matches!(DataLogWriter::create_atomic(&path, ""), Err(DataLogError::FileAlreadyExists)));
+
+    let _ = writer.close_atomic().expect("Failed to close writer");
+    assert!(!temp_path.exists());
+    let reader = DataLogReader::try_new(
+        File::open(&path).expect("Failed to open file"),
+        DataLogReaderConfig::default()
+    ).expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 1);
+    assert!(matches!(DataLogWriter::create_atomic(&path, ""), Err(DataLogError::FileAlreadyExists)));
+
+    // a file created at the final path while writing isn't overwritten
+    let _ = std::fs::remove_file(&path);
+    let writer = DataLogWriter::create_atomic(&path, "").expect("Failed to create writer");
+    std::fs::write(&path, b"other").expect("Failed to write file");
+    assert!(matches!(
+        writer.close_atomic(),
+        Err(DataLogError::Io(err)) if err.kind() == std::io::ErrorKind::AlreadyExists
+    ));
+    assert!(temp_path.exists());
+    assert_eq!(std::fs::read(&path).expect("Failed to read file"), b"other");
+}
+
+#[test]
+fn test_recovering_writer() {
+    use std::sync::{atomic::{AtomicBool, Ordering}, Arc, Mutex};
+    use crate::recovery::RecoveringDataLogWriter;
+
+    #[derive(Clone)]
+    struct SharedSink {
+        bytes: Arc<Mutex<Vec<u8>>>,
+        failing: Arc<AtomicBool>
+    }
+    impl std::io::Write for SharedSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.failing.load(Ordering::Relaxed) {
+                return Err(std::io::ErrorKind::ReadOnlyFilesystem.into());
+            }
+            self.bytes.lock().expect("Sink lock poisoned").extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            if self.failing.load(Ordering::Relaxed) {
+                return Err(std::io::ErrorKind::ReadOnlyFilesystem.into());
+            }
+            Ok(())
+        }
+    }
+
+    let failing = Arc::new(AtomicBool::new(false));
+    let first = SharedSink { bytes: Arc::default(), failing: Arc::clone(&failing) };
+    let second = SharedSink { bytes: Arc::default(), failing: Arc::new(AtomicBool::new(false)) };
+    let replacement = second.clone();
+    let writer = DataLogWriter::new(first.clone(), "robot").expect("Failed to create writer");
+    let mut writer = RecoveringDataLogWriter::new(writer, "robot", move |_| Ok(replacement.clone()));
+
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush writer");
+    failing.store(true, Ordering::Relaxed);
+    writer.write_timestamped(entry, 2, 2_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to recover");
+    assert_eq!(writer.recoveries(), 1);
+    writer.write_timestamped(entry, 3, 3_000).expect("Failed to write entry");
+    let _ = writer.close().expect("Failed to close writer");
+
+    let read = |sink: &SharedSink| {
+        let bytes = sink.bytes.lock().expect("Sink lock poisoned").clone();
+        DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default()).expect("Failed to create reader")
+    };
+    assert_eq!(read(&first).read_entry("counter").len(), 1);
+    let recovered = read(&second);
+    assert_eq!(recovered.get_header_metadata(), "robot");
+    // the second value was buffered when the first sink failed
+    assert_eq!(recovered.read_entry("counter").len(), 1);
+}
+
+#[test]
+fn test_session_metadata() {
+    use crate::{frc_naming::{MatchInfo, MatchType}, session::SessionMetadata};
+
+    let metadata = SessionMetadata::new()
+        .with_team_number(1234)
+        .with_match_info(MatchInfo { event_name: "CASJ".to_string(), match_type: MatchType::Qualification, match_number: 42 })
+        .with_robot_name("Comp Bot")
+        .with_code_version("a1b2c3d")
+        .with_field("alliance", "red");
+    let writer = DataLogWriter::new(Vec::new(), &metadata).expect("Failed to create writer");
+    let bytes = writer.close().expect("Failed to close writer");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+
+    let header: serde_json::Value = serde_json::from_str(reader.get_header_metadata()).expect("Header isn't json");
+    assert_eq!(header["team"], 1234);
+    assert_eq!(header["match_type"], "Qualification");
+    assert_eq!(header["alliance"], "red");
+    assert_eq!(SessionMetadata::parse(reader.get_header_metadata()), Some(metadata));
+    assert_eq!(SessionMetadata::parse("not json"), None);
+}
+
+#[test]
+fn test_write_series() {
+    use frclib_core::value::FrcTimestampedValue;
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    // enough values to be written in several chunks
+    let series = (0..20_000u32).map(|i| FrcTimestampedValue::new(u64::from(i) * 1_000, FrcValue::Double(f64::from(i))));
+    let _ = writer.write_series("position", series).expect("Failed to write series");
+    let _ = writer.write_series("position", [FrcTimestampedValue::new(20_000_000, FrcValue::Double(-1.0))])
+        .expect("Failed to write series");
+    assert!(matches!(
+        writer.write_series("empty", std::iter::empty()),
+        Err(DataLogError::RecordType(_))
+    ));
+    assert!(matches!(
+        writer.write_series("flags", [
+            FrcTimestampedValue::new(1_000, FrcValue::Boolean(true)),
+            FrcTimestampedValue::new(2_000, FrcValue::Double(1.0))
+        ]),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let position = reader.read_entry("position");
+    assert_eq!(position.len(), 20_001);
+    assert_eq!(position[19_999].value, FrcValue::Double(19_999.0));
+    // the value before the mismatch is still written
+    assert_eq!(reader.read_entry("flags").len(), 1);
+}
+
+#[test]
+fn test_entry_id_assignment() {
+    use crate::writer::EntryId;
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    writer.reserve_entry_ids(1..=3).expect("Failed to reserve ids");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    assert_eq!(EntryId::from(speed).entry_id(), 4);
+
+    let angle = writer.get_entry_with_id::<f64>("angle", 2, None).expect("Failed to assign id");
+    assert_eq!(EntryId::from(angle).entry_id(), 2);
+    let far = writer.get_entry_with_id::<bool>("far", 100, None).expect("Failed to assign id");
+    assert_eq!(EntryId::from(far).entry_id(), 100);
+    // existing entries are returned as long as the id matches
+    assert!(writer.get_entry_with_id::<f64>("angle", 2, None).is_ok());
+    assert!(matches!(writer.get_entry_with_id::<f64>("angle", 3, None), Err(DataLogError::EntryAlreadyExists)));
+    assert!(matches!(writer.get_entry_with_id::<f64>("other", 4, None), Err(DataLogError::EntryAlreadyExists)));
+    assert!(matches!(writer.get_entry_with_id::<f64>("other", 0, None), Err(DataLogError::EntryAlreadyExists)));
+    assert!(matches!(writer.reserve_entry_ids([5, 100]), Err(DataLogError::EntryAlreadyExists)));
+    // 1 and 3 are still reserved
+    let next = writer.get_entry::<f64>("next", None).expect("Failed to get entry");
+    assert_eq!(EntryId::from(next).entry_id(), 5);
+
+    writer.write(angle, 1.5).expect("Failed to write");
+    writer.write(far, true).expect("Failed to write");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("angle")[0].value, FrcValue::Double(1.5));
+    assert_eq!(reader.read_entry("far")[0].value, FrcValue::Boolean(true));
+}
+
+#[test]
+fn test_loggable() {
+    use crate::impl_loggable;
+
+    struct Wrist {
+        angle: f64
+    }
+    impl_loggable!(Wrist { angle });
+
+    struct Arm {
+        extension: f64,
+        stalled: bool,
+        wrist: Wrist
+    }
+    impl_loggable!(Arm { extension, stalled, wrist });
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let mut arm = Arm { extension: 0.25, stalled: false, wrist: Wrist { angle: 1.0 } };
+    writer.put_timestamped("/arm", &arm, 1_000).expect("Failed to log arm");
+    arm.stalled = true;
+    writer.put_timestamped("/arm", &arm, 2_000).expect("Failed to log arm");
+    writer.put("/arm/speed", &3.5).expect("Failed to log value");
+    assert!(matches!(writer.put("/arm/stalled", &1.0), Err(DataLogError::EntryTypeMismatch)));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/arm/extension").len(), 2);
+    let stalled = reader.read_entry("/arm/stalled");
+    assert_eq!(stalled[1].value, FrcValue::Boolean(true));
+    assert_eq!(reader.read_entry("/arm/wrist/angle")[0].value, FrcValue::Double(1.0));
+    assert_eq!(reader.read_entry("/arm/speed")[0].value, FrcValue::Double(3.5));
+}
+
+#[test]
+fn test_scoped_writer() {
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    {
+        let mut drivetrain = writer.scoped("/drivetrain/");
+        assert_eq!(drivetrain.full_key("velocity"), "/drivetrain/velocity");
+        let velocity = drivetrain.get_entry::<f64>("velocity", None).expect("Failed to get entry");
+        drivetrain.write_timestamped(velocity, 1.5, 1_000).expect("Failed to write");
+        let mut left = drivetrain.scoped("left");
+        left.put_timestamped("/current", &20.0, 1_000).expect("Failed to log value");
+    }
+    // the same entry as the scoped one
+    let velocity = writer.get_entry::<f64>("/drivetrain/velocity", None).expect("Failed to get entry");
+    writer.write_timestamped(velocity, 2.5, 2_000).expect("Failed to write");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/drivetrain/velocity").len(), 2);
+    assert_eq!(reader.read_entry("/drivetrain/left/current")[0].value, FrcValue::Double(20.0));
+}
+
+#[cfg(feature = "tracing")]
+#[test]
+fn test_tracing_layer() {
+    use tracing_subscriber::layer::SubscriberExt;
+    use crate::{handle::DataLogHandle, tracing_layer::DataLogLayer};
+
+    let handle = DataLogHandle::new(DataLogWriter::new_in_memory("").expect("Failed to create writer"));
+    let subscriber = tracing_subscriber::registry().with(DataLogLayer::new(handle.clone()));
+    tracing::subscriber::with_default(subscriber, || {
+        let span = tracing::info_span!("auto", routine = "two_piece");
+        let _guard = span.enter();
+        tracing::warn!(target: "robot::arm", voltage = 7.5, stalled = true, "Arm stalled");
+        tracing::info!(target: "robot::arm", voltage = "unknown");
+    });
+    let bytes = handle.try_into_writer().expect("Layer still holds the handle")
+        .into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let levels = reader.read_entry("/tracing/robot/arm/level");
+    assert_eq!(levels.len(), 2);
+    assert_eq!(levels[0].value, FrcValue::String("WARN".into()));
+    assert_eq!(reader.read_entry("/tracing/robot/arm/message")[0].value, FrcValue::String("Arm stalled".into()));
+    // the second voltage has another type and is dropped
+    assert_eq!(reader.read_entry("/tracing/robot/arm/voltage").len(), 1);
+    assert_eq!(reader.read_entry("/tracing/robot/arm/stalled")[0].value, FrcValue::Boolean(true));
+    assert_eq!(reader.read_entry("/tracing/spans/auto/routine")[0].value, FrcValue::String("two_piece".into()));
+    assert_eq!(reader.read_entry("/tracing/spans/auto/active").len(), 2);
+}
+
+#[test]
+fn test_console_messages() {
+    use crate::messages::{ConsoleMessage, MessageLevel, MESSAGES_KEY};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    writer.log_message_timestamped(MessageLevel::Warning, "Brownout", 1_000).expect("Failed to log message");
+    writer.log_message_timestamped(MessageLevel::Error, "[nested] brackets", 2_000).expect("Failed to log message");
+    // console output captured as is
+    let messages = writer.get_entry::<String>(MESSAGES_KEY, None).expect("Failed to get entry");
+    writer.write_timestamped(messages, "Robot program starting".to_string(), 3_000).expect("Failed to write");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_messages(), vec![
+        ConsoleMessage { timestamp: 1_000, level: MessageLevel::Warning, text: "Brownout".to_string() },
+        ConsoleMessage { timestamp: 2_000, level: MessageLevel::Error, text: "[nested] brackets".to_string() },
+        ConsoleMessage { timestamp: 3_000, level: MessageLevel::Info, text: "Robot program starting".to_string() }
+    ]);
+    assert_eq!(ConsoleMessage::parse(0, "[LOUD] text").text, "[LOUD] text");
+}
+
+#[test]
+fn test_write_snapshot() {
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let mut snapshot = HashMap::new();
+    let _ = snapshot.insert("/shooter/rpm".to_string(), FrcValue::Double(4_500.0));
+    let _ = snapshot.insert("/shooter/ready".to_string(), FrcValue::Boolean(true));
+    let _ = snapshot.insert("/shooter/target".to_string(), FrcValue::Void);
+    writer.write_snapshot(1_000, snapshot.clone()).expect("Failed to write snapshot");
+    let _ = snapshot.insert("/shooter/rpm".to_string(), FrcValue::Double(4_800.0));
+    writer.write_snapshot(2_000, snapshot).expect("Failed to write snapshot");
+    assert!(matches!(
+        writer.write_snapshot(3_000, [("/shooter/ready".to_string(), FrcValue::Int(1))]),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let rpm = reader.read_entry("/shooter/rpm");
+    assert_eq!(rpm.len(), 2);
+    assert_eq!((rpm[1].timestamp, &rpm[1].value), (2_000, &FrcValue::Double(4_800.0)));
+    assert_eq!(reader.read_entry("/shooter/ready").len(), 2);
+    assert!(reader.read_entry("/shooter/target").is_empty());
+}
+
+#[test]
+fn test_scoped_timer() {
+    use crate::{handle::DataLogHandle, units::UnitTable};
+
+    let handle = DataLogHandle::new(DataLogWriter::new_in_memory("").expect("Failed to create writer"));
+    {
+        let _timer = handle.timer("/profiling/loop_ms").expect("Failed to start timer");
+        std::thread::sleep(std::time::Duration::from_millis(5));
+    }
+    let entry = handle.entry::<f64>("/profiling/loop_ms", None).expect("Failed to get entry");
+    for _ in 0..3 {
+        let _timer = entry.start_timer();
+    }
+    drop(entry);
+    let bytes = handle.try_into_writer().expect("Handle is still shared")
+        .into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let samples = reader.read_entry_in_unit("/profiling/loop_ms", "s", &UnitTable::default())
+        .expect("Timer entry has no unit");
+    assert_eq!(samples.len(), 4);
+    assert!(matches!(samples[0].value, FrcValue::Double(seconds) if seconds >= 0.005));
+}
+
+#[test]
+fn test_non_finite_policy() {
+    use crate::writer::{DataLogWriterConfig, NonFinitePolicy};
+
+    let write_log = |policy: NonFinitePolicy| {
+        let config = DataLogWriterConfig {
+            non_finite_policy: policy,
+            ..Default::default()
+        };
+        let mut writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+        let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+        let poses = writer.get_entry::<Vec<f64>>("poses", None).expect("Failed to get entry");
+        writer.write_timestamped(speed, 1.0, 1_000).expect("Failed to write entry");
+        let result = writer.write_timestamped(speed, f64::NAN, 2_000);
+        let _ = writer.write_timestamped(poses, vec![1.0, f64::INFINITY], 2_000);
+        assert_eq!(writer.non_finite_count(), 2);
+        let bytes = writer.into_inner().expect("Failed to finish log");
+        let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+            .expect("Failed to create reader");
+        let speeds = reader.read_entry("speed").iter().map(|value| value.value.clone()).collect::<Vec<_>>();
+        let poses = reader.read_entry("poses").iter().map(|value| value.value.clone()).collect::<Vec<_>>();
+        (result, speeds, poses)
+    };
+
+    let (result, speeds, _) = write_log(NonFinitePolicy::Allow);
+    assert!(result.is_ok());
+    assert!(matches!(speeds[1], FrcValue::Double(v) if v.is_nan()));
+    let (result, speeds, poses) = write_log(NonFinitePolicy::Skip);
+    assert!(result.is_ok());
+    assert_eq!((speeds.len(), poses.len()), (1, 0));
+    let (_, speeds, poses) = write_log(NonFinitePolicy::Replace(-1.0));
+    assert_eq!(speeds[1], FrcValue::Double(-1.0));
+    assert_eq!(poses[0], FrcValue::DoubleArray(vec![1.0, -1.0].into()));
+    let (result, speeds, _) = write_log(NonFinitePolicy::Reject);
+    assert!(matches!(result, Err(DataLogError::NonFiniteValue)));
+    assert_eq!(speeds.len(), 1);
+
+    // values appended from the real time thread go through the policy too
+    let append_log = |policy: NonFinitePolicy| {
+        use crate::realtime::RealtimeDataLogWriter;
+        let config = DataLogWriterConfig {
+            non_finite_policy: policy,
+            ..Default::default()
+        };
+        let mut writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+        let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+        let (writer, mut appender) = RealtimeDataLogWriter::new(writer, 1024).expect("Failed to start writer");
+        appender.append_f64(speed, 1.0, 1_000).expect("Failed to append value");
+        appender.append_f64(speed, f64::NAN, 2_000).expect("Failed to append value");
+        let bytes = writer.finish().expect("Failed to finish log");
+        let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+            .expect("Failed to create reader");
+        reader.read_entry("speed").iter().map(|value| value.value.clone()).collect::<Vec<_>>()
+    };
+    assert_eq!(append_log(NonFinitePolicy::Skip), vec![FrcValue::Double(1.0)]);
+    assert_eq!(append_log(NonFinitePolicy::Replace(-1.0)), vec![FrcValue::Double(1.0), FrcValue::Double(-1.0)]);
+}
+
+#[test]
+fn test_dry_run() {
+    let mut writer = DataLogWriter::dry_run("dry").expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    for i in 0..1_000u32 {
+        writer.write_timestamped(entry, f64::from(i), u64::from(i)).expect("Failed to write");
+    }
+    assert!(matches!(writer.write_dynamic(entry.into(), FrcValue::Boolean(true).to_timestamped(1_000)), Err(DataLogError::EntryTypeMismatch)));
+
+    let mut real = DataLogWriter::new_in_memory("dry").expect("Failed to create writer");
+    let entry = real.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    for i in 0..1_000u32 {
+        real.write_timestamped(entry, f64::from(i), u64::from(i)).expect("Failed to write");
+    }
+    // the same bytes as a real log, whether flushed or not
+    let bytes = real.into_inner().expect("Failed to finish log");
+    assert_eq!(writer.bytes_written(), bytes.len() as u64);
+    assert_eq!(writer.into_inner().expect("Failed to finish log").bytes_written(), bytes.len() as u64);
+}
+
+#[test]
+fn test_fixed_width_headers() {
+    use crate::{proto::records::{header_len, split_record}, writer::DataLogWriterConfig};
+
+    let config = DataLogWriterConfig {
+        fixed_width_headers: true,
+        ..Default::default()
+    };
+    let mut writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1).expect("Failed to write entry");
+    writer.set_entry_metadata(entry.into(), "patched").expect("Failed to set metadata");
+    writer.write_timestamped(entry, 2, 2).expect("Failed to write entry");
+    let bytes = writer.close().expect("Failed to finish log");
+
+    let mut offset = header_len(&bytes).expect("Log has no header");
+    let mut records = 0;
+    while let Some((_, _, _, len)) = split_record(&bytes[offset..]) {
+        // 4 byte id, 4 byte size and 8 byte timestamp
+        assert_eq!(bytes[offset], 0b0111_1111);
+        offset += len;
+        records += 1;
+    }
+    assert_eq!((records, offset), (5, bytes.len()));
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = reader.read_entry("counter");
+    assert_eq!((values[1].timestamp, &values[1].value), (2, &FrcValue::Int(2)));
+    assert_eq!(reader.read_entry_metadata("counter").last().map(|metadata| metadata.value.as_str()), Some("patched"));
+}
+
+#[test]
+fn test_entry_manifest() {
+    use crate::manifest::{EntryDeclaration, EntryManifest};
+
+    let json = r#"{"entries": [
+        {"key": "/drivetrain/velocity", "type": "double", "metadata": "{\"unit\":\"m/s\"}"},
+        {"key": "/vision/tags", "type": "int64[]"}
+    ]}"#;
+    let manifest = EntryManifest::from_json(json).expect("Failed to parse manifest");
+    #[cfg(feature = "serde")]
+    assert_eq!(serde_json::from_str::<EntryManifest>(json).ok().as_ref(), Some(&manifest));
+    assert_eq!(manifest.entries[0].metadata.as_deref(), Some(r#"{"unit":"m/s"}"#));
+    assert!(EntryManifest::from_json(r#"{"entries": [{"key": "/no/type"}]}"#).is_err());
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entries = writer.declare_entries(&manifest).expect("Failed to declare entries");
+    // declaring again returns the same entries
+    let again = writer.declare_entries(&manifest).expect("Failed to declare entries");
+    assert_eq!(again["/vision/tags"].entry_id(), entries["/vision/tags"].entry_id());
+    let velocity = writer.get_entry::<f64>("/drivetrain/velocity", None).expect("Failed to get entry");
+    writer.write_timestamped(velocity, 1.5, 1_000).expect("Failed to write entry");
+    assert!(matches!(
+        writer.declare_entries(&EntryManifest::new().with_entry(EntryDeclaration::new("/bad", "quaternion"))),
+        Err(DataLogError::RecordType(_))
+    ));
+    assert!(matches!(
+        writer.declare_entries(&EntryManifest::new().with_entry(EntryDeclaration::new("/vision/tags", "double"))),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry_type_str("/vision/tags")[0].value, "int64[]");
+    assert_eq!(reader.read_entry("/drivetrain/velocity").len(), 1);
+}
+
+#[test]
+fn test_channel_writer() {
+    use std::sync::mpsc;
+    use frclib_core::value::FrcTimestampedValue;
+    use crate::channel_writer::ChannelDataLogWriter;
+
+    let writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let (sender, receiver) = mpsc::channel();
+    let consumer = ChannelDataLogWriter::new(writer, receiver).expect("Failed to start consumer");
+    let producers = (0..4u32)
+        .map(|producer| {
+            let sender = sender.clone();
+            std::thread::spawn(move || {
+                for i in 0..100u32 {
+                    let value = FrcTimestampedValue::new(u64::from(i), FrcValue::Double(f64::from(i)));
+                    sender.send((format!("/producer/{producer}"), value)).expect("Consumer stopped");
+                }
+            })
+        })
+        .collect::<Vec<_>>();
+    for producer in producers {
+        producer.join().expect("Producer panicked");
+    }
+    sender.send(("/producer/0".to_string(), FrcTimestampedValue::new(100, FrcValue::Boolean(true))))
+        .expect("Consumer stopped");
+    drop(sender);
+
+    while !consumer.is_finished() {
+        std::thread::yield_now();
+    }
+    assert_eq!(consumer.dropped(), 1);
+    let writer = consumer.join().expect("Consumer failed");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    for producer in 0..4 {
+        assert_eq!(reader.read_entry(&format!("/producer/{producer}")).len(), 100);
+    }
+}
+
+#[cfg(feature = "embedded-io")]
+#[test]
+fn test_embedded_writer() {
+    use crate::embedded::EmbeddedDataLogWriter;
+
+    let mut writer = EmbeddedDataLogWriter::new(Vec::new(), "coprocessor").expect("Failed to write header");
+    let latency = writer.start_entry("/vision/latency", "double", "{\"unit\":\"ms\"}", 0).expect("Failed to start entry");
+    let targets = writer.start_entry("/vision/targets", "int64", "", 0).expect("Failed to start entry");
+    let corners = writer.start_entry("/vision/corners", "double[]", "", 0).expect("Failed to start entry");
+    for i in 1..=10u32 {
+        writer.append_double(latency, f64::from(i), u64::from(i) * 1_000).expect("Failed to write value");
+        writer.append_int(targets, i64::from(i % 3), u64::from(i) * 1_000).expect("Failed to write value");
+    }
+    writer.append_double_array(corners, &[1.0, 2.0, 3.0], 5_000).expect("Failed to write value");
+    writer.finish_entry(targets, 20_000).expect("Failed to finish entry");
+    let minimal = writer.into_inner();
+
+    let mut fixed = EmbeddedDataLogWriter::new(Vec::new(), "coprocessor")
+        .expect("Failed to write header")
+        .with_fixed_width_headers();
+    let latency_fixed = fixed.start_entry("/vision/latency", "double", "", 0).expect("Failed to start entry");
+    fixed.append_double(latency_fixed, 1.0, 1_000).expect("Failed to write value");
+    let fixed = fixed.into_inner();
+
+    let reader = DataLogReader::try_new(minimal.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let latencies = reader.read_entry("/vision/latency");
+    assert_eq!(latencies.len(), 10);
+    assert_eq!(latencies.last().map(|value| value.value.clone()), Some(FrcValue::Double(10.0)));
+    assert_eq!(reader.read_entry("/vision/targets").len(), 10);
+    assert_eq!(
+        reader.read_entry("/vision/corners").first().map(|value| value.value.clone()),
+        Some(FrcValue::DoubleArray(vec![1.0, 2.0, 3.0].into()))
+    );
+
+    let reader = DataLogReader::try_new(fixed.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/vision/latency").len(), 1);
+}
+
+#[test]
+fn test_exact_type_checking() {
+    use frclib_core::{structure::{FrcStructDesc, FrcStructDescDB, FrcStructureBytes}, value::{FrcTimestampedValue, FrcType}};
+
+    // hashes the same as the builtin double type
+    static SHADOW: FrcStructDesc = FrcStructDesc {
+        schema_supplier: || "float64 value".to_string(),
+        type_str: "double",
+        size: 8
+    };
+    FrcStructDescDB::add_ref(&SHADOW);
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let shadow = writer.get_entry_dynamic("shadow", FrcType::Struct(&SHADOW), None).expect("Failed to get entry");
+    assert!(matches!(
+        writer.get_entry_dynamic("shadow", FrcType::Double, None),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    assert!(matches!(
+        writer.write_dynamic(shadow, FrcTimestampedValue::new(now(), FrcValue::Double(1.0))),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    // a struct and an array of it share a serial
+    assert!(matches!(
+        writer.get_entry_dynamic("shadow", FrcType::StructArray(&SHADOW), None),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    let value = FrcValue::Struct(Box::new(FrcStructureBytes::from_parts(&SHADOW, 1, vec![0; 8].into_boxed_slice())));
+    writer.write_dynamic(shadow, FrcTimestampedValue::new(now(), value)).expect("Failed to write entry");
+
+    let double = writer.get_entry_dynamic("double", FrcType::Double, None).expect("Failed to get entry");
+    writer.write_dynamic(double, FrcTimestampedValue::new(now(), FrcValue::Double(1.0))).expect("Failed to write entry");
+    let raw = writer.get_entry_raw("jpeg", "jpeg", None).expect("Failed to get entry");
+    writer.write_dynamic(raw, FrcTimestampedValue::new(now(), FrcValue::Raw(vec![1, 2].into()))).expect("Failed to write entry");
+}
+
+#[test]
+fn test_append_primitives() {
+    use crate::writer::{DataLogWriterConfig, NonFinitePolicy};
+
+    let config = DataLogWriterConfig {
+        non_finite_policy: NonFinitePolicy::Replace(0.0),
+        ..Default::default()
+    };
+    let mut writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    let states = writer.get_entry::<Vec<f64>>("states", None).expect("Failed to get entry");
+    let flags = writer.get_entry::<Vec<bool>>("flags", None).expect("Failed to get entry");
+    let mode = writer.get_entry::<String>("mode", None).expect("Failed to get entry");
+    let count = writer.get_entry::<i64>("count", None).expect("Failed to get entry");
+
+    let mut swerve = [0.0; 8];
+    for i in 0..10u32 {
+        swerve[0] = f64::from(i);
+        writer.append_double(speed, f64::from(i), u64::from(i)).expect("Failed to append");
+        writer.append_double_slice(states, &swerve, u64::from(i)).expect("Failed to append");
+    }
+    writer.append_double(speed, f64::NAN, 10).expect("Failed to append");
+    writer.append_boolean_slice(flags, &[true, false], 10).expect("Failed to append");
+    writer.append_string(mode, "auto", 10).expect("Failed to append");
+    writer.set_log_on_change(count.into(), true).expect("Failed to set log on change");
+    for _ in 0..3 {
+        writer.append_int(count, 4, 10).expect("Failed to append");
+    }
+    assert!(matches!(writer.append_int(speed, 1, 11), Err(DataLogError::EntryTypeMismatch)));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let speeds = reader.read_entry("speed");
+    assert_eq!(speeds.len(), 11);
+    assert_eq!(speeds[9].value, FrcValue::Double(9.0));
+    assert_eq!(speeds[10].value, FrcValue::Double(0.0));
+    let states = reader.read_entry("states");
+    assert_eq!(states.len(), 10);
+    let mut last_state = [0.0; 8];
+    last_state[0] = 9.0;
+    assert_eq!(states[9].value, FrcValue::DoubleArray(last_state.to_vec().into()));
+    assert_eq!(reader.read_entry("flags")[0].value, FrcValue::BooleanArray(vec![true, false].into()));
+    assert_eq!(reader.read_entry("mode")[0].value, FrcValue::String("auto".into()));
+    assert_eq!(reader.read_entry("count").len(), 1);
+}
+
+#[test]
+fn test_entry_aliases() {
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("/drive/speed", None).expect("Failed to get entry");
+    let legacy = writer.alias_entry(speed.into(), "/Drivetrain/Velocity", None).expect("Failed to alias entry");
+    assert!(matches!(
+        writer.alias_entry(speed.into(), "/Drivetrain/Velocity", None),
+        Err(DataLogError::EntryAlreadyExists)
+    ));
+    assert!(matches!(
+        writer.get_entry::<i64>("/Drivetrain/Velocity", None),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+
+    writer.write_timestamped(speed, 1.0, 1_000).expect("Failed to write entry");
+    writer.append_double(speed, 2.0, 2_000).expect("Failed to append");
+    writer.close_entry(legacy).expect("Failed to close alias");
+    writer.write_timestamped(speed, 3.0, 3_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/drive/speed").len(), 3);
+    let legacy = reader.read_entry("/Drivetrain/Velocity");
+    assert_eq!(legacy.len(), 2);
+    assert_eq!((legacy[1].timestamp, &legacy[1].value), (2_000, &FrcValue::Double(2.0)));
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_duration_timestamps() {
+    use std::time::{Duration, SystemTime};
+    use crate::time_sync::{timestamp_from_duration, timestamp_from_system_time};
+
+    assert_eq!(timestamp_from_duration(Duration::from_millis(1_500)), 1_500_000);
+    let before = now();
+    let ahead = timestamp_from_system_time(SystemTime::now() + Duration::from_secs(10));
+    assert!(ahead >= before + 9_000_000 && ahead <= now() + 11_000_000);
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    writer.write_since_start(entry, 1.0, Duration::from_secs(2)).expect("Failed to write entry");
+    writer.write_at_system_time(entry, 2.0, SystemTime::now() + Duration::from_secs(10)).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let speeds = reader.read_entry("speed");
+    assert_eq!(speeds[0].timestamp, 2_000_000);
+    assert!(speeds[1].timestamp >= before + 9_000_000);
+}
+
+#[test]
+fn test_sequence_timestamps() {
+    use std::num::NonZeroU64;
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let events = writer.get_entry::<String>("events", None).expect("Failed to get entry");
+    let ticks = writer.get_entry::<i64>("ticks", None).expect("Failed to get entry");
+    writer.set_sequence_timestamps(events.into(), NonZeroU64::new(1)).expect("Failed to set sequence timestamps");
+    writer.set_sequence_timestamps(ticks.into(), NonZeroU64::new(10)).expect("Failed to set sequence timestamps");
+    for event in ["a", "b", "c"] {
+        writer.write_timestamped(events, event.to_string(), 500).expect("Failed to write entry");
+        writer.write_timestamped(ticks, 1, 500).expect("Failed to write entry");
+    }
+    writer.set_sequence_timestamps(ticks.into(), None).expect("Failed to set sequence timestamps");
+    writer.write_timestamped(ticks, 2, 1_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let timestamps = |key: &str| reader.read_entry(key).iter().map(|value| value.timestamp).collect::<Vec<_>>();
+    assert_eq!(timestamps("events"), vec![500, 501, 502]);
+    assert_eq!(timestamps("ticks"), vec![500, 510, 520, 1_000]);
+}
+
+#[test]
+fn test_last_value_cache() {
+    use crate::writer::DataLogWriterConfig;
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    writer.write_timestamped(speed, 1.0, 1_000).expect("Failed to write entry");
+    assert!(writer.last_value(speed).is_none());
+
+    let config = DataLogWriterConfig {
+        cache_last_values: true,
+        ..Default::default()
+    };
+    let mut writer = DataLogWriter::with_config(Vec::new(), "", config).expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    let frame = writer.get_entry_raw("frame", "jpeg", None).expect("Failed to get entry");
+    assert!(writer.last_value(speed).is_none());
+    writer.write_timestamped(speed, 1.0, 1_000).expect("Failed to write entry");
+    writer.append_double(speed, 2.0, 2_000).expect("Failed to append");
+    writer.write_raw(frame, &[1, 2, 3], 3_000).expect("Failed to write entry");
+    let latest = writer.last_value(speed).expect("Value wasn't cached");
+    assert_eq!((latest.timestamp, &latest.value), (2_000, &FrcValue::Double(2.0)));
+    let latest = writer.last_value(frame).expect("Value wasn't cached");
+    assert_eq!(latest.value, FrcValue::Raw(vec![1, 2, 3].into()));
+
+    writer.close_entry(speed.into()).expect("Failed to close entry");
+    assert!(writer.last_value(speed).is_none());
+}
+
+#[test]
+fn test_background_queue_policy() {
+    use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    use crate::background_writer::{BackgroundDataLogWriter, QueuePolicy};
+
+    /// Holds the worker in its first write until opened
+    struct GatedSink {
+        bytes: Vec<u8>,
+        entered: Arc<AtomicBool>,
+        open: Arc<AtomicBool>
+    }
+    impl std::io::Write for GatedSink {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.entered.store(true, Ordering::Release);
+            while !self.open.load(Ordering::Acquire) {
+                std::thread::yield_now();
+            }
+            self.bytes.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let run = |policy: QueuePolicy| {
+        let (entered, open) = (Arc::new(AtomicBool::new(false)), Arc::new(AtomicBool::new(false)));
+        let sink = GatedSink { bytes: Vec::new(), entered: Arc::clone(&entered), open: Arc::clone(&open) };
+        let writer = BackgroundDataLogWriter::with_policy(sink, "", 4, policy).expect("Failed to create writer");
+        let handle = writer.handle();
+        let entry = handle.get_entry::<i64>("counter", None).expect("Failed to get entry");
+        handle.flush().expect("Failed to queue flush");
+        while !entered.load(Ordering::Acquire) {
+            std::thread::yield_now();
+        }
+        let results = (0..10).map(|i| handle.append_timestamped(entry, i, 1_000)).collect::<Vec<_>>();
+        let dropped = (handle.dropped(entry), handle.total_dropped());
+        open.store(true, Ordering::Release);
+        let bytes = writer.finish().expect("Failed to finish log").bytes;
+        let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+            .expect("Failed to create reader");
+        let values = reader.read_entry("counter").iter().map(|value| value.value.clone()).collect::<Vec<_>>();
+        (results, dropped, values)
+    };
+
+    let (results, dropped, values) = run(QueuePolicy::DropNewest);
+    assert_eq!(results.iter().filter(|result| matches!(result, Err(DataLogError::BackgroundWriterFull))).count(), 6);
+    assert_eq!(dropped, (6, 6));
+    assert_eq!(values, (0..4).map(FrcValue::Int).collect::<Vec<_>>());
+
+    let (results, dropped, values) = run(QueuePolicy::DropOldest);
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(dropped, (6, 6));
+    assert_eq!(values, (6..10).map(FrcValue::Int).collect::<Vec<_>>());
+
+    let writer = BackgroundDataLogWriter::with_policy(Vec::new(), "", 1, QueuePolicy::Block).expect("Failed to create writer");
+    let handle = writer.handle();
+    let entry = handle.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    for i in 0..100 {
+        handle.append_timestamped(entry, i, 1_000).expect("Failed to queue value");
+    }
+    assert_eq!(handle.total_dropped(), 0);
+    let bytes = writer.finish().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 100);
+}
+
+#[test]
+fn test_shutdown_finalize() {
+    use std::{sync::{Arc, Mutex}, time::Duration};
+    use crate::{handle::DataLogHandle, proto::records::{parse_records, ControlRecord}, rotating_writer::RotatingDataLogWriter, shutdown};
+
+    let path = "./test_logs/test_shutdown_finalize.wpilog";
+    let rotating_path = "./test_logs/test_shutdown_finalize_rotating.wpilog";
+    let _ = std::fs::remove_file(path);
+    let _ = std::fs::remove_file(rotating_path);
+    let handle = DataLogHandle::new(DataLogWriter::create(path, "").expect("Failed to create writer"));
+    shutdown::register(&handle);
+    let rotating = RotatingDataLogWriter::new(|_| rotating_path.into(), "", Duration::from_hours(1))
+        .expect("Failed to create writer");
+    let rotating = Arc::new(Mutex::new(rotating));
+    shutdown::register_shared(&rotating);
+    shutdown::install_panic_hook();
+
+    let entry = handle.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    handle.write_timestamped(entry, 1.0, 1_000).expect("Failed to write entry");
+    let rotating_entry = rotating.lock().expect("Failed to lock writer").get_entry::<i64>("count", None).expect("Failed to get entry");
+    assert_eq!(std::fs::metadata(path).expect("Failed to read file").len(), 0);
+
+    let panicking = handle.clone();
+    let result = std::thread::spawn(move || {
+        panicking.write_timestamped(entry, 2.0, 2_000).expect("Failed to write entry");
+        panic!("Robot code panicked");
+    }).join();
+    assert!(result.is_err());
+
+    // the panic hook finished the entries and closed both logs while the writers are still alive
+    for (path, key) in [(path, "speed"), (rotating_path, "count")] {
+        let bytes = std::fs::read(path).expect("Failed to read file");
+        let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+            .expect("Failed to create reader");
+        assert!(reader.get_all_entry_keys().iter().any(|existing| *existing == key));
+        let records = parse_records(&bytes[12..], &mut HashMap::new()).expect("Failed to parse records");
+        assert!(matches!(records.last(), Some(Record::Control(ControlRecord::Finish, _, _))));
+    }
+    assert!(matches!(handle.get_entry::<f64>("speed", None), Err(DataLogError::OutsideEntryLifetime)));
+    assert!(handle.write_timestamped(entry, 3.0, 3_000).is_err());
+    assert!(rotating.lock().expect("Failed to lock writer").write_timestamped(rotating_entry, 1, 1_000).is_err());
+}
+
+#[test]
+fn test_match_segments() {
+    use crate::{frc_naming::{MatchInfo, MatchType}, match_segments::MatchSegmentedDataLogWriter};
+
+    let dir = std::path::Path::new("./test_logs/test_match_segments");
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).expect("Failed to create dir");
+
+    let mut log = MatchSegmentedDataLogWriter::create(dir, "").expect("Failed to create writer");
+    let entry = log.writer_mut().get_entry::<i64>("counter", None).expect("Failed to get entry");
+    log.writer_mut().write_timestamped(entry, 0, 1_000).expect("Failed to write entry");
+
+    // a practice run without a match
+    assert!(log.set_enabled(true).expect("Failed to enable"));
+    assert!(!log.set_enabled(true).expect("Failed to enable"));
+    log.writer_mut().write_timestamped(entry, 1, 2_000).expect("Failed to write entry");
+    assert!(!log.set_enabled(false).expect("Failed to disable"));
+
+    log.set_match_info(Some(MatchInfo {
+        event_name: "CASJ".to_string(),
+        match_type: MatchType::Qualification,
+        match_number: 12
+    }));
+    assert!(log.set_enabled(true).expect("Failed to enable"));
+    log.writer_mut().write_timestamped(entry, 2, 3_000).expect("Failed to write entry");
+    // the pause between autonomous and teleop stays in the same file
+    assert!(!log.set_enabled(false).expect("Failed to disable"));
+    assert!(!log.set_enabled(true).expect("Failed to enable"));
+    log.writer_mut().write_timestamped(entry, 3, 4_000).expect("Failed to write entry");
+    assert_eq!(log.segment(), 2);
+    let match_path = log.path().to_path_buf();
+    assert!(match_path.to_string_lossy().ends_with("_CASJ_Q12.wpilog"));
+    let _ = log.close().expect("Failed to close writer");
+
+    let mut counts = std::fs::read_dir(dir).expect("Failed to read dir")
+        .map(|file| {
+            let reader = DataLogReader::try_new(
+                File::open(file.expect("Failed to read dir").path()).expect("Failed to open file"),
+                DataLogReaderConfig::default()
+            ).expect("Failed to create reader");
+            reader.read_entry("counter").len()
+        })
+        .collect::<Vec<_>>();
+    counts.sort_unstable();
+    assert_eq!(counts, vec![1, 1, 2]);
+
+    // a file that couldn't be started is tried again on the next call
+    let dir = std::path::Path::new("./test_logs/test_match_segments_retry");
+    let _ = std::fs::remove_dir_all(dir);
+    std::fs::create_dir_all(dir).expect("Failed to create dir");
+    let mut log = MatchSegmentedDataLogWriter::create(dir, "").expect("Failed to create writer");
+    std::fs::remove_dir_all(dir).expect("Failed to remove dir");
+    assert!(log.set_enabled(true).is_err());
+    std::fs::create_dir_all(dir).expect("Failed to create dir");
+    assert!(log.set_enabled(true).expect("Failed to enable"));
+    assert_eq!(log.segment(), 1);
+}
+
+#[test]
+fn test_wpilib_compat() {
+    use crate::wpilib_compat::{DataLog, DoubleLogEntry, StringArrayLogEntry};
+
+    let log = DataLog::new(DataLogWriter::new_in_memory("").expect("Failed to create writer"));
+
+    let angle = log.start("/arm/angle", "double", "").expect("Failed to start entry");
+    assert_eq!(log.start("/arm/angle", "double", "").expect("Failed to start entry").entry_id(), angle.entry_id());
+    assert!(matches!(log.start("/arm/angle", "int64", ""), Err(DataLogError::EntryTypeMismatch)));
+    log.append_double(angle, 0.5, 1_000).expect("Failed to append value");
+    log.set_metadata(angle, r#"{"unit":"rad"}"#).expect("Failed to set metadata");
+
+    let frame = log.start("/camera/frame", "jpeg", "").expect("Failed to start entry");
+    log.append_raw(frame, &[0xff, 0xd8], 1_000).expect("Failed to append value");
+
+    let mut velocity = DoubleLogEntry::new(&log, "/arm/velocity", "").expect("Failed to start entry");
+    assert!(!velocity.has_last_value());
+    velocity.update(1.5, 1_000).expect("Failed to append value");
+    velocity.update(1.5, 2_000).expect("Failed to append value");
+    velocity.update(2.0, 3_000).expect("Failed to append value");
+    assert_eq!(velocity.get_last_value(), Some(&2.0));
+
+    let mut modes = StringArrayLogEntry::new(&log, "/modes", "").expect("Failed to start entry");
+    modes.update(&["auto".to_string()], 0).expect("Failed to append value");
+    modes.finish().expect("Failed to finish entry");
+    log.finish(angle).expect("Failed to finish entry");
+
+    let handle = log.handle().clone();
+    drop((log, velocity));
+    let writer = handle.try_into_writer().expect("Log is still shared");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/arm/angle").len(), 1);
+    assert_eq!(reader.read_entry("/camera/frame").len(), 1);
+    assert_eq!(reader.read_entry("/arm/velocity").len(), 2);
+    assert_eq!(reader.read_entry("/modes").len(), 1);
+}
+
+#[test]
+fn test_file_locking() {
+    use std::io::Write;
+    use crate::locking::{is_locked, LockedFilePolicy};
+
+    let path = "./test_logs/test_file_locking.wpilog";
+    let _ = std::fs::remove_file(path);
+    let mut writer = DataLogWriter::create(path, "").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    assert!(is_locked(path).expect("Failed to check lock"));
+
+    assert!(matches!(
+        DataLogReader::open(path, DataLogReaderConfig::default()),
+        Err(DataLogError::FileLocked)
+    ));
+
+    // the start of a record that hasn't been written completely
+    std::fs::OpenOptions::new().append(true).open(path).expect("Failed to open file")
+        .write_all(&[0x00, 0x01, 0x08]).expect("Failed to write file");
+    let config = DataLogReaderConfig {
+        locked_files: LockedFilePolicy::ReadSnapshot,
+        ..Default::default()
+    };
+    let reader = DataLogReader::open(path, config).expect("Failed to read snapshot");
+    assert_eq!(reader.read_entry("counter").len(), 1);
+
+    // a compressed log can end inside a record too
+    #[cfg(feature = "zstd")]
+    {
+        let compressed_path = "./test_logs/test_file_locking.wpilog.zst";
+        let _ = std::fs::remove_file(compressed_path);
+        let file = File::create(compressed_path).expect("Failed to create file");
+        file.try_lock().expect("Failed to lock file");
+        let mut plain = std::fs::read(path).expect("Failed to read file");
+        // with its header but only part of its payload
+        plain.extend_from_slice(&[0x10, 0x01, 0x02]);
+        (&file).write_all(&zstd::encode_all(plain.as_slice(), 0).expect("Failed to compress log"))
+            .expect("Failed to write file");
+        let reader = DataLogReader::open(compressed_path, config).expect("Failed to read snapshot");
+        assert_eq!(reader.read_entry("counter").len(), 1);
+    }
+
+    drop(writer);
+    assert!(!is_locked(path).expect("Failed to check lock"));
+    assert!(matches!(
+        DataLogReader::open("./test_logs/missing.wpilog", DataLogReaderConfig::default()),
+        Err(DataLogError::FileDoesNotExist)
+    ));
+}
+
+#[test]
+fn test_rolling_statistics() {
+    use std::num::NonZeroUsize;
+    use frclib_core::value::FrcValue;
+    use crate::statistics::RollingWindow;
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let current = writer.get_entry::<f64>("/drive/current", None).expect("Failed to get entry");
+    let name = writer.get_entry::<String>("/drive/name", None).expect("Failed to get entry");
+    assert!(matches!(
+        writer.add_rolling_statistics(name.into(), RollingWindow::Samples(NonZeroUsize::MIN)),
+        Err(DataLogError::EntryTypeMismatch)
+    ));
+    let entries = writer.add_rolling_statistics(current.into(), RollingWindow::Samples(NonZeroUsize::new(3).expect("Zero window")))
+        .expect("Failed to add statistics");
+
+    for (index, value) in [1.0, 5.0, 3.0, 7.0].into_iter().enumerate() {
+        writer.write_timestamped(current, value, 1_000 * (index as u64 + 1)).expect("Failed to write entry");
+    }
+    writer.append_double(current, f64::from(2), 5_000).expect("Failed to append value");
+    // a closed aggregate entry gets no more records, the others keep going
+    writer.close_entry(entries.mean).expect("Failed to close entry");
+    writer.write_timestamped(current, 9.0, 6_000).expect("Failed to write entry");
+
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let records = crate::proto::records::parse_records(
+        &bytes[crate::proto::records::header_len(&bytes).expect("Missing header")..],
+        &mut HashMap::new()
+    ).expect("Failed to parse records");
+    assert_eq!(records.iter().filter(|record| record.is_data() && record.get_id() == entries.mean.entry_id()).count(), 5);
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = |key: &str| reader.read_entry(key).into_iter().map(|tv| tv.value.clone()).collect::<Vec<_>>();
+    assert_eq!(values("/drive/current_mean"), [1.0, 3.0, 3.0, 5.0, 4.0].map(FrcValue::Double));
+    assert_eq!(values("/drive/current_min"), [1.0, 1.0, 1.0, 3.0, 2.0, 2.0].map(FrcValue::Double));
+    assert_eq!(values("/drive/current_max"), [1.0, 5.0, 5.0, 7.0, 7.0, 9.0].map(FrcValue::Double));
+}
+
+#[test]
+fn test_text_mirror() {
+    use crate::text_mirror::TextMirrorSink;
+
+    let mut writer = DataLogWriter::new(TextMirrorSink::new(Vec::new(), Vec::new()), "").expect("Failed to create writer");
+    let angle = writer.get_entry::<f64>("/arm/angle", None).expect("Failed to get entry");
+    let frame = writer.get_entry_raw("/camera/frame", "jpeg", None).expect("Failed to get entry");
+    writer.write_timestamped(angle, 42.0, 1_234_567).expect("Failed to write entry");
+    writer.write_raw(frame, &[0xff, 0xd8, 0xff], 2_000_000).expect("Failed to write entry");
+    writer.close_entry(angle.into()).expect("Failed to close entry");
+    let sink = writer.into_inner().expect("Failed to finish log");
+    assert!(sink.mirror_error().is_none());
+
+    let (log, mirror) = sink.into_parts();
+    let reader = DataLogReader::try_new(log.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/arm/angle").len(), 1);
+
+    let mirror = String::from_utf8(mirror).expect("Mirror isn't utf8");
+    let lines = mirror.lines().collect::<Vec<_>>();
+    assert!(lines[0].ends_with(" start /arm/angle (double)"));
+    assert!(lines[1].ends_with(" start /camera/frame (jpeg)"));
+    assert_eq!(lines[2], "t=1.234 /arm/angle = 42");
+    assert_eq!(lines[3], "t=2.000 /camera/frame = <3 bytes>");
+    assert!(lines[4].ends_with(" finish /arm/angle"));
+}
+
+#[cfg(feature = "encryption")]
+#[test]
+fn test_encrypted_writing() {
+    use crate::encryption::Truncation;
+
+    let key = [7u8; 32];
+    let mut writer = DataLogWriter::new_encrypted(Vec::new(), &key, "scouting").expect("Failed to create writer");
+    let entry = writer.get_entry::<String>("/scouting/notes", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, "fast intake".to_string(), 1_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    writer.write_timestamped(entry, "weak climb".to_string(), 2_000).expect("Failed to write entry");
+    let bytes = writer.close_encrypted().expect("Failed to finish log");
+    assert!(!bytes.windows(11).any(|window| window == b"fast intake"), "Expected the log to be encrypted");
+
+    let reader = DataLogReader::try_new_encrypted(bytes.as_slice(), &key, Truncation::Reject, DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "scouting");
+    assert_eq!(reader.read_entry("/scouting/notes").len(), 2);
+
+    let wrong_key = [8u8; 32];
+    assert!(matches!(
+        DataLogReader::try_new_encrypted(bytes.as_slice(), &wrong_key, Truncation::Reject, DataLogReaderConfig::default()),
+        Err(DataLogError::Decryption(_))
+    ));
+    let mut tampered = bytes;
+    let last = tampered.len() - 1;
+    tampered[last] ^= 1;
+    assert!(DataLogReader::try_new_encrypted(tampered.as_slice(), &key, Truncation::Reject, DataLogReaderConfig::default()).is_err());
+
+    // a log cut off before the stream was finished only keeps what was flushed when that is allowed
+    let mut writer = DataLogWriter::new_encrypted(Vec::new(), &key, "").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1, 1_000).expect("Failed to write entry");
+    writer.flush().expect("Failed to flush");
+    let truncated = writer.into_inner().expect("Failed to take sink").get_ref().clone();
+    assert!(matches!(
+        DataLogReader::try_new_encrypted(truncated.as_slice(), &key, Truncation::Reject, DataLogReaderConfig::default()),
+        Err(DataLogError::Decryption(_))
+    ));
+    let reader = DataLogReader::try_new_encrypted(truncated.as_slice(), &key, Truncation::Allow, DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 1);
+}
+
+#[test]
+fn test_borrowed_data_record() {
+    use crate::DataRecordRef;
+    use crate::proto::entries::{DOUBLE_ARRAY_TYPE_SERIAL, RAW_TYPE_SERIAL, STRING_ARRAY_TYPE_SERIAL, STRING_TYPE_SERIAL};
+
+    let mut payload = Vec::new();
+    encode_payload(&"hello".into_frc_value(), &mut payload);
+    let Ok(DataRecordRef::String(string)) = DataRecordRef::from_binary(&payload, STRING_TYPE_SERIAL) else {
+        panic!("Expected a string");
+    };
+    assert_eq!(string, "hello");
+    assert!(std::ptr::eq(string.as_ptr(), payload.as_ptr()), "Expected the string to be borrowed");
+
+    let mut payload = Vec::new();
+    encode_payload(&FrcValue::DoubleArray(Box::new([1.5, -2.0, 3.25])), &mut payload);
+    let Ok(DataRecordRef::DoubleArray(doubles)) = DataRecordRef::from_binary(&payload, DOUBLE_ARRAY_TYPE_SERIAL) else {
+        panic!("Expected a double array");
+    };
+    assert_eq!(doubles.len(), 3);
+    assert_eq!(doubles.get(1), Some(-2.0));
+    assert_eq!(doubles.get(3), None);
+    // the start of this element fits in a usize but its end doesn't
+    assert_eq!(doubles.get(usize::MAX / 8), None);
+    assert_eq!(doubles.iter().collect::<Vec<_>>(), vec![1.5, -2.0, 3.25]);
+
+    let mut payload = Vec::new();
+    encode_payload(&FrcValue::StringArray(Box::new(["a".into(), "".into(), "bcd".into()])), &mut payload);
+    let Ok(DataRecordRef::StringArray(strings)) = DataRecordRef::from_binary(&payload, STRING_ARRAY_TYPE_SERIAL) else {
+        panic!("Expected a string array");
+    };
+    assert_eq!(strings.iter().collect::<Vec<_>>(), vec!["a", "", "bcd"]);
+    assert!(DataRecordRef::from_binary(&payload[..payload.len() - 1], STRING_ARRAY_TYPE_SERIAL).is_err());
+
+    // writing a borrowed record again gives the same bytes as the owned one
+    let record = DataRecordRef::from_binary(&payload, STRING_ARRAY_TYPE_SERIAL).expect("Failed to parse record");
+    let mut borrowed = Vec::new();
+    record.write_to(1_000, 3, &mut borrowed).expect("Failed to write record");
+    let mut owned = Vec::new();
+    DataRecord::from(record).write_to(1_000, 3, &mut owned).expect("Failed to write record");
+    assert_eq!(borrowed, owned);
+
+    let raw = [1u8, 2, 3];
+    let Ok(DataRecordRef::Raw(bytes)) = DataRecordRef::from_binary(&raw, RAW_TYPE_SERIAL) else {
+        panic!("Expected raw bytes");
+    };
+    assert!(std::ptr::eq(bytes, raw.as_slice()));
+}
+
+#[test]
+fn test_bulk_array_decode() {
+    use crate::proto::entries::{BOOLEAN_ARRAY_TYPE_SERIAL, DOUBLE_ARRAY_TYPE_SERIAL, FLOAT_ARRAY_TYPE_SERIAL, INT_ARRAY_TYPE_SERIAL};
+
+    let doubles = (0..1_000).map(|index| f64::from(index) / 4.0).collect::<Box<[_]>>();
+    let mut payload = Vec::new();
+    encode_payload(&FrcValue::DoubleArray(doubles.clone()), &mut payload);
+    // bytes after the last whole element are ignored, and the payload starts unaligned
+    payload.push(0xff);
+    let mut shifted = vec![0u8];
+    shifted.extend_from_slice(&payload);
+    let Ok(DataRecord::DoubleArray(decoded)) = DataRecord::from_binary(&shifted[1..], DOUBLE_ARRAY_TYPE_SERIAL) else {
+        panic!("Expected a double array");
+    };
+    assert_eq!(decoded, doubles);
+
+    let mut payload = Vec::new();
+    encode_payload(&FrcValue::IntArray(Box::new([i64::MIN, -1, 0, i64::MAX])), &mut payload);
+    let Ok(DataRecord::IntegerArray(decoded)) = DataRecord::from_binary(&payload, INT_ARRAY_TYPE_SERIAL) else {
+        panic!("Expected an int array");
+    };
+    assert_eq!(&*decoded, &[i64::MIN, -1, 0, i64::MAX]);
+
+    let mut payload = Vec::new();
+    encode_payload(&FrcValue::FloatArray(Box::new([1.5, -0.25])), &mut payload);
+    let Ok(DataRecord::FloatArray(decoded)) = DataRecord::from_binary(&payload, FLOAT_ARRAY_TYPE_SERIAL) else {
+        panic!("Expected a float array");
+    };
+    assert_eq!(&*decoded, &[1.5, -0.25]);
+
+    let Ok(DataRecord::BooleanArray(decoded)) = DataRecord::from_binary(&[1, 0, 2], BOOLEAN_ARRAY_TYPE_SERIAL) else {
+        panic!("Expected a boolean array");
+    };
+    assert_eq!(&*decoded, &[true, false, true]);
+}
+
+#[test]
+fn test_streaming_record_reader() {
+    use std::io::Read;
+    use crate::proto::{records::{header_len, split_record}, util::StreamingRecordByteReader};
+
+    /// Hands out one byte per read like a slow pipe
+    struct Trickle<'a>(&'a [u8]);
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = self.0.len().min(buf.len()).min(1);
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    let mut writer = DataLogWriter::new_in_memory("stream").expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("/drive/velocity", None).expect("Failed to get entry");
+    for index in 0..100u32 {
+        writer.write_timestamped(entry, f64::from(index), 1_000 + u64::from(index)).expect("Failed to write entry");
+    }
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(Trickle(&bytes), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "stream");
+    assert_eq!(reader.read_entry("/drive/velocity").len(), 100);
+
+    let mut stream = StreamingRecordByteReader::new(Trickle(&bytes));
+    assert_eq!(stream.bytes(6).expect("Failed to read magic"), b"WPILOG");
+    stream.skip(2).expect("Failed to skip version");
+    let metadata_len = stream.u32().expect("Failed to read metadata length") as usize;
+    assert_eq!(stream.string(metadata_len).expect("Failed to read metadata"), "stream");
+    let mut records = 0;
+    while stream.record().expect("Failed to read record").is_some() {
+        records += 1;
+    }
+    // the start record and every value
+    assert_eq!(records, 101);
+
+    // a record cut off at the end stays buffered until the rest of it arrives
+    let header = header_len(&bytes).expect("Failed to read header");
+    let (.., first_len) = split_record(&bytes[header..]).expect("Failed to split record");
+    let mut stream = StreamingRecordByteReader::new(&bytes[header..header + first_len - 1]);
+    assert!(stream.record().expect("Failed to read record").is_none());
+    assert!(stream.has_partial_record());
+    assert_eq!(stream.bytes_buffered(), first_len - 1);
+}
+
+#[test]
+fn test_record_to_bytes() {
+    use crate::ControlRecord;
+
+    let records = [
+        Record::Control(ControlRecord::Start("/arm/angle".into(), "double".into(), "{\"unit\":\"deg\"}".into()), 12, 3),
+        Record::Control(ControlRecord::Metadata(String::new()), 70_000, 3),
+        Record::Data(DataRecord::Double(1.5), 1_000_000, 3),
+        Record::Data(DataRecord::StringArray(Box::new(["a".into(), "bc".into()])), u64::MAX, 300),
+        Record::Data(DataRecord::Raw(vec![0u8; 70_000].into_boxed_slice()), 5, 70_000),
+        Record::Control(ControlRecord::Finish, 2_000_000, 3),
+    ];
+    for record in records {
+        let bytes = record.to_bytes().expect("Failed to serialize record");
+        assert_eq!(Some(bytes.len()), record.serialized_size());
+        assert_eq!(bytes.len(), bytes.capacity());
+
+        let mut written = Vec::new();
+        record.write_to(&mut written).expect("Failed to write record");
+        assert_eq!(bytes, written);
+    }
+}
+
+#[test]
+fn test_unknown_control_record() {
+    use crate::ControlRecord;
+
+    let unknown = ControlRecord::Unknown { kind: 7, payload: Box::new([1, 2, 3]) };
+    let mut bytes = Vec::new();
+    unknown.write_to(1_000, 4, &mut bytes).expect("Failed to write record");
+    let (.., payload, _) = crate::proto::records::split_record(&bytes).expect("Failed to split record");
+    let Ok((ControlRecord::Unknown { kind, payload }, id)) = ControlRecord::from_binary(payload) else {
+        panic!("Expected an unknown control record");
+    };
+    assert_eq!((kind, &*payload, id), (7, &[1u8, 2, 3][..], 4));
+
+    // readers skip it and copying records keeps it
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let entry = writer.get_entry::<i64>("counter", None).expect("Failed to get entry");
+    writer.write_record(Record::Control(ControlRecord::Unknown { kind: 7, payload: Box::new([9]) }, 1_000, crate::writer::EntryId::from(entry).entry_id()))
+        .expect("Failed to write record");
+    writer.write_timestamped(entry, 1, 2_000).expect("Failed to write entry");
+    let log = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(log.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("counter").len(), 1);
+
+    let mut type_map = HashMap::new();
+    let header = crate::proto::records::header_len(&log).expect("Failed to read header");
+    let records = crate::proto::records::parse_records(&log[header..], &mut type_map).expect("Failed to parse records");
+    let mut copied = Vec::new();
+    for record in &records {
+        copied.extend(record.to_bytes().expect("Failed to serialize record"));
+    }
+    assert_eq!(copied, &log[header..]);
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_record_serde() {
+    use crate::{ControlRecord, TimestampedValue};
+
+    let records = vec![
+        Record::Control(ControlRecord::Start("/arm/angle".into(), "double".into(), String::new()), 12, 3),
+        Record::Control(ControlRecord::Unknown { kind: 9, payload: Box::new([1, 2]) }, 13, 3),
+        Record::Data(DataRecord::Double(1.5), 1_000, 3),
+        Record::Data(DataRecord::StringArray(Box::new(["a".into(), "bc".into()])), 2_000, 4),
+        Record::Data(DataRecord::Raw(Box::new([0xff, 0])), 3_000, 5),
+    ];
+    let json = serde_json::to_string(&records).expect("Failed to serialize records");
+    let decoded = serde_json::from_str::<Vec<Record>>(&json).expect("Failed to deserialize records");
+    assert_eq!(decoded.len(), records.len());
+    for (record, decoded) in records.iter().zip(&decoded) {
+        assert_eq!(record.to_bytes().ok(), decoded.to_bytes().ok());
+    }
+
+    let value = TimestampedValue::new(1_000, "hello".to_string());
+    let json = serde_json::to_string(&value).expect("Failed to serialize value");
+    assert_eq!(json, r#"{"timestamp":1000,"value":"hello"}"#);
+    let decoded = serde_json::from_str::<TimestampedValue<String>>(&json).expect("Failed to deserialize value");
+    assert_eq!((decoded.timestamp, decoded.value), (1_000, value.value));
+}
+
+#[cfg(feature = "proptest")]
+#[test]
+fn test_generated_records() {
+    use proptest::test_runner::TestRunner;
+    use crate::{proto::records::split_record, strategies};
+
+    TestRunner::default().run(&strategies::record(), |record| {
+        let bytes = record.to_bytes().expect("Failed to serialize record");
+        let (id, ..) = split_record(&bytes).expect("Failed to split record");
+        let mut type_map = HashMap::new();
+        if let Record::Data(data, ..) = &record {
+            let _ = type_map.insert(id, data.get_type_serial());
+        }
+        let decoded = Record::from_binary(&bytes, &type_map).expect("Failed to parse record");
+        assert_eq!(decoded.to_bytes().ok(), Some(bytes));
+        Ok(())
+    }).expect("Records didn't round trip");
+
+    TestRunner::default().run(&strategies::log(6), |log| {
+        let reader = DataLogReader::try_new(log.bytes.as_slice(), DataLogReaderConfig::default())
+            .expect("Failed to create reader");
+        assert_eq!(reader.get_header_metadata(), log.metadata);
+        for (key, _) in log.entries.values() {
+            assert_eq!(reader.read_entry(key).len(), log.value_count(key));
+        }
+        Ok(())
+    }).expect("Logs didn't round trip");
+
+    TestRunner::default().run(&strategies::near_valid_log(4), |bytes| {
+        // only has to not panic
+        let _ = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default());
+        Ok(())
+    }).expect("Reading a damaged log panicked");
+}
+
+#[cfg(feature = "fuzzing")]
+#[test]
+fn test_fuzz_entry_points() {
+    use crate::fuzzing::{fuzz_parse_log, fuzz_parse_record};
+
+    let mut writer = DataLogWriter::new_in_memory("fuzz").expect("Failed to create writer");
+    let entry = writer.get_entry::<Vec<i64>>("/vision/tags", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, vec![1, 2, 3], 1_000).expect("Failed to write entry");
+    let log = writer.into_inner().expect("Failed to finish log");
+    let header = crate::proto::records::header_len(&log).expect("Failed to read header");
+
+    // seeds like the ones in a corpus, a valid log and every cut and record of it
+    for len in 0..=log.len() {
+        fuzz_parse_log(&log[..len]);
+        fuzz_parse_log(&log[header.min(len)..len]);
+    }
+    for type_index in 0..=u8::MAX {
+        let mut record = vec![type_index];
+        record.extend_from_slice(&log[header..]);
+        fuzz_parse_record(&record);
+    }
+    // an int64[] whose payload is cut in the middle of an element
+    fuzz_parse_record(&[7, 0x01, 0x01, 0x03, 0x00, 1, 2, 3]);
+    fuzz_parse_record(&[]);
+}
+
+#[test]
+fn test_write_raw_from_reader() {
+    /// Fails like a camera disconnecting in the middle of a frame
+    struct FailingRead;
+    impl std::io::Read for FailingRead {
+        fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("camera disconnected"))
+        }
+    }
+    let frame = (0..200_000u32).map(|i| (i % 251) as u8).collect::<Vec<_>>();
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let frames = writer.get_entry_raw("/camera/frames", "jpeg", None).expect("Failed to get entry");
+    writer.write_raw_from(frames, frame.as_slice(), frame.len() as u64, 1_000).expect("Failed to write entry");
+    // a payload shorter than its length is padded so the record after it is still read
+    assert!(matches!(writer.write_raw_from(frames, &[1u8, 2][..], 4, 2_000), Err(DataLogError::Io(_))));
+    // and so is a payload that fails partway, after the bytes read before the failure
+    let failing = std::io::Read::chain(&[3u8][..], FailingRead);
+    assert!(matches!(writer.write_raw_from(frames, failing, 3, 2_500), Err(DataLogError::Io(_))));
+    writer.write_raw(frames, &[7], 3_000).expect("Failed to write entry");
+    let number = writer.get_entry::<f64>("/number", None).expect("Failed to get entry");
+    assert!(matches!(writer.write_raw_from(number.into(), &[1u8][..], 1, 4_000), Err(DataLogError::EntryTypeMismatch)));
+    assert!(matches!(writer.write_raw_from(frames, std::io::empty(), u64::from(u32::MAX) + 1, 4_000), Err(DataLogError::RecordTooLarge)));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = reader.read_entry("/camera/frames");
+    assert_eq!(values.len(), 4);
+    assert!(matches!(&values[0].value, FrcValue::Raw(bytes) if bytes.as_ref() == frame.as_slice()));
+    assert!(matches!(&values[1].value, FrcValue::Raw(bytes) if bytes.as_ref() == [1, 2, 0, 0]));
+    assert!(matches!(&values[2].value, FrcValue::Raw(bytes) if bytes.as_ref() == [3, 0, 0]));
+    assert!(matches!(&values[3].value, FrcValue::Raw(bytes) if bytes.as_ref() == [7]));
+}
+
+#[test]
+fn test_time_conversions() {
+    use std::time::Duration;
+    use frclib_core::value::FrcTimestamp;
+    use crate::time::{format_clock, format_seconds, from_duration, from_seconds, to_duration, to_seconds};
+
+    assert_eq!(to_duration(1_500_000), Duration::from_millis(1_500));
+    assert_eq!(from_duration(Duration::from_millis(1_500)), 1_500_000);
+    assert_eq!(from_duration(Duration::MAX), FrcTimestamp::MAX);
+    assert!((to_seconds(83_250_000) - 83.25).abs() < f64::EPSILON);
+    assert_eq!(from_seconds(83.25), 83_250_000);
+    assert_eq!(from_seconds(0.000_000_6), 1);
+    assert_eq!(from_seconds(-1.0), 0);
+    assert_eq!(from_seconds(f64::NAN), 0);
+    assert_eq!(from_seconds(f64::INFINITY), FrcTimestamp::MAX);
+    for timestamp in [0, 1, 999_999, 1_000_000, 123_456_789] {
+        assert_eq!(from_seconds(to_seconds(timestamp)), timestamp);
+    }
+
+    assert_eq!(format_seconds(83_250_000), "83.250000");
+    assert_eq!(format_seconds(7), "0.000007");
+    assert_eq!(format_clock(83_250_999), "1:23.250");
+    assert_eq!(format_clock(7_283_250_000), "2:01:23.250");
+}
+
+#[test]
+#[cfg(feature = "chrono")]
+fn test_time_date_conversions() {
+    use chrono::{TimeZone, Utc};
+    use crate::time::{from_date_time, to_date_time};
+
+    let start = Utc.with_ymd_and_hms(2024, 3, 9, 16, 0, 0).single().expect("Invalid date");
+    let later = Utc.with_ymd_and_hms(2024, 3, 9, 16, 1, 23).single().expect("Invalid date");
+    assert_eq!(to_date_time(83_000_000, start), Some(later));
+    assert_eq!(from_date_time(later, start), 83_000_000);
+    assert_eq!(from_date_time(start, later), 0);
+}
+
+#[test]
+fn test_peek_header() {
+    use crate::reader::DataLogHeader;
+
+    let path = "./test_logs/test_peek_header.wpilog";
+    let _ = std::fs::remove_file(path);
+    let mut writer = DataLogWriter::create(path, "{\"team\":1234}").expect("Failed to create writer");
+    let entry = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    writer.write_timestamped(entry, 1.0, 1_000).expect("Failed to write entry");
+    let _ = writer.close().expect("Failed to close writer");
+
+    let header = DataLogReader::peek_header(path).expect("Failed to peek header");
+    assert_eq!(header, DataLogHeader {
+        format_version: (1, 0),
+        metadata: "{\"team\":1234}".to_string()
+    });
+    let _ = std::fs::remove_file(path);
+
+    assert!(matches!(DataLogReader::peek_header(path), Err(DataLogError::FileDoesNotExist)));
+    assert!(matches!(DataLogReader::peek_header_from(&b"NOTLOG\x00\x01"[..]), Err(DataLogError::MagicMismatch)));
+    for short in [&b""[..], b"WP", b"\x28\xB5\x2F"] {
+        assert!(matches!(DataLogReader::try_new(short, DataLogReaderConfig::default()), Err(DataLogError::MagicMismatch)));
+    }
+    assert!(matches!(DataLogReader::peek_header_from(&b"WPILOG\x00\x01\x05\x00\x00\x00ab"[..]), Err(DataLogError::RecordReaderOutOfBounds(_))));
+}
+
+#[test]
+fn test_csv_export() {
+    use crate::export::{csv::{write_csv, CsvExportConfig}, RowAlignment};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    let mode = writer.get_entry::<String>("mode, current", None).expect("Failed to get entry");
+    let targets = writer.get_entry::<Vec<i64>>("targets", None).expect("Failed to get entry");
+    writer.write_timestamped(speed, 1.0, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(speed, 1.5, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(mode, "say \"hi\"".to_string(), 2_000).expect("Failed to write entry");
+    writer.write_timestamped(targets, vec![1, 2], 2_000).expect("Failed to write entry");
+    writer.write_timestamped(speed, 2.0, 3_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let keys = ["speed", "mode, current", "targets"];
+    let csv = |config: CsvExportConfig| {
+        let mut out = Vec::new();
+        write_csv(&reader, &keys, config, &mut out).expect("Failed to write csv");
+        String::from_utf8(out).expect("Invalid utf8")
+    };
+
+    let config = CsvExportConfig {
+        timestamps_in_seconds: false,
+        ..Default::default()
+    };
+    assert_eq!(csv(config), concat!(
+        "timestamp,speed,\"mode, current\",targets\n",
+        "1000,1.5,,\n",
+        "2000,,\"say \"\"hi\"\"\",\"[1, 2]\"\n",
+        "3000,2,,\n"
+    ));
+    let config = CsvExportConfig {
+        alignment: RowAlignment::SampleAndHold,
+        window: Some((2_000, 3_000)),
+        ..Default::default()
+    };
+    assert_eq!(csv(config), concat!(
+        "timestamp,speed,\"mode, current\",targets\n",
+        "0.002000,,\"say \"\"hi\"\"\",\"[1, 2]\"\n",
+        "0.003000,2,\"say \"\"hi\"\"\",\"[1, 2]\"\n"
+    ));
+    assert!(matches!(write_csv(&reader, &["missing"], CsvExportConfig::default(), Vec::new()), Err(DataLogError::NoSuchEntry)));
+}
+
+#[test]
+fn test_json_export() {
+    use crate::export::json::{write_json, JsonExportConfig, JsonLayout};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    let targets = writer.get_entry::<Vec<i64>>("targets", None).expect("Failed to get entry");
+    writer.write_timestamped(speed, 1.5, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(targets, vec![1, 2], 2_000).expect("Failed to write entry");
+    writer.write_timestamped(speed, f64::NAN, 3_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let json = |config: JsonExportConfig| {
+        let mut out = Vec::new();
+        write_json(&reader, &["speed", "targets"], config, &mut out).expect("Failed to write json");
+        String::from_utf8(out).expect("Invalid utf8")
+    };
+
+    let document: serde_json::Value = serde_json::from_str(&json(JsonExportConfig {
+        timestamps_in_seconds: false,
+        ..Default::default()
+    })).expect("Invalid json");
+    assert_eq!(document, serde_json::json!({
+        "speed": { "type": "double", "values": [{ "t": 1_000, "value": 1.5 }, { "t": 3_000, "value": null }] },
+        "targets": { "type": "int64[]", "values": [{ "t": 2_000, "value": [1, 2] }] }
+    }));
+    let pretty = json(JsonExportConfig { pretty: true, ..Default::default() });
+    assert!(pretty.lines().count() > 1);
+    assert_eq!(serde_json::from_str::<serde_json::Value>(&pretty).ok().map(|value| value["speed"]["values"][0]["t"].clone()), Some(serde_json::json!(0.001)));
+
+    let lines = json(JsonExportConfig {
+        layout: JsonLayout::Lines,
+        pretty: true,
+        window: Some((2_000, 3_000)),
+        ..Default::default()
+    });
+    assert_eq!(lines, concat!(
+        "{\"key\":\"targets\",\"t\":0.002,\"value\":[1,2]}\n",
+        "{\"key\":\"speed\",\"t\":0.003,\"value\":null}\n"
+    ));
+    assert!(matches!(write_json(&reader, &["missing"], JsonExportConfig::default(), Vec::new()), Err(DataLogError::NoSuchEntry)));
+}
+
+#[test]
+#[cfg(feature = "parquet")]
+fn test_parquet_export() {
+    use arrow_array::{cast::AsArray, types::{Float64Type, Int64Type, UInt64Type}, Array};
+    use arrow_schema::DataType;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use crate::export::{parquet::{write_parquet, ParquetExportConfig}, RowAlignment};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    let targets = writer.get_entry::<Vec<i64>>("targets", None).expect("Failed to get entry");
+    let frames = writer.get_entry_raw("frames", "jpeg", None).expect("Failed to get entry");
+    writer.write_timestamped(speed, 1.5, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(targets, vec![1, 2], 2_000).expect("Failed to write entry");
+    writer.write_raw(frames, &[0xFF, 0xD8], 2_000).expect("Failed to write entry");
+    writer.write_timestamped(speed, 2.5, 3_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+
+    let path = "./test_logs/test_parquet_export.parquet";
+    let config = ParquetExportConfig {
+        alignment: RowAlignment::SampleAndHold,
+        ..Default::default()
+    };
+    write_parquet(&reader, &["speed", "targets", "frames"], config, std::fs::File::create(path).expect("Failed to create file"))
+        .expect("Failed to write parquet");
+
+    let file = std::fs::File::open(path).expect("Failed to open file");
+    let batches = ParquetRecordBatchReaderBuilder::try_new(file).expect("Failed to read parquet")
+        .build().expect("Failed to read parquet")
+        .collect::<Result<Vec<_>, _>>().expect("Failed to read batches");
+    assert_eq!(batches.len(), 1);
+    let batch = &batches[0];
+    let schema = batch.schema();
+    assert_eq!(schema.field(0).data_type(), &DataType::UInt64);
+    assert_eq!(schema.field(1).data_type(), &DataType::Float64);
+    assert!(matches!(schema.field(2).data_type(), DataType::List(item) if item.data_type() == &DataType::Int64));
+    assert_eq!(schema.field(3).data_type(), &DataType::Binary);
+
+    assert_eq!(batch.column(0).as_primitive::<UInt64Type>().values().to_vec(), vec![1_000, 2_000, 3_000]);
+    assert_eq!(batch.column(1).as_primitive::<Float64Type>().values().to_vec(), vec![1.5, 1.5, 2.5]);
+    let targets = batch.column(2).as_list::<i32>();
+    assert!(targets.is_null(0));
+    assert_eq!(targets.value(2).as_primitive::<Int64Type>().values().to_vec(), vec![1, 2]);
+    let frames = batch.column(3).as_binary::<i32>();
+    assert!(frames.is_null(0));
+    assert_eq!(frames.value(1), [0xFF, 0xD8]);
+    let _ = std::fs::remove_file(path);
+}
+
+#[test]
+#[cfg(feature = "arrow")]
+fn test_arrow_ipc_export() {
+    use arrow_array::{cast::AsArray, types::{Float64Type, UInt64Type}, Array};
+    use arrow_ipc::reader::StreamReader;
+    use arrow_schema::DataType;
+    use crate::export::arrow::{write_arrow_ipc, ArrowExportConfig};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    let mode = writer.get_entry::<String>("mode", None).expect("Failed to get entry");
+    writer.write_timestamped(speed, 1.5, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(mode, "auto".to_string(), 2_000).expect("Failed to write entry");
+    writer.write_timestamped(speed, 2.5, 3_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+
+    let mut stream = Vec::new();
+    let config = ArrowExportConfig {
+        window: Some((0, 2_000)),
+        ..Default::default()
+    };
+    write_arrow_ipc(&reader, &["speed", "mode"], config, &mut stream).expect("Failed to write stream");
+
+    let batches = StreamReader::try_new(stream.as_slice(), None).expect("Failed to read stream")
+        .collect::<Result<Vec<_>, _>>().expect("Failed to read batches");
+    assert_eq!(batches.len(), 1);
+    let batch = &batches[0];
+    assert_eq!(batch.schema().field(2).data_type(), &DataType::Utf8);
+    assert_eq!(batch.column(0).as_primitive::<UInt64Type>().values().to_vec(), vec![1_000, 2_000]);
+    let speed = batch.column(1).as_primitive::<Float64Type>();
+    assert!((speed.value(0) - 1.5).abs() < f64::EPSILON);
+    assert!(speed.is_null(1));
+    let mode = batch.column(2).as_string::<i32>();
+    assert!(mode.is_null(0));
+    assert_eq!(mode.value(1), "auto");
+}
+
+#[test]
+fn test_influx_export() {
+    use crate::export::influx::{write_influx_lines, InfluxExportConfig};
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("drive speed", None).expect("Failed to get entry");
+    let count = writer.get_entry::<i64>("count", None).expect("Failed to get entry");
+    let mode = writer.get_entry::<String>("mode", None).expect("Failed to get entry");
+    let pose = writer.get_entry::<Vec<f64>>("pose", None).expect("Failed to get entry");
+    let frames = writer.get_entry_raw("frames", "jpeg", None).expect("Failed to get entry");
+    writer.write_timestamped(speed, 1.5, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(speed, f64::NAN, 2_000).expect("Failed to write entry");
+    writer.write_timestamped(count, 3, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(mode, "say \"hi\"".to_string(), 1_000).expect("Failed to write entry");
+    writer.write_timestamped(pose, vec![1.0, f64::INFINITY, 3.0], 1_000).expect("Failed to write entry");
+    writer.write_raw(frames, &[0xFF], 1_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+
+    let config = InfluxExportConfig {
+        measurement: "robot log",
+        tags: &[("event", "CASJ")],
+        start_time: 1_000_000,
+        window: None
+    };
+    let mut lines = Vec::new();
+    write_influx_lines(&reader, &["drive speed", "count", "mode", "pose", "frames"], config, &mut lines).expect("Failed to write lines");
+    assert_eq!(String::from_utf8(lines).expect("Invalid utf8"), concat!(
+        "robot\\ log,entry=drive\\ speed,event=CASJ value=1.5 1001000000\n",
+        "robot\\ log,entry=count,event=CASJ value=3i 1001000000\n",
+        "robot\\ log,entry=mode,event=CASJ value=\"say \\\"hi\\\"\" 1001000000\n",
+        "robot\\ log,entry=pose,event=CASJ 0=1,2=3 1001000000\n"
+    ));
+}
+
+#[test]
+#[cfg(feature = "rosbag2")]
+fn test_rosbag2_conversion() {
+    use crate::rosbag2::{rosbag2_to_wpilog, wpilog_to_rosbag2};
+
+    let pose = [1.0f64, 2.0, 0.5].iter().flat_map(|double| double.to_le_bytes()).collect::<Vec<_>>();
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("/drive/speed", None).expect("Failed to get entry");
+    let targets = writer.get_entry::<Vec<i64>>("vision targets", None).expect("Failed to get entry");
+    let mode = writer.get_entry::<String>("mode", None).expect("Failed to get entry");
+    let flags = writer.get_entry::<Vec<bool>>("flags", None).expect("Failed to get entry");
+    let odometry = writer.get_entry_raw("odometry", "struct:Pose2d", None).expect("Failed to get entry");
+    writer.write_timestamped(speed, 1.5, 1_000).expect("Failed to write entry");
+    writer.write_timestamped(targets, vec![4, 7], 2_000).expect("Failed to write entry");
+    writer.write_timestamped(mode, "auto".to_string(), 2_500).expect("Failed to write entry");
+    writer.write_timestamped(flags, vec![true], 2_500).expect("Failed to write entry");
+    writer.write_raw(odometry, &pose, 3_000).expect("Failed to write entry");
+    writer.write_timestamped(speed, -2.0, 4_000).expect("Failed to write entry");
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+
+    let bag = std::path::Path::new("./test_logs/test_rosbag2");
+    let _ = std::fs::remove_dir_all(bag);
+    let conversion = wpilog_to_rosbag2(&reader, bag, 1_000_000).expect("Failed to convert log");
+    assert_eq!(conversion.messages, 5);
+    assert_eq!(conversion.skipped, vec!["flags".to_string()]);
+    assert!(matches!(wpilog_to_rosbag2(&reader, bag, 0), Err(DataLogError::FileAlreadyExists)));
+    let metadata = std::fs::read_to_string(bag.join("metadata.yaml")).expect("Failed to read metadata");
+    assert!(metadata.contains("name: /vision_targets\n        type: std_msgs/msg/Int64MultiArray"));
+    assert!(metadata.contains("message_count: 5\n  topics_with_message_count"));
+
+    let db = rusqlite::Connection::open(bag.join("test_rosbag2_0.db3")).expect("Failed to open database");
+    let (timestamp, data) = db.query_row(
+        "SELECT timestamp, data FROM messages JOIN topics ON topics.id = topic_id WHERE name = '/drive/speed' ORDER BY timestamp",
+        [],
+        |row| Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
+    ).expect("Failed to query message");
+    assert_eq!(timestamp, 1_001_000_000);
+    assert_eq!(data, [&[0, 1, 0, 0][..], &1.5f64.to_le_bytes()].concat());
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let conversion = rosbag2_to_wpilog(bag, &mut writer).expect("Failed to convert bag");
+    assert_eq!(conversion.messages, 5);
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = |key: &str| reader.read_entry(key).into_iter()
+        .map(|value| (value.timestamp, value.value.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(values("/drive/speed"), vec![(0, FrcValue::Double(1.5)), (3_000, FrcValue::Double(-2.0))]);
+    assert_eq!(values("/vision_targets"), vec![(1_000, FrcValue::IntArray(Box::new([4, 7])))]);
+    assert_eq!(values("/mode"), vec![(1_500, FrcValue::String("auto".into()))]);
+    assert_eq!(values("/odometry"), vec![(2_000, FrcValue::Raw(pose.into()))]);
+    assert_eq!(reader.read_entry_type_str("/odometry")[0].value, "struct:Pose2d");
+    assert!(reader.get_all_entry_keys().iter().any(|key| *key == "/.schema/struct:Translation2d"));
+    let _ = std::fs::remove_dir_all(bag);
+}
+
+#[test]
+fn test_ds_log_import() {
+    use crate::ds_logs::{merge_ds_logs, parse_dsevents, parse_dslog, write_ds_logs, DsStatus, DS_EVENTS_KEY};
+
+    // 2023-11-14T22:13:20.5Z as LabVIEW time
+    let header = |bytes: &mut Vec<u8>, seconds: i64| {
+        bytes.extend(4i32.to_be_bytes());
+        bytes.extend((seconds + 2_082_844_800).to_be_bytes());
+        bytes.extend((1u64 << 63).to_be_bytes());
+    };
+    let mut dslog = Vec::new();
+    header(&mut dslog, 1_700_000_000);
+    // a record with a REV hub, disabled with the brownout bit clear
+    dslog.extend([10, 5, 12, 128, 50, !(DsStatus::BROWNOUT | DsStatus::DS_DISABLED).bits(), 20, 80, 2, 0, 0, 0, 0, 33]);
+    dslog.extend([0; 36]);
+    dslog.extend([12, 0, 11, 0, 60, !DsStatus::DS_TELEOP.bits(), 20, 80, 2, 0, 0, 0, 0, 0]);
+    // cut off by the Driver Station still writing
+    dslog.extend([12, 0, 11]);
+
+    let mut dsevents = Vec::new();
+    header(&mut dsevents, 1_700_000_000);
+    dsevents.extend((1_700_000_000i64 + 2_082_844_800).to_be_bytes());
+    dsevents.extend((3u64 << 62).to_be_bytes());
+    dsevents.extend(9i32.to_be_bytes());
+    dsevents.extend(b"Warning  ");
+
+    let log = parse_dslog(&dslog).expect("Failed to parse dslog");
+    assert_eq!(log.start_time, 1_700_000_000_500_000);
+    assert_eq!(log.records.len(), 2);
+    assert!((log.records[0].battery_voltage - 12.5).abs() < f64::EPSILON);
+    assert!((log.records[0].trip_time_ms - 5.0).abs() < f64::EPSILON);
+    assert!((log.records[0].packet_loss - 0.2).abs() < f64::EPSILON);
+    assert_eq!(log.records[0].status, DsStatus::BROWNOUT | DsStatus::DS_DISABLED);
+    assert_eq!(log.records[1].timestamp, 1_700_000_000_520_000);
+    let events = parse_dsevents(&dsevents).expect("Failed to parse dsevents");
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].timestamp, 1_700_000_000_750_000);
+    assert_eq!(events[0].text, "Warning");
+    assert!(matches!(parse_dslog(&[0, 0, 0, 3]), Err(DataLogError::RecordReaderOutOfBounds(_))));
+    let mut corrupt = vec![0, 0, 0, 4];
+    corrupt.extend_from_slice(&i64::MIN.to_be_bytes());
+    corrupt.extend_from_slice(&[0; 8]);
+    assert!(matches!(parse_dslog(&corrupt), Err(DataLogError::RecordDeserialize(_))));
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let import = write_ds_logs(&mut writer, Some(&log), &events, None).expect("Failed to import");
+    assert_eq!((import.records, import.events, import.skipped), (2, 1, 0));
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.read_entry("/DSLog/BatteryVoltage").len(), 2);
+    assert_eq!(reader.read_entry(DS_EVENTS_KEY)[0].timestamp, 250_000);
+
+    // a robot log that started 2 seconds before the Driver Station log
+    let mut robot = DataLogWriter::new_in_memory("robot").expect("Failed to create writer");
+    let system_time = robot.get_entry::<i64>("systemTime", None).expect("Failed to get entry");
+    let speed = robot.get_entry::<f64>("drive/speed", None).expect("Failed to get entry");
+    robot.write_timestamped(system_time, 1_700_000_003_500_000, 5_000_000).expect("Failed to write entry");
+    robot.write_timestamped(speed, 1.5, 2_010_000).expect("Failed to write entry");
+    let robot_log = robot.into_inner().expect("Failed to finish log");
+
+    let merged = merge_ds_logs(&robot_log, Some(&log), &events, None, Vec::new()).expect("Failed to merge");
+    let reader = DataLogReader::try_new(merged.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(DataLogReader::peek_header_from(merged.as_slice()).expect("Failed to read header").metadata, "robot");
+    assert_eq!(reader.read_entry("drive/speed")[0].value, FrcValue::Double(1.5));
+    let voltage = reader.read_entry("/DSLog/BatteryVoltage");
+    assert_eq!((voltage[0].timestamp, voltage[1].timestamp), (2_000_000, 2_020_000));
+    assert_eq!(reader.read_entry("/DSLog/Status/Brownout")[1].value, FrcValue::Boolean(false));
+    assert_eq!(reader.read_entry(DS_EVENTS_KEY)[0].timestamp, 2_250_000);
+}
+
+#[test]
+fn test_csv_import() {
+    use crate::{export::csv::{write_csv, CsvExportConfig}, import::csv::{import_csv, CsvImportConfig}};
+    use frclib_core::value::FrcType;
+
+    let csv = "name,t,current,enabled,wheels\r\n\"motor, left\",2000,\"1\",true,\"[1.5, 2]\"\n\nbench,1000,2.5,,[3]\n";
+    let config = CsvImportConfig {
+        timestamp_column: Some("t"),
+        timestamps_in_seconds: false,
+        column_types: &[("current", FrcType::Double)]
+    };
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    assert_eq!(import_csv(csv.as_bytes(), config, &mut writer).expect("Failed to import csv"), 7);
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = |key: &str| reader.read_entry(key).into_iter()
+        .map(|value| (value.timestamp, value.value.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(values("name"), vec![(1000, FrcValue::String("bench".into())), (2000, FrcValue::String("motor, left".into()))]);
+    assert_eq!(values("current"), vec![(1000, FrcValue::Double(2.5)), (2000, FrcValue::Double(1.0))]);
+    assert_eq!(values("enabled"), vec![(2000, FrcValue::Boolean(true))]);
+    assert_eq!(values("wheels"), vec![(1000, FrcValue::DoubleArray(Box::new([3.0]))), (2000, FrcValue::DoubleArray(Box::new([1.5, 2.0])))]);
+
+    // an exported table imports back
+    let mut csv = Vec::new();
+    write_csv(&reader, &["name", "enabled"], CsvExportConfig::default(), &mut csv).expect("Failed to write csv");
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    assert_eq!(import_csv(csv.as_slice(), CsvImportConfig::default(), &mut writer).expect("Failed to import csv"), 3);
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    assert!(matches!(import_csv(&b"t,a\n1,2,3\n"[..], CsvImportConfig::default(), &mut writer), Err(DataLogError::Import(_))));
+    assert!(matches!(import_csv(&b"t,a\nsoon,2\n"[..], CsvImportConfig::default(), &mut writer), Err(DataLogError::Import(_))));
+    assert!(matches!(import_csv(&b"t,a\n1,\"2\n"[..], CsvImportConfig::default(), &mut writer), Err(DataLogError::Import(_))));
+}
+
+#[test]
+fn test_json_import() {
+    use crate::{export::json::{write_json, JsonExportConfig, JsonLayout}, import::json::{import_json, JsonImportConfig}};
+
+    let json = r#"
+        {"t": 2000, "key": "speed", "value": 1}
+        {"t": 1000, "key": "speed", "value": 2}
+        [{"t": 1500, "key": "targets", "value": [1, 2.5]}, {"t": 0, "key": "frame", "type": "jpeg", "value": [255, 216]}]
+        {"t": 500, "key": "config", "type": "json", "value": {"gear": 2}}
+        {"t": 3000, "key": "battery", "type": "double", "value": null}
+    "#;
+    let config = JsonImportConfig { timestamps_in_seconds: false };
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    assert_eq!(import_json(json.as_bytes(), config, &mut writer).expect("Failed to import json"), 6);
+    let bytes = writer.into_inner().expect("Failed to finish log");
+    let reader = DataLogReader::try_new(bytes.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let values = |key: &str| reader.read_entry(key).into_iter()
+        .map(|value| (value.timestamp, value.value.clone()))
+        .collect::<Vec<_>>();
+    assert_eq!(values("speed"), vec![(1000, FrcValue::Int(2)), (2000, FrcValue::Int(1))]);
+    assert_eq!(values("targets"), vec![(1500, FrcValue::DoubleArray(Box::new([1.0, 2.5])))]);
+    assert_eq!(values("frame"), vec![(0, FrcValue::Raw(Box::new([255, 216])))]);
+    assert_eq!(reader.read_entry_type_str("frame")[0].value, "jpeg");
+    assert_eq!(values("config"), vec![(500, FrcValue::Raw(b"{\"gear\":2}".to_vec().into_boxed_slice()))]);
+    assert!(matches!(values("battery")[0].1, FrcValue::Double(value) if value.is_nan()));
+
+    // both exported layouts import back
+    for layout in [JsonLayout::Document, JsonLayout::Lines] {
+        let mut json = Vec::new();
+        let config = JsonExportConfig { layout, ..Default::default() };
+        write_json(&reader, &["speed", "targets"], config, &mut json).expect("Failed to write json");
+        let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+        assert_eq!(import_json(json.as_slice(), JsonImportConfig::default(), &mut writer).expect("Failed to import json"), 3);
+    }
+
+    let mut writer = DataLogWriter::new_in_memory("").expect("Failed to create writer");
+    let mismatch = br#"{"t": 0, "key": "a", "value": 1} {"t": 1, "key": "a", "type": "string", "value": "b"}"#;
+    assert!(matches!(import_json(&mismatch[..], config, &mut writer), Err(DataLogError::EntryTypeMismatch)));
+    let untyped = br#"{"t": 0, "key": "b", "value": null}"#;
+    assert!(matches!(import_json(&untyped[..], config, &mut writer), Err(DataLogError::Import(_))));
+    assert!(matches!(import_json(&b"{"[..], config, &mut writer), Err(DataLogError::Io(_))));
+}
+
+#[test]
+fn test_rewrite_trim_and_merge() {
+    use crate::rewrite::{merge, trim};
+
+    let mut writer = DataLogWriter::new_in_memory("robot").expect("Failed to create writer");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    let schema = writer.get_entry_raw("/.schema/struct:Point", "structschema", None).expect("Failed to get entry");
+    writer.write_raw(schema, b"double x;double y", 0).expect("Failed to write entry");
+    for timestamp in [1_000, 2_000, 3_000] {
+        #[allow(clippy::cast_precision_loss)]
+        writer.write_timestamped(speed, timestamp as f64, timestamp).expect("Failed to write entry");
+    }
+    let robot = writer.into_inner().expect("Failed to finish log");
+
+    let trimmed = trim(&robot, 1_500, 2_500, Vec::new()).expect("Failed to trim log");
+    let reader = DataLogReader::try_new(trimmed.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "robot");
+    let values = reader.read_entry("speed");
+    assert_eq!(values.len(), 1);
+    assert_eq!(values[0].timestamp, 2_000);
+    assert_eq!(reader.read_entry("/.schema/struct:Point").len(), 1);
+
+    let mut writer = DataLogWriter::new_in_memory("coprocessor").expect("Failed to create writer");
+    let targets = writer.get_entry::<i64>("targets", None).expect("Failed to get entry");
+    let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+    writer.write_timestamped(targets, 3, 1_500).expect("Failed to write entry");
+    writer.write_timestamped(speed, 4.0, 4_000).expect("Failed to write entry");
+    let coprocessor = writer.into_inner().expect("Failed to finish log");
+
+    let merged = merge(&[&robot, &coprocessor], Vec::new()).expect("Failed to merge logs");
+    let reader = DataLogReader::try_new(merged.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "robot");
+    assert_eq!(reader.read_entry("speed").len(), 4);
+    assert_eq!(reader.read_entry("targets")[0].value, FrcValue::Int(3));
+    assert!(matches!(merge(&[&robot, b"not a log"], Vec::new()), Err(DataLogError::MagicMismatch)));
+}
+
+#[test]
+fn test_rewrite_concat() {
+    use crate::{proto::records::{parse_records, ControlRecord}, rewrite::concat};
+
+    let log = |speed_value: f64, extra: Option<&str>| {
+        let mut writer = DataLogWriter::new_in_memory("session").expect("Failed to create writer");
+        let speed = writer.get_entry::<f64>("speed", None).expect("Failed to get entry");
+        writer.write_timestamped(speed, speed_value, 1_000).expect("Failed to write entry");
+        if let Some(key) = extra {
+            let extra = writer.get_entry::<bool>(key, None).expect("Failed to get entry");
+            writer.write_timestamped(extra, true, 2_000).expect("Failed to write entry");
+        }
+        writer.into_inner().expect("Failed to finish log")
+    };
+    let first = log(1.0, None);
+    let second = log(2.0, Some("rebooted"));
+
+    let joined = concat(&first, &second, Some(10_000), Vec::new()).expect("Failed to concatenate logs");
+    let reader = DataLogReader::try_new(joined.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    let speed = reader.read_entry("speed");
+    assert_eq!(speed.iter().map(|value| value.timestamp).collect::<Vec<_>>(), vec![1_000, 11_000]);
+    assert_eq!(speed[1].value, FrcValue::Double(2.0));
+    assert_eq!(reader.read_entry("rebooted")[0].timestamp, 12_000);
+
+    let joined = concat(&first, &second, Some(10_000), Vec::new()).expect("Failed to concatenate logs");
+    let records = parse_records(&joined[19..], &mut HashMap::new()).expect("Failed to parse records");
+    let starts = records.iter()
+        .filter(|record| matches!(record, Record::Control(ControlRecord::Start(..), _, _)))
+        .map(|record| (record.get_id(), record.get_timestamp()))
+        .collect::<Vec<_>>();
+    assert_eq!(starts.len(), 3);
+    assert_eq!(starts[1].0, starts[0].0, "speed should continue the entry of the first log");
+    assert!(starts[1].1 >= 10_000);
+
+    // by default the second log starts after every record of the first, including its start records
+    let joined = concat(&first, &second, None, Vec::new()).expect("Failed to concatenate logs");
+    let records = parse_records(&joined[19..], &mut HashMap::new()).expect("Failed to parse records");
+    let first_len = parse_records(&first[19..], &mut HashMap::new()).expect("Failed to parse records").len();
+    let (first_records, second_records) = records.split_at(first_len);
+    let first_end = first_records.iter().map(Record::get_timestamp).max();
+    assert!(second_records.iter().all(|record| Some(record.get_timestamp()) > first_end));
+}
+
+#[test]
+fn test_rewrite_rename() {
+    use crate::rewrite::rename;
+
+    let mut writer = DataLogWriter::new_in_memory("robot").expect("Failed to create writer");
+    for (key, value) in [("/oldSubsystem/angle", 1.0), ("/oldSubsystem/wrist/angle", 2.0), ("/oldSubsystem/speed", 3.0), ("/drive/speed", 4.0)] {
+        let entry = writer.get_entry::<f64>(key, None).expect("Failed to get entry");
+        writer.write_timestamped(entry, value, 1_000).expect("Failed to write entry");
+    }
+    let log = writer.into_inner().expect("Failed to finish log");
+
+    let rules = [
+        ("/oldSubsystem/*", "/arm/*"),
+        ("/oldSubsystem/wrist/*", "/wrist/*"),
+        ("/oldSubsystem/speed", "/arm/velocity")
+    ];
+    let renamed = rename(&log, &rules, Vec::new()).expect("Failed to rename entries");
+    let reader = DataLogReader::try_new(renamed.as_slice(), DataLogReaderConfig::default())
+        .expect("Failed to create reader");
+    assert_eq!(reader.get_header_metadata(), "robot");
+    let mut keys = reader.get_all_entry_keys().into_iter().cloned().collect::<Vec<_>>();
+    keys.sort();
+    assert_eq!(keys, ["/arm/angle", "/arm/velocity", "/drive/speed", "/wrist/angle"]);
+    assert_eq!(reader.read_entry("/wrist/angle")[0].value, FrcValue::Double(2.0));
+    assert_eq!(reader.read_entry("/arm/velocity")[0].value, FrcValue::Double(3.0));
+
+    // collisions are found before anything is written
+    let mut out = Vec::new();
+    assert!(matches!(rename(&log, &[("/oldSubsystem/speed", "/drive/speed")], &mut out), Err(DataLogError::EntryAlreadyExists)));
+    assert!(out.is_empty());
+    for rules in [[("/oldSubsystem/*", "/arm/")], [("/oldSubsystem/speed", "/arm/*")]] {
+        assert!(matches!(rename(&log, &rules, Vec::new()), Err(DataLogError::RenameRule(_))));
+    }
+}
+
+#[test]
+fn test_query_key_patterns() {
+    use crate::query::Query;
+
+    let matches = |pattern: &str, key: &str| Query::parse(pattern).expect("Failed to parse query").matches_key(key);
+    assert!(matches("/drive/*", "/drive/speed"));
+    assert!(matches("/drive/*", "/drive/"));
+    assert!(matches("*/speed", "/drive/left/speed"));
+    assert!(matches("/arm/?", "/arm/a"));
+    assert!(!matches("/arm/?", "/arm/"));
+    assert!(matches("/*/*/angle", "/arm/wrist/angle"));
+    assert!(!matches("/*/angle", "/arm/wrist/velocity"));
+    assert!(matches("*a*b", "xaxxab"));
+    assert!(!matches("*a*b", "xaxxa"));
+    // would backtrack exponentially with a recursive matcher
+    let key = "a".repeat(200);
+    assert!(!matches("*a*a*a*a*a*a*a*a*b", &key));
+
+    assert!(matches!(Query::parse("/arm/* where t < 0"), Err(DataLogError::QueryParse(_))));
+    assert!(matches!(Query::parse("/arm/* where t > -1s"), Err(DataLogError::QueryParse(_))));
+    assert!(Query::parse("/arm/* where t <= 0").is_ok());
+}
@@ -0,0 +1,142 @@
+use std::{collections::HashMap, io::Write};
+
+use frclib_core::value::{FrcTimestamp, IntoFrcValue};
+
+use crate::{proto::{entries::{get_str_type_serial, RAW_TYPE_SERIAL, SUPPORTED_TYPES_SERIALS}, records::{header_len, split_record, ControlRecord}}, DataRecord, DataRecordRef};
+
+/// A sink that writes the log to an inner sink and mirrors every record as a readable line to another,
+/// like `t=1.234 /arm/angle = 42.5`, for bringing up logging code without opening the log in a viewer
+///
+/// Entries starting and finishing are mirrored as `t=0.020 start /arm/angle (double)` and `t=9.000 finish /arm/angle`,
+/// raw and struct values only show their size. The mirror failing doesn't fail the log,
+/// nothing more is mirrored and the error is kept in [`TextMirrorSink::mirror_error`].
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::{text_mirror::TextMirrorSink, DataLogWriter};
+///
+/// let file = File::create("/home/lvuser/logs/match.wpilog").expect("Failed to create file");
+/// let mut writer = DataLogWriter::new(TextMirrorSink::new(file, std::io::stderr()), "").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("/arm/angle", None).expect("Failed to get entry");
+/// writer.write(entry, 42.0).expect("Failed to write entry");
+/// ```
+#[derive(Debug)]
+pub struct TextMirrorSink<W: Write, M: Write> {
+    inner: W,
+    mirror: M,
+    /// The start of a record cut off between writes
+    pending: Vec<u8>,
+    header_read: bool,
+    /// The key and type serial of every started entry
+    entries: HashMap<u32, (String, u32)>,
+    error: Option<std::io::Error>
+}
+
+impl <W: Write, M: Write> TextMirrorSink<W, M> {
+    /// Writes the log to `inner` and mirrors its records as lines to `mirror`
+    #[must_use]
+    pub fn new(inner: W, mirror: M) -> Self {
+        Self {
+            inner,
+            mirror,
+            pending: Vec::new(),
+            header_read: false,
+            entries: HashMap::new(),
+            error: None
+        }
+    }
+
+    /// The inner sink
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// The error that stopped the mirror, [`None`] while it is healthy
+    #[must_use]
+    pub const fn mirror_error(&self) -> Option<&std::io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Returns the inner sink and the mirror
+    pub fn into_parts(self) -> (W, M) {
+        (self.inner, self.mirror)
+    }
+
+    /// Mirrors every whole record in `pending`
+    fn mirror_records(&mut self) -> std::io::Result<()> {
+        let mut consumed = if self.header_read {
+            0
+        } else {
+            let Some(len) = header_len(&self.pending) else {
+                return Ok(());
+            };
+            self.header_read = true;
+            len
+        };
+
+        while let Some((id, timestamp, payload, len)) = split_record(&self.pending[consumed..]) {
+            consumed += len;
+            if id == 0 {
+                match ControlRecord::from_binary(payload) {
+                    Ok((ControlRecord::Start(name, entry_type, _), entry_id)) => {
+                        writeln!(self.mirror, "t={} start {name} ({entry_type})", seconds(timestamp))?;
+                        let serial = get_str_type_serial(&entry_type);
+                        let serial = if SUPPORTED_TYPES_SERIALS.contains(&serial) { serial } else { RAW_TYPE_SERIAL };
+                        let _ = self.entries.insert(entry_id, (name, serial));
+                    }
+                    Ok((ControlRecord::Finish, entry_id)) => {
+                        if let Some((name, _)) = self.entries.remove(&entry_id) {
+                            writeln!(self.mirror, "t={} finish {name}", seconds(timestamp))?;
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            let Some((name, serial)) = self.entries.get(&id) else {
+                continue;
+            };
+            // raw payloads are only measured, not copied
+            match DataRecordRef::from_binary(payload, *serial) {
+                Ok(DataRecordRef::Raw(_)) | Err(_) => {
+                    writeln!(self.mirror, "t={} {name} = <{} bytes>", seconds(timestamp), payload.len())?;
+                }
+                Ok(value) => {
+                    let value = DataRecord::from(value).into_frc_value();
+                    writeln!(self.mirror, "t={} {name} = {value}", seconds(timestamp))?;
+                }
+            }
+        }
+        let _ = self.pending.drain(..consumed);
+        Ok(())
+    }
+}
+
+/// A timestamp in microseconds as seconds with millisecond precision
+fn seconds(timestamp: FrcTimestamp) -> String {
+    format!("{}.{:03}", timestamp / 1_000_000, timestamp % 1_000_000 / 1_000)
+}
+
+impl <W: Write, M: Write> Write for TextMirrorSink<W, M> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        if self.error.is_none() {
+            self.pending.extend_from_slice(&buf[..written]);
+            if let Err(err) = self.mirror_records() {
+                self.pending = Vec::new();
+                self.error = Some(err);
+            }
+        }
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.error.is_none() {
+            if let Err(err) = self.mirror.flush() {
+                self.error = Some(err);
+            }
+        }
+        self.inner.flush()
+    }
+}
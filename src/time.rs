@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use frclib_core::value::FrcTimestamp;
+
+/// The number of microseconds, the unit of every [`FrcTimestamp`], in a second
+pub const MICROS_PER_SECOND: u64 = 1_000_000;
+
+/// Converts a timestamp to the time since robot start it stands for
+#[must_use]
+pub const fn to_duration(timestamp: FrcTimestamp) -> Duration {
+    Duration::from_micros(timestamp)
+}
+
+/// Converts a time since robot start to a timestamp, times too long for a timestamp become [`FrcTimestamp::MAX`]
+#[must_use]
+pub fn from_duration(duration: Duration) -> FrcTimestamp {
+    duration.as_micros().try_into().unwrap_or(FrcTimestamp::MAX)
+}
+
+/// Converts a timestamp to seconds since robot start, the unit most plots and `WPILib` APIs use
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn to_seconds(timestamp: FrcTimestamp) -> f64 {
+    // split so timestamps past 2^53 micros only lose precision in the fraction
+    (timestamp / MICROS_PER_SECOND) as f64 + (timestamp % MICROS_PER_SECOND) as f64 / 1e6
+}
+
+/// Converts seconds since robot start to a timestamp rounded to the nearest microsecond,
+/// negative and NaN seconds become 0 and seconds too long for a timestamp become [`FrcTimestamp::MAX`]
+#[must_use]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+pub fn from_seconds(seconds: f64) -> FrcTimestamp {
+    // float to int casts saturate and turn NaN into 0
+    (seconds * 1e6).round() as FrcTimestamp
+}
+
+/// Formats a timestamp as seconds with every microsecond, like `83.250000`,
+/// without going through a float so the digits are exact
+#[must_use]
+pub fn format_seconds(timestamp: FrcTimestamp) -> String {
+    format!("{}.{:06}", timestamp / MICROS_PER_SECOND, timestamp % MICROS_PER_SECOND)
+}
+
+/// Formats a timestamp as a clock with milliseconds, like `1:23.250`,
+/// with hours added once the time reaches one, like `2:01:23.250`
+#[must_use]
+pub fn format_clock(timestamp: FrcTimestamp) -> String {
+    let millis = timestamp / 1_000 % 1_000;
+    let secs = timestamp / MICROS_PER_SECOND;
+    let (hours, minutes, secs) = (secs / 3_600, secs % 3_600 / 60, secs % 60);
+    if hours > 0 {
+        format!("{hours}:{minutes:02}:{secs:02}.{millis:03}")
+    } else {
+        format!("{minutes}:{secs:02}.{millis:03}")
+    }
+}
+
+/// Converts a timestamp to a date, given the date of robot start that timestamps count from,
+/// returns [`None`] if the date is out of the range `chrono` supports
+#[cfg(feature = "chrono")]
+#[must_use]
+pub fn to_date_time(timestamp: FrcTimestamp, robot_start: chrono::DateTime<chrono::Utc>) -> Option<chrono::DateTime<chrono::Utc>> {
+    robot_start.checked_add_signed(chrono::TimeDelta::microseconds(i64::try_from(timestamp).ok()?))
+}
+
+/// Converts a date to a timestamp, given the date of robot start that timestamps count from,
+/// dates before robot start become 0 and dates too far after it become [`FrcTimestamp::MAX`]
+#[cfg(feature = "chrono")]
+#[must_use]
+pub fn from_date_time(time: chrono::DateTime<chrono::Utc>, robot_start: chrono::DateTime<chrono::Utc>) -> FrcTimestamp {
+    if time <= robot_start {
+        return 0;
+    }
+    (time - robot_start).num_microseconds().map_or(FrcTimestamp::MAX, i64::unsigned_abs)
+}
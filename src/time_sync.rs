@@ -0,0 +1,178 @@
+use std::{io::Write, time::{Duration, SystemTime}};
+
+use frclib_core::value::FrcTimestamp;
+
+use crate::{now, proto::records::{header_len, split_record, write_encoded_data_record}, time::from_duration};
+
+/// How many bytes a [`RebasingSink`] holds while waiting for a time base by default, 4 MiB
+pub const DEFAULT_MAX_HELD: usize = 4 * 1024 * 1024;
+
+/// Converts a time since robot start, the epoch of the timestamps writers take, to a timestamp in microseconds
+#[must_use]
+#[deprecated(note = "use `time::from_duration`, which does the same conversion")]
+pub fn timestamp_from_duration(since_start: Duration) -> FrcTimestamp {
+    from_duration(since_start)
+}
+
+/// Converts a system time to a timestamp since robot start by how far it is from the current time,
+/// times from before robot start become 0
+#[must_use]
+pub fn timestamp_from_system_time(time: SystemTime) -> FrcTimestamp {
+    let timestamp = now();
+    match SystemTime::now().duration_since(time) {
+        Ok(ago) => timestamp.saturating_sub(from_duration(ago)),
+        Err(ahead) => timestamp.saturating_add(from_duration(ahead.duration()))
+    }
+}
+
+/// A sink that holds the log until a time base is known, then shifts every timestamp onto it
+///
+/// Robot programs usually start logging before the driver station or FMS gives them the real time,
+/// records written before then are kept in memory and written with the offset applied once
+/// [`DataLogWriter::set_time_base`](crate::DataLogWriter::set_time_base) is called,
+/// every later record is shifted by the same offset so the log stays monotonic.
+///
+/// Nothing reaches the inner sink while holding, so a crash before the time base is known loses the held records.
+/// If more than the held limit is written first the sink gives up waiting and passes records through unshifted.
+/// # Example
+/// ```rust,no_run
+/// use std::fs::File;
+/// use frclib_datalog::{time_sync::RebasingSink, DataLogWriter};
+///
+/// let file = File::create("/home/lvuser/logs/match.wpilog").expect("Failed to create file");
+/// let mut writer = DataLogWriter::new(RebasingSink::new(file), "").expect("Failed to create writer");
+/// let entry = writer.get_entry::<f64>("test", None).expect("Failed to get entry");
+/// writer.write(entry, 10.0).expect("Failed to write entry");
+///
+/// // once the driver station has set the clock
+/// writer.sync_time_base_to_system_clock().expect("Failed to set time base");
+/// ```
+#[derive(Debug)]
+pub struct RebasingSink<W: Write> {
+    inner: W,
+    /// Bytes not passed on yet, the held records or a record cut off between writes
+    pending: Vec<u8>,
+    scratch: Vec<u8>,
+    header_written: bool,
+    offset: Option<i64>,
+    max_held: usize
+}
+
+impl <W: Write> RebasingSink<W> {
+    /// Creates a sink that holds everything written to it until a time base is set
+    #[must_use]
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            pending: Vec::new(),
+            scratch: Vec::new(),
+            header_written: false,
+            offset: None,
+            max_held: DEFAULT_MAX_HELD
+        }
+    }
+
+    /// Sets how many bytes are held before giving up on a time base, defaults to [`DEFAULT_MAX_HELD`]
+    #[must_use]
+    pub const fn with_max_held(mut self, max_held: usize) -> Self {
+        self.max_held = max_held;
+        self
+    }
+
+    /// Whether records are still held waiting for a time base
+    #[must_use]
+    pub const fn is_holding(&self) -> bool {
+        self.offset.is_none()
+    }
+
+    /// The offset added to every timestamp, [`None`] while holding
+    #[must_use]
+    pub const fn offset(&self) -> Option<i64> {
+        self.offset
+    }
+
+    /// The inner sink
+    #[must_use]
+    pub const fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Sets the offset added to every timestamp, returning whether it was set.
+    ///
+    /// The offset can only be set once, it isn't set if the sink already has one
+    /// or gave up waiting for it.
+    ///
+    /// # Errors
+    /// - an IO error if passing the held records on fails
+    pub fn set_offset(&mut self, offset: i64) -> std::io::Result<bool> {
+        if self.offset.is_some() {
+            return Ok(false);
+        }
+        self.offset = Some(offset);
+        self.pass_on()?;
+        Ok(true)
+    }
+
+    /// Passes everything on, without an offset if there is still none, and returns the inner sink
+    ///
+    /// # Errors
+    /// - an IO error if passing the held records on fails
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let _ = self.set_offset(0)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+
+    /// Writes the header once `pending` holds all of it, returning its length
+    fn pass_on_header(&mut self) -> std::io::Result<Option<usize>> {
+        let Some(len) = header_len(&self.pending) else {
+            return Ok(None);
+        };
+        let header = &self.pending[..len];
+        self.inner.write_all(header)?;
+        self.header_written = true;
+        Ok(Some(header.len()))
+    }
+
+    /// Writes every whole record in `pending` to the inner sink with the offset applied
+    fn pass_on(&mut self) -> std::io::Result<()> {
+        let Some(offset) = self.offset else {
+            if self.pending.len() > self.max_held {
+                return self.set_offset(0).map(|_| ());
+            }
+            return Ok(());
+        };
+
+        let mut consumed = if self.header_written {
+            0
+        } else {
+            match self.pass_on_header()? {
+                Some(len) => len,
+                None => return Ok(())
+            }
+        };
+
+        self.scratch.clear();
+        while let Some((id, timestamp, payload, len)) = split_record(&self.pending[consumed..]) {
+            write_encoded_data_record(timestamp.saturating_add_signed(offset), id, payload, &mut self.scratch)
+                .map_err(std::io::Error::other)?;
+            consumed += len;
+        }
+        self.inner.write_all(&self.scratch)?;
+        let _ = self.pending.drain(..consumed);
+        Ok(())
+    }
+}
+
+impl <W: Write> Write for RebasingSink<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.pending.extend_from_slice(buf);
+        self.pass_on()?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.pass_on()?;
+        self.inner.flush()
+    }
+}
@@ -0,0 +1,160 @@
+use std::{io::Write, sync::atomic::{AtomicU64, Ordering}};
+
+use frclib_core::value::{FrcTimestampedValue, FrcValue};
+use tracing::{field::{Field, Visit}, span::{Attributes, Id, Record}, Event, Metadata, Subscriber};
+use tracing_subscriber::{layer::Context, registry::LookupSpan, Layer};
+
+use crate::{handle::DataLogHandle, now};
+
+/// The prefix of every key written by a [`DataLogLayer`]
+pub const TRACING_KEY_PREFIX: &str = "/tracing";
+
+/// A [`tracing_subscriber`] layer that records events and spans into a datalog,
+/// so application logs line up with the telemetry written next to them
+///
+/// Events are written under `/tracing/<target>`, with `::` in the target turned into `/`:
+/// the level to `level`, the message to `message` and every other field to an entry of its name.
+/// Span fields are written under `/tracing/spans/<span name>` along with an `active` entry
+/// that is `true` while the span is entered, spans sharing a name share their entries.
+///
+/// A field keeps the type it was first written with,
+/// values of another type are dropped and counted in [`DataLogLayer::dropped`] like any write that fails.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::{handle::DataLogHandle, tracing_layer::DataLogLayer, DataLogWriter};
+/// use tracing_subscriber::layer::SubscriberExt;
+///
+/// let writer = DataLogWriter::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create writer");
+/// let handle = DataLogHandle::new(writer);
+/// let subscriber = tracing_subscriber::registry().with(DataLogLayer::new(handle.clone()));
+/// tracing::subscriber::set_global_default(subscriber).expect("Failed to set subscriber");
+///
+/// // writes /tracing/<crate>/level, /tracing/<crate>/message and /tracing/<crate>/voltage
+/// tracing::warn!(voltage = 7.2, "Brownout");
+/// ```
+#[derive(Debug)]
+pub struct DataLogLayer<W: Write = std::fs::File> {
+    handle: DataLogHandle<W>,
+    dropped: AtomicU64
+}
+
+impl <W: Write> DataLogLayer<W> {
+    /// Creates a layer writing to the log behind `handle`
+    #[must_use]
+    pub const fn new(handle: DataLogHandle<W>) -> Self {
+        Self {
+            handle,
+            dropped: AtomicU64::new(0)
+        }
+    }
+
+    /// The number of field values that couldn't be written
+    #[must_use]
+    pub fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// The handle the layer writes to
+    #[must_use]
+    pub const fn handle(&self) -> &DataLogHandle<W> {
+        &self.handle
+    }
+
+    /// Writes every field to `<base>/<field name>`
+    fn record(&self, base: &str, fields: Vec<(&'static str, FrcValue)>) {
+        let timestamp = now();
+        let mut writer = self.handle.lock();
+        let failed = fields.into_iter()
+            .filter(|(name, value)| writer.get_entry_dynamic(&format!("{base}/{name}"), value.get_type(), None)
+                .and_then(|id| writer.write_dynamic(id, FrcTimestampedValue::new(timestamp, value.clone())))
+                .is_err())
+            .count();
+        drop(writer);
+        let _ = self.dropped.fetch_add(failed as u64, Ordering::Relaxed);
+    }
+}
+
+/// The key events of a target are written under
+fn event_key(metadata: &Metadata<'_>) -> String {
+    format!("{TRACING_KEY_PREFIX}/{}", metadata.target().replace("::", "/"))
+}
+
+/// The key the fields of a span are written under
+fn span_key(metadata: &Metadata<'_>) -> String {
+    format!("{TRACING_KEY_PREFIX}/spans/{}", metadata.name())
+}
+
+/// Collects the fields of an event or span as values
+#[derive(Debug, Default)]
+struct FieldVisitor {
+    fields: Vec<(&'static str, FrcValue)>
+}
+
+impl Visit for FieldVisitor {
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.fields.push((field.name(), FrcValue::Double(value)));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.push((field.name(), FrcValue::Int(value)));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        // logs only hold signed integers
+        self.fields.push((field.name(), FrcValue::Int(i64::try_from(value).unwrap_or(i64::MAX))));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.push((field.name(), FrcValue::Boolean(value)));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.fields.push((field.name(), FrcValue::String(value.into())));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.fields.push((field.name(), FrcValue::String(format!("{value:?}").into())));
+    }
+}
+
+impl <S, W> Layer<S> for DataLogLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: Write + Send + 'static
+{
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        visitor.fields.push(("level", FrcValue::String(event.metadata().level().as_str().into())));
+        event.record(&mut visitor);
+        self.record(&event_key(event.metadata()), visitor.fields);
+    }
+
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        let mut visitor = FieldVisitor::default();
+        attrs.record(&mut visitor);
+        if !visitor.fields.is_empty() {
+            self.record(&span_key(attrs.metadata()), visitor.fields);
+        }
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(metadata) = ctx.metadata(id) else {
+            return;
+        };
+        let mut visitor = FieldVisitor::default();
+        values.record(&mut visitor);
+        self.record(&span_key(metadata), visitor.fields);
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(metadata) = ctx.metadata(id) {
+            self.record(&span_key(metadata), vec![("active", FrcValue::Boolean(true))]);
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        if let Some(metadata) = ctx.metadata(id) {
+            self.record(&span_key(metadata), vec![("active", FrcValue::Boolean(false))]);
+        }
+    }
+}
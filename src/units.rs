@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use frclib_core::value::FrcValue;
+
+use crate::DataLogError;
+
+/// The metadata field conventionally used to declare the unit of an entry
+pub const UNIT_METADATA_KEY: &str = "unit";
+
+/// How a single unit relates to the base unit of its dimension
+///
+/// A value `x` in this unit is `x * scale + offset` in the base unit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnitDefinition {
+    /// The dimension this unit measures, units can only be converted within a dimension
+    pub dimension: &'static str,
+    /// The multiplier to get to the base unit
+    pub scale: f64,
+    /// The offset to get to the base unit, only non zero for things like temperatures
+    pub offset: f64,
+}
+
+impl UnitDefinition {
+    /// Creates a new unit definition with no offset
+    #[must_use]
+    pub const fn new(dimension: &'static str, scale: f64) -> Self {
+        Self {
+            dimension,
+            scale,
+            offset: 0.0
+        }
+    }
+
+    /// Creates a new unit definition with an offset
+    #[must_use]
+    pub const fn with_offset(dimension: &'static str, scale: f64, offset: f64) -> Self {
+        Self {
+            dimension,
+            scale,
+            offset
+        }
+    }
+}
+
+/// A lookup table of unit names to their definitions
+///
+/// The [`Default`] table contains the common units used in FRC,
+/// more can be registered with [`UnitTable::insert`]
+#[derive(Debug, Clone)]
+pub struct UnitTable {
+    units: HashMap<String, UnitDefinition>
+}
+
+impl UnitTable {
+    /// Creates a table with no units in it
+    #[must_use]
+    pub fn empty() -> Self {
+        Self {
+            units: HashMap::new()
+        }
+    }
+
+    /// Adds or replaces a unit, returning the previous definition if there was one
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn insert(&mut self, name: impl ToString, definition: UnitDefinition) -> Option<UnitDefinition> {
+        self.units.insert(name.to_string(), definition)
+    }
+
+    /// Adds or replaces a unit under multiple names
+    pub fn insert_aliases(&mut self, names: &[&str], definition: UnitDefinition) {
+        for name in names {
+            let _ = self.insert(name, definition);
+        }
+    }
+
+    /// Gets the definition of a unit
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&UnitDefinition> {
+        self.units.get(name)
+    }
+
+    /// Gets a function that converts from one unit to another
+    ///
+    /// # Errors
+    /// - [`DataLogError::UnitConversion`] if either unit is unknown or the units measure different dimensions
+    pub fn converter(&self, from: &str, to: &str) -> Result<impl Fn(f64) -> f64, DataLogError> {
+        let from = self.get(from)
+            .ok_or(DataLogError::UnitConversion("Unknown source unit"))?;
+        let to = self.get(to)
+            .ok_or(DataLogError::UnitConversion("Unknown target unit"))?;
+        if from.dimension != to.dimension {
+            return Err(DataLogError::UnitConversion("Units have different dimensions"));
+        }
+        let (from, to) = (*from, *to);
+        Ok(move |value: f64| (value.mul_add(from.scale, from.offset) - to.offset) / to.scale)
+    }
+
+    /// Converts a value from one unit to another
+    ///
+    /// # Errors
+    /// - [`DataLogError::UnitConversion`] if either unit is unknown or the units measure different dimensions
+    pub fn convert(&self, value: f64, from: &str, to: &str) -> Result<f64, DataLogError> {
+        self.converter(from, to).map(|convert| convert(value))
+    }
+}
+
+impl Default for UnitTable {
+    fn default() -> Self {
+        use std::f64::consts::PI;
+        let mut table = Self::empty();
+
+        table.insert_aliases(&["m", "meter", "meters"], UnitDefinition::new("length", 1.0));
+        table.insert_aliases(&["cm", "centimeter", "centimeters"], UnitDefinition::new("length", 0.01));
+        table.insert_aliases(&["mm", "millimeter", "millimeters"], UnitDefinition::new("length", 0.001));
+        table.insert_aliases(&["in", "inch", "inches"], UnitDefinition::new("length", 0.0254));
+        table.insert_aliases(&["ft", "foot", "feet"], UnitDefinition::new("length", 0.3048));
+
+        table.insert_aliases(&["rad", "radian", "radians"], UnitDefinition::new("angle", 1.0));
+        table.insert_aliases(&["deg", "degree", "degrees"], UnitDefinition::new("angle", PI / 180.0));
+        table.insert_aliases(&["rot", "rotation", "rotations"], UnitDefinition::new("angle", 2.0 * PI));
+
+        table.insert_aliases(&["s", "second", "seconds"], UnitDefinition::new("time", 1.0));
+        table.insert_aliases(&["ms", "millisecond", "milliseconds"], UnitDefinition::new("time", 1e-3));
+        table.insert_aliases(&["us", "microsecond", "microseconds"], UnitDefinition::new("time", 1e-6));
+
+        table.insert_aliases(&["m/s", "meters per second"], UnitDefinition::new("velocity", 1.0));
+        table.insert_aliases(&["ft/s", "feet per second"], UnitDefinition::new("velocity", 0.3048));
+
+        table.insert_aliases(&["rad/s", "radians per second"], UnitDefinition::new("angular velocity", 1.0));
+        table.insert_aliases(&["deg/s", "degrees per second"], UnitDefinition::new("angular velocity", PI / 180.0));
+        table.insert_aliases(&["rps", "rotations per second"], UnitDefinition::new("angular velocity", 2.0 * PI));
+        table.insert_aliases(&["rpm", "rotations per minute"], UnitDefinition::new("angular velocity", PI / 30.0));
+
+        table.insert_aliases(&["kg", "kilogram", "kilograms"], UnitDefinition::new("mass", 1.0));
+        table.insert_aliases(&["lb", "lbs", "pound", "pounds"], UnitDefinition::new("mass", 0.453_592_37));
+
+        table.insert_aliases(&["C", "celsius"], UnitDefinition::new("temperature", 1.0));
+        table.insert_aliases(&["K", "kelvin"], UnitDefinition::with_offset("temperature", 1.0, -273.15));
+        table.insert_aliases(&["F", "fahrenheit"], UnitDefinition::with_offset("temperature", 5.0 / 9.0, -160.0 / 9.0));
+
+        table.insert_aliases(&["V", "volt", "volts"], UnitDefinition::new("voltage", 1.0));
+        table.insert_aliases(&["A", "amp", "amps"], UnitDefinition::new("current", 1.0));
+
+        table
+    }
+}
+
+/// Reads the unit out of an entries json metadata, if there is one
+#[must_use]
+pub fn unit_from_metadata(metadata: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(metadata).ok()?
+        .get(UNIT_METADATA_KEY)?
+        .as_str()
+        .map(ToString::to_string)
+}
+
+/// Applies a conversion to every number in a value,
+/// integers are widened to doubles so precision isn't lost
+pub(crate) fn convert_value(value: &FrcValue, convert: impl Fn(f64) -> f64) -> Result<FrcValue, DataLogError> {
+    #[allow(clippy::cast_precision_loss, clippy::cast_possible_truncation)]
+    match value {
+        FrcValue::Double(v) => Ok(FrcValue::Double(convert(*v))),
+        FrcValue::Float(v) => Ok(FrcValue::Float(convert(f64::from(*v)) as f32)),
+        FrcValue::Int(v) => Ok(FrcValue::Double(convert(*v as f64))),
+        FrcValue::DoubleArray(v) => Ok(FrcValue::DoubleArray(v.iter().map(|v| convert(*v)).collect())),
+        FrcValue::FloatArray(v) => Ok(FrcValue::FloatArray(v.iter().map(|v| convert(f64::from(*v)) as f32).collect())),
+        FrcValue::IntArray(v) => Ok(FrcValue::DoubleArray(v.iter().map(|v| convert(*v as f64)).collect())),
+        _ => Err(DataLogError::UnitConversion("Value is not numeric"))
+    }
+}
@@ -0,0 +1,375 @@
+use std::{fs::File, io::Write};
+
+use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcValue};
+
+use crate::{handle::DataLogHandle, now, proto::entries::get_type_from_str, writer::EntryId, DataLogError, DataLogWriter};
+
+/// A [`DataLogWriter`] behind the method names and semantics of `wpiutil`'s `DataLog`,
+/// so Java and C++ logging code can be translated line by line
+///
+/// Like in `WPILib` a timestamp of 0 means the current time, and starting an entry that already exists
+/// returns its id. Control records like starting, finishing and metadata are timestamped with the current time.
+/// Clones write to the same log, see [`DataLogHandle`] for the native way of sharing a writer.
+/// # Example
+/// ```rust,no_run
+/// use frclib_datalog::wpilib_compat::{DataLog, DoubleLogEntry};
+///
+/// let log = DataLog::create("/home/lvuser/logs/match.wpilog", "").expect("Failed to create log");
+///
+/// // DataLog.start / appendDouble
+/// let entry = log.start("/arm/angle", "double", "").expect("Failed to start entry");
+/// log.append_double(entry, 0.5, 0).expect("Failed to append value");
+///
+/// // new DoubleLogEntry(log, "/arm/velocity") / update
+/// let mut velocity = DoubleLogEntry::new(&log, "/arm/velocity", "").expect("Failed to start entry");
+/// velocity.update(1.5, 0).expect("Failed to append value");
+/// ```
+#[derive(Debug)]
+pub struct DataLog<W: Write = File> {
+    handle: DataLogHandle<W>
+}
+
+impl <W: Write> Clone for DataLog<W> {
+    fn clone(&self) -> Self {
+        Self {
+            handle: self.handle.clone()
+        }
+    }
+}
+
+impl <W: Write> From<DataLogHandle<W>> for DataLog<W> {
+    fn from(handle: DataLogHandle<W>) -> Self {
+        Self { handle }
+    }
+}
+
+impl DataLog<File> {
+    /// Creates a log at `path`, see [`DataLogWriter::create`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at `path`
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn create(path: impl AsRef<std::path::Path>, metadata: impl ToString) -> Result<Self, DataLogError> {
+        DataLogWriter::create(path, metadata).map(Self::new)
+    }
+}
+
+impl <W: Write> DataLog<W> {
+    /// Wraps a writer
+    #[must_use]
+    pub fn new(writer: DataLogWriter<W>) -> Self {
+        Self {
+            handle: DataLogHandle::new(writer)
+        }
+    }
+
+    /// The handle of the writer, to mix in the native API
+    #[must_use]
+    pub const fn handle(&self) -> &DataLogHandle<W> {
+        &self.handle
+    }
+
+    /// Starts an entry of the type `entry_type`, like `double` or `struct:Pose2d`, returning its id.
+    ///
+    /// Type strings this crate doesn't know, including structs that aren't registered,
+    /// start a raw entry written with [`DataLog::append_raw`].
+    ///
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the type string is empty
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn start(&self, name: &str, entry_type: &str, metadata: &str) -> Result<EntryId, DataLogError> {
+        let metadata = Some(metadata.to_string());
+        let mut writer = self.handle.lock();
+        match get_type_from_str(entry_type) {
+            Some(entry_type) => writer.get_entry_dynamic(name, entry_type, metadata),
+            None => writer.get_entry_raw(name, entry_type, metadata)
+        }
+    }
+
+    /// Finishes an entry, see [`DataLogWriter::close_entry`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was already finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn finish(&self, entry: EntryId) -> Result<(), DataLogError> {
+        self.handle.close_entry(entry)
+    }
+
+    /// Replaces the metadata of an entry, see [`DataLogWriter::set_entry_metadata`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn set_metadata(&self, entry: EntryId, metadata: &str) -> Result<(), DataLogError> {
+        self.handle.lock().set_entry_metadata(entry, metadata)
+    }
+
+    /// Flushes the log, see [`DataLogWriter::flush`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn flush(&self) -> Result<(), DataLogError> {
+        self.handle.flush()
+    }
+
+    /// Appends already encoded bytes to a raw entry, see [`DataLogWriter::write_raw`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold raw bytes
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_raw(&self, entry: EntryId, value: &[u8], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().write_raw(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `boolean` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `boolean` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_boolean(&self, entry: EntryId, value: bool, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_boolean(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to an `int64` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `int64` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_integer(&self, entry: EntryId, value: i64, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_int(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `float` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `float` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_float(&self, entry: EntryId, value: f32, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_float(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `double` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `double` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_double(&self, entry: EntryId, value: f64, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_double(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `string` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `string` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_string(&self, entry: EntryId, value: &str, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_string(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `boolean[]` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `boolean[]` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_boolean_array(&self, entry: EntryId, value: &[bool], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_boolean_slice(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to an `int64[]` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `int64[]` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_integer_array(&self, entry: EntryId, value: &[i64], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_int_slice(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `float[]` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `float[]` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_float_array(&self, entry: EntryId, value: &[f32], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_float_slice(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `double[]` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `double[]` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_double_array(&self, entry: EntryId, value: &[f64], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.handle.lock().append_double_slice(entry, value, or_now(timestamp))
+    }
+
+    /// Appends a value to a `string[]` entry
+    ///
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `string[]` values
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn append_string_array(&self, entry: EntryId, value: &[String], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        let value = FrcValue::StringArray(value.iter().map(|string| string.as_str().into()).collect());
+        self.handle.write_dynamic(entry, FrcTimestampedValue::new(or_now(timestamp), value))
+    }
+}
+
+/// `WPILib` treats a timestamp of 0 as the current time
+fn or_now(timestamp: FrcTimestamp) -> FrcTimestamp {
+    if timestamp == 0 {
+        now()
+    } else {
+        timestamp
+    }
+}
+
+macro_rules! log_entry {
+    ($(#[$doc:meta])* $name:ident, $type_str:literal, $value:ty, $last:ty, $append:ident) => {
+        $(#[$doc])*
+        #[derive(Debug)]
+        pub struct $name<W: Write = File> {
+            log: DataLog<W>,
+            entry: EntryId,
+            last: Option<$last>
+        }
+
+        impl <W: Write> $name<W> {
+            #[doc = concat!("Starts a `", $type_str, "` entry, see [`DataLog::start`]")]
+            ///
+            /// # Errors
+            /// - [`DataLogError::EntryTypeMismatch`] if the entry already exists with a different type
+            /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+            /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+            /// - [`DataLogError::Io`] if an IO error occurs
+            pub fn new(log: &DataLog<W>, name: &str, metadata: &str) -> Result<Self, DataLogError> {
+                Ok(Self {
+                    log: log.clone(),
+                    entry: log.start(name, $type_str, metadata)?,
+                    last: None
+                })
+            }
+
+            /// The id of the entry, for use with the [`DataLog`]
+            #[must_use]
+            pub const fn entry(&self) -> EntryId {
+                self.entry
+            }
+
+            /// Appends a value, a timestamp of 0 is the current time
+            ///
+            /// # Errors
+            /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+            /// - [`DataLogError::Io`] if an IO error occurs
+            pub fn append(&self, value: $value, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+                self.log.$append(self.entry, value, timestamp)
+            }
+
+            /// Appends a value if it differs from the last value passed to this,
+            /// a timestamp of 0 is the current time
+            ///
+            /// # Errors
+            /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+            /// - [`DataLogError::Io`] if an IO error occurs
+            // any change is logged, like WPILib compares values exactly
+            #[allow(clippy::float_cmp)]
+            pub fn update(&mut self, value: $value, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+                if self.last.as_ref().is_some_and(|last| *last == value) {
+                    return Ok(());
+                }
+                self.last = Some(<$last>::from(value));
+                self.append(value, timestamp)
+            }
+
+            /// Whether a value has been passed to [`update`](Self::update)
+            #[must_use]
+            pub const fn has_last_value(&self) -> bool {
+                self.last.is_some()
+            }
+
+            /// The last value passed to [`update`](Self::update)
+            #[must_use]
+            pub const fn get_last_value(&self) -> Option<&$last> {
+                self.last.as_ref()
+            }
+
+            /// Replaces the metadata of the entry
+            ///
+            /// # Errors
+            /// - [`DataLogError::OutsideEntryLifetime`] if the entry was finished
+            /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+            /// - [`DataLogError::Io`] if an IO error occurs
+            pub fn set_metadata(&self, metadata: &str) -> Result<(), DataLogError> {
+                self.log.set_metadata(self.entry, metadata)
+            }
+
+            /// Finishes the entry
+            ///
+            /// # Errors
+            /// - [`DataLogError::OutsideEntryLifetime`] if the entry was already finished
+            /// - [`DataLogError::Io`] if an IO error occurs
+            pub fn finish(self) -> Result<(), DataLogError> {
+                self.log.finish(self.entry)
+            }
+        }
+    };
+}
+
+log_entry!(
+    /// A `raw` entry, like `wpiutil`'s `RawLogEntry`
+    RawLogEntry, "raw", &[u8], Vec<u8>, append_raw
+);
+log_entry!(
+    /// A `boolean` entry, like `wpiutil`'s `BooleanLogEntry`
+    BooleanLogEntry, "boolean", bool, bool, append_boolean
+);
+log_entry!(
+    /// An `int64` entry, like `wpiutil`'s `IntegerLogEntry`
+    IntegerLogEntry, "int64", i64, i64, append_integer
+);
+log_entry!(
+    /// A `float` entry, like `wpiutil`'s `FloatLogEntry`
+    FloatLogEntry, "float", f32, f32, append_float
+);
+log_entry!(
+    /// A `double` entry, like `wpiutil`'s `DoubleLogEntry`
+    DoubleLogEntry, "double", f64, f64, append_double
+);
+log_entry!(
+    /// A `string` entry, like `wpiutil`'s `StringLogEntry`
+    StringLogEntry, "string", &str, String, append_string
+);
+log_entry!(
+    /// A `boolean[]` entry, like `wpiutil`'s `BooleanArrayLogEntry`
+    BooleanArrayLogEntry, "boolean[]", &[bool], Vec<bool>, append_boolean_array
+);
+log_entry!(
+    /// An `int64[]` entry, like `wpiutil`'s `IntegerArrayLogEntry`
+    IntegerArrayLogEntry, "int64[]", &[i64], Vec<i64>, append_integer_array
+);
+log_entry!(
+    /// A `float[]` entry, like `wpiutil`'s `FloatArrayLogEntry`
+    FloatArrayLogEntry, "float[]", &[f32], Vec<f32>, append_float_array
+);
+log_entry!(
+    /// A `double[]` entry, like `wpiutil`'s `DoubleArrayLogEntry`
+    DoubleArrayLogEntry, "double[]", &[f64], Vec<f64>, append_double_array
+);
+log_entry!(
+    /// A `string[]` entry, like `wpiutil`'s `StringArrayLogEntry`
+    StringArrayLogEntry, "string[]", &[String], Vec<String>, append_string_array
+);
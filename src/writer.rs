@@ -1,14 +1,23 @@
-use std::{collections::HashMap, fs::File, io::Write, num::NonZeroU32, sync::atomic::{AtomicU32, Ordering}};
+use std::{collections::{BTreeMap, HashMap, HashSet}, fs::File, io::{Read, Write}, num::{NonZeroU32, NonZeroU64}, path::{Path, PathBuf}, sync::atomic::{AtomicU32, Ordering}, time::{Duration, Instant, SystemTime}};
 
 use byteorder::WriteBytesExt;
-use frclib_core::value::{FrcTimestamp, FrcTimestampedValue, FrcType, FrcValue, IntoFrcValue, StaticallyFrcTyped};
+use frclib_core::{structure::{FrcStructDesc, FrcStructDescDB}, value::{FrcTimestamp, FrcTimestampedValue, FrcType, FrcValue, IntoFrcValue, StaticallyFrcTyped}};
 
-use crate::{now, proto::{entries::{get_data_type, get_data_type_serial, EntryLifeStatus}, records::{ControlRecord, DataRecord}}, DataLogError};
+#[cfg(feature = "encryption")]
+use crate::encryption::EncryptedSink;
+#[cfg(feature = "integrity")]
+use crate::integrity::IntegritySink;
+use crate::{locking::create_locked, loggable::Loggable, messages::{format_message, MessageLevel, MESSAGES_KEY}, now, proto::{entries::{get_data_type_serial, get_full_type_str, get_type_from_str, type_str_matches, EntryLifeStatus, DOUBLE_ARRAY_TYPE_SERIAL, DOUBLE_TYPE_SERIAL, FLOAT_ARRAY_TYPE_SERIAL, FLOAT_TYPE_SERIAL}, records::{encode_payload, write_encoded_data_record_with_width, ControlRecord, EncodedRecordHeader, HeaderWidth}}, arming::ArmableSink, statistics::{as_number, RollingStatistics, RollingWindow}, dry_run::CountingSink, manifest::EntryManifest, scoped::ScopedDataLogWriter, temp_file::TempFileSink, time::from_duration, time_sync::{timestamp_from_system_time, RebasingSink}, DataLogError, DataRecord, Record};
 
 static DATALOG_INCREMENTER: AtomicU32 = AtomicU32::new(1);
 
-const WPILOG_MAGIC: [u8; 6] = *b"WPILOG";
-const WPILOG_VERSION: [u8; 2] = [0, 1];
+pub(crate) const WPILOG_MAGIC: [u8; 6] = *b"WPILOG";
+/// How many bytes of a series are encoded before they are written to the sink
+const SERIES_CHUNK_LEN: usize = 64 * 1024;
+/// The capacity [`std::io::BufWriter::new`] uses
+const DEFAULT_BUFFER_CAPACITY: usize = 8 * 1024;
+/// The version of the format written by default as (major, minor)
+pub const WPILOG_VERSION: (u8, u8) = (1, 0);
 
 /// A unique identifier for a data entry in a specific datalog
 #[derive(Debug, Clone, Copy)]
@@ -18,13 +27,21 @@ pub struct EntryId {
 }
 
 /// A unique identifier for a data entry in a specific datalog
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct TypedEntryId<T: IntoFrcValue> {
     datalog_id: u32,
     entry_id: u32,
     _phantom: std::marker::PhantomData<T>
 }
 
+// manual impls so non `Copy` value types like `Vec<f64>` still have `Copy` ids
+impl <T: IntoFrcValue> Clone for TypedEntryId<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+impl <T: IntoFrcValue> Copy for TypedEntryId<T> {}
+
 impl <T: IntoFrcValue> From<TypedEntryId<T>> for EntryId {
     fn from(value: TypedEntryId<T>) -> Self {
         Self::new(value.datalog_id, value.entry_id)
@@ -49,49 +66,306 @@ impl EntryId {
         }
     }
 
-    const fn typed<T: IntoFrcValue>(self) -> TypedEntryId<T> {
+    pub(crate) const fn datalog_id(self) -> u32 {
+        self.datalog_id
+    }
+
+    pub(crate) const fn entry_id(self) -> u32 {
+        self.entry_id
+    }
+
+    pub(crate) const fn typed<T: IntoFrcValue>(self) -> TypedEntryId<T> {
         TypedEntryId::new(self.datalog_id, self.entry_id)
     }
 }
 
+/// The entries [`DataLogWriter::add_rolling_statistics`] logs the aggregates of an entry to
+#[derive(Debug, Clone, Copy)]
+pub struct RollingStatisticsEntries {
+    /// The mean over the window, under `<key>_mean`
+    pub mean: EntryId,
+    /// The smallest value in the window, under `<key>_min`
+    pub min: EntryId,
+    /// The largest value in the window, under `<key>_max`
+    pub max: EntryId
+}
+
+/// The rolling aggregates of an entry and the entries they are written to,
+/// [`None`] once an aggregate entry is closed
+#[derive(Debug)]
+struct DerivedChannels {
+    statistics: RollingStatistics,
+    mean: Option<u32>,
+    min: Option<u32>,
+    max: Option<u32>
+}
+
+/// The type of the values an entry holds
+///
+/// Values are checked against the serial first and then against the full type string,
+/// so type strings whose serials collide can't be mixed up.
+#[derive(Debug, Clone)]
+struct ValueType {
+    serial: NonZeroU32,
+    /// The full type string, `raw` for raw entries with a custom type string
+    type_str: String
+}
+
+impl ValueType {
+    fn of(ty: &FrcType) -> Option<Self> {
+        Some(Self {
+            serial: get_data_type_serial(ty),
+            type_str: get_full_type_str(ty)?
+        })
+    }
+
+    fn raw() -> Self {
+        Self {
+            serial: get_data_type_serial(&FrcType::Raw),
+            type_str: "raw".to_string()
+        }
+    }
+
+    fn matches(&self, ty: &FrcType) -> bool {
+        self.serial == get_data_type_serial(ty) && type_str_matches(ty, &self.type_str)
+    }
+}
+
 #[derive(Debug)]
 struct EntryData {
     key: String,
     entry_type: String,
-    prehashed_type: NonZeroU32,
+    metadata: String,
+    value_type: ValueType,
     lifestatus: EntryLifeStatus,
-    packing_buffer: Vec<u8>
+    packing_buffer: Vec<u8>,
+    /// The last value written, only kept when the entry is set to log on change
+    last_value: Option<FrcValue>,
+    /// The latest value written with its timestamp, only kept when the writer caches them
+    latest: Option<FrcTimestampedValue>,
+    log_on_change: bool,
+    /// The latest timestamp written
+    last_timestamp: Option<FrcTimestamp>,
+    /// How far apart timestamps are assigned when the entry uses sequence timestamps,
+    /// see [`DataLogWriter::set_sequence_timestamps`]
+    sequence_step: Option<NonZeroU64>,
+    /// The number of timestamps older than the latest one
+    out_of_order: u64,
+    /// The number of values holding NaN or infinite floats
+    non_finite: u64,
+    /// Entries that get a record of every value written to this one, see [`DataLogWriter::alias_entry`]
+    aliases: Vec<u32>,
+    /// Rolling aggregates written along with every value, see [`DataLogWriter::add_rolling_statistics`]
+    derived: Option<Box<DerivedChannels>>
+}
+
+impl EntryData {
+    /// Writes an encoded payload as a record of the entry and of every alias of it
+    fn write_payload(&self, width: HeaderWidth, entry_id: u32, timestamp: FrcTimestamp, payload: &[u8], out: &mut impl Write) -> Result<(), DataLogError> {
+        write_encoded_data_record_with_width(width, timestamp, entry_id, payload, out)?;
+        for alias in &self.aliases {
+            write_encoded_data_record_with_width(width, timestamp, *alias, payload, out)?;
+        }
+        Ok(())
+    }
+
+    /// Adds a value to the rolling aggregates of the entry, if it has any, and writes them
+    fn write_derived(&mut self, width: HeaderWidth, timestamp: FrcTimestamp, value: &FrcValue, out: &mut impl Write) -> Result<(), DataLogError> {
+        let (Some(derived), Some(number)) = (self.derived.as_deref_mut(), as_number(value)) else {
+            return Ok(());
+        };
+        derived.statistics.push(timestamp, number);
+        let aggregates = [
+            (derived.mean, derived.statistics.mean()),
+            (derived.min, derived.statistics.min()),
+            (derived.max, derived.statistics.max())
+        ];
+        for (entry_id, aggregate) in aggregates {
+            if let (Some(entry_id), Some(aggregate)) = (entry_id, aggregate) {
+                write_encoded_data_record_with_width(width, timestamp, entry_id, &aggregate.to_le_bytes(), out)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies the non finite policy to a value about to be written, returning whether to write it
+    fn check_finite(&mut self, value: &mut FrcValue, policy: NonFinitePolicy) -> Result<bool, DataLogError> {
+        let non_finite = match value {
+            FrcValue::Double(v) => !v.is_finite(),
+            FrcValue::Float(v) => !v.is_finite(),
+            FrcValue::DoubleArray(values) => values.iter().any(|v| !v.is_finite()),
+            FrcValue::FloatArray(values) => values.iter().any(|v| !v.is_finite()),
+            _ => false
+        };
+        if !non_finite {
+            return Ok(true);
+        }
+        self.non_finite += 1;
+        match policy {
+            NonFinitePolicy::Allow => Ok(true),
+            NonFinitePolicy::Skip => Ok(false),
+            NonFinitePolicy::Replace(sentinel) => {
+                #[allow(clippy::cast_possible_truncation)]
+                let sentinel_f32 = sentinel as f32;
+                match value {
+                    FrcValue::Double(v) => *v = sentinel,
+                    FrcValue::Float(v) => *v = sentinel_f32,
+                    FrcValue::DoubleArray(values) => values.iter_mut()
+                        .filter(|v| !v.is_finite())
+                        .for_each(|v| *v = sentinel),
+                    FrcValue::FloatArray(values) => values.iter_mut()
+                        .filter(|v| !v.is_finite())
+                        .for_each(|v| *v = sentinel_f32),
+                    _ => {}
+                }
+                Ok(true)
+            }
+            NonFinitePolicy::Reject => Err(DataLogError::NonFiniteValue)
+        }
+    }
+
+    /// Applies the out of order policy to a timestamp about to be written, returning the one to write
+    /// 
+    /// Entries set to sequence timestamps ignore it after their first value and step from the latest one.
+    fn order_timestamp(&mut self, timestamp: FrcTimestamp, policy: OutOfOrderPolicy) -> Result<FrcTimestamp, DataLogError> {
+        if let (Some(step), Some(last)) = (self.sequence_step, self.last_timestamp) {
+            let timestamp = last.saturating_add(step.get());
+            self.last_timestamp = Some(timestamp);
+            return Ok(timestamp);
+        }
+        let timestamp = match self.last_timestamp {
+            Some(last) if timestamp < last => {
+                self.out_of_order += 1;
+                match policy {
+                    OutOfOrderPolicy::Allow => timestamp,
+                    OutOfOrderPolicy::Clamp => last,
+                    OutOfOrderPolicy::Reject => return Err(DataLogError::OutOfOrderTimestamp)
+                }
+            }
+            _ => timestamp
+        };
+        self.last_timestamp = Some(self.last_timestamp.map_or(timestamp, |last| last.max(timestamp)));
+        Ok(timestamp)
+    }
+}
+
+/// Whether no entry may ever get `id`, 0 is for control records and with the `integrity` feature
+/// [`CHECKPOINT_ENTRY_ID`](crate::integrity::CHECKPOINT_ENTRY_ID) is for checkpoints
+const fn is_reserved_id(id: u32) -> bool {
+    #[cfg(feature = "integrity")]
+    if id == crate::integrity::CHECKPOINT_ENTRY_ID {
+        return true;
+    }
+    id == 0
+}
+
+/// The entries that haven't been closed along with their ids
+fn alive_entries(entry_data: &BTreeMap<u32, EntryData>) -> impl Iterator<Item = (&EntryData, u32)> {
+    entry_data.iter()
+        .map(|(id, data)| (data, *id))
+        .filter(|(data, _)| matches!(data.lifestatus, EntryLifeStatus::Alive { .. }))
+}
+
+/// What the [`DataLogWriter`] does with a timestamp older than the latest one of its entry
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutOfOrderPolicy {
+    /// Write it as is
+    #[default]
+    Allow,
+    /// Write it with the latest timestamp of the entry instead
+    Clamp,
+    /// Don't write it and return [`DataLogError::OutOfOrderTimestamp`]
+    Reject
+}
+
+/// What the [`DataLogWriter`] does with values holding NaN or infinite floats,
+/// which analysis scripts downstream often choke on
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum NonFinitePolicy {
+    /// Write it as is
+    #[default]
+    Allow,
+    /// Don't write it
+    Skip,
+    /// Write it with every non finite float replaced by this
+    Replace(f64),
+    /// Don't write it and return [`DataLogError::NonFiniteValue`]
+    Reject
+}
+
+/// Configuration for the [`DataLogWriter`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataLogWriterConfig {
+    /// Flush the buffered records whenever this much time has passed since the last flush,
+    /// bounding how much data a crash can lose
+    pub auto_flush_interval: Option<Duration>,
+    /// The capacity of the write buffer in bytes, [`None`] uses the [`std::io::BufWriter`] default
+    pub buffer_capacity: Option<usize>,
+    /// Flush once at least this many bytes are waiting in the buffer
+    pub flush_after_bytes: Option<usize>,
+    /// Flush once at least this many records have been written since the last flush
+    pub flush_after_records: Option<usize>,
+    /// Write the schema of every struct registered in the [`FrcStructDescDB`] when the log is created,
+    /// so structs logged later, even by other crates, can always be decoded
+    pub publish_registered_schemas: bool,
+    /// What to do with timestamps older than the latest one of their entry,
+    /// every one is counted in [`DataLogWriter::out_of_order_count`] regardless
+    pub out_of_order_policy: OutOfOrderPolicy,
+    /// The format version written in the header as (major, minor), [`None`] writes [`WPILOG_VERSION`]
+    pub format_version: Option<(u8, u8)>,
+    /// What to do with values holding NaN or infinite floats,
+    /// every one is counted in [`DataLogWriter::non_finite_count`] regardless
+    pub non_finite_policy: NonFinitePolicy,
+    /// Write every record header with a 4 byte id, 4 byte size and 8 byte timestamp instead of as few bytes as needed,
+    /// so tools can patch timestamps or ids in place later without rewriting the file
+    pub fixed_width_headers: bool,
+    /// Keep the latest value written to every entry so it can be read back with [`DataLogWriter::last_value`]
+    pub cache_last_values: bool,
 }
 
 /// A datalog writer
+/// 
+/// The writer can target any [`Write`] sink like sockets, compression encoders or in memory buffers,
+/// [`DataLogWriter::create`] is a convenience for writing to a new file.
 /// # Example
-/// ```rust
-/// use std::{path::PathBuf, fs:File};
+/// ```rust,no_run
+/// use std::{path::PathBuf, fs::File};
 /// use frclib_datalog::DataLogWriter;
-/// use frclib_core::value::FrcValue;
 /// 
 /// let path = PathBuf::from("path/to/file");
-/// let reader = DataLogWriter::try_new(File::create(path).unwrap(), Default::default())
+/// let mut writer = DataLogWriter::new(File::create(path).unwrap(), "")
 ///         .expect("Failed to create writer");
 /// 
-/// let entry = writer.get_entry::<i32>("test", None).expect("Failed to get entry");
-/// writer.write_timestamped(entry, 10, now() - 5).expect("Failed to write entry");
-/// writer.write_timestamped(entry, 20, now() + 20).expect("Failed to write entry");
-/// writer.write_timestamped(entry, 30, now() + 50).expect("Failed to write entry");
+/// let entry = writer.get_entry::<i64>("test", None).expect("Failed to get entry");
+/// writer.write_timestamped(entry, 10, 1_000).expect("Failed to write entry");
+/// writer.write_timestamped(entry, 20, 2_000).expect("Failed to write entry");
+/// writer.write_timestamped(entry, 30, 3_000).expect("Failed to write entry");
 /// 
 /// ```
 #[derive(Debug)]
 pub struct DataLogWriter<W: Write = File> {
-    /// The writer
-    writer: std::io::BufWriter<W>,
+    /// The writer, only [`None`] after the sink was taken by [`DataLogWriter::into_inner`]
+    writer: Option<std::io::BufWriter<W>>,
     /// The entry type map
-    entry_data: Vec<EntryData>,
+    entry_data: BTreeMap<u32, EntryData>,
     /// The map of keys to entry ids
     entry_id_map: HashMap<String, u32>,
     /// Highest entry id
     highest_entry_id: u32,
+    /// Ids only given to entries that ask for them, see [`DataLogWriter::reserve_entry_ids`]
+    reserved_ids: HashSet<u32>,
     /// The datalog id
-    datalog_id: u32
+    datalog_id: u32,
+    /// The writer configuration
+    config: DataLogWriterConfig,
+    /// When the writer was last flushed
+    last_flush: Instant,
+    /// The number of records written since the last flush
+    records_since_flush: usize,
+    /// The struct types whose schemas have been written and their schema entry ids
+    published_schemas: Vec<(&'static FrcStructDesc, u32)>,
+    /// Data records are dropped while paused
+    paused: bool
 }
 
 impl <W: Write> DataLogWriter<W> {
@@ -100,61 +374,174 @@ impl <W: Write> DataLogWriter<W> {
     /// # Errors
     ///  - [`DataLogError::MetadataTooLarge`] if the metadata is too large
     ///  - [`DataLogError::Io`] if an IO error occurs
-    #[allow(clippy::needless_pass_by_value)]
     pub fn new(buffer: W, metadata: impl ToString) -> Result<Self, DataLogError> {
+        Self::with_config(buffer, metadata, DataLogWriterConfig::default())
+    }
+
+    /// Creates a new datalog writer with a specific configuration
+    /// 
+    /// # Errors
+    ///  - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    ///  - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn with_config(buffer: W, metadata: impl ToString, config: DataLogWriterConfig) -> Result<Self, DataLogError> {
         let mut w = Self {
-            writer: std::io::BufWriter::new(buffer),
-            entry_data: Vec::new(),
+            writer: Some(match config.buffer_capacity {
+                Some(capacity) => std::io::BufWriter::with_capacity(capacity, buffer),
+                None => std::io::BufWriter::new(buffer)
+            }),
+            entry_data: BTreeMap::new(),
             entry_id_map: HashMap::new(),
             highest_entry_id: 0,
-            datalog_id: DATALOG_INCREMENTER.fetch_add(1, Ordering::SeqCst)
+            reserved_ids: HashSet::new(),
+            datalog_id: DATALOG_INCREMENTER.fetch_add(1, Ordering::SeqCst),
+            config,
+            last_flush: Instant::now(),
+            records_since_flush: 0,
+            published_schemas: Vec::new(),
+            paused: false
         };
 
-        let metadata = metadata.to_string();
+        w.write_header(&metadata.to_string())?;
+
+        if w.config.publish_registered_schemas {
+            for desc in inventory::iter::<FrcStructDesc> {
+                // primitives are registered with empty schemas and don't need publishing
+                if !(desc.schema_supplier)().is_empty() {
+                    w.publish_struct_schema(desc)?;
+                }
+            }
+        }
 
-        w.writer.write_all(&WPILOG_MAGIC)?;
-        w.writer.write_all(&WPILOG_VERSION)?;
+        Ok(w)
+    }
+
+    fn write_header(&mut self, metadata: &str) -> Result<(), DataLogError> {
+        let (major, minor) = self.config.format_version.unwrap_or(WPILOG_VERSION);
+        let sink = self.sink()?;
+        sink.write_all(&WPILOG_MAGIC)?;
+        // the version is a little endian u16 with the major version in the high byte
+        sink.write_all(&[minor, major])?;
         if let Ok(len) = metadata.len().try_into() {
-            w.writer.write_u32::<byteorder::LittleEndian>(len)?;
-            w.writer.write_all(metadata.as_bytes())?;
+            sink.write_u32::<byteorder::LittleEndian>(len)?;
+            sink.write_all(metadata.as_bytes())?;
         } else {
             return Err(DataLogError::MetadataTooLarge);
         }
+        Ok(())
+    }
+
+    const fn header_width(&self) -> HeaderWidth {
+        if self.config.fixed_width_headers {
+            HeaderWidth::Full
+        } else {
+            HeaderWidth::Minimal
+        }
+    }
 
-        Ok(w)
+    fn sink(&mut self) -> std::io::Result<&mut std::io::BufWriter<W>> {
+        self.writer.as_mut().ok_or_else(|| std::io::ErrorKind::BrokenPipe.into())
     }
 
     fn get_entry_data(&self, id: u32) -> Result<&EntryData, DataLogError> {
-        self.entry_data.get(id as usize).ok_or(DataLogError::NoSuchEntry)
+        self.entry_data.get(&id).ok_or(DataLogError::NoSuchEntry)
     }
 
     fn get_entry_data_mut(&mut self, id: u32) -> Result<&mut EntryData, DataLogError> {
-        self.entry_data.get_mut(id as usize).ok_or(DataLogError::NoSuchEntry)
+        self.entry_data.get_mut(&id).ok_or(DataLogError::NoSuchEntry)
     }
 
     fn inner_write(&mut self, id: EntryId, tv: FrcTimestampedValue, check_type: bool) -> Result<(), DataLogError> {
-        if tv.value == FrcValue::Void {
+        if self.paused {
             return Ok(());
         }
 
+        // the sink is moved out while encoding so the record can go straight into it
+        let mut sink = self.writer.take()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        let result = self.encode_record(id, tv, check_type, &mut sink);
+        self.writer = Some(sink);
+        if result? {
+            self.records_since_flush += 1;
+        }
+
+        self.maybe_flush().map(|_| ())
+    }
+
+    /// Validates a value and writes its record to `out`, returning whether anything was written
+    fn encode_record(&mut self, id: EntryId, mut tv: FrcTimestampedValue, check_type: bool, out: &mut impl Write) -> Result<bool, DataLogError> {
+        if tv.value == FrcValue::Void {
+            return Ok(false);
+        }
+
         if id.datalog_id != self.datalog_id {
             return Err(DataLogError::InvalidDataLog);
         }
 
-        let data = self.get_entry_data(id.entry_id)?;
+        let policy = self.config.out_of_order_policy;
+        let non_finite_policy = self.config.non_finite_policy;
+        let cache = self.config.cache_last_values;
+        let width = self.header_width();
+        let data = self.get_entry_data_mut(id.entry_id)?;
 
         if let EntryLifeStatus::Dead { .. } = data.lifestatus {
             return Err(DataLogError::OutsideEntryLifetime);
         }
 
-        if check_type && data.prehashed_type != get_data_type_serial(&tv.value.get_type()) {
+        if check_type && !data.value_type.matches(&tv.value.get_type()) {
             return Err(DataLogError::EntryTypeMismatch);
         }
 
-        let timestamp = tv.timestamp;
-        let data_record = DataRecord::from(tv.value);
+        if data.log_on_change && data.last_value.as_ref() == Some(&tv.value) {
+            return Ok(false);
+        }
+
+        match &tv.value {
+            FrcValue::Struct(bytes) if bytes.data.len() != bytes.desc.size => {
+                return Err(DataLogError::RecordSerialize("Struct payload doesn't match the struct size"));
+            }
+            FrcValue::StructArray(bytes) if bytes.desc.size == 0 || bytes.data.len() % bytes.desc.size != 0 => {
+                return Err(DataLogError::RecordSerialize("Struct array payload isn't a multiple of the struct size"));
+            }
+            _ => {}
+        }
+
+        if !data.check_finite(&mut tv.value, non_finite_policy)? {
+            return Ok(false);
+        }
+
+        tv.timestamp = data.order_timestamp(tv.timestamp, policy)?;
+
+        // encode into the entries own buffer so writing doesn't allocate once the buffer has grown
+        data.packing_buffer.clear();
+        encode_payload(&tv.value, &mut data.packing_buffer);
+        data.write_payload(width, id.entry_id, tv.timestamp, &data.packing_buffer, out)?;
+        data.write_derived(width, tv.timestamp, &tv.value, out)?;
+        if cache {
+            data.latest = Some(tv.clone());
+        }
+        if data.log_on_change {
+            data.last_value = Some(tv.value);
+        }
+        Ok(true)
+    }
+
+    fn write_batch_records(&mut self, records: impl Iterator<Item = (EntryId, FrcTimestampedValue, bool)>) -> Result<(), DataLogError> {
+        if self.paused {
+            return Ok(());
+        }
+
+        let mut batch = Vec::new();
+        let mut count = 0;
+        for (id, tv, check_type) in records {
+            if self.encode_record(id, tv, check_type, &mut batch)? {
+                count += 1;
+            }
+        }
+        self.sink()?.write_all(&batch)?;
+        self.records_since_flush += count;
 
-        data_record.write_to(timestamp, id.entry_id, &mut self.writer)
+        self.maybe_flush().map(|_| ())
     }
 
     /// Writes a value to the datalog.
@@ -165,6 +552,7 @@ impl <W: Write> DataLogWriter<W> {
     /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
     /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
     /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
     #[inline]
     pub fn write<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T) -> Result<(), DataLogError> {
         self.inner_write(id.into(), value.into_frc_value().to_timestamped(now()), false)
@@ -178,11 +566,37 @@ impl <W: Write> DataLogWriter<W> {
     /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
     /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
     /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
     #[inline]
     pub fn write_timestamped<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
         self.inner_write(id.into(), value.into_frc_value().to_timestamped(timestamp), false)
     }
 
+    /// Writes a value to the datalog at a time since robot start, see [`DataLogWriter::write_timestamped`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    #[inline]
+    pub fn write_since_start<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, since_start: Duration) -> Result<(), DataLogError> {
+        self.write_timestamped(id, value, from_duration(since_start))
+    }
+
+    /// Writes a value to the datalog at a system time, placed relative to the current time,
+    /// see [`timestamp_from_system_time`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    #[inline]
+    pub fn write_at_system_time<T: IntoFrcValue>(&mut self, id: TypedEntryId<T>, value: T, time: SystemTime) -> Result<(), DataLogError> {
+        self.write_timestamped(id, value, timestamp_from_system_time(time))
+    }
+
     /// Writes a value to the datalog.
     /// 
     /// If the value is [`FrcValue::Void`],  this function will no-op.
@@ -192,146 +606,1528 @@ impl <W: Write> DataLogWriter<W> {
     /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
     /// - [`DataLogError::Io`] if an IO error occurs
     /// - [`DataLogError::EntryTypeMismatch`] if the value type doesn't match the entry type
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
     pub fn write_dynamic(&mut self, id: EntryId, value: FrcTimestampedValue) -> Result<(), DataLogError> {
         self.inner_write(id, value, true)
     }
 
-    /// Gets the entry id for a key, creating it if it doesn't exist
+    /// Logs a value under `key`, structs log each of their fields as a sub-entry, see [`Loggable`]
     /// 
     /// # Errors
-    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
-    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::EntryTypeMismatch`] if an entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of an entry and the [`OutOfOrderPolicy`] rejects it
+    #[inline]
+    pub fn put(&mut self, key: &str, value: &impl Loggable) -> Result<(), DataLogError> {
+        value.log(self, key, now())
+    }
+
+    /// Logs a value under `key` with a specific timestamp, see [`DataLogWriter::put`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if an entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of an entry and the [`OutOfOrderPolicy`] rejects it
+    #[inline]
+    pub fn put_timestamped(&mut self, key: &str, value: &impl Loggable, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        value.log(self, key, timestamp)
+    }
+
+    /// Appends a console message with a level to the [`MESSAGES_KEY`] entry, creating it if it doesn't exist
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry already exists with a type other than string
     /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
     /// - [`DataLogError::Io`] if an IO error occurs
-    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
-    #[allow(unused_results, clippy::needless_pass_by_value)]
-    #[inline(never)]
-    pub fn get_entry_dynamic(&mut self, key: impl ToString, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
-        if FrcType::Void == entry_type {
-            return Err(
-                DataLogError::RecordType(
-                    "Cannot create a void entry"
-                )
-            )
-        }
+    pub fn log_message(&mut self, level: MessageLevel, text: &str) -> Result<(), DataLogError> {
+        self.log_message_timestamped(level, text, now())
+    }
 
-        let key = key.to_string();
+    /// Appends a console message with a level and a specific timestamp, see [`DataLogWriter::log_message`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry already exists with a type other than string
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest message and the [`OutOfOrderPolicy`] rejects it
+    pub fn log_message_timestamped(&mut self, level: MessageLevel, text: &str, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        let id = self.get_entry::<String>(MESSAGES_KEY, None)?;
+        self.write_timestamped(id, format_message(level, text), timestamp)
+    }
 
-        if let Some(id) = self.entry_id_map.get(&key) {
-            let data = self.get_entry_data(*id)?;
-            if data.prehashed_type != get_data_type_serial(&entry_type) {
-                return Err(DataLogError::EntryTypeMismatch);
-            }
-            if let EntryLifeStatus::Dead{ .. } = data.lifestatus {
-                return Err(DataLogError::OutsideEntryLifetime);
-            }
-            return Ok(EntryId {
-                datalog_id: self.datalog_id,
-                entry_id: *id
-            })
+    /// A view of the writer that puts every key it creates under `prefix`, see [`ScopedDataLogWriter`]
+    pub fn scoped(&mut self, prefix: &str) -> ScopedDataLogWriter<'_, W> {
+        ScopedDataLogWriter::new(self, prefix)
+    }
+
+    /// Writes a record payload that was encoded ahead of time, the type isn't checked
+    /// 
+    /// Entries that log on change or have rolling aggregates, writers caching values and floats that the
+    /// [`NonFinitePolicy`] checks need the value, so those payloads are decoded back into one and written
+    /// like any other. Struct payloads can't be and are written as is.
+    pub(crate) fn write_encoded(&mut self, entry_id: u32, timestamp: FrcTimestamp, payload: &[u8]) -> Result<(), DataLogError> {
+        if self.paused {
+            return Ok(());
         }
 
-        let id = self.highest_entry_id;
-        self.entry_id_map.insert(key.clone(), id);
-        self.entry_data.push(EntryData {
-            key: key.clone(),
-            entry_type: entry_type.to_string(),
-            prehashed_type: get_data_type_serial(&entry_type),
-            lifestatus: EntryLifeStatus::Alive{ start: crate::now() },
-            packing_buffer: match entry_type {
-                FrcType::Struct(desc) | FrcType::StructArray(desc) => {
-                    Vec::with_capacity(desc.size)
-                },
-                _ => Vec::new()
+        let policy = self.config.out_of_order_policy;
+        let cache = self.config.cache_last_values;
+        let width = self.header_width();
+        let data = self.entry_data.get_mut(&entry_id).ok_or(DataLogError::NoSuchEntry)?;
+        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
+            return Err(DataLogError::OutsideEntryLifetime);
+        }
+        let floats = matches!(data.value_type.serial.get(),
+            FLOAT_TYPE_SERIAL | DOUBLE_TYPE_SERIAL | FLOAT_ARRAY_TYPE_SERIAL | DOUBLE_ARRAY_TYPE_SERIAL);
+        if data.log_on_change || floats || cache || data.derived.is_some() {
+            if let Ok(record) = DataRecord::from_binary(payload, data.value_type.serial.get()) {
+                let id = EntryId { datalog_id: self.datalog_id, entry_id };
+                return self.inner_write(id, record.into_frc_value().to_timestamped(timestamp), false);
             }
-        });
+        }
 
-        self.highest_entry_id += 1;
+        let timestamp = data.order_timestamp(timestamp, policy)?;
+        let sink = self.writer.as_mut()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        data.write_payload(width, entry_id, timestamp, payload, sink)?;
+        data.latest = None;
+        self.records_since_flush += 1;
 
-        let metadata = if let Some(metadata) = metadata {
-            if metadata.len() > u32::MAX as usize {
-                return Err(DataLogError::MetadataTooLarge);
-            }
-            metadata
-        } else {
-            String::new()
-        };
+        self.maybe_flush().map(|_| ())
+    }
 
-        let control_record = ControlRecord::Start(
-            key,
-            get_data_type(&entry_type)
-                .ok_or_else( ||
-                    DataLogError::RecordType(
-                        "Cannot create a void entry"
-                    )
-                )?
-                .to_string(),
-            metadata
-        );
+    /// Writes an already built record as is, for tools that copy records between logs.
+    /// 
+    /// The record bypasses the entry bookkeeping of the writer, so its entry id must not
+    /// be used by an entry created through this writer and entries it starts aren't tracked.
+    /// While the writer is paused data records are dropped, control records are still written
+    /// so the entries they start and finish keep their lifetimes.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryAlreadyExists`] if the entry id is reserved, like
+    ///   [`CHECKPOINT_ENTRY_ID`](crate::integrity::CHECKPOINT_ENTRY_ID) with the `integrity` feature
+    /// - [`DataLogError::RecordTooLarge`] if the payload doesn't fit in a record
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn write_record(&mut self, record: Record) -> Result<(), DataLogError> {
+        #[cfg(feature = "integrity")]
+        if record.get_id() == crate::integrity::CHECKPOINT_ENTRY_ID {
+            return Err(DataLogError::EntryAlreadyExists);
+        }
+        if self.paused && matches!(record, Record::Data(..)) {
+            return Ok(());
+        }
+        let width = self.header_width();
+        record.write_to_with_width(width, self.sink()?)?;
+        self.records_since_flush += 1;
 
-        control_record.write_to(crate::now(), id, &mut self.writer)?;
+        self.maybe_flush().map(|_| ())
+    }
 
-        Ok(EntryId {
-            datalog_id: self.datalog_id,
-            entry_id: id
-        })
+    pub(crate) const fn datalog_id(&self) -> u32 {
+        self.datalog_id
     }
 
-    /// Gets the entry id for a key, creating it if it doesn't exist
+    /// Writes many values with one write to the sink, for bulk imports and high rate capture.
+    /// 
+    /// If any value fails nothing is written to the sink, but the values before it have already
+    /// updated their entries, like the latest timestamp the [`OutOfOrderPolicy`] checks against
+    /// and the last value of entries that log on change.
     /// 
     /// # Errors
-    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
-    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if an entry is not in this datalog
     /// - [`DataLogError::Io`] if an IO error occurs
-    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
-    #[inline]
-    pub fn get_entry<T: StaticallyFrcTyped>(&mut self, key: impl ToString, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
-        self.get_entry_dynamic(
-            key,
-            T::TYPE,
-            metadata
-        ).map(EntryId::typed::<T>)
+    /// - [`DataLogError::OutOfOrderTimestamp`] if a timestamp is older than the latest one of its entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn write_batch<T: IntoFrcValue + Clone>(&mut self, values: &[(TypedEntryId<T>, T, FrcTimestamp)]) -> Result<(), DataLogError> {
+        self.write_batch_records(values.iter().map(|(id, value, timestamp)| {
+            ((*id).into(), value.clone().into_frc_value().to_timestamped(*timestamp), false)
+        }))
     }
 
-    /// Closes an entry, this will invalidate the entry id and any clones of it.
+    /// Writes many values with one write to the sink, see [`DataLogWriter::write_batch`]
+    /// 
+    /// If any value fails nothing is written to the sink, but the values before it have already
+    /// updated their entries, like the latest timestamp the [`OutOfOrderPolicy`] checks against
+    /// and the last value of entries that log on change.
     /// 
     /// # Errors
-    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
-    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::InvalidDataLog`] if an entry is not in this datalog
     /// - [`DataLogError::Io`] if an IO error occurs
-    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
-    pub fn close_entry(&mut self, id: EntryId) -> Result<(), DataLogError> {
-        if id.datalog_id != self.datalog_id {
-            return Err(DataLogError::InvalidDataLog);
-        }
-
-        let data = self.get_entry_data_mut(id.entry_id)?;
-
-        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
-            return Err(DataLogError::OutsideEntryLifetime);
-        } else if let EntryLifeStatus::Alive { start } = data.lifestatus {
-            data.lifestatus = EntryLifeStatus::Dead {
-                start,
-                end: crate::now()
-            };
-        }
-
-        // try and reclaim some memory
-        data.key = String::new();
-        data.entry_type = String::new();
-        data.packing_buffer = Vec::new();
-
-        ControlRecord::Finish.write_to(crate::now(), id.entry_id, &mut self.writer)?;
+    /// - [`DataLogError::EntryTypeMismatch`] if a value type doesn't match its entry type
+    /// - [`DataLogError::OutOfOrderTimestamp`] if a timestamp is older than the latest one of its entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn write_batch_dynamic(&mut self, values: &[(EntryId, FrcTimestampedValue)]) -> Result<(), DataLogError> {
+        self.write_batch_records(values.iter().map(|(id, value)| (*id, value.clone(), true)))
+    }
 
-        Ok(())
+    /// Writes a flat map of values at one timestamp with one write to the sink,
+    /// creating any missing entries with the type of their value,
+    /// like the output telemetry frameworks hand over each loop.
+    /// 
+    /// [`FrcValue::Void`] values are skipped. Entries are created before anything is written,
+    /// if any value fails no value is written but the entries created stay.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if an entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of an entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn write_snapshot(&mut self, timestamp: FrcTimestamp, values: impl IntoIterator<Item = (String, FrcValue)>) -> Result<(), DataLogError> {
+        let records = values.into_iter()
+            .filter(|(_, value)| !matches!(value, FrcValue::Void))
+            .map(|(key, value)| {
+                let id = self.get_entry_dynamic(&key, value.get_type(), None)?;
+                Ok((id, value.to_timestamped(timestamp), true))
+            })
+            .collect::<Result<Vec<_>, DataLogError>>()?;
+        self.write_batch_records(records.into_iter())
     }
 
-    /// Flushes to the file
+    /// Creates every entry of a manifest, returning their ids by key
+    /// 
+    /// Entries that already exist with the same type are returned as is.
     /// 
     /// # Errors
+    /// - [`DataLogError::RecordType`] if a type string is unknown or names an unregistered struct
+    /// - [`DataLogError::EntryTypeMismatch`] if an entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if an entry is closed
+    /// - [`DataLogError::MetadataTooLarge`] if a metadata is too large
     /// - [`DataLogError::Io`] if an IO error occurs
-    pub fn flush(&mut self) -> Result<(), DataLogError> {
-        self.writer.flush()?;
-        Ok(())
+    pub fn declare_entries(&mut self, manifest: &EntryManifest) -> Result<HashMap<String, EntryId>, DataLogError> {
+        manifest.entries.iter()
+            .map(|entry| {
+                let entry_type = get_type_from_str(&entry.entry_type)
+                    .ok_or(DataLogError::RecordType("Unknown entry type in manifest"))?;
+                let id = self.get_entry_dynamic(&entry.key, entry_type, entry.metadata.clone())?;
+                Ok((entry.key.clone(), id))
+            })
+            .collect()
     }
-}
\ No newline at end of file
+
+    /// Writes a whole series of values to the entry `key`, creating it with the type
+    /// of the first value if it doesn't exist, for converters generating logs from other formats.
+    /// 
+    /// The series is written to the sink in chunks as it is iterated, so it never has to fit in memory.
+    /// If a value fails the values before it are still written.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry doesn't exist and the series has no values to take its type from
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if a value type doesn't match the entry type
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if a timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn write_series(&mut self, key: impl ToString, values: impl IntoIterator<Item = FrcTimestampedValue>) -> Result<EntryId, DataLogError> {
+        let key = key.to_string();
+        let mut values = values.into_iter().peekable();
+        // void values are skipped when writing anyway, they just can't give the entry its type
+        while values.next_if(|tv| tv.value == FrcValue::Void).is_some() {}
+
+        let id = if let Some(&entry_id) = self.entry_id_map.get(&key) {
+            EntryId::new(self.datalog_id, entry_id)
+        } else {
+            let entry_type = values.peek()
+                .map(|tv| tv.value.get_type())
+                .ok_or(DataLogError::RecordType("Cannot infer the entry type of an empty series"))?;
+            self.get_entry_dynamic(&key, entry_type, None)?
+        };
+        if self.paused {
+            return Ok(id);
+        }
+
+        let mut chunk = Vec::new();
+        let result = values.try_for_each(|tv| {
+            if self.encode_record(id, tv, true, &mut chunk)? {
+                self.records_since_flush += 1;
+            }
+            if chunk.len() >= SERIES_CHUNK_LEN {
+                self.sink()?.write_all(&chunk)?;
+                chunk.clear();
+            }
+            Ok::<_, DataLogError>(())
+        });
+        // the values encoded before a failure are still written
+        self.sink()?.write_all(&chunk)?;
+        result?;
+
+        let _ = self.maybe_flush()?;
+        Ok(id)
+    }
+
+    /// Writes a value queued by a [`crate::background_writer::BackgroundWriterHandle`]
+    pub(crate) fn write_queued(&mut self, id: EntryId, value: FrcTimestampedValue, check_type: bool) -> Result<(), DataLogError> {
+        self.inner_write(id, value, check_type)
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type,
+    ///   or the entry is a struct and `/.schema/struct:<name>` holds an entry that isn't a `structschema`
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[allow(clippy::needless_pass_by_value)]
+    #[inline(never)]
+    pub fn get_entry_dynamic(&mut self, key: &str, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.entry_dynamic(key, entry_type, metadata, None)
+    }
+
+    /// Gets the entry id for a key, creating it with the id `entry_id` if it doesn't exist,
+    /// so a rewritten log can keep the id layout of the original.
+    /// 
+    /// Reserved ids can be assigned this way, see [`DataLogWriter::reserve_entry_ids`].
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryAlreadyExists`] if the id is 0 or the checkpoint id, belongs to another entry
+    ///   or the key already exists with a different id
+    /// - [`DataLogError::RecordType`] if the entry type is [`FrcType::Void`]
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn get_entry_dynamic_with_id(&mut self, key: &str, entry_id: u32, entry_type: FrcType, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        self.entry_dynamic(key, entry_type, metadata, Some(entry_id))
+    }
+
+    /// Reserves entry ids so entries created without an id never get them,
+    /// they can still be assigned with [`DataLogWriter::get_entry_with_id`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryAlreadyExists`] if an id is 0 or the checkpoint id or belongs to an entry, no ids are reserved then
+    pub fn reserve_entry_ids(&mut self, ids: impl IntoIterator<Item = u32>) -> Result<(), DataLogError> {
+        let ids = ids.into_iter().collect::<Vec<_>>();
+        if ids.iter().any(|&id| is_reserved_id(id) || self.entry_data.contains_key(&id)) {
+            return Err(DataLogError::EntryAlreadyExists);
+        }
+        self.reserved_ids.extend(ids);
+        Ok(())
+    }
+
+    #[allow(unused_results)]
+    fn entry_dynamic(&mut self, key: &str, entry_type: FrcType, metadata: Option<String>, requested_id: Option<u32>) -> Result<EntryId, DataLogError> {
+        if FrcType::Void == entry_type {
+            return Err(
+                DataLogError::RecordType(
+                    "Cannot create a void entry"
+                )
+            )
+        }
+
+        if let Some(id) = self.entry_id_map.get(key) {
+            if requested_id.is_some_and(|requested| requested != *id) {
+                return Err(DataLogError::EntryAlreadyExists);
+            }
+            let data = self.get_entry_data(*id)?;
+            if !data.value_type.matches(&entry_type) {
+                return Err(DataLogError::EntryTypeMismatch);
+            }
+            if let EntryLifeStatus::Dead{ .. } = data.lifestatus {
+                return Err(DataLogError::OutsideEntryLifetime);
+            }
+            return Ok(EntryId {
+                datalog_id: self.datalog_id,
+                entry_id: *id
+            })
+        }
+
+        if requested_id.is_some_and(|requested| is_reserved_id(requested) || self.entry_data.contains_key(&requested)) {
+            return Err(DataLogError::EntryAlreadyExists);
+        }
+
+        let metadata = if let Some(metadata) = metadata {
+            if metadata.len() > u32::MAX as usize {
+                return Err(DataLogError::MetadataTooLarge);
+            }
+            metadata
+        } else {
+            String::new()
+        };
+
+        if let FrcType::Struct(desc) | FrcType::StructArray(desc) = entry_type {
+            self.publish_struct_schema(desc)?;
+        }
+        let value_type = ValueType::of(&entry_type)
+            .ok_or(
+                DataLogError::RecordType(
+                    "Cannot create a void entry"
+                )
+            )?;
+        let record_type = value_type.type_str.clone();
+        let packing_capacity = match entry_type {
+            FrcType::Struct(desc) | FrcType::StructArray(desc) => desc.size,
+            _ => 0
+        };
+
+        let id = match requested_id {
+            Some(id) => self.start_entry_with_id(id, key.to_string(), record_type, value_type, packing_capacity, metadata)?,
+            None => self.start_entry(key.to_string(), record_type, value_type, packing_capacity, metadata)?
+        };
+
+        Ok(EntryId {
+            datalog_id: self.datalog_id,
+            entry_id: id
+        })
+    }
+
+    /// Whether an entry has or may get `id`
+    fn is_id_taken(&self, id: u32) -> bool {
+        is_reserved_id(id) || self.entry_data.contains_key(&id) || self.reserved_ids.contains(&id)
+    }
+
+    #[allow(unused_results)]
+    fn start_entry(&mut self, key: String, record_type: String, value_type: ValueType, packing_capacity: usize, metadata: String) -> Result<u32, DataLogError> {
+        let mut id = self.highest_entry_id + 1;
+        while self.is_id_taken(id) {
+            id = id.checked_add(1).ok_or(DataLogError::RecordType("Ran out of entry ids"))?;
+        }
+        self.highest_entry_id = id;
+        self.start_entry_with_id(id, key, record_type, value_type, packing_capacity, metadata)
+    }
+
+    #[allow(unused_results)]
+    fn start_entry_with_id(&mut self, id: u32, key: String, record_type: String, value_type: ValueType, packing_capacity: usize, metadata: String) -> Result<u32, DataLogError> {
+        self.reserved_ids.remove(&id);
+        self.entry_id_map.insert(key.clone(), id);
+        self.entry_data.insert(id, EntryData {
+            key: key.clone(),
+            entry_type: record_type.clone(),
+            metadata: metadata.clone(),
+            value_type,
+            lifestatus: EntryLifeStatus::Alive{ start: crate::now() },
+            packing_buffer: Vec::with_capacity(packing_capacity),
+            last_value: None,
+            latest: None,
+            log_on_change: false,
+            last_timestamp: None,
+            sequence_step: None,
+            out_of_order: 0,
+            non_finite: 0,
+            aliases: Vec::new(),
+            derived: None
+        });
+
+        let control_record = ControlRecord::Start(
+            key,
+            record_type,
+            metadata
+        );
+
+        let width = self.header_width();
+        control_record.write_to_with_width(width, crate::now(), id, self.sink()?)?;
+
+        Ok(id)
+    }
+
+    /// Writes the schema of a struct and any structs nested in it to `/.schema/struct:<name>`,
+    /// so the log can be decoded without the rust types
+    fn publish_struct_schema(&mut self, desc: &'static FrcStructDesc) -> Result<(), DataLogError> {
+        if self.published_schemas.iter().any(|(published, _)| published.type_str == desc.type_str) {
+            return Ok(());
+        }
+
+        let schema = (desc.schema_supplier)();
+        for field in schema.split(';') {
+            let Some(field_type) = field.split_whitespace().next() else {
+                continue;
+            };
+            if let Some(nested) = FrcStructDescDB::get(field_type) {
+                self.publish_struct_schema(nested)?;
+            }
+        }
+
+        // the key may already hold an entry made by hand, which must not be replaced
+        let key = format!("/.schema/struct:{}", desc.type_str);
+        let existing = self.entry_id_map.get(&key).and_then(|id| Some((*id, self.entry_data.get(id)?)));
+        let id = match existing {
+            Some((_, data)) if data.entry_type != "structschema" => return Err(DataLogError::EntryTypeMismatch),
+            Some((id, data)) if matches!(data.lifestatus, EntryLifeStatus::Alive { .. }) => id,
+            _ => self.start_entry(key, "structschema".to_string(), ValueType::raw(), 0, String::new())?
+        };
+        self.published_schemas.push((desc, id));
+        self.write_struct_schema(desc, id, now())
+    }
+
+    fn write_struct_schema(&mut self, desc: &FrcStructDesc, id: u32, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        // schemas are written even while paused so entries created then can still be decoded
+        let schema = (desc.schema_supplier)();
+        let width = self.header_width();
+        write_encoded_data_record_with_width(width, timestamp, id, schema.as_bytes(), self.sink()?)?;
+        self.records_since_flush += 1;
+
+        self.maybe_flush().map(|_| ())
+    }
+
+    /// Gets the entry id for a key, creating it if it doesn't exist
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[inline]
+    pub fn get_entry<T: StaticallyFrcTyped>(&mut self, key: &str, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.get_entry_dynamic(
+            key,
+            T::TYPE,
+            metadata
+        ).map(EntryId::typed::<T>)
+    }
+
+    /// Gets the entry id for a key, creating it with the id `entry_id` if it doesn't exist,
+    /// see [`DataLogWriter::get_entry_dynamic_with_id`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::EntryAlreadyExists`] if the id is 0 or the checkpoint id, belongs to another entry
+    ///   or the key already exists with a different id
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry type doesn't match the existing entry type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[inline]
+    pub fn get_entry_with_id<T: StaticallyFrcTyped>(&mut self, key: &str, entry_id: u32, metadata: Option<String>) -> Result<TypedEntryId<T>, DataLogError> {
+        self.get_entry_dynamic_with_id(
+            key,
+            entry_id,
+            T::TYPE,
+            metadata
+        ).map(EntryId::typed::<T>)
+    }
+
+    /// Gets the id of an entry holding bytes in a custom format, creating it if it doesn't exist.
+    /// 
+    /// `type_str` is written as the entry type as is, so readers that know the format
+    /// (like `"jpeg"` camera frames or `"can"` captures) can recognize it.
+    /// Values are written with [`DataLogWriter::write_raw`].
+    /// 
+    /// # Errors
+    /// - [`DataLogError::RecordType`] if the type string is empty
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry already exists with a different type
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn get_entry_raw(&mut self, key: &str, type_str: &str, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        if type_str.is_empty() {
+            return Err(DataLogError::RecordType("Raw entries need a type string"));
+        }
+
+        if let Some(id) = self.entry_id_map.get(key) {
+            let data = self.get_entry_data(*id)?;
+            if !data.value_type.matches(&FrcType::Raw) || data.entry_type != type_str {
+                return Err(DataLogError::EntryTypeMismatch);
+            }
+            if let EntryLifeStatus::Dead{ .. } = data.lifestatus {
+                return Err(DataLogError::OutsideEntryLifetime);
+            }
+            return Ok(EntryId {
+                datalog_id: self.datalog_id,
+                entry_id: *id
+            })
+        }
+
+        let metadata = metadata.unwrap_or_default();
+        if metadata.len() > u32::MAX as usize {
+            return Err(DataLogError::MetadataTooLarge);
+        }
+
+        let id = self.start_entry(key.to_string(), type_str.to_string(), ValueType::raw(), 0, metadata)?;
+
+        Ok(EntryId {
+            datalog_id: self.datalog_id,
+            entry_id: id
+        })
+    }
+
+    /// Writes bytes to an entry created with [`DataLogWriter::get_entry_raw`], or any raw entry,
+    /// without copying them into an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold raw bytes
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn write_raw(&mut self, id: EntryId, bytes: &[u8], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+        if !self.get_entry_data(id.entry_id)?.value_type.matches(&FrcType::Raw) {
+            return Err(DataLogError::EntryTypeMismatch);
+        }
+        self.write_encoded(id.entry_id, timestamp, bytes)
+    }
+
+    /// Writes `len` bytes read from `payload` to a raw entry, copying them into the sink in chunks
+    /// so large blobs like camera frames or heap dumps are never held in memory as a whole
+    /// 
+    /// The record header is written before the payload is read, so if `payload` ends or fails before `len` bytes
+    /// the rest of the record is filled with zeros to keep the log readable and an error is returned.
+    /// Entries with aliases aren't supported, as the payload would have to be read once for every alias,
+    /// and the value isn't kept for [`DataLogWriter::last_value`] even when the writer caches values.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold raw bytes
+    /// - [`DataLogError::RecordTooLarge`] if `len` doesn't fit in a record
+    /// - [`DataLogError::Io`] if an IO error occurs, reading `payload` fails, the entry has aliases or `payload` ends before `len` bytes
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn write_raw_from(&mut self, id: EntryId, payload: impl Read, len: u64, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+        let payload_size = u32::try_from(len).map_err(|_| DataLogError::RecordTooLarge)?;
+        let data = self.get_entry_data(id.entry_id)?;
+        if !data.value_type.matches(&FrcType::Raw) {
+            return Err(DataLogError::EntryTypeMismatch);
+        }
+        if !data.aliases.is_empty() {
+            return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "streamed payloads can't be written to aliased entries").into());
+        }
+        if self.paused {
+            return Ok(());
+        }
+
+        let policy = self.config.out_of_order_policy;
+        let width = self.header_width();
+        let data = self.entry_data.get_mut(&id.entry_id).ok_or(DataLogError::NoSuchEntry)?;
+        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
+            return Err(DataLogError::OutsideEntryLifetime);
+        }
+        let timestamp = data.order_timestamp(timestamp, policy)?;
+        data.latest = None;
+
+        let sink = self.writer.as_mut()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        sink.write_all(EncodedRecordHeader::new(width, timestamp, id.entry_id, payload_size).as_bytes())?;
+        let mut payload = payload.take(len);
+        let copied = std::io::copy(&mut payload, sink);
+        // the header already promised `len` bytes, padding keeps the records after this one readable,
+        // also when reading the payload failed partway, as every byte read before that was written
+        let _ = std::io::copy(&mut std::io::repeat(0).take(payload.limit()), sink)?;
+        self.records_since_flush += 1;
+        let _ = self.maybe_flush()?;
+
+        if copied? < len {
+            return Err(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "the payload ended before its length").into());
+        }
+        Ok(())
+    }
+
+    /// Writes a `boolean` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `boolean` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_boolean(&mut self, id: impl Into<EntryId>, value: bool, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::Boolean,
+            timestamp,
+            false,
+            |out| out.push(u8::from(value)),
+            || FrcValue::Boolean(value)
+        )
+    }
+
+    /// Writes an `int64` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `int64` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_int(&mut self, id: impl Into<EntryId>, value: i64, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::Int,
+            timestamp,
+            false,
+            |out| out.extend_from_slice(&value.to_le_bytes()),
+            || FrcValue::Int(value)
+        )
+    }
+
+    /// Writes a `float` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `float` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_float(&mut self, id: impl Into<EntryId>, value: f32, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::Float,
+            timestamp,
+            !value.is_finite(),
+            |out| out.extend_from_slice(&value.to_le_bytes()),
+            || FrcValue::Float(value)
+        )
+    }
+
+    /// Writes a `double` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `double` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_double(&mut self, id: impl Into<EntryId>, value: f64, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::Double,
+            timestamp,
+            !value.is_finite(),
+            |out| out.extend_from_slice(&value.to_le_bytes()),
+            || FrcValue::Double(value)
+        )
+    }
+
+    /// Writes a `string` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `string` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_string(&mut self, id: impl Into<EntryId>, value: &str, timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::String,
+            timestamp,
+            false,
+            |out| out.extend_from_slice(value.as_bytes()),
+            || FrcValue::String(value.into())
+        )
+    }
+
+    /// Writes a `boolean[]` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `boolean[]` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_boolean_slice(&mut self, id: impl Into<EntryId>, value: &[bool], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::BooleanArray,
+            timestamp,
+            false,
+            |out| out.extend(value.iter().map(|b| u8::from(*b))),
+            || FrcValue::BooleanArray(value.into())
+        )
+    }
+
+    /// Writes an `int64[]` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `int64[]` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_int_slice(&mut self, id: impl Into<EntryId>, value: &[i64], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::IntArray,
+            timestamp,
+            false,
+            |out| value.iter().for_each(|i| out.extend_from_slice(&i.to_le_bytes())),
+            || FrcValue::IntArray(value.into())
+        )
+    }
+
+    /// Writes a `float[]` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `float[]` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_float_slice(&mut self, id: impl Into<EntryId>, value: &[f32], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::FloatArray,
+            timestamp,
+            value.iter().any(|f| !f.is_finite()),
+            |out| value.iter().for_each(|f| out.extend_from_slice(&f.to_le_bytes())),
+            || FrcValue::FloatArray(value.into())
+        )
+    }
+
+    /// Writes a `double[]` value straight from borrowed data, without building an [`FrcValue`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `double[]` values
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::OutOfOrderTimestamp`] if the timestamp is older than the latest one of the entry and the [`OutOfOrderPolicy`] rejects it
+    pub fn append_double_slice(&mut self, id: impl Into<EntryId>, value: &[f64], timestamp: FrcTimestamp) -> Result<(), DataLogError> {
+        self.append_with(
+            id.into(),
+            &FrcType::DoubleArray,
+            timestamp,
+            value.iter().any(|d| !d.is_finite()),
+            |out| value.iter().for_each(|d| out.extend_from_slice(&d.to_le_bytes())),
+            || FrcValue::DoubleArray(value.into())
+        )
+    }
+
+    /// Encodes a value into the entry's buffer and writes it, for the `append_*` functions
+    /// 
+    /// Entries that log on change and writers caching values keep the last value and non finite floats
+    /// may be replaced by the [`NonFinitePolicy`], so those values are built with `fallback` and written like any other.
+    fn append_with(
+        &mut self,
+        id: EntryId,
+        entry_type: &FrcType,
+        timestamp: FrcTimestamp,
+        non_finite: bool,
+        encode: impl FnOnce(&mut Vec<u8>),
+        fallback: impl FnOnce() -> FrcValue
+    ) -> Result<(), DataLogError> {
+        if self.paused {
+            return Ok(());
+        }
+
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+
+        let policy = self.config.out_of_order_policy;
+        let cache = self.config.cache_last_values;
+        let width = self.header_width();
+        let data = self.entry_data.get_mut(&id.entry_id).ok_or(DataLogError::NoSuchEntry)?;
+
+        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
+            return Err(DataLogError::OutsideEntryLifetime);
+        }
+
+        if !data.value_type.matches(entry_type) {
+            return Err(DataLogError::EntryTypeMismatch);
+        }
+
+        if data.log_on_change || non_finite || cache || data.derived.is_some() {
+            return self.inner_write(id, fallback().to_timestamped(timestamp), false);
+        }
+
+        let timestamp = data.order_timestamp(timestamp, policy)?;
+
+        data.packing_buffer.clear();
+        encode(&mut data.packing_buffer);
+        let sink = self.writer.as_mut()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        data.write_payload(width, id.entry_id, timestamp, &data.packing_buffer, sink)?;
+        self.records_since_flush += 1;
+
+        self.maybe_flush().map(|_| ())
+    }
+
+    /// The number of values written with a timestamp older than the latest one of their entry,
+    /// see [`OutOfOrderPolicy`]
+    #[must_use]
+    pub fn out_of_order_count(&self) -> u64 {
+        self.entry_data.values().map(|data| data.out_of_order).sum()
+    }
+
+    /// The number of values written holding NaN or infinite floats, see [`NonFinitePolicy`]
+    #[must_use]
+    pub fn non_finite_count(&self) -> u64 {
+        self.entry_data.values().map(|data| data.non_finite).sum()
+    }
+
+    /// The latest value written to an entry with its timestamp,
+    /// kept when [`DataLogWriterConfig::cache_last_values`] is set
+    /// 
+    /// Returns [`None`] if nothing was written yet, the entry is closed or isn't in this datalog.
+    #[must_use]
+    pub fn last_value(&self, id: impl Into<EntryId>) -> Option<&FrcTimestampedValue> {
+        let id = id.into();
+        if id.datalog_id != self.datalog_id {
+            return None;
+        }
+        self.entry_data.get(&id.entry_id)?.latest.as_ref()
+    }
+
+    /// Sets whether an entry only writes values that differ from the last value written to it,
+    /// signals that are sampled every loop but rarely change take up far less space this way
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    pub fn set_log_on_change(&mut self, id: EntryId, log_on_change: bool) -> Result<(), DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+
+        let data = self.get_entry_data_mut(id.entry_id)?;
+        data.log_on_change = log_on_change;
+        data.last_value = None;
+        Ok(())
+    }
+
+    /// Makes the writer assign the timestamps of an entry instead of taking them, each one `step`
+    /// after the latest, so an ordered stream of events keeps its order even when several share a microsecond.
+    /// 
+    /// The first value after this keeps its timestamp if the entry has none yet, [`None`] goes back to taking timestamps.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    pub fn set_sequence_timestamps(&mut self, id: EntryId, step: Option<NonZeroU64>) -> Result<(), DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+
+        self.get_entry_data_mut(id.entry_id)?.sequence_step = step;
+        Ok(())
+    }
+
+    /// Replaces the metadata of an entry, usually a json string
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn set_entry_metadata(&mut self, id: EntryId, metadata: impl ToString) -> Result<(), DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+
+        let metadata = metadata.to_string();
+        if u32::try_from(metadata.len()).is_err() {
+            return Err(DataLogError::MetadataTooLarge);
+        }
+
+        let data = self.get_entry_data_mut(id.entry_id)?;
+        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
+            return Err(DataLogError::OutsideEntryLifetime);
+        }
+        data.metadata.clone_from(&metadata);
+
+        let width = self.header_width();
+        ControlRecord::Metadata(metadata).write_to_with_width(width, now(), id.entry_id, self.sink()?)
+    }
+
+    /// Closes an entry, this will invalidate the entry id and any clones of it.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::Io`] if an IO error occurs
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    pub fn close_entry(&mut self, id: EntryId) -> Result<(), DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+
+        let data = self.get_entry_data_mut(id.entry_id)?;
+
+        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
+            return Err(DataLogError::OutsideEntryLifetime);
+        } else if let EntryLifeStatus::Alive { start } = data.lifestatus {
+            data.lifestatus = EntryLifeStatus::Dead {
+                start,
+                end: crate::now()
+            };
+        }
+
+        // try and reclaim some memory, the type and metadata are kept for reopening
+        data.key = String::new();
+        data.packing_buffer = Vec::new();
+        data.last_value = None;
+        data.latest = None;
+        data.aliases = Vec::new();
+        data.derived = None;
+        for other in self.entry_data.values_mut() {
+            other.aliases.retain(|alias| *alias != id.entry_id);
+            if let Some(derived) = other.derived.as_deref_mut() {
+                for channel in [&mut derived.mean, &mut derived.min, &mut derived.max] {
+                    if *channel == Some(id.entry_id) {
+                        *channel = None;
+                    }
+                }
+            }
+        }
+
+        let width = self.header_width();
+        ControlRecord::Finish.write_to_with_width(width, crate::now(), id.entry_id, self.sink()?)?;
+
+        Ok(())
+    }
+
+    /// Logs the rolling mean, min and max of a numeric entry over `window` along with every value written to it,
+    /// under the sibling keys `<key>_mean`, `<key>_min` and `<key>_max`, so dashboards get smoothed values
+    /// without post-processing the log.
+    /// 
+    /// The aggregates are written by the writer only, values written to their entries directly aren't part of them.
+    /// Adding them again restarts the window, closing the entry stops them and closing an aggregate entry stops that aggregate.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry or an aggregate entry is closed
+    /// - [`DataLogError::EntryTypeMismatch`] if the entry doesn't hold `double`, `float` or `int64` values,
+    ///   or an entry already exists under an aggregate key with another type
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn add_rolling_statistics(&mut self, id: EntryId, window: RollingWindow) -> Result<RollingStatisticsEntries, DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+
+        let data = self.get_entry_data(id.entry_id)?;
+        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
+            return Err(DataLogError::OutsideEntryLifetime);
+        }
+        if ![FrcType::Double, FrcType::Float, FrcType::Int].iter().any(|ty| data.value_type.matches(ty)) {
+            return Err(DataLogError::EntryTypeMismatch);
+        }
+
+        let key = data.key.clone();
+        let entries = RollingStatisticsEntries {
+            mean: self.get_entry_dynamic(&format!("{key}_mean"), FrcType::Double, None)?,
+            min: self.get_entry_dynamic(&format!("{key}_min"), FrcType::Double, None)?,
+            max: self.get_entry_dynamic(&format!("{key}_max"), FrcType::Double, None)?
+        };
+        self.get_entry_data_mut(id.entry_id)?.derived = Some(Box::new(DerivedChannels {
+            statistics: RollingStatistics::new(window),
+            mean: Some(entries.mean.entry_id),
+            min: Some(entries.min.entry_id),
+            max: Some(entries.max.entry_id)
+        }));
+        Ok(entries)
+    }
+
+    /// Creates an entry under `alias_key` with the type of `id` that gets a record of every value written to `id`,
+    /// so a value can be logged under a legacy key and a new one while moving between them.
+    /// 
+    /// Values written to the alias itself aren't copied to `id`,
+    /// closing the alias stops the copies while `id` keeps being written.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::InvalidDataLog`] if the entry is not in this datalog
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::OutsideEntryLifetime`] if the entry is closed
+    /// - [`DataLogError::EntryAlreadyExists`] if an entry already exists under `alias_key`
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn alias_entry(&mut self, id: EntryId, alias_key: impl ToString, metadata: Option<String>) -> Result<EntryId, DataLogError> {
+        if id.datalog_id != self.datalog_id {
+            return Err(DataLogError::InvalidDataLog);
+        }
+
+        let alias_key = alias_key.to_string();
+        if self.entry_id_map.contains_key(&alias_key) {
+            return Err(DataLogError::EntryAlreadyExists);
+        }
+
+        let data = self.get_entry_data(id.entry_id)?;
+        if let EntryLifeStatus::Dead { .. } = data.lifestatus {
+            return Err(DataLogError::OutsideEntryLifetime);
+        }
+
+        let metadata = metadata.unwrap_or_default();
+        if u32::try_from(metadata.len()).is_err() {
+            return Err(DataLogError::MetadataTooLarge);
+        }
+
+        let (entry_type, value_type) = (data.entry_type.clone(), data.value_type.clone());
+        let alias = self.start_entry(alias_key, entry_type, value_type, 0, metadata)?;
+        self.get_entry_data_mut(id.entry_id)?.aliases.push(alias);
+
+        Ok(EntryId {
+            datalog_id: self.datalog_id,
+            entry_id: alias
+        })
+    }
+
+    /// Starts a closed entry again with a new id, keeping its type and latest metadata.
+    /// 
+    /// Ids from before it was closed stay invalid,
+    /// [`DataLogWriter::get_entry`] returns the new id after reopening.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::NoSuchEntry`] if the entry doesn't exist
+    /// - [`DataLogError::EntryAlreadyExists`] if the entry is still open
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn reopen_entry(&mut self, key: &str) -> Result<EntryId, DataLogError> {
+        let old_id = *self.entry_id_map.get(key).ok_or(DataLogError::NoSuchEntry)?;
+        let data = self.get_entry_data(old_id)?;
+        if let EntryLifeStatus::Alive { .. } = data.lifestatus {
+            return Err(DataLogError::EntryAlreadyExists);
+        }
+
+        let (entry_type, metadata, value_type) = (data.entry_type.clone(), data.metadata.clone(), data.value_type.clone());
+        let id = self.start_entry(key.to_string(), entry_type, value_type, 0, metadata)?;
+
+        Ok(EntryId {
+            datalog_id: self.datalog_id,
+            entry_id: id
+        })
+    }
+
+    /// Stops writing data records while keeping every entry alive,
+    /// like during long disabled periods. Writes succeed but are dropped until [`DataLogWriter::resume`],
+    /// except control records given to [`DataLogWriter::write_record`]
+    pub const fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Starts writing data records again after [`DataLogWriter::pause`]
+    pub const fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    /// Whether data records are being dropped, see [`DataLogWriter::pause`]
+    #[must_use]
+    pub const fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Flushes to the file
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn flush(&mut self) -> Result<(), DataLogError> {
+        self.sink()?.flush()?;
+        self.last_flush = Instant::now();
+        self.records_since_flush = 0;
+        Ok(())
+    }
+
+    /// Flushes if any of the flush policies in the [`DataLogWriterConfig`] are met,
+    /// returning whether a flush happened.
+    /// 
+    /// This is called by every write, call it periodically if writes can be infrequent.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn maybe_flush(&mut self) -> Result<bool, DataLogError> {
+        let config = &self.config;
+        let should_flush = config.auto_flush_interval.is_some_and(|interval| self.last_flush.elapsed() >= interval)
+            || config.flush_after_bytes.is_some_and(|bytes| self.writer.as_ref().is_some_and(|writer| writer.buffer().len() >= bytes))
+            || config.flush_after_records.is_some_and(|records| self.records_since_flush >= records);
+        if should_flush {
+            self.flush()?;
+        }
+        Ok(should_flush)
+    }
+
+    /// Starts writing to a new sink, returning the old one.
+    /// 
+    /// Open entries are finished in the old sink and started again in the new one,
+    /// so existing entry ids stay valid.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn rotate(&mut self, sink: W, metadata: impl ToString) -> Result<W, DataLogError> {
+        let timestamp = now();
+        let width = self.header_width();
+
+        let old = self.writer.as_mut().ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        for (_, entry_id) in alive_entries(&self.entry_data) {
+            ControlRecord::Finish.write_to_with_width(width, timestamp, entry_id, old)?;
+        }
+        let capacity = old.capacity();
+        let old = self.restart(std::io::BufWriter::with_capacity(capacity, sink), &metadata.to_string(), timestamp)?;
+
+        old.ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?
+            .into_inner()
+            .map_err(|err| DataLogError::Io(err.into_error()))
+    }
+
+    /// Abandons the current sink after it failed and continues the log in `sink`, returning the old one.
+    /// 
+    /// Nothing more is written to the old sink, records still buffered for it are lost.
+    /// Open entries are started again in the new sink so existing entry ids stay valid,
+    /// see [`RecoveringDataLogWriter`](crate::recovery::RecoveringDataLogWriter) to do this automatically.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn recover(&mut self, sink: W, metadata: impl ToString) -> Result<Option<W>, DataLogError> {
+        let capacity = self.writer.as_ref().map_or(DEFAULT_BUFFER_CAPACITY, std::io::BufWriter::capacity);
+        let old = self.restart(std::io::BufWriter::with_capacity(capacity, sink), &metadata.to_string(), now())?;
+        // into_parts doesn't flush, the old sink already failed
+        Ok(old.map(|old| old.into_parts().0))
+    }
+
+    /// Replaces the sink with `new` and starts every open entry in it, returning the old sink
+    fn restart(
+        &mut self,
+        new: std::io::BufWriter<W>,
+        metadata: &str,
+        timestamp: FrcTimestamp
+    ) -> Result<Option<std::io::BufWriter<W>>, DataLogError> {
+        // log on change entries should still start the new sink with a value
+        for data in self.entry_data.values_mut() {
+            data.last_value = None;
+        }
+        let old = self.writer.replace(new);
+        self.records_since_flush = 0;
+
+        self.write_header(metadata)?;
+        let width = self.header_width();
+        let new = self.writer.as_mut().ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?;
+        for (data, entry_id) in alive_entries(&self.entry_data) {
+            ControlRecord::Start(data.key.clone(), data.entry_type.clone(), data.metadata.clone())
+                .write_to_with_width(width, timestamp, entry_id, new)?;
+        }
+        // the new sink has to be self describing too
+        for (desc, id) in self.published_schemas.clone() {
+            if self.get_entry_data(id).is_ok_and(|data| matches!(data.lifestatus, EntryLifeStatus::Alive { .. })) {
+                self.write_struct_schema(desc, id, timestamp)?;
+            }
+        }
+        self.flush()?;
+        Ok(old)
+    }
+
+    /// Finishes every entry that is still open, flushes and returns the underlying sink
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn close(mut self) -> Result<W, DataLogError> {
+        self.finish_open_entries()?;
+        self.into_inner()
+    }
+
+    /// Finishes every entry that is still open, flushes and drops the underlying sink,
+    /// for closing a writer that can't be moved out of where it is shared.
+    /// Every later write fails.
+    pub(crate) fn close_in_place(&mut self) -> Result<(), DataLogError> {
+        self.finish_open_entries()?;
+        let _ = self.writer.take()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?
+            .into_inner()
+            .map_err(|err| DataLogError::Io(err.into_error()))?;
+        Ok(())
+    }
+
+    fn finish_open_entries(&mut self) -> Result<(), DataLogError> {
+        let alive = alive_entries(&self.entry_data)
+            .map(|(_, entry_id)| EntryId::new(self.datalog_id, entry_id))
+            .collect::<Vec<_>>();
+        for id in alive {
+            self.close_entry(id)?;
+        }
+        Ok(())
+    }
+
+    /// Flushes and returns the underlying sink
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn into_inner(mut self) -> Result<W, DataLogError> {
+        self.writer.take()
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::BrokenPipe))?
+            .into_inner()
+            .map_err(|err| DataLogError::Io(err.into_error()))
+    }
+}
+
+impl <W: Write> Drop for DataLogWriter<W> {
+    fn drop(&mut self) {
+        // best effort, a writer that is dropped without flushing shouldn't truncate the log
+        if let Some(writer) = &mut self.writer {
+            let _ = writer.flush();
+        }
+    }
+}
+
+impl DataLogWriter<Vec<u8>> {
+    /// Creates a datalog writer that writes into memory,
+    /// the finished log can be taken with [`DataLogWriter::into_inner`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn new_in_memory(metadata: impl ToString) -> Result<Self, DataLogError> {
+        Self::new(Vec::new(), metadata)
+    }
+
+    /// Takes everything written so far out of the in memory buffer
+    #[cfg(feature = "tokio")]
+    pub(crate) fn take_written(&mut self) -> Result<Vec<u8>, DataLogError> {
+        let sink = self.sink()?;
+        sink.flush()?;
+        Ok(std::mem::take(sink.get_mut()))
+    }
+
+    /// The number of bytes written so far that haven't been taken
+    #[cfg(feature = "tokio")]
+    pub(crate) fn written_len(&self) -> usize {
+        self.writer.as_ref().map_or(0, |writer| writer.get_ref().len() + writer.buffer().len())
+    }
+}
+impl DataLogWriter<File> {
+    /// Creates a new file at `path` and a datalog writer for it,
+    /// this will not overwrite an existing file
+    /// 
+    /// The file is exclusively locked until the writer is dropped, see [`locking`](crate::locking).
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at `path`
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn create(path: impl AsRef<Path>, metadata: impl ToString) -> Result<Self, DataLogError> {
+        let file = create_locked(path.as_ref())
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::AlreadyExists => DataLogError::FileAlreadyExists,
+                _ => DataLogError::Io(err)
+            })?;
+        Self::new(file, metadata)
+    }
+
+    /// Flushes and waits for the data to reach the disk instead of just the OS cache,
+    /// use this at moments where losing data isn't acceptable like the end of a match
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn sync(&mut self) -> Result<(), DataLogError> {
+        self.flush()?;
+        self.sink()?.get_ref().sync_data()?;
+        Ok(())
+    }
+}
+
+impl DataLogWriter<CountingSink> {
+    /// Creates a datalog writer that checks and encodes everything but writes nothing,
+    /// see [`CountingSink`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    pub fn dry_run(metadata: impl ToString) -> Result<Self, DataLogError> {
+        Self::new(CountingSink::default(), metadata)
+    }
+}
+
+impl <W: Write> DataLogWriter<CountingSink<W>> {
+    /// The size of the log so far in bytes, including records still buffered
+    #[must_use]
+    pub fn bytes_written(&self) -> u64 {
+        self.writer.as_ref()
+            .map_or(0, |writer| writer.get_ref().bytes_written() + writer.buffer().len() as u64)
+    }
+}
+
+impl DataLogWriter<TempFileSink> {
+    /// Creates a datalog writer for `path` that writes to `<path>.tmp` until it is closed
+    /// with [`DataLogWriter::close_atomic`], see [`TempFileSink`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at `path` or the temporary path
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn create_atomic(path: impl Into<PathBuf>, metadata: impl ToString) -> Result<Self, DataLogError> {
+        let sink = TempFileSink::create(path)
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::AlreadyExists => DataLogError::FileAlreadyExists,
+                _ => DataLogError::Io(err)
+            })?;
+        Self::new(sink, metadata)
+    }
+
+    /// Finishes every open entry and moves the file to its final path, returning it
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs, of kind [`std::io::ErrorKind::AlreadyExists`]
+    ///   if a file was created at the final path since, see [`TempFileSink::persist`]
+    pub fn close_atomic(self) -> Result<File, DataLogError> {
+        Ok(self.close()?.persist()?)
+    }
+}
+
+/// A sink that compresses everything written to it with zstd
+#[cfg(feature = "zstd")]
+pub type CompressedSink<W> = zstd::Encoder<'static, W>;
+
+#[cfg(feature = "zstd")]
+impl <W: Write> DataLogWriter<CompressedSink<W>> {
+    /// Creates a datalog writer that compresses the log with zstd before writing it to `sink`,
+    /// a `level` of 0 uses the zstd default.
+    /// 
+    /// Every flush ends a compressed block, so flushing less often compresses better.
+    /// The log must be finished with [`DataLogWriter::close_compressed`] to be complete,
+    /// [`DataLogReader`](crate::DataLogReader) still reads everything flushed before a crash.
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn new_compressed(sink: W, metadata: impl ToString, level: i32) -> Result<Self, DataLogError> {
+        Self::new(zstd::Encoder::new(sink, level)?, metadata)
+    }
+
+    /// Finishes every open entry and the compressed stream, returning the sink
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn close_compressed(self) -> Result<W, DataLogError> {
+        Ok(self.close()?.finish()?)
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl DataLogWriter<CompressedSink<File>> {
+    /// Creates a new file at `path`, conventionally ending in `.wpilog.zst`,
+    /// and a compressing datalog writer for it, see [`DataLogWriter::new_compressed`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at `path`
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn create_compressed(path: impl AsRef<Path>, metadata: impl ToString, level: i32) -> Result<Self, DataLogError> {
+        let file = create_locked(path.as_ref())
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::AlreadyExists => DataLogError::FileAlreadyExists,
+                _ => DataLogError::Io(err)
+            })?;
+        Self::new_compressed(file, metadata, level)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl <W: Write> DataLogWriter<EncryptedSink<W>> {
+    /// Creates a datalog writer that encrypts the log with `key` before writing it to `sink`, see [`EncryptedSink`]
+    /// 
+    /// The log must be finished with [`DataLogWriter::close_encrypted`] to be complete,
+    /// [`DataLogReader::try_new_encrypted`](crate::DataLogReader::try_new_encrypted) still reads everything flushed
+    /// before a crash with [`Truncation::Allow`](crate::encryption::Truncation::Allow).
+    /// 
+    /// # Errors
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn new_encrypted(sink: W, key: &[u8; 32], metadata: impl ToString) -> Result<Self, DataLogError> {
+        Self::new(EncryptedSink::new(sink, key)?, metadata)
+    }
+
+    /// Finishes every open entry and the encrypted stream, returning the sink
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn close_encrypted(self) -> Result<W, DataLogError> {
+        Ok(self.close()?.finish()?)
+    }
+}
+
+#[cfg(feature = "encryption")]
+impl DataLogWriter<EncryptedSink<File>> {
+    /// Creates a new file at `path`, conventionally ending in `.wpilog.enc`,
+    /// and an encrypting datalog writer for it, see [`DataLogWriter::new_encrypted`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::FileAlreadyExists`] if a file already exists at `path`
+    /// - [`DataLogError::MetadataTooLarge`] if the metadata is too large
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn create_encrypted(path: impl AsRef<Path>, key: &[u8; 32], metadata: impl ToString) -> Result<Self, DataLogError> {
+        let file = create_locked(path.as_ref())
+            .map_err(|err| match err.kind() {
+                std::io::ErrorKind::AlreadyExists => DataLogError::FileAlreadyExists,
+                _ => DataLogError::Io(err)
+            })?;
+        Self::new_encrypted(file, key, metadata)
+    }
+}
+
+impl <W: Write> DataLogWriter<RebasingSink<W>> {
+    /// Shifts every timestamp in the log by `offset` microseconds, including the records held so far,
+    /// returning whether the offset was applied, see [`RebasingSink`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn set_time_base(&mut self, offset: i64) -> Result<bool, DataLogError> {
+        let sink = self.sink()?;
+        // records still in the buffer get the offset too once they reach the sink
+        let applied = sink.get_mut().set_offset(offset)?;
+        sink.flush()?;
+        Ok(applied)
+    }
+
+    /// Sets the time base so timestamps become microseconds since the unix epoch,
+    /// call this once the system clock is set, see [`DataLogWriter::set_time_base`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn sync_time_base_to_system_clock(&mut self) -> Result<bool, DataLogError> {
+        let unix_time = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_err(std::io::Error::other)?
+            .as_micros();
+        let offset = i128::try_from(unix_time).unwrap_or(i128::MAX) - i128::from(now());
+        self.set_time_base(i64::try_from(offset)?)
+    }
+}
+
+#[cfg(feature = "integrity")]
+impl <W: Write> DataLogWriter<IntegritySink<W>> {
+    /// Writes a checkpoint covering everything since the previous one, see [`IntegritySink`]
+    ///
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn write_checkpoint(&mut self) -> Result<(), DataLogError> {
+        let sink = self.sink()?;
+        sink.flush()?;
+        sink.get_mut().checkpoint()?;
+        Ok(())
+    }
+}
+
+impl <W: Write> DataLogWriter<ArmableSink<W>> {
+    /// Writes the records kept while disarmed and writes everything from then on, see [`ArmableSink`]
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn arm(&mut self) -> Result<(), DataLogError> {
+        let sink = self.sink()?;
+        sink.flush()?;
+        sink.get_mut().arm()?;
+        Ok(())
+    }
+
+    /// Goes back to keeping only the latest records in memory, like when the robot is disabled again
+    /// 
+    /// # Errors
+    /// - [`DataLogError::Io`] if an IO error occurs
+    pub fn disarm(&mut self) -> Result<(), DataLogError> {
+        let sink = self.sink()?;
+        // the buffer is flushed so the sink switches on a record boundary
+        sink.flush()?;
+        sink.get_mut().disarm();
+        Ok(())
+    }
+}